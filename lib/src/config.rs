@@ -1,9 +1,12 @@
+use std::collections::HashMap;
 use std::fs;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
 
 use anyhow::anyhow;
+use regex::Regex;
 use serde_derive::{Deserialize, Serialize};
 use toml;
 
@@ -25,6 +28,7 @@ pub struct Config {
     pub repos: RwLock<repos::RepoConfig>,
 
     config_dir: PathBuf,
+    db: ConfigDatabase,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -43,6 +47,23 @@ pub struct MainConfig {
     pub listen_addr: Option<String>,
     pub clone_root_dir: String,
     pub num_http_threads: Option<usize>,
+    // externally reachable URL of this octobot instance (e.g. "https://octobot.company.com")
+    pub base_url: Option<String>,
+    // append a "View in octobot" link to channel messages. requires base_url.
+    pub include_octobot_link: Option<bool>,
+    // maximum number of repo clones in use at once. unlimited if unset.
+    pub max_concurrent_clones: Option<usize>,
+    // maximum number of backports run at once, each in its own clone. unlimited if unset.
+    pub max_concurrent_backports: Option<usize>,
+    // regexes for secrets to redact from logged webhook bodies (defaults to common token formats)
+    pub redact_patterns: Option<Vec<String>>,
+    // always start version script runs from a fresh clone instead of reusing one (defaults to false)
+    pub fresh_version_clones: Option<bool>,
+    // seconds to wait on shutdown for queued backports, version scripts and slack messages (defaults to 30)
+    pub shutdown_timeout_secs: Option<u64>,
+    // tag log lines with the delivery id of the webhook being handled, including in workers (defaults to false).
+    // worker requests carry the id regardless.
+    pub log_correlation_ids: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -61,8 +82,61 @@ pub struct MetricsConfig {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct SlackConfig {
     pub bot_token: String,
+    // users whose activity is not notified about. Repos may extend or replace this list.
     #[serde(default)]
     pub ignored_users: Vec<String>,
+    // channels that PRs may route notifications to with a "Slack-Channel: #foo" trailer
+    #[serde(default)]
+    pub allowed_channel_overrides: Vec<String>,
+    // post a single channel message for pushes that update multiple PRs
+    #[serde(default)]
+    pub batch_push_notifications: bool,
+    // notify the PR thread and owner once all review conversations are resolved
+    #[serde(default)]
+    pub notify_conversations_resolved: bool,
+    // drop slack messages for webhooks redelivered at startup; JIRA/git processing still happens
+    #[serde(default)]
+    pub suppress_redelivery_notifications: bool,
+    // neutralize @here/@channel/@everyone mentions in messages posted to channels
+    #[serde(default)]
+    pub sanitize_mentions: bool,
+    // channels exempt from sanitize_mentions
+    #[serde(default)]
+    pub mentions_allowed_channels: Vec<String>,
+    // note the PR author's team (from author_teams) in PR notifications
+    #[serde(default)]
+    pub include_author_team: bool,
+    // note the PR size (see github.pr_size_thresholds) in PR notifications
+    #[serde(default)]
+    pub include_pr_size: bool,
+    // render PR-opened and push notifications with Block Kit blocks instead of attachments
+    #[serde(default)]
+    pub use_blocks: bool,
+    // github login -> team name
+    #[serde(default)]
+    pub author_teams: HashMap<String, String>,
+    // channel to alert about github users without a slack mapping (defaults to none, i.e. disabled)
+    pub unmapped_users_channel: Option<String>,
+    // minimum seconds between unmapped user alerts (defaults to 3600)
+    pub unmapped_users_alert_secs: Option<u64>,
+    // nag repo channels about non-draft PRs open this many hours without any review (defaults to none, i.e. disabled)
+    pub stale_pr_hours: Option<u64>,
+    // hours between repeated, escalating nags about the same PR (defaults to 24)
+    pub stale_pr_nag_hours: Option<u64>,
+    // channel to alert when repos are added to/removed from the GitHub App installation (defaults to none, i.e. disabled)
+    pub installation_alerts_channel: Option<String>,
+    // channel to post secret scanning alerts to (defaults to none, i.e. disabled)
+    pub security_alerts_channel: Option<String>,
+    // channel to escalate PRs to once they collect too many changes-requested reviews (defaults to none, i.e. disabled)
+    pub escalation_channel: Option<String>,
+    // number of changes-requested reviews on a PR which triggers an escalation (defaults to 3)
+    pub changes_requested_escalation_threshold: Option<u32>,
+    // post one channel message listing the affected PRs instead of a channel message per PR when
+    // a force-push affects more than this many PRs. participants are still messaged directly
+    // (defaults to none, i.e. disabled)
+    pub force_push_summary_threshold: Option<usize>,
+    // messages to send to slack per minute on average, allowing short bursts (defaults to 60)
+    pub max_messages_per_minute: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -72,6 +146,42 @@ pub struct GithubConfig {
     pub api_token: Option<String>,
     pub app_id: Option<u32>,
     pub app_key_file: Option<String>,
+    // number of times to retry submitting a check run on failure (defaults to 2)
+    pub check_run_retries: Option<u32>,
+    // skip the jira check for draft PRs until they are ready for review (defaults to false)
+    pub defer_draft_jira_check: Option<bool>,
+    // ignore re-added backport labels on the same PR within this many seconds (defaults to 300, 0 disables)
+    pub backport_label_window_secs: Option<u64>,
+    // events to accept without processing, counted as ignored (defaults to ["star", "watch"])
+    pub ignored_events: Option<Vec<String>>,
+    // label which opts a PR out of backports (defaults to "no-backport")
+    pub no_backport_label: Option<String>,
+    // what to do when a PR has both backport labels and the no-backport label (defaults to "prefer-no-backport")
+    pub backport_conflict_policy: Option<BackportConflictPolicy>,
+    // skip notifications and JIRA updates for pushes merging one release branch into another (defaults to false)
+    pub skip_release_merges: Option<bool>,
+    // announce failed GitHub Actions workflow runs in repo channels (defaults to false)
+    pub notify_workflow_failures: Option<bool>,
+    // upper bounds of changed lines for XS/S/M/L PRs; larger PRs are XL (defaults to [10, 100, 500, 1000])
+    pub pr_size_thresholds: Option<Vec<u32>>,
+    // label PRs with their size, e.g. "size/M" (defaults to false)
+    pub pr_size_labels: Option<bool>,
+    // respond to duplicate webhook deliveries with a 200 and this body (e.g. "duplicate, ignored")
+    // instead of a 400, so GitHub doesn't show them as failed. They are never reprocessed.
+    pub duplicate_webhook_response: Option<String>,
+    // extra headers to send with every GitHub API request, e.g. for a proxy (defaults to none).
+    // they never replace the Authorization or Accept headers octobot sets itself.
+    pub extra_headers: Option<HashMap<String, String>>,
+    // path a GitHub Enterprise install is served under, e.g. "github" for
+    // https://ghe.company.com/github (defaults to none)
+    pub api_path_prefix: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackportConflictPolicy {
+    PreferNoBackport,
+    PreferBackport,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -96,6 +206,56 @@ pub struct JiraConfig {
     pub restrict_comment_visibility_to_role: Option<String>,
     // optional suffix to add to the username for the login dialog (e.g. "@company.com")
     pub login_suffix: Option<String>,
+    // state to transition to when a PR enters the merge queue (defaults to [], i.e. disabled)
+    pub queued_states: Option<Vec<String>>,
+    // base branch regexes for which opened PRs are not submitted for review (defaults to [])
+    pub skip_review_branches: Option<Vec<String>>,
+    // note the number of merged commits in merge comments (defaults to false)
+    pub include_commit_count: Option<bool>,
+    // note the deploy environment (ENV=...) emitted by version scripts in merge comments (defaults to false)
+    pub include_deploy_environment: Option<bool>,
+    // warn PR owners about JIRA keys from projects not configured for the repo (defaults to false)
+    pub warn_unconfigured_projects: Option<bool>,
+    // set fixed JIRAs' fix-version from the PR milestone when (de)milestoned (defaults to false)
+    pub sync_milestone_versions: Option<bool>,
+    // slack channel to record every JIRA transition octobot performs (defaults to none, i.e. disabled)
+    pub transition_audit_channel: Option<String>,
+    // record a hash of each comment as an issue property and skip re-posting it (defaults to false)
+    pub dedupe_comments: Option<bool>,
+    // comment on opened PRs with the summary and status of each referenced JIRA (defaults to false)
+    pub pr_summary_comment: Option<bool>,
+    // comment on fixed JIRAs when octobot opens a backport PR for them (defaults to false)
+    pub backport_comments: Option<bool>,
+    // comment on JIRAs referenced by PRs whose GitHub Actions workflow runs fail (defaults to false)
+    pub workflow_failure_comments: Option<bool>,
+    // transition JIRAs when PRs targeting release branches are submitted for review (defaults to true)
+    pub release_branch_review_transitions: Option<bool>,
+    // state to transition JIRAs to when a GitHub release of their fix version is published (defaults to [], i.e. disabled)
+    pub released_states: Option<Vec<String>>,
+    // longest commit title quoted in merge comments before it's cut off with "..." (defaults to 200, 0 for no limit)
+    pub max_quoted_title_length: Option<usize>,
+    // don't submit JIRAs for review from revert PRs; they are still notified (defaults to false)
+    pub skip_revert_reviews: Option<bool>,
+    // link fixed JIRAs to PRs submitted for review instead of commenting the PR URL (defaults to false)
+    pub use_remote_links: Option<bool>,
+    // comment on fixed JIRAs when auto-merge is enabled on their PR, once per PR (defaults to false)
+    pub auto_merge_comments: Option<bool>,
+    // times to retry submitting a PR for review in the background when JIRA is unavailable or a comment or transition fails; queued retries survive restarts (defaults to 0, i.e. submit inline without retrying)
+    pub review_retries: Option<u32>,
+    // seconds before the first review retry, doubling after each one (defaults to 30)
+    pub review_retry_delay_secs: Option<u64>,
+    // show the type and priority of referenced JIRAs in PR opened notifications (defaults to false)
+    pub issue_details_in_notifications: Option<bool>,
+    // assign unassigned fixed JIRAs to the PR author when submitted for review (defaults to false).
+    // the JIRA username is the author's email in the users table, minus login_suffix.
+    pub sync_assignee: Option<bool>,
+    // warn when a PR submitted for review fixes a JIRA another open PR also fixes (defaults to false)
+    pub warn_shared_keys: Option<bool>,
+    // log mutating JIRA calls (comments, transitions, versions) instead of making them (defaults to false)
+    pub dry_run: Option<bool>,
+    // extra headers to send with every JIRA API request, e.g. for a proxy (defaults to none).
+    // they never replace the Authorization or Accept headers octobot sets itself.
+    pub extra_headers: Option<HashMap<String, String>>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -131,8 +291,9 @@ impl Config {
             jira: config.jira,
             ldap: config.ldap,
             users: RwLock::new(users::UserConfig::new(db.clone())),
-            repos: RwLock::new(repos::RepoConfig::new(db)),
+            repos: RwLock::new(repos::RepoConfig::new(db.clone())),
             config_dir: dir.to_path_buf(),
+            db,
         }
     }
 
@@ -179,6 +340,10 @@ impl Config {
         self.repos.write().unwrap()
     }
 
+    pub fn check_db(&self) -> Result<()> {
+        self.db.check()
+    }
+
     pub fn slack_db_path(&self) -> String {
         self.config_dir
             .join("slack_db.sqlite3")
@@ -186,6 +351,13 @@ impl Config {
             .to_string()
     }
 
+    pub fn slack_retry_db_path(&self) -> String {
+        self.config_dir
+            .join("slack_retry_db.sqlite3")
+            .to_string_lossy()
+            .to_string()
+    }
+
     pub fn webhook_db_path(&self) -> String {
         self.config_dir
             .join("webhook_db.sqlite3")
@@ -201,6 +373,14 @@ impl ConfigModel {
                 listen_addr: None,
                 clone_root_dir: String::new(),
                 num_http_threads: None,
+                base_url: None,
+                include_octobot_link: None,
+                max_concurrent_clones: None,
+                max_concurrent_backports: None,
+                redact_patterns: None,
+                fresh_version_clones: None,
+                shutdown_timeout_secs: None,
+                log_correlation_ids: None,
             },
             admin: None,
             metrics: None,
@@ -210,10 +390,43 @@ impl ConfigModel {
                 api_token: None,
                 app_id: None,
                 app_key_file: None,
+                check_run_retries: None,
+                defer_draft_jira_check: None,
+                backport_label_window_secs: None,
+                ignored_events: None,
+                no_backport_label: None,
+                backport_conflict_policy: None,
+                skip_release_merges: None,
+                notify_workflow_failures: None,
+                pr_size_thresholds: None,
+                pr_size_labels: None,
+                duplicate_webhook_response: None,
+                extra_headers: None,
+                api_path_prefix: None,
             },
             slack: SlackConfig {
                 bot_token: String::new(),
                 ignored_users: vec![],
+                allowed_channel_overrides: vec![],
+                batch_push_notifications: false,
+                notify_conversations_resolved: false,
+                suppress_redelivery_notifications: false,
+                sanitize_mentions: false,
+                mentions_allowed_channels: vec![],
+                include_author_team: false,
+                include_pr_size: false,
+                use_blocks: false,
+                author_teams: HashMap::new(),
+                unmapped_users_channel: None,
+                unmapped_users_alert_secs: None,
+                stale_pr_hours: None,
+                stale_pr_nag_hours: None,
+                installation_alerts_channel: None,
+                security_alerts_channel: None,
+                escalation_channel: None,
+                changes_requested_escalation_threshold: None,
+                force_push_summary_threshold: None,
+                max_messages_per_minute: None,
             },
             jira: None,
             ldap: None,
@@ -221,6 +434,97 @@ impl ConfigModel {
     }
 }
 
+impl MainConfig {
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout_secs.unwrap_or(30))
+    }
+
+    pub fn log_correlation_ids(&self) -> bool {
+        self.log_correlation_ids.unwrap_or(false)
+    }
+
+    pub fn fresh_version_clones(&self) -> bool {
+        self.fresh_version_clones.unwrap_or(false)
+    }
+
+    pub fn redact_patterns(&self) -> Vec<String> {
+        if let Some(ref patterns) = self.redact_patterns {
+            patterns.clone()
+        } else {
+            vec![
+                // github tokens
+                r"\bgh[pousr]_[A-Za-z0-9]{36,}\b".into(),
+                r"\bgithub_pat_[A-Za-z0-9_]{22,}\b".into(),
+                // slack tokens
+                r"\bxox[abposr]-[A-Za-z0-9-]{10,}\b".into(),
+                // AWS access keys
+                r"\bAKIA[0-9A-Z]{16}\b".into(),
+            ]
+        }
+    }
+
+    // Link to the octobot UI page for the given repo, if enabled
+    pub fn octobot_link(&self, repo_full_name: &str) -> Option<String> {
+        if !self.include_octobot_link.unwrap_or(false) {
+            return None;
+        }
+
+        self.base_url.as_ref().map(|base| {
+            format!(
+                "{}/#!/repos?needle={}",
+                base.trim_end_matches('/'),
+                repo_full_name
+            )
+        })
+    }
+}
+
+impl SlackConfig {
+    pub fn author_team(&self, login: &str) -> Option<&str> {
+        if !self.include_author_team {
+            return None;
+        }
+        self.author_teams.get(login).map(|t| t.as_str())
+    }
+
+    pub fn sanitizes_mentions(&self, channel: &str) -> bool {
+        self.sanitize_mentions && !self.mentions_allowed_channels.iter().any(|c| c == channel)
+    }
+
+    pub fn unmapped_users_alert_interval(&self) -> Duration {
+        Duration::from_secs(self.unmapped_users_alert_secs.unwrap_or(3600))
+    }
+
+    pub fn stale_pr_threshold(&self) -> Option<Duration> {
+        self.stale_pr_hours.map(|h| Duration::from_secs(h * 3600))
+    }
+
+    pub fn stale_pr_nag_interval(&self) -> Duration {
+        Duration::from_secs(self.stale_pr_nag_hours.unwrap_or(24) * 3600)
+    }
+
+    // The escalation channel and changes-requested threshold, if escalation is enabled
+    pub fn changes_requested_escalation(&self) -> Option<(&str, u32)> {
+        match self.escalation_channel {
+            Some(ref c) if !c.is_empty() => Some((
+                c.as_str(),
+                self.changes_requested_escalation_threshold.unwrap_or(3),
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn max_messages_per_minute(&self) -> u32 {
+        self.max_messages_per_minute.unwrap_or(60)
+    }
+
+    // Whether a force-push affecting this many PRs gets a single summary notification
+    pub fn summarize_force_push(&self, affected_prs: usize) -> bool {
+        self.force_push_summary_threshold
+            .is_some_and(|t| affected_prs > t)
+    }
+}
+
 impl GithubConfig {
     pub fn app_key(&self) -> Result<Vec<u8>> {
         let key_file = &self
@@ -234,6 +538,78 @@ impl GithubConfig {
         file_open.read_to_end(&mut contents)?;
         Ok(contents)
     }
+
+    pub fn check_run_retries(&self) -> u32 {
+        self.check_run_retries.unwrap_or(2)
+    }
+
+    pub fn defer_draft_jira_check(&self) -> bool {
+        self.defer_draft_jira_check.unwrap_or(false)
+    }
+
+    pub fn backport_label_window(&self) -> Duration {
+        Duration::from_secs(self.backport_label_window_secs.unwrap_or(300))
+    }
+
+    pub fn no_backport_label(&self) -> String {
+        self.no_backport_label
+            .clone()
+            .unwrap_or_else(|| "no-backport".into())
+    }
+
+    pub fn backport_conflict_policy(&self) -> BackportConflictPolicy {
+        self.backport_conflict_policy
+            .unwrap_or(BackportConflictPolicy::PreferNoBackport)
+    }
+
+    pub fn skip_release_merges(&self) -> bool {
+        self.skip_release_merges.unwrap_or(false)
+    }
+
+    pub fn notify_workflow_failures(&self) -> bool {
+        self.notify_workflow_failures.unwrap_or(false)
+    }
+
+    pub fn pr_size_thresholds(&self) -> Vec<u32> {
+        match self.pr_size_thresholds {
+            Some(ref t) => t.clone(),
+            None => vec![10, 100, 500, 1000],
+        }
+    }
+
+    pub fn pr_size_labels(&self) -> bool {
+        self.pr_size_labels.unwrap_or(false)
+    }
+
+    pub fn ignored_events(&self) -> Vec<String> {
+        match self.ignored_events {
+            Some(ref events) => events.clone(),
+            None => vec!["star".into(), "watch".into()],
+        }
+    }
+
+    pub fn duplicate_webhook_response(&self) -> Option<&str> {
+        self.duplicate_webhook_response.as_deref()
+    }
+
+    pub fn extra_headers(&self) -> HashMap<String, String> {
+        self.extra_headers.clone().unwrap_or_default()
+    }
+
+    pub fn api_path_prefix(&self) -> &str {
+        self.api_path_prefix
+            .as_deref()
+            .map(|p| p.trim_matches('/'))
+            .unwrap_or_default()
+    }
+
+    // The host along with any path prefix, e.g. "ghe.company.com/github"
+    pub fn prefixed_host(&self) -> String {
+        match self.api_path_prefix() {
+            "" => self.host.clone(),
+            prefix => format!("{}/{}", self.host, prefix),
+        }
+    }
 }
 
 impl JiraConfig {
@@ -269,6 +645,14 @@ impl JiraConfig {
         }
     }
 
+    pub fn queued_states(&self) -> Vec<String> {
+        if let Some(ref states) = self.queued_states {
+            states.clone() // hmm. do these w/o a clone?
+        } else {
+            vec![]
+        }
+    }
+
     pub fn fixed_resolutions(&self) -> Vec<String> {
         if let Some(ref res) = self.fixed_resolutions {
             res.clone() // hmm. do these w/o a clone?
@@ -277,6 +661,111 @@ impl JiraConfig {
         }
     }
 
+    pub fn include_commit_count(&self) -> bool {
+        self.include_commit_count.unwrap_or(false)
+    }
+
+    pub fn include_deploy_environment(&self) -> bool {
+        self.include_deploy_environment.unwrap_or(false)
+    }
+
+    pub fn warn_unconfigured_projects(&self) -> bool {
+        self.warn_unconfigured_projects.unwrap_or(false)
+    }
+
+    pub fn dedupe_comments(&self) -> bool {
+        self.dedupe_comments.unwrap_or(false)
+    }
+
+    pub fn pr_summary_comment(&self) -> bool {
+        self.pr_summary_comment.unwrap_or(false)
+    }
+
+    pub fn backport_comments(&self) -> bool {
+        self.backport_comments.unwrap_or(false)
+    }
+
+    pub fn workflow_failure_comments(&self) -> bool {
+        self.workflow_failure_comments.unwrap_or(false)
+    }
+
+    pub fn release_branch_review_transitions(&self) -> bool {
+        self.release_branch_review_transitions.unwrap_or(true)
+    }
+
+    pub fn max_quoted_title_length(&self) -> usize {
+        self.max_quoted_title_length.unwrap_or(200)
+    }
+
+    pub fn skip_revert_reviews(&self) -> bool {
+        self.skip_revert_reviews.unwrap_or(false)
+    }
+
+    pub fn use_remote_links(&self) -> bool {
+        self.use_remote_links.unwrap_or(false)
+    }
+
+    pub fn auto_merge_comments(&self) -> bool {
+        self.auto_merge_comments.unwrap_or(false)
+    }
+
+    pub fn review_retries(&self) -> u32 {
+        self.review_retries.unwrap_or(0)
+    }
+
+    pub fn review_retry_delay(&self) -> Duration {
+        Duration::from_secs(self.review_retry_delay_secs.unwrap_or(30))
+    }
+
+    pub fn issue_details_in_notifications(&self) -> bool {
+        self.issue_details_in_notifications.unwrap_or(false)
+    }
+
+    pub fn sync_assignee(&self) -> bool {
+        self.sync_assignee.unwrap_or(false)
+    }
+
+    pub fn warn_shared_keys(&self) -> bool {
+        self.warn_shared_keys.unwrap_or(false)
+    }
+
+    pub fn dry_run(&self) -> bool {
+        self.dry_run.unwrap_or(false)
+    }
+
+    pub fn extra_headers(&self) -> HashMap<String, String> {
+        self.extra_headers.clone().unwrap_or_default()
+    }
+
+    pub fn released_states(&self) -> Vec<String> {
+        self.released_states.clone().unwrap_or_default()
+    }
+
+    pub fn sync_milestone_versions(&self) -> bool {
+        self.sync_milestone_versions.unwrap_or(false)
+    }
+
+    pub fn skips_review_for_branch(&self, branch: &str) -> bool {
+        let patterns = match self.skip_review_branches {
+            Some(ref p) => p,
+            None => return false,
+        };
+
+        patterns
+            .iter()
+            .any(|pattern| match Regex::new(&format!("^(?:{})$", pattern)) {
+                Ok(r) => r.is_match(branch),
+                Err(e) => {
+                    log::error!(
+                        "Error parsing skip review branch regex '{}': {}",
+                        pattern,
+                        e
+                    );
+                    false
+                }
+            })
+    }
+
     pub fn fix_versions(&self) -> String {
         if let Some(ref field) = self.fix_versions_field {
             field.clone()
@@ -341,4 +830,56 @@ app_key_file = "some-file.key"
         let config = parse_string(config_str).unwrap();
         assert_eq!("foo", config.slack.bot_token);
     }
+
+    #[test]
+    fn test_octobot_link() {
+        let mut main = ConfigModel::new().main;
+        assert_eq!(None, main.octobot_link("some-org/some-repo"));
+
+        main.base_url = Some("https://octobot.company.com/".into());
+        assert_eq!(None, main.octobot_link("some-org/some-repo"));
+
+        main.include_octobot_link = Some(true);
+        assert_eq!(
+            Some("https://octobot.company.com/#!/repos?needle=some-org/some-repo".to_string()),
+            main.octobot_link("some-org/some-repo")
+        );
+
+        main.base_url = None;
+        assert_eq!(None, main.octobot_link("some-org/some-repo"));
+    }
+
+    #[test]
+    fn test_skips_review_for_branch() {
+        let config_str = r#"
+host = "jira.company.com"
+username = "user"
+password = "pass"
+skip_review_branches = ["integration", "feature/.*"]
+"#;
+        let jira: JiraConfig = toml::from_str(config_str).unwrap();
+        assert!(jira.skips_review_for_branch("integration"));
+        assert!(jira.skips_review_for_branch("feature/foo"));
+        assert!(!jira.skips_review_for_branch("integration-2"));
+        assert!(!jira.skips_review_for_branch("master"));
+    }
+
+    #[test]
+    fn test_github_api_path_prefix() {
+        let config_str = r#"
+webhook_secret = "abcd"
+host = "ghe.company.com"
+api_path_prefix = "/github/"
+"#;
+        let mut github: GithubConfig = toml::from_str(config_str).unwrap();
+        assert_eq!("github", github.api_path_prefix());
+        assert_eq!("ghe.company.com/github", github.prefixed_host());
+        assert_eq!(
+            "https://ghe.company.com/github/api/v3",
+            crate::github::api::api_base(&github.prefixed_host())
+        );
+
+        github.api_path_prefix = None;
+        assert_eq!("ghe.company.com", github.prefixed_host());
+    }
 }
@@ -22,6 +22,12 @@ impl ConfigDatabase {
     pub fn connect(&self) -> Result<Connection> {
         self.db.connect()
     }
+
+    // Runs a trivial query to verify the database is usable
+    pub fn check(&self) -> Result<()> {
+        self.connect()?.query_row("SELECT 1", [], |_| Ok(()))?;
+        Ok(())
+    }
 }
 
 fn migrate(conn: &mut Connection) -> Result<()> {
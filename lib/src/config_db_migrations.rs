@@ -72,6 +72,59 @@ pub fn all_migrations() -> Vec<Box<dyn Migration>> {
         sql(r#"alter table repos add column use_threads tinyint not null default 1"#),
         sql(r#"alter table users add column muted_repos varchar not null default ''"#),
         sql(r#"alter table users add column mute_team_dm tinyint not null default 0"#),
+        sql(r#"alter table repos add column jira_comments_public tinyint not null default 0"#),
+        sql(r#"alter table repos add column default_reviewers varchar not null default ''"#),
+        sql(r#"alter table repos add column notify_commit_comments tinyint not null default 1"#),
+        sql(r#"alter table repos add column jira_check_name varchar not null default ''"#),
+        sql(r#"alter table repos add column suggested_reviewers varchar not null default ''"#),
+        sql(r#"
+    create table repos_branch_jiras (
+        repo_id integer not null,
+        position integer not null,
+        branch_regex varchar not null,
+        jira_projects varchar not null,
+
+        PRIMARY KEY( repo_id, position )
+    );
+    "#),
+        sql(r#"alter table repos add column backport_authors varchar not null default ''"#),
+        sql(r#"alter table repos add column skip_reviewer_refetch tinyint not null default 0"#),
+        sql(r#"alter table repos add column discussions_channel varchar not null default ''"#),
+        sql(
+            r#"alter table repos add column version_script_min_commits integer not null default 1"#,
+        ),
+        sql(r#"alter table repos add column jira_check_title varchar not null default ''"#),
+        sql(r#"alter table repos add column jira_check_summary varchar not null default ''"#),
+        sql(r#"
+    create table repos_path_labels (
+        repo_id integer not null,
+        position integer not null,
+        path_glob varchar not null,
+        labels varchar not null,
+
+        PRIMARY KEY( repo_id, position )
+    );
+    "#),
+        sql(
+            r#"alter table repos add column version_script_mode varchar not null default 'script'"#,
+        ),
+        sql(r#"alter table repos add column frozen_branches varchar not null default ''"#),
+        sql(r#"alter table repos add column message_templates varchar not null default ''"#),
+        sql(r#"alter table repos add column merge_strategy varchar not null default 'merge'"#),
+        sql(r#"alter table users add column out_of_office tinyint not null default 0"#),
+        sql(r#"alter table users add column backup_reviewer varchar not null default ''"#),
+        sql(r#"alter table repos add column channel_overrides varchar not null default ''"#),
+        sql(r#"alter table repos add column version_script_paths varchar not null default ''"#),
+        sql(r#"alter table repos add column stale_pr_days integer"#),
+        sql(r#"alter table repos add column backport_label_prefix varchar"#),
+        sql(r#"alter table repos add column ignored_users varchar not null default ''"#),
+        sql(r#"alter table repos add column ignored_users_mode varchar not null default 'merge'"#),
+        sql(r#"alter table repos add column merged_channel_only tinyint not null default 0"#),
+        sql(r#"alter table repos add column reviewer_pool varchar not null default ''"#),
+        sql(r#"alter table repos add column auto_assign_count integer"#),
+        sql(r#"alter table repos add column reviewer_pool_cursor integer not null default 0"#),
+        sql(r#"alter table repos add column max_commits_for_jira integer"#),
+        sql(r#"alter table repos add column notify_events varchar not null default ''"#),
     ]
 }
 
@@ -0,0 +1,66 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+tokio::task_local! {
+    static CORRELATION_ID: String;
+}
+
+// Runs `fut` with the given correlation id visible to `current()`, e.g. so that log lines
+// emitted while handling a webhook can be tied back to its delivery.
+pub async fn scope<F: Future>(id: Option<String>, fut: F) -> F::Output {
+    match id {
+        Some(id) => CORRELATION_ID.scope(id, fut).await,
+        None => fut.await,
+    }
+}
+
+// Whether log lines should be tagged with the correlation id
+static LOGGED: AtomicBool = AtomicBool::new(false);
+
+// The correlation id of the task currently running, if any.
+pub fn current() -> Option<String> {
+    CORRELATION_ID.try_with(|id| id.clone()).ok()
+}
+
+pub fn set_logged(logged: bool) {
+    LOGGED.store(logged, Ordering::Relaxed);
+}
+
+// The correlation id to tag log lines with, if enabled.
+pub fn logged() -> Option<String> {
+    if LOGGED.load(Ordering::Relaxed) {
+        current()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scope() {
+        assert_eq!(None, current());
+
+        let inner = scope(Some("abc-123".into()), async { current() }).await;
+        assert_eq!(Some("abc-123".to_string()), inner);
+
+        assert_eq!(None, scope(None, async { current() }).await);
+        assert_eq!(None, current());
+    }
+
+    #[tokio::test]
+    async fn test_logged() {
+        let id = Some("abc-123".to_string());
+
+        assert_eq!(None, scope(id.clone(), async { logged() }).await);
+
+        set_logged(true);
+        assert_eq!(id, scope(id.clone(), async { logged() }).await);
+        assert_eq!(None, logged());
+
+        set_logged(false);
+        assert_eq!(None, scope(id.clone(), async { logged() }).await);
+    }
+}
@@ -1,16 +1,20 @@
-use std::sync::Arc;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
 
 use anyhow::{anyhow, bail};
 use async_trait::async_trait;
 use log::{error, info};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use regex::Regex;
 use serde_derive::{Deserialize, Serialize};
 use std::collections::HashMap;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
 
 use crate::errors::*;
 use crate::github::models::*;
 use crate::github::models_checks::*;
-use crate::http_client::HTTPClient;
+use crate::http_client::{self, HTTPClient, TokenRefresher};
 use crate::jwt;
 use crate::metrics::Metrics;
 
@@ -62,6 +66,14 @@ pub trait Session: Send + Sync {
         labels: Vec<String>,
     ) -> Result<()>;
 
+    async fn remove_pull_request_label(
+        &self,
+        owner: &str,
+        repo: &str,
+        number: u32,
+        label: &str,
+    ) -> Result<()>;
+
     async fn get_pull_request_commits(
         &self,
         owner: &str,
@@ -69,6 +81,22 @@ pub trait Session: Send + Sync {
         number: u32,
     ) -> Result<Vec<Commit>>;
 
+    async fn get_pull_request_files(
+        &self,
+        owner: &str,
+        repo: &str,
+        number: u32,
+    ) -> Result<Vec<PullRequestFile>>;
+
+    // Files changed between two commits
+    async fn get_compare_files(
+        &self,
+        owner: &str,
+        repo: &str,
+        base: &str,
+        head: &str,
+    ) -> Result<Vec<PullRequestFile>>;
+
     async fn get_pull_request_reviews(
         &self,
         owner: &str,
@@ -124,12 +152,18 @@ pub trait Session: Send + Sync {
 
     // checks api
     async fn get_suites(&self, pr: &PullRequest) -> Result<Vec<CheckSuite>>;
-    async fn get_check_run(&self, pr: &PullRequest, id: u32) -> Result<CheckRun>;
-    async fn create_check_run(&self, pr: &PullRequest, run: &CheckRun) -> Result<u32>;
+    async fn get_check_run(&self, pr: &PullRequest, id: u64) -> Result<CheckRun>;
+    async fn get_check_runs(
+        &self,
+        pr: &PullRequest,
+        commit_sha: &str,
+        name: &str,
+    ) -> Result<Vec<CheckRun>>;
+    async fn create_check_run(&self, pr: &PullRequest, run: &CheckRun) -> Result<u64>;
     async fn update_check_run(
         &self,
         pr: &PullRequest,
-        check_run_id: u32,
+        check_run_id: u64,
         run: &CheckRun,
     ) -> Result<()>;
     async fn get_team_members(&self, repo: &Repo, id: u32) -> Result<Vec<User>>;
@@ -141,6 +175,9 @@ pub trait Session: Send + Sync {
         max_count: usize,
     ) -> Result<Vec<WebhookDelivery>>;
     async fn redeliver_webhook(&self, id: u32) -> Result<()>;
+
+    // A cheap authenticated request to verify connectivity
+    async fn ping(&self) -> Result<()>;
 }
 
 #[async_trait]
@@ -152,6 +189,8 @@ pub trait GithubSessionFactory: Send + Sync {
     fn bot_name(&self) -> String;
 }
 
+// `host` may include the path a GitHub Enterprise install is served under, e.g.
+// "ghe.company.com/github"
 pub fn api_base(host: &str) -> String {
     if host == "github.com" {
         "https://api.github.com".to_string()
@@ -161,12 +200,67 @@ pub fn api_base(host: &str) -> String {
 }
 
 pub struct GithubApp {
+    auth: Arc<AppAuth>,
+    app: Option<App>,
+}
+
+// What a GithubApp needs to mint installation tokens, shared with its sessions so that they
+// can refresh their token
+struct AppAuth {
     host: String,
     app_id: u32,
     // DER formatted API private key
     app_key: Vec<u8>,
-    app: Option<App>,
     metrics: Option<Arc<Metrics>>,
+    extra_headers: HashMap<String, String>,
+    tokens: TokenCache,
+}
+
+// Refresh installation tokens this long before they expire so they don't expire mid-request
+const TOKEN_REFRESH_WINDOW: time::Duration = time::Duration::minutes(5);
+
+struct CachedToken {
+    token: String,
+    expires_at: OffsetDateTime,
+}
+
+// Installation tokens by installation path. The lock is held while minting so that concurrent
+// sessions wait for one new token instead of each minting their own.
+#[derive(Default)]
+pub struct TokenCache {
+    tokens: tokio::sync::Mutex<HashMap<String, CachedToken>>,
+}
+
+impl TokenCache {
+    pub fn new() -> TokenCache {
+        TokenCache::default()
+    }
+
+    // The cached token for `key`, unless it is close to expiring or was `rejected`, in which
+    // case `mint` provides a new one (and its expiry)
+    pub async fn get<F, Fut>(&self, key: &str, rejected: Option<&str>, mint: F) -> Result<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(String, OffsetDateTime)>>,
+    {
+        let mut tokens = self.tokens.lock().await;
+        if let Some(cached) = tokens.get(key) {
+            let fresh = cached.expires_at - TOKEN_REFRESH_WINDOW > OffsetDateTime::now_utc();
+            if fresh && Some(cached.token.as_str()) != rejected {
+                return Ok(cached.token.clone());
+            }
+        }
+
+        let (token, expires_at) = mint().await?;
+        tokens.insert(
+            key.into(),
+            CachedToken {
+                token: token.clone(),
+                expires_at,
+            },
+        );
+        Ok(token)
+    }
 }
 
 pub struct GithubOauthApp {
@@ -174,6 +268,7 @@ pub struct GithubOauthApp {
     api_token: String,
     user: Option<User>,
     metrics: Option<Arc<Metrics>>,
+    extra_headers: HashMap<String, String>,
 }
 
 impl GithubApp {
@@ -182,17 +277,23 @@ impl GithubApp {
         app_id: u32,
         app_key: &[u8],
         metrics: Option<Arc<Metrics>>,
+        extra_headers: HashMap<String, String>,
     ) -> Result<GithubApp> {
         let mut github = GithubApp {
-            host: host.into(),
-            app_id,
-            app_key: app_key.into(),
+            auth: Arc::new(AppAuth {
+                host: host.into(),
+                app_id,
+                app_key: app_key.into(),
+                metrics,
+                extra_headers,
+                tokens: TokenCache::new(),
+            }),
             app: None,
-            metrics,
         };
 
         github.app = Some(
             github
+                .auth
                 .new_client()?
                 .get("/app")
                 .await
@@ -203,7 +304,9 @@ impl GithubApp {
 
         Ok(github)
     }
+}
 
+impl AppAuth {
     fn new_client(&self) -> Result<HTTPClient> {
         let jwt_token = jwt::new_token(self.app_id, &self.app_key);
 
@@ -216,6 +319,7 @@ impl GithubApp {
             reqwest::header::AUTHORIZATION,
             format!("Bearer {}", jwt_token).parse().unwrap(),
         );
+        http_client::add_extra_headers(&mut headers, &self.extra_headers)?;
 
         let client = HTTPClient::new_with_headers(&api_base(&self.host), headers)?;
         if let Some(ref m) = self.metrics {
@@ -228,7 +332,19 @@ impl GithubApp {
         }
     }
 
-    async fn new_token(&self, installation_url: &str) -> Result<String> {
+    async fn installation_token(
+        &self,
+        installation_url: &str,
+        rejected: Option<&str>,
+    ) -> Result<String> {
+        self.tokens
+            .get(installation_url, rejected, || {
+                self.new_token(installation_url)
+            })
+            .await
+    }
+
+    async fn new_token(&self, installation_url: &str) -> Result<(String, OffsetDateTime)> {
         let client = self.new_client()?;
 
         // All we care about for now is the installation id
@@ -240,6 +356,7 @@ impl GithubApp {
         #[derive(Deserialize)]
         struct AccessToken {
             token: String,
+            expires_at: Option<String>,
         }
 
         let installation: Installation = client.get(installation_url).await?;
@@ -251,7 +368,33 @@ impl GithubApp {
                 &String::new(),
             )
             .await?;
-        Ok(token.token)
+
+        // installation tokens last an hour
+        let expires_at = token
+            .expires_at
+            .and_then(|e| OffsetDateTime::parse(&e, &Rfc3339).ok())
+            .unwrap_or_else(|| OffsetDateTime::now_utc() + time::Duration::hours(1));
+        Ok((token.token, expires_at))
+    }
+}
+
+// Gives a session a new installation token when GitHub rejects its current one
+struct InstallationTokenRefresher {
+    auth: Arc<AppAuth>,
+    installation_url: String,
+    token: Mutex<String>,
+}
+
+#[async_trait]
+impl TokenRefresher for InstallationTokenRefresher {
+    async fn refresh(&self) -> Result<String> {
+        let rejected = self.token.lock().unwrap().clone();
+        let token = self
+            .auth
+            .installation_token(&self.installation_url, Some(&rejected))
+            .await?;
+        *self.token.lock().unwrap() = token.clone();
+        Ok(format!("Bearer {}", token))
     }
 }
 
@@ -265,32 +408,49 @@ impl GithubSessionFactory for GithubApp {
     }
 
     async fn get_token_org(&self, org: &str) -> Result<String> {
-        self.new_token(&format!("/orgs/{}/installation", org)).await
+        self.auth
+            .installation_token(&format!("/orgs/{}/installation", org), None)
+            .await
     }
 
     async fn get_token_repo(&self, owner: &str, repo: &str) -> Result<String> {
-        self.new_token(&format!("/repos/{}/{}/installation", owner, repo))
+        self.auth
+            .installation_token(&format!("/repos/{}/{}/installation", owner, repo), None)
             .await
     }
 
     async fn new_session(&self, owner: &str, repo: &str) -> Result<GithubSession> {
-        GithubSession::new(
-            &self.host,
+        let installation_url = format!("/repos/{}/{}/installation", owner, repo);
+        let token = self
+            .auth
+            .installation_token(&installation_url, None)
+            .await?;
+        let refresher = InstallationTokenRefresher {
+            auth: self.auth.clone(),
+            installation_url,
+            token: Mutex::new(token.clone()),
+        };
+
+        Ok(GithubSession::new(
+            &self.auth.host,
             &self.bot_name(),
-            &self.get_token_repo(owner, repo).await?,
-            Some(self.app_id),
-            self.metrics.clone(),
-        )
+            &token,
+            Some(self.auth.app_id),
+            self.auth.metrics.clone(),
+            &self.auth.extra_headers,
+        )?
+        .with_token_refresher(Arc::new(refresher)))
     }
 
     async fn new_service_session(&self) -> Result<GithubSession> {
-        let jwt_token = jwt::new_token(self.app_id, &self.app_key);
+        let jwt_token = jwt::new_token(self.auth.app_id, &self.auth.app_key);
         GithubSession::new(
-            &self.host,
+            &self.auth.host,
             &self.bot_name(),
             &jwt_token,
-            Some(self.app_id),
-            self.metrics.clone(),
+            Some(self.auth.app_id),
+            self.auth.metrics.clone(),
+            &self.auth.extra_headers,
         )
     }
 }
@@ -300,12 +460,14 @@ impl GithubOauthApp {
         host: &str,
         api_token: &str,
         metrics: Option<Arc<Metrics>>,
+        extra_headers: HashMap<String, String>,
     ) -> Result<GithubOauthApp> {
         let mut github = GithubOauthApp {
             host: host.into(),
             api_token: api_token.into(),
             user: None,
             metrics,
+            extra_headers,
         };
 
         github.user = Some(
@@ -348,6 +510,7 @@ impl GithubSessionFactory for GithubOauthApp {
             &self.api_token,
             None,
             self.metrics.clone(),
+            &self.extra_headers,
         )
     }
 
@@ -371,6 +534,7 @@ impl GithubSession {
         token: &str,
         app_id: Option<u32>,
         metrics: Option<Arc<Metrics>>,
+        extra_headers: &HashMap<String, String>,
     ) -> Result<GithubSession> {
         let mut headers = reqwest::header::HeaderMap::new();
 
@@ -394,6 +558,7 @@ impl GithubSession {
             reqwest::header::AUTHORIZATION,
             format!("Bearer {}", token).parse().unwrap(),
         );
+        http_client::add_extra_headers(&mut headers, extra_headers)?;
 
         let client = HTTPClient::new_with_headers(&api_base(host), headers)?;
         let client = match metrics {
@@ -413,6 +578,11 @@ impl GithubSession {
         })
     }
 
+    pub fn with_token_refresher(mut self, refresher: Arc<dyn TokenRefresher>) -> Self {
+        self.client = self.client.with_token_refresher(refresher);
+        self
+    }
+
     async fn do_get_pull_requests(
         &self,
         owner: &str,
@@ -621,6 +791,33 @@ impl Session for GithubSession {
             .map_err(|e| anyhow!("Error adding label: {}/{} #{}: {}", owner, repo, number, e))
     }
 
+    async fn remove_pull_request_label(
+        &self,
+        owner: &str,
+        repo: &str,
+        number: u32,
+        label: &str,
+    ) -> Result<()> {
+        self.client
+            .delete_void(&format!(
+                "repos/{}/{}/issues/{}/labels/{}",
+                owner,
+                repo,
+                number,
+                utf8_percent_encode(label, NON_ALPHANUMERIC)
+            ))
+            .await
+            .map_err(|e| {
+                anyhow!(
+                    "Error removing label: {}/{} #{}: {}",
+                    owner,
+                    repo,
+                    number,
+                    e
+                )
+            })
+    }
+
     async fn get_pull_request_commits(
         &self,
         owner: &str,
@@ -659,6 +856,72 @@ impl Session for GithubSession {
         Ok(result)
     }
 
+    async fn get_pull_request_files(
+        &self,
+        owner: &str,
+        repo: &str,
+        number: u32,
+    ) -> Result<Vec<PullRequestFile>> {
+        let mut result = vec![];
+        let mut page = 1;
+
+        loop {
+            let next: Vec<PullRequestFile> = self
+                .client
+                .get(&format!(
+                    "repos/{}/{}/pulls/{}/files?per_page=100&page={}",
+                    owner, repo, number, page,
+                ))
+                .await
+                .map_err(|e| {
+                    anyhow!(
+                        "Error looking up PR files: {}/{} #{}: {}",
+                        owner,
+                        repo,
+                        number,
+                        e
+                    )
+                })?;
+
+            if next.is_empty() {
+                break;
+            }
+
+            result.extend(next);
+            page += 1;
+        }
+
+        Ok(result)
+    }
+
+    async fn get_compare_files(
+        &self,
+        owner: &str,
+        repo: &str,
+        base: &str,
+        head: &str,
+    ) -> Result<Vec<PullRequestFile>> {
+        let comparison: Comparison = self
+            .client
+            .get(&format!(
+                "repos/{}/{}/compare/{}...{}",
+                owner, repo, base, head
+            ))
+            .await
+            .map_err(|e| {
+                anyhow!(
+                    "Error comparing commits: {}/{} {}...{}: {}",
+                    owner,
+                    repo,
+                    base,
+                    head,
+                    e
+                )
+            })?;
+
+        Ok(comparison.files)
+    }
+
     async fn get_pull_request_reviews(
         &self,
         owner: &str,
@@ -923,7 +1186,7 @@ impl Session for GithubSession {
             })
     }
 
-    async fn get_check_run(&self, pr: &PullRequest, id: u32) -> Result<CheckRun> {
+    async fn get_check_run(&self, pr: &PullRequest, id: u64) -> Result<CheckRun> {
         self.client
             .get(&format!(
                 "/repos/{}/check-runs/{}",
@@ -940,10 +1203,40 @@ impl Session for GithubSession {
             })
     }
 
-    async fn create_check_run(&self, pr: &PullRequest, run: &CheckRun) -> Result<u32> {
+    async fn get_check_runs(
+        &self,
+        pr: &PullRequest,
+        commit_sha: &str,
+        name: &str,
+    ) -> Result<Vec<CheckRun>> {
+        let mut url = format!(
+            "/repos/{}/commits/{}/check-runs?check_name={}",
+            pr.base.repo.full_name,
+            commit_sha,
+            utf8_percent_encode(name, NON_ALPHANUMERIC),
+        );
+        if let Some(app_id) = self.app_id {
+            url += &format!("&app_id={}", app_id);
+        }
+
+        self.client
+            .get::<CheckRunList>(&url)
+            .await
+            .map(|list| list.check_runs)
+            .map_err(|e| {
+                anyhow!(
+                    "Error getting check runs for {} {}: {}",
+                    pr.base.repo.full_name,
+                    commit_sha,
+                    e
+                )
+            })
+    }
+
+    async fn create_check_run(&self, pr: &PullRequest, run: &CheckRun) -> Result<u64> {
         #[derive(Deserialize, Serialize, Clone, Debug)]
         pub struct Resp {
-            pub id: u32,
+            pub id: u64,
         }
 
         self.client
@@ -965,7 +1258,7 @@ impl Session for GithubSession {
     async fn update_check_run(
         &self,
         pr: &PullRequest,
-        check_run_id: u32,
+        check_run_id: u64,
         run: &CheckRun,
     ) -> Result<()> {
         self.client
@@ -1064,6 +1357,15 @@ impl Session for GithubSession {
             .post_void_opt(&format!("/app/hook/deliveries/{}/attempts", id), body)
             .await
     }
+
+    async fn ping(&self) -> Result<()> {
+        // doesn't count against the rate limit
+        self.client
+            .get::<serde_json::Value>("/rate_limit")
+            .await
+            .map(|_| ())
+            .map_err(|e| anyhow!("Error pinging GitHub: {}", e))
+    }
 }
 
 fn parse_link_header(value: &str) -> HashMap<String, String> {
@@ -1098,6 +1400,76 @@ fn parse_next_cursor(res: &reqwest::Response) -> Option<String> {
 mod tests {
     use super::*;
 
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Mints "token-N" tokens expiring after `lifetime`, counting how many were minted
+    async fn get_token(
+        cache: &TokenCache,
+        minted: &AtomicUsize,
+        lifetime: time::Duration,
+        rejected: Option<&str>,
+    ) -> String {
+        cache
+            .get(
+                "/repos/some-user/some-repo/installation",
+                rejected,
+                || async {
+                    let n = minted.fetch_add(1, Ordering::SeqCst) + 1;
+                    Ok((format!("token-{}", n), OffsetDateTime::now_utc() + lifetime))
+                },
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_token_cache_reuses_token() {
+        let cache = TokenCache::new();
+        let minted = AtomicUsize::new(0);
+        let hour = time::Duration::hours(1);
+
+        assert_eq!("token-1", get_token(&cache, &minted, hour, None).await);
+        assert_eq!("token-1", get_token(&cache, &minted, hour, None).await);
+        assert_eq!(1, minted.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_token_cache_refreshes_before_expiry() {
+        let cache = TokenCache::new();
+        let minted = AtomicUsize::new(0);
+
+        // still valid for a couple of minutes, but inside the refresh window
+        let near_expiry = time::Duration::minutes(2);
+        assert_eq!(
+            "token-1",
+            get_token(&cache, &minted, near_expiry, None).await
+        );
+        assert_eq!(
+            "token-2",
+            get_token(&cache, &minted, near_expiry, None).await
+        );
+        assert_eq!(2, minted.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_token_cache_refreshes_rejected_token() {
+        let cache = TokenCache::new();
+        let minted = AtomicUsize::new(0);
+        let hour = time::Duration::hours(1);
+
+        assert_eq!("token-1", get_token(&cache, &minted, hour, None).await);
+        assert_eq!(
+            "token-2",
+            get_token(&cache, &minted, hour, Some("token-1")).await
+        );
+        // a second session rejecting the old token reuses the refreshed one
+        assert_eq!(
+            "token-2",
+            get_token(&cache, &minted, hour, Some("token-1")).await
+        );
+        assert_eq!(2, minted.load(Ordering::SeqCst));
+    }
+
     #[test]
     fn test_parse_link() {
         let link1 = r#"<https://git.example.com/?per_page=1&cursor=A>; rel="next""#;
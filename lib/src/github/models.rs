@@ -1,13 +1,44 @@
+use std::time::SystemTime;
+
 use anyhow::anyhow;
+use regex::Regex;
 use serde_derive::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
 use url::Url;
 
 use crate::errors::*;
+use crate::github::models_checks::CheckRun;
 
 pub fn is_main_branch(branch_name: &str) -> bool {
     branch_name == "master" || branch_name == "develop" || branch_name == "main"
 }
 
+const PR_SIZES: &[&str] = &["XS", "S", "M", "L", "XL"];
+
+// T-shirt size for a number of changed lines. Each threshold is the (inclusive) upper bound of
+// the next smaller size: e.g. [10, 100] gives XS for up to 10 lines, S up to 100, and M beyond.
+pub fn pr_size(changed_lines: u32, thresholds: &[u32]) -> &'static str {
+    let index = thresholds
+        .iter()
+        .take(PR_SIZES.len() - 1)
+        .take_while(|t| changed_lines > **t)
+        .count();
+    PR_SIZES[index]
+}
+
+// Whether a commit message merges a release branch, e.g. "Merge branch 'release/1.0' into release/1.1"
+// or "Merge pull request #5 from some-org/release/1.0"
+pub fn is_release_merge(message: &str, release_branch_prefix: &str) -> bool {
+    let prefix = regex::escape(release_branch_prefix);
+    let re = Regex::new(&format!(
+        r"^Merge (?:remote-tracking )?branch '(?:origin/)?{}[^']+'|^Merge pull request #[0-9]+ from [^/\s]+/{}\S+",
+        prefix, prefix
+    ))
+    .unwrap();
+    re.is_match(message.lines().next().unwrap_or(""))
+}
+
 // An incomplete container for all the kinds of events that we care about.
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct HookBody {
@@ -20,6 +51,24 @@ pub struct HookBody {
     pub pull_request: Option<PullRequest>,
     pub review: Option<Review>,
     pub label: Option<Label>,
+    pub milestone: Option<Milestone>,
+    pub thread: Option<ReviewThread>,
+    pub discussion: Option<Discussion>,
+    pub workflow_run: Option<WorkflowRun>,
+    pub check_run: Option<CheckRun>,
+    pub alert: Option<SecretScanningAlert>,
+    pub release: Option<Release>,
+    pub deployment_status: Option<DeploymentStatus>,
+
+    // pull_request edited event related stuff
+    pub changes: Option<Changes>,
+
+    // ping event related stuff
+    pub hook: Option<Hook>,
+
+    // installation_repositories event related stuff
+    pub repositories_added: Option<Vec<InstallationRepository>>,
+    pub repositories_removed: Option<Vec<InstallationRepository>>,
 
     // push event related stuff
     #[serde(rename = "ref")]
@@ -44,6 +93,18 @@ impl HookBody {
             pull_request: None,
             review: None,
             label: None,
+            milestone: None,
+            thread: None,
+            discussion: None,
+            workflow_run: None,
+            check_run: None,
+            alert: None,
+            release: None,
+            deployment_status: None,
+            changes: None,
+            hook: None,
+            repositories_added: None,
+            repositories_removed: None,
             ref_name: None,
             after: None,
             before: None,
@@ -157,6 +218,7 @@ pub struct Repo {
     pub name: String,
     pub owner: User,
     pub archived: Option<bool>,
+    pub default_branch: Option<String>,
 }
 
 impl Repo {
@@ -167,6 +229,7 @@ impl Repo {
             name: String::new(),
             owner: User::new(""),
             archived: Some(false),
+            default_branch: None,
         }
     }
 
@@ -189,12 +252,26 @@ impl Repo {
             name: repo.to_string(),
             owner: User::new(user),
             archived: Some(false),
+            default_branch: None,
         })
     }
 
     pub fn archived(&self) -> bool {
         self.archived.unwrap_or(false)
     }
+
+    pub fn commit_url(&self, sha: &str) -> String {
+        format!("{}/commit/{}", self.html_url.trim_end_matches('/'), sha)
+    }
+
+    pub fn compare_url(&self, base: &str, head: &str) -> String {
+        format!(
+            "{}/compare/{}...{}",
+            self.html_url.trim_end_matches('/'),
+            base,
+            head
+        )
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -245,6 +322,11 @@ pub struct PullRequest {
     pub requested_teams: Option<Vec<Team>>,
     pub reviews: Option<Vec<Review>>,
     pub draft: Option<bool>,
+    pub labels: Option<Vec<Label>>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub additions: Option<u32>,
+    pub deletions: Option<u32>,
 }
 
 impl PullRequest {
@@ -265,9 +347,33 @@ impl PullRequest {
             head: BranchRef::new(""),
             base: BranchRef::new(""),
             draft: None,
+            labels: None,
+            created_at: None,
+            updated_at: None,
+            additions: None,
+            deletions: None,
         }
     }
 
+    // Lines added plus lines deleted, if known
+    pub fn changed_lines(&self) -> Option<u32> {
+        Some(self.additions? + self.deletions?)
+    }
+
+    pub fn created_time(&self) -> Option<SystemTime> {
+        let created_at = self.created_at.as_ref()?;
+        OffsetDateTime::parse(created_at, &Rfc3339)
+            .ok()
+            .map(SystemTime::from)
+    }
+
+    pub fn updated_time(&self) -> Option<SystemTime> {
+        let updated_at = self.updated_at.as_ref()?;
+        OffsetDateTime::parse(updated_at, &Rfc3339)
+            .ok()
+            .map(SystemTime::from)
+    }
+
     pub fn is_merged(&self) -> bool {
         self.merged.unwrap_or(false)
     }
@@ -276,6 +382,16 @@ impl PullRequest {
         self.draft.unwrap_or(false) || self.title.to_lowercase().starts_with("wip:")
     }
 
+    // GitHub's "Revert" button titles PRs `Revert "..."` and starts the body with "Reverts ..."
+    pub fn is_revert(&self) -> bool {
+        let title = self.title.trim_start();
+        let body = self.body.as_deref().unwrap_or("").trim_start();
+        title.starts_with("Revert ")
+            || title.starts_with("Revert\"")
+            || body.starts_with("Reverts ")
+            || body.contains("This reverts commit")
+    }
+
     pub fn all_reviewers(&self) -> Vec<User> {
         let mut reviewers = vec![];
         if let Some(ref requested_reviewers) = self.requested_reviewers {
@@ -389,6 +505,312 @@ impl Label {
     }
 }
 
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct PullRequestFile {
+    pub filename: String,
+}
+
+impl PullRequestFile {
+    pub fn new(filename: &str) -> PullRequestFile {
+        PullRequestFile {
+            filename: filename.into(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Comparison {
+    #[serde(default)]
+    pub files: Vec<PullRequestFile>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Milestone {
+    pub title: String,
+}
+
+impl Milestone {
+    pub fn new(title: &str) -> Milestone {
+        Milestone {
+            title: title.into(),
+        }
+    }
+}
+
+// A review conversation, from pull_request_review_thread events
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ReviewThread {
+    pub node_id: String,
+}
+
+impl ReviewThread {
+    pub fn new(node_id: &str) -> ReviewThread {
+        ReviewThread {
+            node_id: node_id.into(),
+        }
+    }
+}
+
+// A secret scanning alert, from secret_scanning_alert events. The secret itself is deliberately
+// not deserialized so that it can't leak into notifications or logs.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct SecretScanningAlert {
+    pub number: u32,
+    pub html_url: String,
+    #[serde(default)]
+    pub secret_type: String,
+    pub secret_type_display_name: Option<String>,
+}
+
+impl SecretScanningAlert {
+    pub fn new(number: u32, secret_type: &str) -> SecretScanningAlert {
+        SecretScanningAlert {
+            number,
+            html_url: String::new(),
+            secret_type: secret_type.into(),
+            secret_type_display_name: None,
+        }
+    }
+
+    pub fn secret_type_name(&self) -> &str {
+        match self.secret_type_display_name {
+            Some(ref n) if !n.is_empty() => n,
+            _ => &self.secret_type,
+        }
+    }
+}
+
+// The status of a GitHub deployment, from deployment_status events
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct DeploymentStatus {
+    // One of "success", "failure", "error", "pending", "in_progress", "queued" or "inactive"
+    pub state: String,
+    pub environment: String,
+    pub description: Option<String>,
+    pub target_url: Option<String>,
+}
+
+impl DeploymentStatus {
+    pub fn new(state: &str, environment: &str) -> DeploymentStatus {
+        DeploymentStatus {
+            state: state.into(),
+            environment: environment.into(),
+            description: None,
+            target_url: None,
+        }
+    }
+}
+
+// A GitHub release, from release events
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Release {
+    pub tag_name: String,
+    pub name: Option<String>,
+    pub body: Option<String>,
+    pub html_url: String,
+    #[serde(default)]
+    pub prerelease: bool,
+    pub target_commitish: Option<String>,
+}
+
+impl Release {
+    pub fn new(tag_name: &str) -> Release {
+        Release {
+            tag_name: tag_name.into(),
+            name: None,
+            body: None,
+            html_url: String::new(),
+            prerelease: false,
+            target_commitish: None,
+        }
+    }
+
+    // The release name, falling back to the tag
+    pub fn display_name(&self) -> &str {
+        match self.name {
+            Some(ref n) if !n.is_empty() => n,
+            _ => &self.tag_name,
+        }
+    }
+
+    // The version this release is for: its tag, minus any leading "v" (e.g. "v1.2.3" => "1.2.3")
+    pub fn version(&self) -> &str {
+        match self.tag_name.strip_prefix('v') {
+            Some(v) if v.starts_with(|c: char| c.is_ascii_digit()) => v,
+            _ => &self.tag_name,
+        }
+    }
+
+    // The branch the release was cut from. None if unknown, or if it was cut from a commit SHA.
+    pub fn target_branch(&self) -> Option<&str> {
+        match self.target_commitish {
+            Some(ref t) if t.len() == 40 && t.chars().all(|c| c.is_ascii_hexdigit()) => None,
+            Some(ref t) if !t.is_empty() => Some(t),
+            _ => None,
+        }
+    }
+}
+
+// A GitHub Actions workflow run, from workflow_run events
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct WorkflowRun {
+    pub name: String,
+    pub html_url: String,
+    pub head_branch: Option<String>,
+    pub head_sha: String,
+    pub conclusion: Option<String>,
+    #[serde(default)]
+    pub pull_requests: Vec<WorkflowRunPullRequest>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct WorkflowRunPullRequest {
+    pub number: u32,
+    pub base: WorkflowRunBranch,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct WorkflowRunBranch {
+    #[serde(rename = "ref")]
+    pub ref_name: String,
+}
+
+impl WorkflowRunPullRequest {
+    pub fn new(number: u32, base: &str) -> WorkflowRunPullRequest {
+        WorkflowRunPullRequest {
+            number,
+            base: WorkflowRunBranch {
+                ref_name: base.into(),
+            },
+        }
+    }
+}
+
+impl WorkflowRun {
+    pub fn new(name: &str) -> WorkflowRun {
+        WorkflowRun {
+            name: name.into(),
+            html_url: String::new(),
+            head_branch: None,
+            head_sha: String::new(),
+            conclusion: None,
+            pull_requests: vec![],
+        }
+    }
+
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            self.conclusion.as_deref(),
+            Some("failure") | Some("timed_out")
+        )
+    }
+}
+
+// A repository added to or removed from the GitHub App installation
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct InstallationRepository {
+    pub full_name: String,
+}
+
+impl InstallationRepository {
+    pub fn new(full_name: &str) -> InstallationRepository {
+        InstallationRepository {
+            full_name: full_name.into(),
+        }
+    }
+}
+
+// The previous values of fields changed by an edit
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Changes {
+    pub base: Option<BaseChange>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct BaseChange {
+    #[serde(rename = "ref")]
+    pub ref_name: ChangedFrom,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ChangedFrom {
+    pub from: String,
+}
+
+impl Changes {
+    pub fn previous_base(&self) -> Option<&str> {
+        self.base.as_ref().map(|b| b.ref_name.from.as_str())
+    }
+}
+
+// The webhook configuration, from ping events
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Hook {
+    pub id: u64,
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+impl Hook {
+    pub fn new(id: u64, events: &[&str]) -> Hook {
+        Hook {
+            id,
+            events: events.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+
+    // The required events this webhook is not subscribed to
+    pub fn missing_events(&self, required: &[&str]) -> Vec<String> {
+        if self.events.iter().any(|e| e == "*") {
+            return vec![];
+        }
+        required
+            .iter()
+            .filter(|r| !self.events.iter().any(|e| e == *r))
+            .map(|r| r.to_string())
+            .collect()
+    }
+}
+
+// A GitHub Discussion, from discussion and discussion_comment events
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Discussion {
+    pub number: u32,
+    pub title: String,
+    pub body: Option<String>,
+    pub html_url: String,
+    pub user: User,
+}
+
+impl Discussion {
+    pub fn new(number: u32, title: &str) -> Discussion {
+        Discussion {
+            number,
+            title: title.into(),
+            body: None,
+            html_url: String::new(),
+            user: User::new(""),
+        }
+    }
+}
+
+impl CommentLike for &Discussion {
+    fn body(&self) -> &str {
+        match self.body {
+            Some(ref body) => body,
+            None => "",
+        }
+    }
+
+    fn user(&self) -> &User {
+        &self.user
+    }
+
+    fn html_url(&self) -> &str {
+        &self.html_url
+    }
+}
+
 pub trait CommentLike: Send + Sync {
     fn user(&self) -> &User;
     fn body(&self) -> &str;
@@ -438,6 +860,8 @@ pub struct Comment {
     pub body: Option<String>,
     pub html_url: String,
     pub user: User,
+    // set for replies to PR review comments
+    pub in_reply_to_id: Option<u64>,
 }
 
 impl<'a> CommentLike for &'a Comment {
@@ -728,6 +1152,7 @@ pub struct WebhookDelivery {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::github::models_checks::*;
 
     #[test]
     fn test_repo_parse() {
@@ -739,6 +1164,29 @@ mod tests {
         assert_eq!("users", repo.owner.login());
     }
 
+    #[test]
+    fn test_repo_links_with_path_prefix() {
+        // webhook payloads from a GitHub served under a path include it in the repo's html_url
+        let repo: Repo = serde_json::from_str(
+            r#"{
+                "html_url": "https://ghe.company.com/github/users/repo",
+                "full_name": "users/repo",
+                "name": "repo",
+                "owner": {"id": 1, "login": "users"}
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            "https://ghe.company.com/github/users/repo/commit/abcdef00001111",
+            repo.commit_url("abcdef00001111")
+        );
+        assert_eq!(
+            "https://ghe.company.com/github/users/repo/compare/release/1.0...main",
+            repo.compare_url("release/1.0", "main")
+        );
+    }
+
     #[test]
     #[should_panic]
     fn test_repo_parse_no_repo() {
@@ -914,4 +1362,220 @@ mod tests {
         pr.draft = Some(true);
         assert!(pr.is_draft());
     }
+
+    #[test]
+    fn test_pr_is_revert() {
+        let mut pr = PullRequest::new();
+        pr.title = "Fix the thing".into();
+        assert!(!pr.is_revert());
+
+        pr.title = "Revert \"Fix the thing\"".into();
+        assert!(pr.is_revert());
+
+        pr.title = "Reverted layout tweaks".into();
+        assert!(!pr.is_revert());
+
+        pr.title = "Undo the thing".into();
+        pr.body = Some("Reverts some-org/some-repo#32".into());
+        assert!(pr.is_revert());
+
+        pr.body = Some("Undo\n\nThis reverts commit abcdef.".into());
+        assert!(pr.is_revert());
+
+        pr.body = Some("Nothing to see here".into());
+        assert!(!pr.is_revert());
+    }
+
+    #[test]
+    fn test_is_release_merge() {
+        assert!(is_release_merge(
+            "Merge branch 'release/1.0' into release/1.1",
+            "release/"
+        ));
+        assert!(is_release_merge(
+            "Merge remote-tracking branch 'origin/release/1.0' into release/1.1",
+            "release/"
+        ));
+        assert!(is_release_merge(
+            "Merge pull request #5 from some-org/release/1.0\n\nForward merge",
+            "release/"
+        ));
+        assert!(is_release_merge("Merge branch 'rel-1.0'", "rel-"));
+
+        assert!(!is_release_merge(
+            "Merge branch 'master' into release/1.1",
+            "release/"
+        ));
+        assert!(!is_release_merge(
+            "Merge pull request #5 from some-org/feature-branch",
+            "release/"
+        ));
+        assert!(!is_release_merge(
+            "Fix [SER-1] merge branch 'release/1.0' handling",
+            "release/"
+        ));
+    }
+
+    #[test]
+    fn test_release_target_branch() {
+        let mut release = Release::new("v1.2.0");
+        assert_eq!(None, release.target_branch());
+
+        release.target_commitish = Some("release/1.2".into());
+        assert_eq!(Some("release/1.2"), release.target_branch());
+
+        release.target_commitish = Some("0123456789abcdef0123456789abcdef01234567".into());
+        assert_eq!(None, release.target_branch());
+
+        release.target_commitish = Some(String::new());
+        assert_eq!(None, release.target_branch());
+    }
+
+    #[test]
+    fn test_hook_missing_events() {
+        let required = ["pull_request", "push", "issue_comment"];
+
+        let hook = Hook::new(1, &["push", "pull_request", "issue_comment", "status"]);
+        assert_eq!(Vec::<String>::new(), hook.missing_events(&required));
+
+        let hook = Hook::new(1, &["push"]);
+        assert_eq!(
+            vec!["pull_request", "issue_comment"],
+            hook.missing_events(&required)
+        );
+
+        let hook = Hook::new(1, &["*"]);
+        assert_eq!(Vec::<String>::new(), hook.missing_events(&required));
+    }
+
+    #[test]
+    fn test_pr_size() {
+        let thresholds = [10, 100, 500, 1000];
+        assert_eq!("XS", pr_size(0, &thresholds));
+        assert_eq!("XS", pr_size(10, &thresholds));
+        assert_eq!("S", pr_size(11, &thresholds));
+        assert_eq!("S", pr_size(100, &thresholds));
+        assert_eq!("M", pr_size(101, &thresholds));
+        assert_eq!("M", pr_size(500, &thresholds));
+        assert_eq!("L", pr_size(501, &thresholds));
+        assert_eq!("L", pr_size(1000, &thresholds));
+        assert_eq!("XL", pr_size(1001, &thresholds));
+
+        // fewer thresholds cap the size
+        assert_eq!("S", pr_size(5000, &[10]));
+        assert_eq!("XS", pr_size(5000, &[]));
+    }
+
+    #[test]
+    fn test_changed_lines() {
+        let mut pr = PullRequest::new();
+        assert_eq!(None, pr.changed_lines());
+        pr.additions = Some(12);
+        assert_eq!(None, pr.changed_lines());
+        pr.deletions = Some(3);
+        assert_eq!(Some(15), pr.changed_lines());
+    }
+
+    #[test]
+    fn test_parse_check_run_event() {
+        // trimmed down check_run "rerequested" delivery from github.com
+        let body: HookBody = serde_json::from_str(
+            r#"{
+  "action": "rerequested",
+  "check_run": {
+    "id": 21856376215,
+    "name": "jira",
+    "node_id": "CR_kwDOJ1yqE88AAAAFF2w2lw",
+    "head_sha": "ce587453ced02b1526dfb4cb910479d431683101",
+    "external_id": "",
+    "url": "https://api.github.com/repos/some-user/some-repo/check-runs/21856376215",
+    "html_url": "https://github.com/some-user/some-repo/runs/21856376215",
+    "details_url": "https://octobot.company.com",
+    "status": "waiting",
+    "conclusion": "stale",
+    "started_at": "2024-02-21T17:31:49Z",
+    "completed_at": null,
+    "output": {
+      "title": null,
+      "summary": null,
+      "text": null,
+      "annotations_count": 0,
+      "annotations_url": "https://api.github.com/repos/some-user/some-repo/check-runs/21856376215/annotations"
+    },
+    "check_suite": {
+      "id": 20911593543,
+      "head_branch": "pr-branch",
+      "head_sha": "ce587453ced02b1526dfb4cb910479d431683101",
+      "status": "requested",
+      "conclusion": null
+    },
+    "app": { "id": 12345, "slug": "octobot", "name": "octobot" },
+    "pull_requests": [
+      {
+        "url": "https://api.github.com/repos/some-user/some-repo/pulls/32",
+        "id": 1737612345,
+        "number": 32,
+        "head": { "ref": "pr-branch", "sha": "ce587453ced02b1526dfb4cb910479d431683101" },
+        "base": { "ref": "main", "sha": "7a0d3e0e6f2a7a56e36a2c8a6b0cd0b3a8d1e9a2" }
+      }
+    ]
+  },
+  "repository": {
+    "id": 660345363,
+    "name": "some-repo",
+    "full_name": "some-user/some-repo",
+    "private": false,
+    "owner": { "login": "some-user", "id": 1234567, "type": "User" },
+    "html_url": "https://github.com/some-user/some-repo",
+    "archived": false
+  },
+  "sender": { "login": "joe-sender", "id": 7654321, "type": "User" },
+  "installation": { "id": 45678901 }
+}"#,
+        )
+        .unwrap();
+
+        let run = body.check_run.unwrap();
+        assert_eq!(Some(21856376215), run.id);
+        assert_eq!("jira", run.name);
+        assert_eq!(CheckStatus::Waiting, run.status);
+        assert_eq!(Some(Conclusion::Stale), run.conclusion);
+        assert_eq!(vec![CheckRunPullRequest { number: 32 }], run.pull_requests);
+        assert_eq!("some-user/some-repo", body.repository.unwrap().full_name);
+    }
+
+    #[test]
+    fn test_parse_check_values() {
+        for status in [
+            "queued",
+            "in_progress",
+            "completed",
+            "waiting",
+            "requested",
+            "pending",
+        ] {
+            let parsed: CheckStatus = serde_json::from_value(serde_json::json!(status)).unwrap();
+            assert_eq!(
+                serde_json::json!(status),
+                serde_json::to_value(parsed).unwrap()
+            );
+        }
+        for conclusion in [
+            "success",
+            "failure",
+            "neutral",
+            "cancelled",
+            "timed_out",
+            "action_required",
+            "skipped",
+            "stale",
+            "startup_failure",
+        ] {
+            let parsed: Conclusion = serde_json::from_value(serde_json::json!(conclusion)).unwrap();
+            assert_eq!(
+                serde_json::json!(conclusion),
+                serde_json::to_value(parsed).unwrap()
+            );
+        }
+    }
 }
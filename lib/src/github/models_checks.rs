@@ -11,7 +11,7 @@ use crate::github::models;
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct CheckSuite {
-    pub id: u32,
+    pub id: u64,
     pub url: String,
     pub repository: models::Repo,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -19,8 +19,10 @@ pub struct CheckSuite {
     pub status: CheckStatus,
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct CheckRun {
+    #[serde(default, skip_serializing)]
+    pub id: Option<u64>,
     pub name: String,
     pub head_sha: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -38,9 +40,18 @@ pub struct CheckRun {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub actions: Option<Vec<CheckAction>>,
+
+    // only present on check_run events
+    #[serde(default, skip_serializing)]
+    pub pull_requests: Vec<CheckRunPullRequest>,
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct CheckRunPullRequest {
+    pub number: u32,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct CheckOutput {
     pub title: Option<String>,
     summary: Option<String>,
@@ -50,7 +61,7 @@ pub struct CheckOutput {
     pub annotations: Option<Vec<CheckAnnotation>>,
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct CheckAnnotation {
     pub path: String,
     pub start_line: u32,
@@ -59,7 +70,7 @@ pub struct CheckAnnotation {
     pub message: String,
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct CheckAction {}
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -76,6 +87,9 @@ pub enum Conclusion {
     Cancelled,
     TimedOut,
     ActionRequired,
+    Skipped,
+    Stale,
+    StartupFailure,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -83,11 +97,15 @@ pub enum CheckStatus {
     Queued,
     InProgress,
     Completed,
+    Waiting,
+    Requested,
+    Pending,
 }
 
 impl CheckRun {
     pub fn new(name: &str, commit_sha: &str, url: Option<String>) -> CheckRun {
         CheckRun {
+            id: None,
             name: name.into(),
             head_sha: commit_sha.to_string(),
             status: CheckStatus::InProgress,
@@ -96,6 +114,7 @@ impl CheckRun {
             details_url: url,
             output: None,
             actions: None,
+            pull_requests: vec![],
         }
     }
 
@@ -134,6 +153,9 @@ impl Serialize2 for Conclusion {
             Conclusion::Cancelled => "cancelled",
             Conclusion::TimedOut => "timed_out",
             Conclusion::ActionRequired => "action_required",
+            Conclusion::Skipped => "skipped",
+            Conclusion::Stale => "stale",
+            Conclusion::StartupFailure => "startup_failure",
         };
         serializer.serialize_str(st)
     }
@@ -164,6 +186,9 @@ impl<'de> Deserialize2<'de> for Conclusion {
                     "cancelled" => Ok(Conclusion::Cancelled),
                     "timed_out" => Ok(Conclusion::TimedOut),
                     "action_required" => Ok(Conclusion::ActionRequired),
+                    "skipped" => Ok(Conclusion::Skipped),
+                    "stale" => Ok(Conclusion::Stale),
+                    "startup_failure" => Ok(Conclusion::StartupFailure),
                     _ => Err(E::custom(format!("unexpected conclusion: '{}'", value))),
                 }
             }
@@ -182,6 +207,9 @@ impl Serialize2 for CheckStatus {
             CheckStatus::Queued => "queued",
             CheckStatus::InProgress => "in_progress",
             CheckStatus::Completed => "completed",
+            CheckStatus::Waiting => "waiting",
+            CheckStatus::Requested => "requested",
+            CheckStatus::Pending => "pending",
         };
         serializer.serialize_str(st)
     }
@@ -209,6 +237,9 @@ impl<'de> Deserialize2<'de> for CheckStatus {
                     "queued" => Ok(CheckStatus::Queued),
                     "in_progress" => Ok(CheckStatus::InProgress),
                     "completed" => Ok(CheckStatus::Completed),
+                    "waiting" => Ok(CheckStatus::Waiting),
+                    "requested" => Ok(CheckStatus::Requested),
+                    "pending" => Ok(CheckStatus::Pending),
                     _ => Err(E::custom(format!("unexpected status: '{}'", value))),
                 }
             }
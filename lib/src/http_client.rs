@@ -1,4 +1,8 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
 use anyhow::{anyhow, bail};
+use async_trait::async_trait;
 use maplit::hashmap;
 use prometheus::{HistogramTimer, HistogramVec, IntCounterVec};
 use reqwest;
@@ -11,6 +15,29 @@ use crate::metrics;
 pub use reqwest::header::HeaderMap;
 pub use reqwest::Response;
 
+// Adds configured extra headers (e.g. for a proxy) to `headers`. Headers already present, and
+// Authorization in any case, are left alone so that the extra headers can never replace auth.
+pub fn add_extra_headers(headers: &mut HeaderMap, extra: &HashMap<String, String>) -> Result<()> {
+    for (name, value) in extra {
+        let name = reqwest::header::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| anyhow!("Invalid extra header name '{}': {}", name, e))?;
+        if name == reqwest::header::AUTHORIZATION || headers.contains_key(&name) {
+            log::warn!("Ignoring extra header '{}': it is set by octobot", name);
+            continue;
+        }
+        let value = reqwest::header::HeaderValue::from_str(value)
+            .map_err(|e| anyhow!("Invalid value for extra header '{}': {}", name, e))?;
+        headers.insert(name, value);
+    }
+    Ok(())
+}
+
+#[async_trait]
+pub trait TokenRefresher: Send + Sync {
+    // A new Authorization header value to use after the current one was rejected
+    async fn refresh(&self) -> Result<String>;
+}
+
 pub struct HTTPClient {
     pub api_base: String,
     pub client: reqwest::Client,
@@ -18,6 +45,9 @@ pub struct HTTPClient {
     metric_api_responses: Option<IntCounterVec>,
     metric_api_duration: Option<HistogramVec>,
     secret_path: Option<String>,
+    token_refresher: Option<Arc<dyn TokenRefresher>>,
+    // replaces the default Authorization header once the token has been refreshed
+    refreshed_auth: RwLock<Option<String>>,
 }
 
 impl HTTPClient {
@@ -32,6 +62,8 @@ impl HTTPClient {
             metric_api_responses: None,
             metric_api_duration: None,
             secret_path: None,
+            token_refresher: None,
+            refreshed_auth: RwLock::new(None),
         })
     }
 
@@ -47,6 +79,8 @@ impl HTTPClient {
             metric_api_responses: None,
             metric_api_duration: None,
             secret_path: None,
+            token_refresher: None,
+            refreshed_auth: RwLock::new(None),
         })
     }
 
@@ -66,6 +100,48 @@ impl HTTPClient {
         self
     }
 
+    // Retry requests rejected with a 401 once, with the Authorization from `refresher`
+    pub fn with_token_refresher(mut self, refresher: Arc<dyn TokenRefresher>) -> Self {
+        self.token_refresher = Some(refresher);
+        self
+    }
+
+    fn authorize(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match *self.refreshed_auth.read().unwrap() {
+            Some(ref auth) => req.header(reqwest::header::AUTHORIZATION, auth.as_str()),
+            None => req,
+        }
+    }
+
+    // Sends the request `build` makes, rebuilding and resending it once after a 401 if a token
+    // refresher is configured
+    async fn send<F>(&self, build: F) -> reqwest::Result<Response>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        let res = self.authorize(build()).send().await;
+
+        let refresher = match self.token_refresher {
+            Some(ref r) => r,
+            None => return res,
+        };
+        match res {
+            Ok(ref r) if r.status() == reqwest::StatusCode::UNAUTHORIZED => (),
+            _ => return res,
+        };
+
+        match refresher.refresh().await {
+            Ok(auth) => {
+                *self.refreshed_auth.write().unwrap() = Some(auth);
+                self.authorize(build()).send().await
+            }
+            Err(e) => {
+                log::error!("Error refreshing token after a 401: {}", e);
+                res
+            }
+        }
+    }
+
     fn make_url(&self, path: &str) -> String {
         if path.is_empty() {
             self.api_base.clone()
@@ -80,7 +156,7 @@ impl HTTPClient {
 
     pub async fn get_raw(&self, path: &str) -> Result<Response> {
         let _timer = self.maybe_start_timer("get", path);
-        let res = self.client.get(&self.make_url(path)).send().await;
+        let res = self.send(|| self.client.get(self.make_url(path))).await;
         let res = self.process_resp(res).await?;
 
         self.maybe_record_ok();
@@ -98,16 +174,33 @@ impl HTTPClient {
         Ok(res)
     }
 
+    // Like `get`, but a 404 is returned as `None` rather than an error
+    pub async fn get_opt<T>(&self, path: &str) -> Result<Option<T>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let _timer = self.maybe_start_timer("get", path);
+        let res = self.send(|| self.client.get(self.make_url(path))).await;
+        if let Ok(ref r) = res {
+            if r.status() == reqwest::StatusCode::NOT_FOUND {
+                self.maybe_record_status(r.status().as_str());
+                return Ok(None);
+            }
+        }
+        let res = self.process_resp(res).await?;
+        let res = self.parse_json(res).await?;
+
+        self.maybe_record_ok();
+        Ok(Some(res))
+    }
+
     pub async fn post<T, U: Serialize>(&self, path: &str, body: &U) -> Result<T>
     where
         T: DeserializeOwned + Send + 'static,
     {
         let _timer = self.maybe_start_timer("post", path);
         let res = self
-            .client
-            .post(&self.make_url(path))
-            .json(body)
-            .send()
+            .send(|| self.client.post(self.make_url(path)).json(body))
             .await;
         let res = self.process_resp(res).await?;
         let res = self.parse_json(res).await?;
@@ -116,13 +209,28 @@ impl HTTPClient {
         Ok(res)
     }
 
+    // Like `post`, but returns the response as-is for any status so that callers can inspect it
+    pub async fn post_raw<U: Serialize>(&self, path: &str, body: &U) -> Result<Response> {
+        let _timer = self.maybe_start_timer("post", path);
+        let res = self
+            .send(|| self.client.post(self.make_url(path)).json(body))
+            .await;
+        match res {
+            Ok(r) => {
+                self.maybe_record_status(r.status().as_str());
+                Ok(r)
+            }
+            Err(e) => {
+                self.maybe_record_status("<unknown>");
+                self.make_clean_err(e)
+            }
+        }
+    }
+
     pub async fn post_void<U: Serialize>(&self, path: &str, body: &U) -> Result<()> {
         let _timer = self.maybe_start_timer("post", path);
         let res = self
-            .client
-            .post(&self.make_url(path))
-            .json(body)
-            .send()
+            .send(|| self.client.post(self.make_url(path)).json(body))
             .await;
         self.process_resp(res).await?;
 
@@ -132,12 +240,15 @@ impl HTTPClient {
 
     pub async fn post_void_opt<U: Serialize>(&self, path: &str, body: Option<&U>) -> Result<()> {
         let _timer = self.maybe_start_timer("post", path);
-        let res = self.client.post(self.make_url(path));
-        let res = match body {
-            None => res,
-            Some(body) => res.json(body),
-        };
-        let res = res.send().await;
+        let res = self
+            .send(|| {
+                let req = self.client.post(self.make_url(path));
+                match body {
+                    None => req,
+                    Some(body) => req.json(body),
+                }
+            })
+            .await;
         self.process_resp(res).await?;
 
         self.maybe_record_ok();
@@ -150,10 +261,7 @@ impl HTTPClient {
     {
         let _timer = self.maybe_start_timer("put", path);
         let res = self
-            .client
-            .put(&self.make_url(path))
-            .json(body)
-            .send()
+            .send(|| self.client.put(self.make_url(path)).json(body))
             .await;
         let res = self.process_resp(res).await?;
         let res = self.parse_json(res).await?;
@@ -165,10 +273,7 @@ impl HTTPClient {
     pub async fn put_void<U: Serialize>(&self, path: &str, body: &U) -> Result<()> {
         let _timer = self.maybe_start_timer("put", path);
         let res = self
-            .client
-            .put(&self.make_url(path))
-            .json(body)
-            .send()
+            .send(|| self.client.put(self.make_url(path)).json(body))
             .await;
         self.process_resp(res).await?;
 
@@ -178,7 +283,7 @@ impl HTTPClient {
 
     pub async fn delete_void(&self, path: &str) -> Result<()> {
         let _timer = self.maybe_start_timer("delete", path);
-        let res = self.client.delete(&self.make_url(path)).send().await;
+        let res = self.send(|| self.client.delete(self.make_url(path))).await;
         self.process_resp(res).await?;
 
         self.maybe_record_ok();
@@ -261,3 +366,153 @@ impl HTTPClient {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::{Read, Write};
+    use std::sync::mpsc;
+
+    // Answers requests on one connection with the given (status, body) responses in order,
+    // sending back the raw headers of each request
+    fn serve(responses: Vec<(u16, &'static str)>) -> (String, mpsc::Receiver<String>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = vec![];
+            let mut chunk = [0u8; 4096];
+            for (status, body) in responses {
+                // wait for a complete request: headers plus content-length body
+                let request = loop {
+                    let text = String::from_utf8_lossy(&buf).to_lowercase();
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let len = text[..end]
+                            .lines()
+                            .find_map(|l| l.strip_prefix("content-length:"))
+                            .map(|v| v.trim().parse::<usize>().unwrap())
+                            .unwrap_or(0);
+                        if buf.len() >= end + 4 + len {
+                            buf.drain(..end + 4 + len);
+                            break text[..end].to_string();
+                        }
+                    }
+                    let n = stream.read(&mut chunk).unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                };
+                let resp = format!(
+                    "HTTP/1.1 {} X\r\nContent-Length: {}\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                stream.write_all(resp.as_bytes()).unwrap();
+                tx.send(request).unwrap();
+            }
+        });
+
+        (format!("http://{}", addr), rx)
+    }
+
+    struct MockRefresher {
+        refreshes: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait]
+    impl TokenRefresher for MockRefresher {
+        async fn refresh(&self) -> Result<String> {
+            self.refreshes
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok("Bearer new-token".into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_extra_headers_sent() {
+        let (base, rx) = serve(vec![(200, "{}")]);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            reqwest::header::AUTHORIZATION,
+            "Bearer the-token".parse().unwrap(),
+        );
+        headers.insert(reqwest::header::ACCEPT, "application/json".parse().unwrap());
+        let extra = hashmap! {
+            "X-Proxy-Auth".to_string() => "proxy-secret".to_string(),
+            "Authorization".to_string() => "Basic sneaky".to_string(),
+            "Accept".to_string() => "text/html".to_string(),
+        };
+        add_extra_headers(&mut headers, &extra).unwrap();
+
+        let client = HTTPClient::new_with_headers(&base, headers).unwrap();
+        client.get::<serde_json::Value>("/thing").await.unwrap();
+
+        let req = rx.recv().unwrap();
+        assert!(req.starts_with("get /thing "), "{}", req);
+        assert!(req.contains("\r\nx-proxy-auth: proxy-secret"), "{}", req);
+        assert!(
+            req.contains("\r\nauthorization: bearer the-token"),
+            "{}",
+            req
+        );
+        assert!(req.contains("\r\naccept: application/json"), "{}", req);
+        assert!(!req.contains("sneaky"), "{}", req);
+        assert!(!req.contains("text/html"), "{}", req);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_on_401() {
+        let (base, rx) = serve(vec![(401, "{}"), (200, "{}"), (200, "{}")]);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            reqwest::header::AUTHORIZATION,
+            "Bearer old-token".parse().unwrap(),
+        );
+        let refresher = Arc::new(MockRefresher {
+            refreshes: std::sync::atomic::AtomicUsize::new(0),
+        });
+        let client = HTTPClient::new_with_headers(&base, headers)
+            .unwrap()
+            .with_token_refresher(refresher.clone());
+
+        client.post_void("/thing", &"body").await.unwrap();
+        // later requests keep using the refreshed token
+        client.delete_void("/thing").await.unwrap();
+
+        let rejected = rx.recv().unwrap();
+        assert!(
+            rejected.contains("authorization: bearer old-token"),
+            "{}",
+            rejected
+        );
+        let retried = rx.recv().unwrap();
+        assert!(retried.starts_with("post /thing "), "{}", retried);
+        assert!(
+            retried.contains("authorization: bearer new-token"),
+            "{}",
+            retried
+        );
+        assert!(!retried.contains("old-token"), "{}", retried);
+        let next = rx.recv().unwrap();
+        assert!(next.starts_with("delete /thing "), "{}", next);
+        assert!(next.contains("authorization: bearer new-token"), "{}", next);
+
+        assert_eq!(
+            1,
+            refresher
+                .refreshes
+                .load(std::sync::atomic::Ordering::SeqCst)
+        );
+    }
+
+    #[test]
+    fn test_extra_headers_invalid() {
+        let mut headers = HeaderMap::new();
+        let extra = hashmap! {"Bad Name".to_string() => "value".to_string()};
+        assert!(add_extra_headers(&mut headers, &extra).is_err());
+        assert!(headers.is_empty());
+    }
+}
@@ -9,11 +9,11 @@ use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use regex::Regex;
 use serde_derive::{Deserialize, Serialize};
 use serde_json;
-use serde_json::json;
+use serde_json::{json, Value};
 
 use crate::config::JiraConfig;
 use crate::errors::*;
-use crate::http_client::HTTPClient;
+use crate::http_client::{self, HTTPClient};
 use crate::jira::models::*;
 use crate::metrics::Metrics;
 use crate::version;
@@ -22,14 +22,34 @@ use crate::version;
 pub trait Session: Send + Sync {
     async fn get_issue(&self, key: &str) -> Result<Issue>;
     async fn get_transitions(&self, key: &str) -> Result<Vec<Transition>>;
+    async fn assign_issue(&self, key: &str, jira_username: &str) -> Result<()>;
 
     async fn transition_issue(&self, key: &str, transition: &TransitionRequest) -> Result<()>;
 
-    async fn comment_issue(&self, key: &str, comment: &str) -> Result<()>;
+    async fn comment_issue(
+        &self,
+        key: &str,
+        comment: &str,
+        visibility: CommentVisibility,
+    ) -> Result<()>;
+
+    // Links the issue to `url`, which also serves as the link's globalId so that relinking the
+    // same URL updates the existing link instead of adding a duplicate.
+    async fn create_remote_link(
+        &self,
+        key: &str,
+        url: &str,
+        title: &str,
+        icon_url: Option<&str>,
+    ) -> Result<()>;
+
+    async fn get_issue_property(&self, key: &str, property: &str) -> Result<Option<Value>>;
+    async fn set_issue_property(&self, key: &str, property: &str, value: &Value) -> Result<()>;
 
     async fn add_version(&self, proj: &str, version: &str) -> Result<Version>;
     async fn get_versions(&self, proj: &str) -> Result<Vec<Version>>;
     async fn assign_fix_version(&self, key: &str, version: &str) -> Result<()>;
+    async fn remove_fix_version(&self, key: &str, version: &str) -> Result<()>;
     async fn reorder_version(&self, version: &Version, position: JiraVersionPosition)
         -> Result<()>;
 
@@ -40,6 +60,18 @@ pub trait Session: Send + Sync {
         &self,
         proj: &str,
     ) -> Result<HashMap<String, Vec<version::Version>>>;
+    async fn find_issues_with_fix_version(&self, proj: &str, version: &str) -> Result<Vec<String>>;
+
+    // A cheap authenticated request to verify connectivity
+    async fn ping(&self) -> Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CommentVisibility {
+    // Restrict to `restrict_comment_visibility_to_role`, if configured
+    Configured,
+    // Never restrict, regardless of configuration
+    Public,
 }
 
 #[derive(Debug)]
@@ -74,6 +106,7 @@ impl JiraSession {
 
         let mut headers = reqwest::header::HeaderMap::new();
         headers.insert(reqwest::header::ACCEPT, "application/json".parse().unwrap());
+        http_client::add_extra_headers(&mut headers, &config.extra_headers())?;
 
         // Login first without basic auth so we don't get a big html page on login errors
 
@@ -150,6 +183,16 @@ impl Session for JiraSession {
             .map_err(|e| anyhow!("Error creating getting issue [{}]: {}", key, e))
     }
 
+    async fn assign_issue(&self, key: &str, jira_username: &str) -> Result<()> {
+        self.client
+            .put_void(
+                &format!("/issue/{}/assignee", key),
+                &json!({ "name": jira_username }),
+            )
+            .await
+            .map_err(|e| anyhow!("Error assigning [{}] to {}: {}", key, jira_username, e))
+    }
+
     async fn get_transitions(&self, key: &str) -> Result<Vec<Transition>> {
         #[derive(Deserialize)]
         struct TransitionsResp {
@@ -173,7 +216,12 @@ impl Session for JiraSession {
             .map_err(|e| anyhow!("Error transitioning [{}]: {}", key, e))
     }
 
-    async fn comment_issue(&self, key: &str, comment: &str) -> Result<()> {
+    async fn comment_issue(
+        &self,
+        key: &str,
+        comment: &str,
+        visibility: CommentVisibility,
+    ) -> Result<()> {
         #[derive(Serialize)]
         struct VisibilityReq {
             #[serde(rename = "type")]
@@ -192,7 +240,12 @@ impl Session for JiraSession {
             visibility: None,
         };
 
-        if let Some(r) = &self.restrict_comment_visibility_to_role {
+        let restrict_to_role = match visibility {
+            CommentVisibility::Configured => self.restrict_comment_visibility_to_role.as_ref(),
+            CommentVisibility::Public => None,
+        };
+
+        if let Some(r) = restrict_to_role {
             req.visibility = Some(VisibilityReq {
                 type_name: "role".to_string(),
                 value: r.clone(),
@@ -216,6 +269,73 @@ impl Session for JiraSession {
             .map_err(|e| anyhow!("Error commenting on [{}]: {}", key, e))
     }
 
+    async fn create_remote_link(
+        &self,
+        key: &str,
+        url: &str,
+        title: &str,
+        icon_url: Option<&str>,
+    ) -> Result<()> {
+        #[derive(Serialize)]
+        struct IconReq {
+            url16x16: String,
+            title: String,
+        }
+
+        #[derive(Serialize)]
+        struct ObjectReq {
+            url: String,
+            title: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            icon: Option<IconReq>,
+        }
+
+        #[derive(Serialize)]
+        struct RemoteLinkReq {
+            #[serde(rename = "globalId")]
+            global_id: String,
+            object: ObjectReq,
+        }
+
+        let req = RemoteLinkReq {
+            global_id: url.to_string(),
+            object: ObjectReq {
+                url: url.to_string(),
+                title: title.to_string(),
+                icon: icon_url.map(|i| IconReq {
+                    url16x16: i.to_string(),
+                    title: "GitHub".to_string(),
+                }),
+            },
+        };
+
+        self.client
+            .post_void(&format!("/issue/{}/remotelink", key), &req)
+            .await
+            .map_err(|e| anyhow!("Error linking [{}] to {}: {}", key, url, e))
+    }
+
+    async fn get_issue_property(&self, key: &str, property: &str) -> Result<Option<Value>> {
+        #[derive(Deserialize)]
+        struct PropertyResp {
+            value: Value,
+        }
+
+        let resp = self
+            .client
+            .get_opt::<PropertyResp>(&format!("/issue/{}/properties/{}", key, property))
+            .await
+            .map_err(|e| anyhow!("Error getting property {} of [{}]: {}", property, key, e))?;
+        Ok(resp.map(|r| r.value))
+    }
+
+    async fn set_issue_property(&self, key: &str, property: &str, value: &Value) -> Result<()> {
+        self.client
+            .put_void(&format!("/issue/{}/properties/{}", key, property), value)
+            .await
+            .map_err(|e| anyhow!("Error setting property {} of [{}]: {}", property, key, e))
+    }
+
     async fn add_version(&self, proj: &str, version: &str) -> Result<Version> {
         #[derive(Serialize)]
         struct AddVersionReq {
@@ -261,6 +381,27 @@ impl Session for JiraSession {
             .map_err(|e| anyhow!("Error adding fix-version {} to [{}]: {}", version, key, e))
     }
 
+    async fn remove_fix_version(&self, key: &str, version: &str) -> Result<()> {
+        let field = self.fix_versions_field.clone();
+        let req = json!({
+            "update": {
+                field: [{"remove" : {"name" : version}}]
+            }
+        });
+
+        self.client
+            .put_void(&format!("/issue/{}", key), &req)
+            .await
+            .map_err(|e| {
+                anyhow!(
+                    "Error removing fix-version {} from [{}]: {}",
+                    version,
+                    key,
+                    e
+                )
+            })
+    }
+
     async fn reorder_version(
         &self,
         version: &Version,
@@ -398,6 +539,186 @@ impl Session for JiraSession {
 
         Ok(HashMap::new())
     }
+
+    async fn find_issues_with_fix_version(
+        &self,
+        project: &str,
+        version: &str,
+    ) -> Result<Vec<String>> {
+        let jql = format!(
+            "(project = \"{}\") and fixVersion = \"{}\"",
+            project, version
+        );
+        let search = self
+            .client
+            .get::<serde_json::Value>(&format!(
+                "/search?maxResults=5000&fields=key&jql={}",
+                utf8_percent_encode(&jql, NON_ALPHANUMERIC)
+            ))
+            .await
+            .map_err(|e| {
+                anyhow!(
+                    "Error finding issues with fix version {} in project {}: {}",
+                    version,
+                    project,
+                    e
+                )
+            })?;
+
+        Ok(search["issues"]
+            .as_array()
+            .unwrap_or(&vec![])
+            .iter()
+            .filter_map(|issue| issue["key"].as_str().map(|k| k.to_string()))
+            .collect())
+    }
+
+    async fn ping(&self) -> Result<()> {
+        self.client
+            .get::<serde_json::Value>("/myself")
+            .await
+            .map(|_| ())
+            .map_err(|e| anyhow!("Error pinging JIRA: {}", e))
+    }
+}
+
+// Wraps another session, passing reads through but only logging the writes it would have made.
+pub struct DryRunSession {
+    inner: Arc<dyn Session>,
+}
+
+impl DryRunSession {
+    pub fn new(inner: Arc<dyn Session>) -> DryRunSession {
+        DryRunSession { inner }
+    }
+}
+
+#[async_trait]
+impl Session for DryRunSession {
+    async fn get_issue(&self, key: &str) -> Result<Issue> {
+        self.inner.get_issue(key).await
+    }
+
+    async fn get_transitions(&self, key: &str) -> Result<Vec<Transition>> {
+        self.inner.get_transitions(key).await
+    }
+
+    async fn assign_issue(&self, key: &str, jira_username: &str) -> Result<()> {
+        info!("Dry run: would assign [{}] to {}", key, jira_username);
+        Ok(())
+    }
+
+    async fn transition_issue(&self, key: &str, req: &TransitionRequest) -> Result<()> {
+        info!("Dry run: would transition [{}]: {:?}", key, req);
+        Ok(())
+    }
+
+    async fn comment_issue(
+        &self,
+        key: &str,
+        comment: &str,
+        visibility: CommentVisibility,
+    ) -> Result<()> {
+        info!(
+            "Dry run: would comment on [{}] ({:?}): {}",
+            key, visibility, comment
+        );
+        Ok(())
+    }
+
+    async fn create_remote_link(
+        &self,
+        key: &str,
+        url: &str,
+        title: &str,
+        _icon_url: Option<&str>,
+    ) -> Result<()> {
+        info!("Dry run: would link [{}] to {} (\"{}\")", key, url, title);
+        Ok(())
+    }
+
+    async fn get_issue_property(&self, key: &str, property: &str) -> Result<Option<Value>> {
+        self.inner.get_issue_property(key, property).await
+    }
+
+    async fn set_issue_property(&self, key: &str, property: &str, value: &Value) -> Result<()> {
+        info!(
+            "Dry run: would set property {} on [{}] to {}",
+            property, key, value
+        );
+        Ok(())
+    }
+
+    async fn add_version(&self, proj: &str, version: &str) -> Result<Version> {
+        info!("Dry run: would add version {} to {}", version, proj);
+        Ok(Version::new(version))
+    }
+
+    async fn get_versions(&self, proj: &str) -> Result<Vec<Version>> {
+        self.inner.get_versions(proj).await
+    }
+
+    async fn assign_fix_version(&self, key: &str, version: &str) -> Result<()> {
+        info!("Dry run: would add fix version {} to [{}]", version, key);
+        Ok(())
+    }
+
+    async fn remove_fix_version(&self, key: &str, version: &str) -> Result<()> {
+        info!(
+            "Dry run: would remove fix version {} from [{}]",
+            version, key
+        );
+        Ok(())
+    }
+
+    async fn reorder_version(
+        &self,
+        version: &Version,
+        position: JiraVersionPosition,
+    ) -> Result<()> {
+        info!(
+            "Dry run: would move version {} to {:?}",
+            version.name, position
+        );
+        Ok(())
+    }
+
+    async fn add_pending_version(&self, key: &str, version: &str) -> Result<()> {
+        info!(
+            "Dry run: would add pending version {} to [{}]",
+            version, key
+        );
+        Ok(())
+    }
+
+    async fn remove_pending_versions(
+        &self,
+        key: &str,
+        versions: &[version::Version],
+    ) -> Result<()> {
+        let versions = versions.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        info!(
+            "Dry run: would remove pending versions {} from [{}]",
+            versions.join(", "),
+            key
+        );
+        Ok(())
+    }
+
+    async fn find_pending_versions(
+        &self,
+        proj: &str,
+    ) -> Result<HashMap<String, Vec<version::Version>>> {
+        self.inner.find_pending_versions(proj).await
+    }
+
+    async fn find_issues_with_fix_version(&self, proj: &str, version: &str) -> Result<Vec<String>> {
+        self.inner.find_issues_with_fix_version(proj, version).await
+    }
+
+    async fn ping(&self) -> Result<()> {
+        self.inner.ping().await
+    }
 }
 
 fn parse_pending_version_field(field: &serde_json::Value) -> Vec<version::Version> {
@@ -469,4 +790,67 @@ mod tests {
         let versions = parse_pending_versions(&search, "the-field");
         assert_eq!(expected, versions);
     }
+
+    #[test]
+    fn test_parse_issue() {
+        // trimmed down response from GET /rest/api/2/issue/SER-1
+        let issue: Issue = serde_json::from_value(json!({
+            "expand": "renderedFields,names,schema,operations,editmeta,changelog",
+            "id": "10002",
+            "self": "https://jira.company.com/rest/api/2/issue/10002",
+            "key": "SER-1",
+            "fields": {
+                "summary": "The server crashes",
+                "issuetype": {
+                    "self": "https://jira.company.com/rest/api/2/issuetype/1",
+                    "id": "1",
+                    "name": "Bug",
+                    "subtask": false
+                },
+                "priority": {
+                    "self": "https://jira.company.com/rest/api/2/priority/2",
+                    "id": "2",
+                    "name": "Critical"
+                },
+                "status": {
+                    "self": "https://jira.company.com/rest/api/2/status/3",
+                    "id": "3",
+                    "name": "In Progress",
+                    "statusCategory": { "id": 4, "key": "indeterminate" }
+                },
+                "assignee": {
+                    "self": "https://jira.company.com/rest/api/2/user?username=joe",
+                    "name": "joe",
+                    "displayName": "Joe Smith",
+                    "active": true
+                },
+                "fixVersions": []
+            }
+        }))
+        .unwrap();
+
+        assert_eq!("SER-1", issue.key);
+        assert_eq!(Some("The server crashes"), issue.fields.summary.as_deref());
+        assert_eq!("Bug", issue.fields.issue_type.unwrap().name);
+        assert_eq!("Critical", issue.fields.priority.unwrap().name);
+        assert_eq!("In Progress", issue.fields.status.unwrap().name);
+        assert_eq!("joe", issue.fields.assignee.unwrap().name);
+    }
+
+    #[test]
+    fn test_parse_issue_unassigned() {
+        let issue: Issue = serde_json::from_value(json!({
+            "key": "SER-2",
+            "fields": {
+                "summary": "Nobody owns this",
+                "assignee": null,
+                "priority": null
+            }
+        }))
+        .unwrap();
+
+        assert!(issue.fields.assignee.is_none());
+        assert!(issue.fields.priority.is_none());
+        assert!(issue.fields.issue_type.is_none());
+    }
 }
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use conventional::{Commit, Simple as _};
 use log;
 
@@ -5,15 +7,88 @@ use crate::errors::*;
 use crate::github;
 use crate::jira;
 
-const JIRA_REF_CONTEXT: &str = "jira";
+pub const JIRA_REF_CONTEXT: &str = "jira";
+
+const CHECK_RUN_RETRY_DELAY: Duration = Duration::from_millis(250);
 
 const ALLOWED_SKIP_TYPES: &[&str] = &["build", "chore", "docs", "refactor", "style", "test"];
 
+const MISSING_REF_TITLE: &str = "Missing JIRA reference";
+
+// Templates for the check output when a PR is missing JIRA references. Blank templates use the
+// default output. Templates may use the placeholders {projects} (the expected project keys,
+// comma separated) and {regex} (a regex matching accepted JIRA keys).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MissingRefTemplate {
+    pub title: String,
+    pub summary: String,
+}
+
+impl MissingRefTemplate {
+    pub fn new(title: &str, summary: &str) -> MissingRefTemplate {
+        MissingRefTemplate {
+            title: title.into(),
+            summary: summary.into(),
+        }
+    }
+
+    // Returns the (title, summary) of the check output for the given projects
+    pub fn render(&self, projects: &[String]) -> (String, String) {
+        let title = if self.title.is_empty() {
+            MISSING_REF_TITLE.to_string()
+        } else {
+            self.fill(&self.title, projects)
+        };
+
+        let summary = if !self.summary.is_empty() {
+            self.fill(&self.summary, projects)
+        } else if projects.len() == 1 {
+            format!(
+                "Expected a JIRA reference in a commit message for the project {}",
+                projects[0]
+            )
+        } else {
+            format!("Expected a JIRA reference in a commit message for at least one of the following projects: {}", projects.join(", "))
+        };
+
+        (title, summary)
+    }
+
+    fn fill(&self, template: &str, projects: &[String]) -> String {
+        template
+            .replace("{projects}", &projects.join(", "))
+            .replace("{regex}", &format!(r"\b({})-[0-9]+\b", projects.join("|")))
+    }
+}
+
 pub async fn check_jira_refs(
     pull_request: &github::PullRequest,
     commits: &[github::Commit],
     projects: &[String],
+    check_name: &str,
     github: &dyn github::api::Session,
+    retries: u32,
+) {
+    check_jira_refs_with_template(
+        pull_request,
+        commits,
+        projects,
+        check_name,
+        &MissingRefTemplate::default(),
+        github,
+        retries,
+    )
+    .await
+}
+
+pub async fn check_jira_refs_with_template(
+    pull_request: &github::PullRequest,
+    commits: &[github::Commit],
+    projects: &[String],
+    check_name: &str,
+    template: &MissingRefTemplate,
+    github: &dyn github::api::Session,
+    retries: u32,
 ) {
     // Always skip projects with no JIRAs configured
     if projects.is_empty() {
@@ -22,13 +97,32 @@ pub async fn check_jira_refs(
 
     // Skip PRs titled accordingly.
     if let Some(commit_type) = conventional_commit_jira_skip_type(&pull_request.title) {
-        if let Err(e) = do_skip_jira_check(pull_request, commits, commit_type, github).await {
+        if let Err(e) = do_skip_jira_check(
+            pull_request,
+            commits,
+            commit_type,
+            check_name,
+            github,
+            retries,
+        )
+        .await
+        {
             log::error!("Error marking skipped jira refs: {}", e);
         }
         return;
     }
 
-    if let Err(e) = do_check_jira_refs(pull_request, commits, projects, github).await {
+    if let Err(e) = do_check_jira_refs(
+        pull_request,
+        commits,
+        projects,
+        check_name,
+        template,
+        github,
+        retries,
+    )
+    .await
+    {
         log::error!("Error checking jira refs: {}", e);
     }
 }
@@ -39,10 +133,13 @@ async fn do_check_jira_refs(
     pull_request: &github::PullRequest,
     commits: &[github::Commit],
     projects: &[String],
+    check_name: &str,
+    template: &MissingRefTemplate,
     github: &dyn github::api::Session,
+    retries: u32,
 ) -> Result<()> {
     let mut run = github::CheckRun::new(
-        JIRA_REF_CONTEXT,
+        check_name,
         get_latest_commit_hash(pull_request, commits),
         None,
     );
@@ -50,29 +147,69 @@ async fn do_check_jira_refs(
     if jira::workflow::get_all_jira_keys(commits, projects).is_empty() {
         run = run.completed(github::Conclusion::Neutral);
 
-        let msg = if projects.len() == 1 {
-            format!(
-                "Expected a JIRA reference in a commit message for the project {}",
-                projects[0]
-            )
-        } else {
-            format!("Expected a JIRA reference in a commit message for at least one of the following projects: {}", projects.join(", "))
-        };
-        run.output = Some(github::CheckOutput::new("Missing JIRA reference", &msg));
+        let (title, summary) = template.render(projects);
+        run.output = Some(github::CheckOutput::new(&title, &summary));
     } else {
         run = run.completed(github::Conclusion::Success);
     }
 
     log::info!(
-        "Creating jira check: PR {}; head: {}; commit: {} -> {:?}",
+        "Submitting jira check: PR {}; head: {}; commit: {} -> {:?}",
         pull_request.html_url,
         pull_request.head.sha,
         run.head_sha,
         run.status,
     );
-    github.create_check_run(pull_request, &run).await?;
+    submit_check_run(pull_request, &run, github, retries).await
+}
+
+// Submits the check run, retrying on failure. Each attempt looks for an existing run of the same
+// name on the commit so that reprocessing an event (or retrying after a failure where the run was
+// actually created) updates that run instead of adding a duplicate.
+async fn submit_check_run(
+    pull_request: &github::PullRequest,
+    run: &github::CheckRun,
+    github: &dyn github::api::Session,
+    retries: u32,
+) -> Result<()> {
+    let mut attempt = 0;
+    loop {
+        match do_submit_check_run(pull_request, run, github).await {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt < retries => {
+                attempt += 1;
+                log::warn!(
+                    "Error submitting {} check run for {} (attempt {} of {}): {}",
+                    run.name,
+                    run.head_sha,
+                    attempt,
+                    retries + 1,
+                    e
+                );
+                tokio::time::sleep(CHECK_RUN_RETRY_DELAY * attempt).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
 
-    Ok(())
+async fn do_submit_check_run(
+    pull_request: &github::PullRequest,
+    run: &github::CheckRun,
+    github: &dyn github::api::Session,
+) -> Result<()> {
+    let existing = github
+        .get_check_runs(pull_request, &run.head_sha, &run.name)
+        .await?;
+
+    match existing
+        .iter()
+        .filter(|r| r.name == run.name)
+        .find_map(|r| r.id)
+    {
+        Some(id) => github.update_check_run(pull_request, id, run).await,
+        None => github.create_check_run(pull_request, run).await.map(|_| ()),
+    }
 }
 
 fn conventional_commit_jira_skip_type(title: &str) -> Option<&str> {
@@ -97,13 +234,15 @@ async fn do_skip_jira_check(
     pull_request: &github::PullRequest,
     commits: &[github::Commit],
     commit_type: &str,
+    check_name: &str,
     github: &dyn github::api::Session,
+    retries: u32,
 ) -> Result<()> {
     let msg = "Skipped JIRA check";
     let body = format!("Skipped JIRA check for commit type: {}", commit_type);
 
     let mut run = github::CheckRun::new(
-        JIRA_REF_CONTEXT,
+        check_name,
         get_latest_commit_hash(pull_request, commits),
         None,
     );
@@ -111,9 +250,7 @@ async fn do_skip_jira_check(
     run = run.completed(github::Conclusion::Neutral);
     run.output = Some(github::CheckOutput::new(msg, &body));
 
-    github.create_check_run(pull_request, &run).await?;
-
-    Ok(())
+    submit_check_run(pull_request, &run, github, retries).await
 }
 
 fn get_latest_commit_hash<'a>(
@@ -5,4 +5,6 @@ pub mod workflow;
 
 pub use self::models::*;
 
-pub use self::check_jira_refs::check_jira_refs;
+pub use self::check_jira_refs::{
+    check_jira_refs, check_jira_refs_with_template, MissingRefTemplate, JIRA_REF_CONTEXT,
+};
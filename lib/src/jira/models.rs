@@ -3,7 +3,27 @@ use serde_derive::{Deserialize, Serialize};
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Issue {
     pub key: String,
+    #[serde(default)]
+    pub fields: IssueFields,
+}
+
+impl Issue {
+    pub fn new(key: &str) -> Issue {
+        Issue {
+            key: key.into(),
+            fields: IssueFields::default(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct IssueFields {
     pub status: Option<Status>,
+    pub summary: Option<String>,
+    #[serde(rename = "issuetype")]
+    pub issue_type: Option<IssueType>,
+    pub priority: Option<Priority>,
+    pub assignee: Option<Assignee>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -11,6 +31,22 @@ pub struct Status {
     pub name: String,
 }
 
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct IssueType {
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Priority {
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Assignee {
+    #[serde(default)]
+    pub name: String,
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Comment {
     pub body: String,
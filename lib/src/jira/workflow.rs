@@ -7,23 +7,87 @@ use regex::Regex;
 
 use crate::config::JiraConfig;
 use crate::errors::*;
-use crate::github::{Commit, CommitLike, PullRequest, PushCommit};
+use crate::github::{Commit, CommitLike, PullRequest, PushCommit, WorkflowRun};
 use crate::jira;
+use crate::jira::api::CommentVisibility;
 use crate::jira::Transition;
 use crate::version;
 
-fn get_jira_keys(strings: Vec<String>, projects: &[String]) -> Vec<String> {
+// A JIRA transition that octobot performed
+#[derive(Clone, Debug, PartialEq)]
+pub struct AppliedTransition {
+    pub key: String,
+    pub from: Option<String>,
+    pub to: String,
+}
+
+// The outcome of submitting a PR's JIRAs for review
+#[derive(Debug, Default)]
+pub struct ReviewSubmission {
+    pub applied: Vec<AppliedTransition>,
+    // failures commenting on or transitioning JIRAs; resubmitting may succeed
+    pub errors: Vec<String>,
+}
+
+impl AppliedTransition {
+    pub fn describe(&self) -> String {
+        format!(
+            "{}: {} → {}",
+            self.key,
+            self.from.as_deref().unwrap_or("Unknown"),
+            self.to
+        )
+    }
+}
+
+// Remembers the (JIRA, commit) pairs already resolved so that commits showing up in more than
+// one push (e.g. after a rebase or force-push) don't comment or transition twice. Resolving
+// with and without a version is tracked separately, since VersionScriptMode::Both does both.
+pub trait ResolvedCommits: Send + Sync {
+    fn was_resolved(&self, key: &str, commit_sha: &str, versioned: bool) -> Result<bool>;
+    fn mark_resolved(&self, key: &str, commit_sha: &str, versioned: bool) -> Result<()>;
+}
+
+fn was_resolved(
+    resolved: Option<&dyn ResolvedCommits>,
+    key: &str,
+    commit_sha: &str,
+    versioned: bool,
+) -> bool {
+    match resolved.map(|r| r.was_resolved(key, commit_sha, versioned)) {
+        Some(Ok(true)) => {
+            info!(
+                "Skipping [{}]: already resolved for commit {}",
+                key, commit_sha
+            );
+            true
+        }
+        Some(Err(e)) => {
+            error!("Error checking if [{}] was resolved: {}", key, e);
+            false
+        }
+        _ => false,
+    }
+}
+
+fn mark_resolved(
+    resolved: Option<&dyn ResolvedCommits>,
+    key: &str,
+    commit_sha: &str,
+    versioned: bool,
+) {
+    if let Some(Err(e)) = resolved.map(|r| r.mark_resolved(key, commit_sha, versioned)) {
+        error!("Error marking [{}] as resolved: {}", key, e);
+    }
+}
+
+fn find_jira_keys(strings: Vec<String>) -> Vec<String> {
     let re = Regex::new(r"\b([A-Z0-9]+-[0-9]+)\b").unwrap();
 
     let mut all_keys = vec![];
     for s in strings {
         for c in re.captures_iter(&s) {
-            let key = c[1].to_string();
-            let proj = get_jira_project(&key).to_string();
-
-            if projects.contains(&proj) {
-                all_keys.push(key);
-            }
+            all_keys.push(c[1].to_string());
         }
     }
 
@@ -33,7 +97,13 @@ fn get_jira_keys(strings: Vec<String>, projects: &[String]) -> Vec<String> {
     all_keys
 }
 
-fn get_fixed_jira_keys<T: CommitLike>(commits: &[T], projects: &[String]) -> Vec<String> {
+fn get_jira_keys(strings: Vec<String>, projects: &[String]) -> Vec<String> {
+    let mut all_keys = find_jira_keys(strings);
+    all_keys.retain(|key| projects.iter().any(|p| p == get_jira_project(key)));
+    all_keys
+}
+
+pub fn get_fixed_jira_keys<T: CommitLike>(commits: &[T], projects: &[String]) -> Vec<String> {
     // Fix [ABC-123][OTHER-567], [YEAH-999]
     let re =
         Regex::new(r"(?i)(?:Fix(?:es|ed)?):?\s*(?-i)((\[?([A-Z0-9]+-[0-9]+)(?:\]|\b)[\s,]*)+)")
@@ -78,6 +148,16 @@ pub(crate) fn get_all_jira_keys<T: CommitLike>(commits: &[T], projects: &[String
     )
 }
 
+// JIRA-looking keys that reference projects other than the given ones
+pub fn get_unconfigured_jira_keys<T: CommitLike>(
+    commits: &[T],
+    projects: &[String],
+) -> Vec<String> {
+    let mut all_keys = find_jira_keys(commits.iter().map(|c| c.message().to_string()).collect());
+    all_keys.retain(|key| !projects.iter().any(|p| p == get_jira_project(key)));
+    all_keys
+}
+
 pub fn references_jira<T: CommitLike>(commits: &[T], project: &str) -> bool {
     let projects = vec![project.to_owned()];
 
@@ -93,6 +173,72 @@ fn get_jira_project(jira_key: &str) -> &str {
     }
 }
 
+const COMMENT_HASHES_PROPERTY: &str = "octobot.comment-hashes";
+const MAX_COMMENT_HASHES: usize = 100;
+
+pub fn comment_hash(comment: &str) -> String {
+    hex::encode(ring::digest::digest(
+        &ring::digest::SHA256,
+        comment.as_bytes(),
+    ))
+}
+
+// Comments on the issue, unless comment deduping is enabled and the same comment was
+// already posted (as recorded in an issue property).
+async fn comment_issue(
+    key: &str,
+    comment: &str,
+    jira: &dyn jira::api::Session,
+    config: &JiraConfig,
+    visibility: CommentVisibility,
+) -> Result<()> {
+    if !config.dedupe_comments() {
+        return jira.comment_issue(key, comment, visibility).await;
+    }
+
+    comment_issue_once(key, comment, jira, visibility).await
+}
+
+// Comments on the issue unless the same comment was already posted, regardless of
+// whether comment deduping is enabled.
+async fn comment_issue_once(
+    key: &str,
+    comment: &str,
+    jira: &dyn jira::api::Session,
+    visibility: CommentVisibility,
+) -> Result<()> {
+    let hash = comment_hash(comment);
+    let mut hashes: Vec<String> = match jira.get_issue_property(key, COMMENT_HASHES_PROPERTY).await
+    {
+        Ok(Some(v)) => serde_json::from_value(v).unwrap_or_default(),
+        Ok(None) => vec![],
+        Err(e) => {
+            error!("Error looking up comment hashes for [{}]: {}", key, e);
+            vec![]
+        }
+    };
+
+    if hashes.contains(&hash) {
+        info!("Skipping duplicate comment on [{}]", key);
+        return Ok(());
+    }
+
+    jira.comment_issue(key, comment, visibility).await?;
+
+    hashes.push(hash);
+    if hashes.len() > MAX_COMMENT_HASHES {
+        hashes.drain(..hashes.len() - MAX_COMMENT_HASHES);
+    }
+    if let Err(e) = jira
+        .set_issue_property(key, COMMENT_HASHES_PROPERTY, &serde_json::json!(hashes))
+        .await
+    {
+        error!("Error recording comment hash for [{}]: {}", key, e);
+    }
+
+    Ok(())
+}
+
 fn needs_transition(state: &Option<jira::Status>, target: &[String]) -> bool {
     if let Some(ref state) = state {
         !target.contains(&state.name)
@@ -101,32 +247,68 @@ fn needs_transition(state: &Option<jira::Status>, target: &[String]) -> bool {
     }
 }
 
+// The favicon of the GitHub host serving `html_url`, for JIRA remote links
+fn github_icon_url(html_url: &str) -> Option<String> {
+    let url = url::Url::parse(html_url).ok()?;
+    Some(format!(
+        "{}/favicon.ico",
+        url.origin().ascii_serialization()
+    ))
+}
+
 pub async fn submit_for_review(
     pr: &PullRequest,
     commits: &[Commit],
     projects: &[String],
+    release_branch_prefix: &str,
     jira: &dyn jira::api::Session,
     config: &JiraConfig,
-) {
+    visibility: CommentVisibility,
+) -> ReviewSubmission {
     let review_states = config.review_states();
     let progress_states = config.progress_states();
+    let mut submission = ReviewSubmission::default();
+
+    // PRs targeting release branches (e.g. backports) may only leave comments
+    let is_release_branch =
+        !release_branch_prefix.is_empty() && pr.base.ref_name.starts_with(release_branch_prefix);
+    let may_transition = !is_release_branch || config.release_branch_review_transitions();
 
     for key in get_fixed_jira_keys(commits, projects) {
-        // add comment
-        if let Err(e) = jira
-            .comment_issue(
+        // add link or comment
+        let result = if config.use_remote_links() {
+            jira.create_remote_link(
+                &key,
+                &pr.html_url,
+                &format!("GitHub PR #{}", pr.number),
+                github_icon_url(&pr.html_url).as_deref(),
+            )
+            .await
+        } else {
+            comment_issue(
                 &key,
                 &format!(
                     "Review submitted for branch {}: {}",
                     pr.base.ref_name, pr.html_url
                 ),
+                jira,
+                config,
+                visibility,
             )
             .await
-        {
+        };
+        if let Err(e) = result {
             error!("Error commenting on key [{}]: {}", key, e);
+            submission
+                .errors
+                .push(format!("Error commenting on [{}]: {}", key, e));
             continue; // give up on transitioning if we can't comment.
         }
 
+        if !may_transition {
+            continue;
+        }
+
         let issue_state = try_get_issue_state(&key, jira).await;
 
         if !needs_transition(&issue_state, &review_states) {
@@ -134,32 +316,51 @@ pub async fn submit_for_review(
         }
 
         // try to transition to in-progress
+        let mut from = issue_state.as_ref().map(|s| s.name.clone());
         if needs_transition(&issue_state, &progress_states) {
-            try_transition(&key, &progress_states, jira).await;
+            match transition(&key, from.clone(), &progress_states, jira).await {
+                Ok(Some(t)) => {
+                    from = Some(t.to.clone());
+                    submission.applied.push(t);
+                }
+                Ok(None) => (),
+                Err(e) => {
+                    submission.errors.push(e.to_string());
+                    continue;
+                }
+            }
         }
 
         // try transition to pending-review
-        try_transition(&key, &review_states, jira).await;
+        match transition(&key, from, &review_states, jira).await {
+            Ok(t) => submission.applied.extend(t),
+            Err(e) => submission.errors.push(e.to_string()),
+        }
     }
 
     let mentioned = get_mentioned_jira_keys(commits, projects);
     for key in get_referenced_jira_keys(commits, projects) {
         // add comment
-        if let Err(e) = jira
-            .comment_issue(
-                &key,
-                &format!(
-                    "Referenced by review submitted for branch {}: {}",
-                    pr.base.ref_name, pr.html_url
-                ),
-            )
-            .await
+        if let Err(e) = comment_issue(
+            &key,
+            &format!(
+                "Referenced by review submitted for branch {}: {}",
+                pr.base.ref_name, pr.html_url
+            ),
+            jira,
+            config,
+            visibility,
+        )
+        .await
         {
             error!("Error commenting on key [{}]: {}", key, e);
+            submission
+                .errors
+                .push(format!("Error commenting on [{}]: {}", key, e));
             continue; // give up on transitioning if we can't comment.
         }
 
-        if mentioned.contains(&key) {
+        if mentioned.contains(&key) || !may_transition {
             continue; // don't transition
         }
 
@@ -170,48 +371,265 @@ pub async fn submit_for_review(
         }
 
         // try to transition to in-progress
-        try_transition(&key, &progress_states, jira).await;
+        let from = issue_state.map(|s| s.name);
+        match transition(&key, from, &progress_states, jira).await {
+            Ok(t) => submission.applied.extend(t),
+            Err(e) => submission.errors.push(e.to_string()),
+        }
+    }
+
+    submission
+}
+
+pub async fn comment_backport(
+    backport_pr: &PullRequest,
+    target_branch: &str,
+    commits: &[Commit],
+    projects: &[String],
+    jira: &dyn jira::api::Session,
+    config: &JiraConfig,
+    visibility: CommentVisibility,
+) {
+    for key in get_fixed_jira_keys(commits, projects) {
+        if let Err(e) = comment_issue(
+            &key,
+            &format!(
+                "Backport to branch {}: {}",
+                target_branch, backport_pr.html_url
+            ),
+            jira,
+            config,
+            visibility,
+        )
+        .await
+        {
+            error!("Error commenting on key [{}]: {}", key, e);
+        }
+    }
+}
+
+pub async fn comment_workflow_failure(
+    run: &WorkflowRun,
+    commits: &[Commit],
+    projects: &[String],
+    jira: &dyn jira::api::Session,
+    config: &JiraConfig,
+    visibility: CommentVisibility,
+) {
+    let msg = format!(
+        "Workflow \"{}\" failed for branch {}: {}",
+        run.name,
+        run.head_branch.as_deref().unwrap_or(""),
+        run.html_url
+    );
+    for key in get_all_jira_keys(commits, projects) {
+        if let Err(e) = comment_issue(&key, &msg, jira, config, visibility).await {
+            error!("Error commenting on key [{}]: {}", key, e);
+        }
+    }
+}
+
+pub async fn mark_queued(
+    pr: &PullRequest,
+    commits: &[Commit],
+    projects: &[String],
+    jira: &dyn jira::api::Session,
+    config: &JiraConfig,
+    visibility: CommentVisibility,
+) -> Vec<AppliedTransition> {
+    let mut applied = vec![];
+    let queued_states = config.queued_states();
+    if queued_states.is_empty() {
+        return applied;
+    }
+
+    for key in get_fixed_jira_keys(commits, projects) {
+        // add comment
+        if let Err(e) = comment_issue(
+            &key,
+            &format!(
+                "Added to merge queue for branch {}: {}",
+                pr.base.ref_name, pr.html_url
+            ),
+            jira,
+            config,
+            visibility,
+        )
+        .await
+        {
+            error!("Error commenting on key [{}]: {}", key, e);
+            continue; // give up on transitioning if we can't comment.
+        }
+
+        let issue_state = try_get_issue_state(&key, jira).await;
+
+        if !needs_transition(&issue_state, &queued_states) {
+            continue;
+        }
+
+        let from = issue_state.map(|s| s.name);
+        applied.extend(try_transition(&key, from, &queued_states, jira).await);
+    }
+
+    applied
+}
+
+pub async fn mark_auto_merge_enabled(
+    pr: &PullRequest,
+    commits: &[Commit],
+    projects: &[String],
+    jira: &dyn jira::api::Session,
+    visibility: CommentVisibility,
+) {
+    let msg = format!(
+        "Queued for auto-merge into branch {}: {}",
+        pr.base.ref_name, pr.html_url
+    );
+    for key in get_fixed_jira_keys(commits, projects) {
+        // auto-merge can be toggled repeatedly on the same PR: only note it once.
+        if let Err(e) = comment_issue_once(&key, &msg, jira, visibility).await {
+            error!("Error commenting on key [{}]: {}", key, e);
+        }
+    }
+}
+
+pub async fn mark_released(
+    version: &str,
+    projects: &[String],
+    jira: &dyn jira::api::Session,
+    config: &JiraConfig,
+) -> Vec<AppliedTransition> {
+    let mut applied = vec![];
+    let released_states = config.released_states();
+    if released_states.is_empty() {
+        return applied;
+    }
+
+    for project in projects {
+        let keys = match jira.find_issues_with_fix_version(project, version).await {
+            Ok(k) => k,
+            Err(e) => {
+                error!("{}", e);
+                continue;
+            }
+        };
+
+        for key in keys {
+            let issue_state = try_get_issue_state(&key, jira).await;
+
+            if !needs_transition(&issue_state, &released_states) {
+                continue;
+            }
+
+            let from = issue_state.map(|s| s.name);
+            applied.extend(try_transition(&key, from, &released_states, jira).await);
+        }
+    }
+
+    applied
+}
+
+pub async fn assign_milestone_version(
+    commits: &[Commit],
+    projects: &[String],
+    jira: &dyn jira::api::Session,
+    milestone: &str,
+) {
+    for key in get_fixed_jira_keys(commits, projects) {
+        if let Err(e) = jira.assign_fix_version(&key, milestone).await {
+            error!(
+                "Error assigning version {} to key {}: {}",
+                milestone, key, e
+            );
+        }
     }
 }
 
+pub async fn remove_milestone_version(
+    commits: &[Commit],
+    projects: &[String],
+    jira: &dyn jira::api::Session,
+    milestone: &str,
+) {
+    for key in get_fixed_jira_keys(commits, projects) {
+        if let Err(e) = jira.remove_fix_version(&key, milestone).await {
+            error!(
+                "Error removing version {} from key {}: {}",
+                milestone, key, e
+            );
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn resolve_issue(
     branch: &str,
     version: Option<&str>,
+    environment: Option<&str>,
     commits: &[PushCommit],
     projects: &[String],
     jira: &dyn jira::api::Session,
     config: &JiraConfig,
-) {
+    visibility: CommentVisibility,
+    resolved: Option<&dyn ResolvedCommits>,
+) -> Vec<AppliedTransition> {
+    let mut applied = vec![];
+    let versioned = version.is_some();
+    // a dry run doesn't touch any JIRAs, so nothing is recorded as resolved either
+    let to_mark = if config.dry_run() { None } else { resolved };
+    let count_desc = if config.include_commit_count() {
+        format!(
+            "\n({} commit{})",
+            commits.len(),
+            if commits.len() == 1 { "" } else { "s" }
+        )
+    } else {
+        String::new()
+    };
+
     for commit in commits {
         let desc = format!(
             "[{}|{}]\n{{quote}}{}{{quote}}",
             Commit::short_hash(&commit),
             commit.html_url(),
-            Commit::title(&commit)
+            truncate(&Commit::title(&commit), config.max_quoted_title_length())
         );
 
         let version_desc = match version {
             None => String::new(),
             Some(v) => format!("\nIncluded in version {}", v),
         };
+        let env_desc = match environment {
+            Some(e) if config.include_deploy_environment() => format!("\nDeployed to {}", e),
+            _ => String::new(),
+        };
 
-        let fix_msg = format!("Merged into branch {}: {}{}", branch, desc, version_desc);
+        let fix_msg = format!(
+            "Merged into branch {}: {}{}{}{}",
+            branch, desc, version_desc, env_desc, count_desc
+        );
         let ref_msg = format!(
-            "Referenced by commit merged into branch {}: {}{}",
-            branch, desc, version_desc
+            "Referenced by commit merged into branch {}: {}{}{}{}",
+            branch, desc, version_desc, env_desc, count_desc
         );
         let resolved_states = config.resolved_states();
 
         for key in get_fixed_jira_keys(&[commit], projects) {
-            if let Err(e) = jira.comment_issue(&key, &fix_msg).await {
+            if was_resolved(resolved, &key, &commit.id, versioned) {
+                continue;
+            }
+
+            if let Err(e) = comment_issue(&key, &fix_msg, jira, config, visibility).await {
                 error!("Error commenting on key [{}]: {}", key, e);
             }
 
             let issue_state = try_get_issue_state(&key, jira).await;
             if !needs_transition(&issue_state, &resolved_states) {
+                mark_resolved(to_mark, &key, &commit.id, versioned);
                 continue;
             }
 
+            // leave failed transitions unmarked so that a later push can retry them
             match find_transition(&key, &resolved_states, jira).await {
                 Ok(Some(transition)) => {
                     let mut req = transition.new_request();
@@ -245,23 +663,39 @@ pub async fn resolve_issue(
                         );
                     } else {
                         info!("Transitioned [{}] to one of [{:?}]", key, resolved_states);
+                        mark_resolved(to_mark, &key, &commit.id, versioned);
+                        applied.push(AppliedTransition {
+                            key: key.clone(),
+                            from: issue_state.map(|s| s.name),
+                            to: transition.to.name.clone(),
+                        });
                     }
                 }
-                Ok(None) => info!(
-                    "JIRA [{}] cannot be transitioned to  any of [{:?}]",
-                    key, resolved_states
-                ),
+                Ok(None) => {
+                    info!(
+                        "JIRA [{}] cannot be transitioned to  any of [{:?}]",
+                        key, resolved_states
+                    );
+                    mark_resolved(to_mark, &key, &commit.id, versioned);
+                }
                 Err(e) => error!("{}", e),
             };
         }
 
         // add comment only to referenced jiras
         for key in get_referenced_jira_keys(&[commit], projects) {
-            if let Err(e) = jira.comment_issue(&key, &ref_msg).await {
+            if was_resolved(resolved, &key, &commit.id, versioned) {
+                continue;
+            }
+            mark_resolved(to_mark, &key, &commit.id, versioned);
+
+            if let Err(e) = comment_issue(&key, &ref_msg, jira, config, visibility).await {
                 error!("Error commenting on key [{}]: {}", key, e);
             }
         }
     }
+
+    applied
 }
 
 pub async fn add_pending_version(
@@ -426,9 +860,125 @@ fn find_relevant_versions(
         .collect::<Vec<_>>()
 }
 
+const MAX_SUMMARY_LEN: usize = 80;
+
+fn truncate_summary(summary: &str) -> String {
+    truncate(summary, MAX_SUMMARY_LEN)
+}
+
+// Cuts text longer than max_len chars down to fit, ending in "...". A max_len of 0 means no limit.
+fn truncate(text: &str, max_len: usize) -> String {
+    if max_len == 0 || text.chars().count() <= max_len {
+        text.to_string()
+    } else {
+        let truncated: String = text.chars().take(max_len.saturating_sub(3)).collect();
+        format!("{}...", truncated)
+    }
+}
+
+// Builds a GitHub comment listing each JIRA referenced by the given commits with its
+// summary and status. Returns None if no JIRAs are referenced.
+pub async fn pr_summary_comment<T: CommitLike>(
+    commits: &[T],
+    projects: &[String],
+    jira: &dyn jira::api::Session,
+    config: &JiraConfig,
+) -> Option<String> {
+    let keys = get_all_jira_keys(commits, projects);
+    if keys.is_empty() {
+        return None;
+    }
+
+    let mut lines = vec!["Referenced JIRAs:".to_string()];
+    for key in keys {
+        let link = format!("[{}]({}/browse/{})", key, config.base_url(), key);
+        match jira.get_issue(&key).await {
+            Ok(issue) => {
+                let summary = truncate_summary(issue.fields.summary.as_deref().unwrap_or(""));
+                let status = issue
+                    .fields
+                    .status
+                    .map(|s| s.name)
+                    .unwrap_or_else(|| "Unknown".into());
+                lines.push(format!("* {}: {} ({})", link, summary, status));
+            }
+            Err(e) => {
+                error!("Error getting JIRA [{}] {}", key, e);
+                lines.push(format!("* {} (not found)", key));
+            }
+        }
+    }
+
+    Some(lines.join("\n"))
+}
+
+// Assigns the JIRAs fixed by the given commits to `assignee`, leaving assigned ones alone
+pub async fn assign_fixed_issues(
+    commits: &[Commit],
+    projects: &[String],
+    assignee: &str,
+    jira: &dyn jira::api::Session,
+) {
+    for key in get_fixed_jira_keys(commits, projects) {
+        match jira.get_issue(&key).await {
+            Ok(issue) if issue.fields.assignee.is_some() => continue,
+            Ok(_) => (),
+            Err(e) => {
+                error!("Error getting JIRA [{}] {}", key, e);
+                continue;
+            }
+        };
+
+        if let Err(e) = jira.assign_issue(&key, assignee).await {
+            error!("Error assigning JIRA [{}] to {}: {}", key, assignee, e);
+        }
+    }
+}
+
+// Describes the type and priority of each JIRA referenced by the given commits, one per line,
+// e.g. "SER-1: Bug, priority Critical". JIRAs that can't be fetched or have neither are left out.
+pub async fn issue_details<T: CommitLike>(
+    commits: &[T],
+    projects: &[String],
+    jira: &dyn jira::api::Session,
+) -> Option<String> {
+    let mut lines = vec![];
+    for key in get_all_jira_keys(commits, projects) {
+        let issue = match jira.get_issue(&key).await {
+            Ok(i) => i,
+            Err(e) => {
+                error!("Error getting JIRA [{}] {}", key, e);
+                continue;
+            }
+        };
+
+        let details = issue
+            .fields
+            .issue_type
+            .map(|t| t.name)
+            .into_iter()
+            .chain(
+                issue
+                    .fields
+                    .priority
+                    .map(|p| format!("priority {}", p.name)),
+            )
+            .collect::<Vec<_>>();
+        if !details.is_empty() {
+            lines.push(format!("{}: {}", key, details.join(", ")));
+        }
+    }
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
 async fn try_get_issue_state(key: &str, jira: &dyn jira::api::Session) -> Option<jira::Status> {
     match jira.get_issue(key).await {
-        Ok(issue) => issue.status,
+        Ok(issue) => issue.fields.status,
         Err(e) => {
             error!("Error getting JIRA [{}] {}", key, e);
             None
@@ -436,22 +986,55 @@ async fn try_get_issue_state(key: &str, jira: &dyn jira::api::Session) -> Option
     }
 }
 
-async fn try_transition(key: &str, to: &[String], jira: &dyn jira::api::Session) {
-    match find_transition(key, to, jira).await {
-        Ok(Some(transition)) => {
-            let req = transition.new_request();
-            if let Err(e) = jira.transition_issue(key, &req).await {
-                error!(
-                    "Error transitioning JIRA issue [{}] to one of [{:?}]: {}",
-                    key, to, e
-                );
-            } else {
-                info!("Transitioned [{}] to one of [{:?}]", key, to);
-            }
+async fn try_transition(
+    key: &str,
+    from: Option<String>,
+    to: &[String],
+    jira: &dyn jira::api::Session,
+) -> Option<AppliedTransition> {
+    transition(key, from, to, jira).await.unwrap_or(None)
+}
+
+// Transitions the issue to the first of `to` that it can reach, if any. Errors are logged and
+// returned.
+async fn transition(
+    key: &str,
+    from: Option<String>,
+    to: &[String],
+    jira: &dyn jira::api::Session,
+) -> Result<Option<AppliedTransition>> {
+    let transition = match find_transition(key, to, jira).await {
+        Ok(Some(t)) => t,
+        Ok(None) => {
+            info!("JIRA [{}] cannot be transitioned to any of [{:?}]", key, to);
+            return Ok(None);
+        }
+        Err(e) => {
+            error!("{}", e);
+            return Err(e);
         }
-        Ok(None) => info!("JIRA [{}] cannot be transitioned to any of [{:?}]", key, to),
-        Err(e) => error!("{}", e),
     };
+
+    let req = transition.new_request();
+    if let Err(e) = jira.transition_issue(key, &req).await {
+        error!(
+            "Error transitioning JIRA issue [{}] to one of [{:?}]: {}",
+            key, to, e
+        );
+        return Err(anyhow!(
+            "Error transitioning [{}] to one of [{:?}]: {}",
+            key,
+            to,
+            e
+        ));
+    }
+
+    info!("Transitioned [{}] to one of [{:?}]", key, to);
+    Ok(Some(AppliedTransition {
+        key: key.to_string(),
+        from,
+        to: transition.to.name,
+    }))
 }
 
 async fn find_transition(
@@ -569,6 +1152,24 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn test_get_unconfigured_jira_keys() {
+        let projects = vec!["KEY".to_string()];
+        let mut commit = Commit::new();
+        commit.commit.message =
+            "Fix [KEY-1], OTHER-2: Fixed stuff\n\nSee [THIRD-3], [OTHER-2]".into();
+        assert_eq!(
+            vec!["OTHER-2", "THIRD-3"],
+            get_unconfigured_jira_keys(&[commit.clone()], &projects)
+        );
+
+        commit.commit.message = "Fix [KEY-1]".into();
+        assert_eq!(
+            Vec::<String>::new(),
+            get_unconfigured_jira_keys(&[commit], &projects)
+        );
+    }
+
     #[test]
     pub fn test_pick_transition() {
         let t1 = Transition {
@@ -3,6 +3,7 @@
 pub mod config;
 pub mod config_db;
 mod config_db_migrations;
+pub mod correlation;
 pub mod db;
 pub mod github;
 pub mod http_client;
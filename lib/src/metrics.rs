@@ -23,6 +23,10 @@ pub struct Metrics {
     pub github_api_responses: IntCounterVec,
     pub github_api_duration: HistogramVec,
 
+    pub ignored_webhook_events: IntCounterVec,
+    pub webhook_events: IntCounterVec,
+    pub webhook_duration: HistogramVec,
+
     pub current_connection_count: Gauge,
     pub current_webhook_count: Gauge,
 
@@ -124,6 +128,31 @@ impl Metrics {
             )
             .unwrap(),
 
+            ignored_webhook_events: register_int_counter_vec_with_registry!(
+                "ignored_webhook_events",
+                "Webhook events ignored by design",
+                &["event"],
+                registry.as_ref()
+            )
+            .unwrap(),
+
+            webhook_events: register_int_counter_vec_with_registry!(
+                "webhook_events",
+                "Webhook events by type and outcome",
+                &["event", "outcome"],
+                registry.as_ref()
+            )
+            .unwrap(),
+
+            webhook_duration: register_histogram_vec_with_registry!(
+                "webhook_duration",
+                "Duration of webhook event handling in seconds",
+                &["event"],
+                http_duration_buckets(),
+                registry.as_ref()
+            )
+            .unwrap(),
+
             current_connection_count: register_gauge_with_registry!(
                 "current_connection_count",
                 "The number of current http connections",
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use anyhow::anyhow;
 use log::error;
 use rusqlite::types::ToSql;
@@ -25,6 +27,193 @@ pub struct RepoInfo {
     // Used for backporting. Defaults to "release/"
     #[serde(default)]
     pub release_branch_prefix: String,
+    // Post JIRA comments without visibility restrictions, even if the JIRA config restricts them.
+    #[serde(default)]
+    pub jira_comments_public: bool,
+    // Reviewers to request when a PR is opened without any requested reviewers.
+    #[serde(default)]
+    pub default_reviewers: Vec<String>,
+    // Whether to send notifications for comments on commits
+    #[serde(default = "default_true")]
+    pub notify_commit_comments: bool,
+    // Name of the JIRA reference check run. Defaults to "jira"
+    #[serde(default)]
+    pub jira_check_name: String,
+    // Candidates to suggest (the least loaded one) when a PR is opened without any reviewers.
+    #[serde(default)]
+    pub suggested_reviewers: Vec<String>,
+    // Branch-scoped subsets of the JIRA projects above. The first entry matching a branch
+    // restricts the projects considered for it.
+    #[serde(default)]
+    pub branch_jira_projects: Vec<BranchJiraProjects>,
+    // Commit authors whose PRs may be backported automatically. Empty allows anyone.
+    #[serde(default)]
+    pub backport_authors: Vec<String>,
+    // Use the webhook's PR as-is instead of refetching it to get reviewers.
+    #[serde(default)]
+    pub skip_reviewer_refetch: bool,
+    // Slack channel to mirror GitHub Discussions to. Left blank, discussion events are ignored.
+    #[serde(default)]
+    pub discussions_channel: String,
+    // Only run version scripts for pushes with at least this many commits. Defaults to 1
+    #[serde(default = "default_version_script_min_commits")]
+    pub version_script_min_commits: u32,
+    // Title/summary templates for the JIRA check output when references are missing. Blank uses
+    // the default text. Supports {projects} and {regex} placeholders.
+    #[serde(default)]
+    pub jira_check_title: String,
+    #[serde(default)]
+    pub jira_check_summary: String,
+    // Labels to apply to PRs touching paths matching a glob.
+    #[serde(default)]
+    pub path_labels: Vec<PathLabels>,
+    // How merged JIRAs are resolved when a version script is configured. Defaults to "script"
+    #[serde(default)]
+    pub version_script_mode: VersionScriptMode,
+    // Branch globs (e.g. "release/*") that are frozen: PRs targeting them get a warning.
+    #[serde(default)]
+    pub frozen_branches: Vec<String>,
+    // Overrides for notification messages, keyed by event (e.g. "pr_opened", "pr_merged").
+    // Supports {user}, {title} and {number} placeholders.
+    #[serde(default)]
+    pub message_templates: Option<HashMap<String, String>>,
+    // How backports apply the original PR onto the target branch. Defaults to "merge"
+    #[serde(default)]
+    pub merge_strategy: MergeStrategy,
+    // (branch glob, channel) pairs replacing the channel above for matching branches, e.g.
+    // ("release/*", "releases"). The first matching glob wins.
+    #[serde(default)]
+    pub channel_overrides: Vec<(String, String)>,
+    // Path globs a push must touch for the version script to run. Empty runs it for any push.
+    // Other pushes still resolve their JIRAs, just without a version.
+    #[serde(default)]
+    pub version_script_paths: Vec<String>,
+    // Remind assignees and reviewers of open PRs without any activity for this many days
+    #[serde(default)]
+    pub stale_pr_days: Option<u32>,
+    // Prefix of labels requesting a backport to the branch named by the rest of the label.
+    // Defaults to "backport-"
+    #[serde(default)]
+    pub backport_label_prefix: Option<String>,
+    // Users whose activity is not notified about for this repo, combined with the global
+    // slack.ignored_users list according to ignored_users_mode.
+    #[serde(default)]
+    pub ignored_users: Vec<String>,
+    // How ignored_users combines with the global list. Defaults to "merge"
+    #[serde(default)]
+    pub ignored_users_mode: IgnoredUsersMode,
+    // Notify merged PRs only in the channel, without messaging each participant directly
+    #[serde(default)]
+    pub merged_channel_only: bool,
+    // Reviewers to request in turn when a PR is opened without any requested reviewers.
+    #[serde(default)]
+    pub reviewer_pool: Vec<String>,
+    // How many reviewers to request from reviewer_pool per PR. Defaults to 1
+    #[serde(default)]
+    pub auto_assign_count: Option<u32>,
+    // PRs with more commits than this are too big to act on their JIRAs. Defaults to 20
+    #[serde(default)]
+    pub max_commits_for_jira: Option<usize>,
+    // PR events to notify about, e.g. ["review_requested", "merged"]. Notifies all events if unset
+    #[serde(default)]
+    pub notify_events: Option<Vec<String>>,
+}
+
+// How to resolve JIRAs on push for JIRA projects with a version script
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum VersionScriptMode {
+    // Resolve with the script's version, falling back to a plain resolve if the script fails
+    #[default]
+    Script,
+    // Skip the version script and resolve without a version
+    Resolve,
+    // Resolve without a version right away, then run the version script
+    Both,
+}
+
+impl VersionScriptMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VersionScriptMode::Script => "script",
+            VersionScriptMode::Resolve => "resolve",
+            VersionScriptMode::Both => "both",
+        }
+    }
+
+    pub fn parse(value: &str) -> VersionScriptMode {
+        match value {
+            "resolve" => VersionScriptMode::Resolve,
+            "both" => VersionScriptMode::Both,
+            _ => VersionScriptMode::Script,
+        }
+    }
+}
+
+// How a backport applies the original PR's changes
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergeStrategy {
+    // Cherry-pick the PR's merge commit
+    #[default]
+    Merge,
+    // Cherry-pick the PR's commits into a single commit listing their titles
+    Squash,
+    // Cherry-pick the PR's commits one by one
+    Rebase,
+}
+
+impl MergeStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MergeStrategy::Merge => "merge",
+            MergeStrategy::Squash => "squash",
+            MergeStrategy::Rebase => "rebase",
+        }
+    }
+
+    pub fn parse(value: &str) -> MergeStrategy {
+        match value {
+            "squash" => MergeStrategy::Squash,
+            "rebase" => MergeStrategy::Rebase,
+            _ => MergeStrategy::Merge,
+        }
+    }
+}
+
+// How a repo's ignored users combine with the global list
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum IgnoredUsersMode {
+    // Ignore users from both the global list and the repo's list
+    #[default]
+    Merge,
+    // Ignore only the repo's list, disregarding the global one
+    Replace,
+}
+
+impl IgnoredUsersMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IgnoredUsersMode::Merge => "merge",
+            IgnoredUsersMode::Replace => "replace",
+        }
+    }
+
+    pub fn parse(value: &str) -> IgnoredUsersMode {
+        match value {
+            "replace" => IgnoredUsersMode::Replace,
+            _ => IgnoredUsersMode::Merge,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_version_script_min_commits() -> u32 {
+    1
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -50,6 +239,28 @@ pub struct RepoJiraConfig {
     pub release_branch_regex: String,
 }
 
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct BranchJiraProjects {
+    // A regex matching the branches this applies to
+    #[serde(default)]
+    pub branch_regex: String,
+
+    // The JIRA projects to consider for matching branches
+    #[serde(default)]
+    pub jira_projects: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct PathLabels {
+    // A glob matching changed file paths. "*" stays within a directory, "**" spans directories.
+    #[serde(default)]
+    pub path_glob: String,
+
+    // The labels to apply to PRs changing matching paths
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
 #[derive(Clone)]
 pub struct RepoConfig {
     db: ConfigDatabase,
@@ -65,6 +276,34 @@ impl RepoInfo {
             use_threads: false,
             jira_config: vec![],
             release_branch_prefix: String::new(),
+            jira_comments_public: false,
+            default_reviewers: vec![],
+            notify_commit_comments: true,
+            jira_check_name: String::new(),
+            suggested_reviewers: vec![],
+            branch_jira_projects: vec![],
+            backport_authors: vec![],
+            skip_reviewer_refetch: false,
+            discussions_channel: String::new(),
+            version_script_min_commits: default_version_script_min_commits(),
+            jira_check_title: String::new(),
+            jira_check_summary: String::new(),
+            path_labels: vec![],
+            version_script_mode: VersionScriptMode::Script,
+            frozen_branches: vec![],
+            message_templates: None,
+            channel_overrides: vec![],
+            merge_strategy: MergeStrategy::Merge,
+            version_script_paths: vec![],
+            stale_pr_days: None,
+            backport_label_prefix: None,
+            ignored_users: vec![],
+            ignored_users_mode: IgnoredUsersMode::Merge,
+            merged_channel_only: false,
+            reviewer_pool: vec![],
+            auto_assign_count: None,
+            max_commits_for_jira: None,
+            notify_events: None,
         }
     }
 
@@ -96,6 +335,175 @@ impl RepoInfo {
         info.release_branch_prefix = value;
         info
     }
+
+    pub fn with_jira_comments_public(self, value: bool) -> RepoInfo {
+        let mut info = self;
+        info.jira_comments_public = value;
+        info
+    }
+
+    pub fn with_notify_commit_comments(self, value: bool) -> RepoInfo {
+        let mut info = self;
+        info.notify_commit_comments = value;
+        info
+    }
+
+    pub fn with_default_reviewers(self, value: Vec<String>) -> RepoInfo {
+        let mut info = self;
+        info.default_reviewers = value;
+        info
+    }
+
+    pub fn with_jira_check_name(self, value: String) -> RepoInfo {
+        let mut info = self;
+        info.jira_check_name = value;
+        info
+    }
+
+    pub fn with_jira_check_template(self, title: String, summary: String) -> RepoInfo {
+        let mut info = self;
+        info.jira_check_title = title;
+        info.jira_check_summary = summary;
+        info
+    }
+
+    pub fn with_suggested_reviewers(self, value: Vec<String>) -> RepoInfo {
+        let mut info = self;
+        info.suggested_reviewers = value;
+        info
+    }
+
+    pub fn with_backport_authors(self, value: Vec<String>) -> RepoInfo {
+        let mut info = self;
+        info.backport_authors = value;
+        info
+    }
+
+    pub fn with_skip_reviewer_refetch(self, value: bool) -> RepoInfo {
+        let mut info = self;
+        info.skip_reviewer_refetch = value;
+        info
+    }
+
+    pub fn with_merged_channel_only(self, value: bool) -> RepoInfo {
+        let mut info = self;
+        info.merged_channel_only = value;
+        info
+    }
+
+    pub fn with_reviewer_pool(self, pool: Vec<String>, count: Option<u32>) -> RepoInfo {
+        let mut info = self;
+        info.reviewer_pool = pool;
+        info.auto_assign_count = count;
+        info
+    }
+
+    pub fn with_max_commits_for_jira(self, value: Option<usize>) -> RepoInfo {
+        let mut info = self;
+        info.max_commits_for_jira = value;
+        info
+    }
+
+    pub fn with_notify_events(self, value: Option<Vec<String>>) -> RepoInfo {
+        let mut info = self;
+        info.notify_events = value;
+        info
+    }
+
+    pub fn with_discussions_channel(self, value: String) -> RepoInfo {
+        let mut info = self;
+        info.discussions_channel = value;
+        info
+    }
+
+    pub fn with_version_script_min_commits(self, value: u32) -> RepoInfo {
+        let mut info = self;
+        info.version_script_min_commits = value;
+        info
+    }
+
+    pub fn with_branch_jira_projects(self, branch_regex: &str, jira_projects: &[&str]) -> RepoInfo {
+        let mut info = self;
+        info.branch_jira_projects.push(BranchJiraProjects {
+            branch_regex: branch_regex.into(),
+            jira_projects: jira_projects.iter().map(|p| p.to_string()).collect(),
+        });
+        info
+    }
+
+    pub fn with_version_script_mode(self, value: VersionScriptMode) -> RepoInfo {
+        let mut info = self;
+        info.version_script_mode = value;
+        info
+    }
+
+    pub fn with_frozen_branches(self, value: Vec<String>) -> RepoInfo {
+        let mut info = self;
+        info.frozen_branches = value;
+        info
+    }
+
+    pub fn with_version_script_paths(self, value: Vec<String>) -> RepoInfo {
+        let mut info = self;
+        info.version_script_paths = value;
+        info
+    }
+
+    pub fn with_stale_pr_days(self, value: Option<u32>) -> RepoInfo {
+        let mut info = self;
+        info.stale_pr_days = value;
+        info
+    }
+
+    pub fn with_backport_label_prefix(self, value: &str) -> RepoInfo {
+        let mut info = self;
+        info.backport_label_prefix = Some(value.into());
+        info
+    }
+
+    pub fn with_ignored_users(self, users: Vec<String>, mode: IgnoredUsersMode) -> RepoInfo {
+        let mut info = self;
+        info.ignored_users = users;
+        info.ignored_users_mode = mode;
+        info
+    }
+
+    pub fn with_message_template(self, event: &str, template: &str) -> RepoInfo {
+        let mut info = self;
+        info.message_templates
+            .get_or_insert_with(HashMap::new)
+            .insert(event.into(), template.into());
+        info
+    }
+
+    pub fn with_merge_strategy(self, value: MergeStrategy) -> RepoInfo {
+        let mut info = self;
+        info.merge_strategy = value;
+        info
+    }
+
+    pub fn with_channel_override(self, branch_glob: &str, channel: &str) -> RepoInfo {
+        let mut info = self;
+        info.channel_overrides
+            .push((branch_glob.into(), channel.into()));
+        info
+    }
+
+    fn channel_for_branch(&self, branch: &str) -> Option<String> {
+        self.channel_overrides
+            .iter()
+            .find(|(glob, _)| glob_to_regex(glob).is_some_and(|re| re.is_match(branch)))
+            .map(|(_, channel)| channel.clone())
+    }
+
+    pub fn with_path_labels(self, path_glob: &str, labels: &[&str]) -> RepoInfo {
+        let mut info = self;
+        info.path_labels.push(PathLabels {
+            path_glob: path_glob.into(),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+        });
+        info
+    }
 }
 
 impl RepoJiraConfig {
@@ -141,20 +549,60 @@ impl RepoConfig {
         let tx = conn.transaction()?;
 
         tx.execute(
-            r#"INSERT INTO repos (repo, channel, force_push_notify, use_threads, release_branch_prefix)
-               VALUES (?1, ?2, ?3, ?4, ?5)"#,
+            r#"INSERT INTO repos (repo, channel, force_push_notify, use_threads, release_branch_prefix,
+                                  jira_comments_public, default_reviewers, notify_commit_comments,
+                                  jira_check_name, suggested_reviewers, backport_authors,
+                                  skip_reviewer_refetch, discussions_channel,
+                                  version_script_min_commits, jira_check_title,
+                                  jira_check_summary, version_script_mode, frozen_branches,
+                                  message_templates, merge_strategy, channel_overrides,
+                                  version_script_paths, stale_pr_days, backport_label_prefix,
+                                  ignored_users, ignored_users_mode, merged_channel_only,
+                                  reviewer_pool, auto_assign_count, max_commits_for_jira,
+                                  notify_events)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16,
+                       ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30,
+                       ?31)"#,
             [
                 &repo.repo,
                 &repo.channel,
                 &db::to_tinyint(repo.force_push_notify) as &dyn ToSql,
                 &db::to_tinyint(repo.use_threads) as &dyn ToSql,
                 &repo.release_branch_prefix,
+                &db::to_tinyint(repo.jira_comments_public) as &dyn ToSql,
+                &db::from_string_vec(&repo.default_reviewers),
+                &db::to_tinyint(repo.notify_commit_comments) as &dyn ToSql,
+                &repo.jira_check_name,
+                &db::from_string_vec(&repo.suggested_reviewers),
+                &db::from_string_vec(&repo.backport_authors),
+                &db::to_tinyint(repo.skip_reviewer_refetch) as &dyn ToSql,
+                &repo.discussions_channel,
+                &repo.version_script_min_commits,
+                &repo.jira_check_title,
+                &repo.jira_check_summary,
+                &repo.version_script_mode.as_str(),
+                &db::from_string_vec(&repo.frozen_branches),
+                &from_message_templates(&repo.message_templates)?,
+                &repo.merge_strategy.as_str(),
+                &from_channel_overrides(&repo.channel_overrides)?,
+                &db::from_string_vec(&repo.version_script_paths),
+                &repo.stale_pr_days,
+                &repo.backport_label_prefix,
+                &db::from_string_vec(&repo.ignored_users),
+                &repo.ignored_users_mode.as_str(),
+                &db::to_tinyint(repo.merged_channel_only),
+                &db::from_string_vec(&repo.reviewer_pool),
+                &repo.auto_assign_count,
+                &repo.max_commits_for_jira,
+                &from_notify_events(&repo.notify_events),
             ],
         )
         .map_err(|e| anyhow!("Error inserting repo {}: {}", repo.repo, e))?;
 
         let id = tx.last_insert_rowid();
         self.insert_jiras(&tx, id, &repo.jira_config)?;
+        self.insert_branch_jiras(&tx, id, &repo.branch_jira_projects)?;
+        self.insert_path_labels(&tx, id, &repo.path_labels)?;
 
         tx.commit()?;
 
@@ -176,14 +624,66 @@ impl RepoConfig {
                     channel = ?2,
                     force_push_notify = ?3,
                     use_threads = ?4,
-                    release_branch_prefix = ?5
-               WHERE id = ?6"#,
+                    release_branch_prefix = ?5,
+                    jira_comments_public = ?6,
+                    default_reviewers = ?7,
+                    notify_commit_comments = ?8,
+                    jira_check_name = ?9,
+                    suggested_reviewers = ?10,
+                    backport_authors = ?11,
+                    skip_reviewer_refetch = ?12,
+                    discussions_channel = ?13,
+                    version_script_min_commits = ?14,
+                    jira_check_title = ?15,
+                    jira_check_summary = ?16,
+                    version_script_mode = ?17,
+                    frozen_branches = ?18,
+                    message_templates = ?19,
+                    merge_strategy = ?20,
+                    channel_overrides = ?21,
+                    version_script_paths = ?22,
+                    stale_pr_days = ?23,
+                    backport_label_prefix = ?24,
+                    ignored_users = ?25,
+                    ignored_users_mode = ?26,
+                    merged_channel_only = ?27,
+                    reviewer_pool = ?28,
+                    auto_assign_count = ?29,
+                    max_commits_for_jira = ?30,
+                    notify_events = ?31
+               WHERE id = ?32"#,
             [
                 &repo.repo,
                 &repo.channel,
                 &db::to_tinyint(repo.force_push_notify) as &dyn ToSql,
                 &db::to_tinyint(repo.use_threads) as &dyn ToSql,
                 &repo.release_branch_prefix,
+                &db::to_tinyint(repo.jira_comments_public) as &dyn ToSql,
+                &db::from_string_vec(&repo.default_reviewers),
+                &db::to_tinyint(repo.notify_commit_comments) as &dyn ToSql,
+                &repo.jira_check_name,
+                &db::from_string_vec(&repo.suggested_reviewers),
+                &db::from_string_vec(&repo.backport_authors),
+                &db::to_tinyint(repo.skip_reviewer_refetch) as &dyn ToSql,
+                &repo.discussions_channel,
+                &repo.version_script_min_commits,
+                &repo.jira_check_title,
+                &repo.jira_check_summary,
+                &repo.version_script_mode.as_str(),
+                &db::from_string_vec(&repo.frozen_branches),
+                &from_message_templates(&repo.message_templates)?,
+                &repo.merge_strategy.as_str(),
+                &from_channel_overrides(&repo.channel_overrides)?,
+                &db::from_string_vec(&repo.version_script_paths),
+                &repo.stale_pr_days,
+                &repo.backport_label_prefix,
+                &db::from_string_vec(&repo.ignored_users),
+                &repo.ignored_users_mode.as_str(),
+                &db::to_tinyint(repo.merged_channel_only),
+                &db::from_string_vec(&repo.reviewer_pool),
+                &repo.auto_assign_count,
+                &repo.max_commits_for_jira,
+                &from_notify_events(&repo.notify_events),
                 &id,
             ],
         )
@@ -194,6 +694,25 @@ impl RepoConfig {
 
         self.insert_jiras(&tx, id as i64, &repo.jira_config)?;
 
+        tx.execute(
+            r#"DELETE from repos_branch_jiras where repo_id = ?1"#,
+            [&id],
+        )
+        .map_err(|e| {
+            anyhow!(
+                "Error clearing repo branch jira entries {}: {}",
+                repo.repo,
+                e
+            )
+        })?;
+
+        self.insert_branch_jiras(&tx, id as i64, &repo.branch_jira_projects)?;
+
+        tx.execute(r#"DELETE from repos_path_labels where repo_id = ?1"#, [&id])
+            .map_err(|e| anyhow!("Error clearing repo path labels {}: {}", repo.repo, e))?;
+
+        self.insert_path_labels(&tx, id as i64, &repo.path_labels)?;
+
         tx.commit()?;
 
         Ok(())
@@ -223,6 +742,66 @@ impl RepoConfig {
         Ok(())
     }
 
+    fn insert_branch_jiras(
+        &mut self,
+        tx: &Transaction,
+        id: i64,
+        branch_jiras: &[BranchJiraProjects],
+    ) -> Result<()> {
+        for (index, entry) in branch_jiras.iter().enumerate() {
+            tx.execute(
+                r#"INSERT INTO repos_branch_jiras (repo_id, position, branch_regex, jira_projects)
+               VALUES (?1, ?2, ?3, ?4)"#,
+                [
+                    &id,
+                    &(index as i64) as &dyn ToSql,
+                    &entry.branch_regex,
+                    &db::from_string_vec(&entry.jira_projects),
+                ],
+            )
+            .map_err(|e| {
+                anyhow!(
+                    "Error inserting branch jiras {} for repo {}: {}",
+                    entry.branch_regex,
+                    id,
+                    e
+                )
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn insert_path_labels(
+        &mut self,
+        tx: &Transaction,
+        id: i64,
+        path_labels: &[PathLabels],
+    ) -> Result<()> {
+        for (index, entry) in path_labels.iter().enumerate() {
+            tx.execute(
+                r#"INSERT INTO repos_path_labels (repo_id, position, path_glob, labels)
+               VALUES (?1, ?2, ?3, ?4)"#,
+                [
+                    &id,
+                    &(index as i64) as &dyn ToSql,
+                    &entry.path_glob,
+                    &db::from_string_vec(&entry.labels),
+                ],
+            )
+            .map_err(|e| {
+                anyhow!(
+                    "Error inserting path labels {} for repo {}: {}",
+                    entry.path_glob,
+                    id,
+                    e
+                )
+            })?;
+        }
+
+        Ok(())
+    }
+
     pub fn delete(&mut self, id: i32) -> Result<()> {
         let mut conn = self.db.connect()?;
         let tx = conn.transaction()?;
@@ -230,6 +809,12 @@ impl RepoConfig {
         tx.execute("DELETE from repos_jiras where repo_id = ?1", [&id])
             .map_err(|e| anyhow!("Error clearing repo jira entries {}: {}", id, e))?;
 
+        tx.execute("DELETE from repos_branch_jiras where repo_id = ?1", [&id])
+            .map_err(|e| anyhow!("Error clearing repo branch jira entries {}: {}", id, e))?;
+
+        tx.execute("DELETE from repos_path_labels where repo_id = ?1", [&id])
+            .map_err(|e| anyhow!("Error clearing repo path labels {}: {}", id, e))?;
+
         tx.execute("DELETE from repos where id = ?1", [&id])
             .map_err(|e| anyhow!("Error deleting repo {}: {}", id, e))?;
 
@@ -248,6 +833,11 @@ impl RepoConfig {
             Some(i) => i,
         };
 
+        // branch overrides only replace the repo channel, not JIRA project channels
+        let default_channel = info
+            .channel_for_branch(branch)
+            .unwrap_or_else(|| info.channel.clone());
+
         let configs = self.filter_configs(info.jira_config, branch);
 
         let channels = configs
@@ -258,10 +848,10 @@ impl RepoConfig {
             .map(|c| c.channel)
             .collect::<Vec<_>>();
 
-        if channels.is_empty() && info.channel.is_empty() {
+        if channels.is_empty() && default_channel.is_empty() {
             vec![]
         } else if channels.is_empty() {
-            vec![info.channel]
+            vec![default_channel]
         } else {
             channels
         }
@@ -279,50 +869,298 @@ impl RepoConfig {
             .unwrap_or(false)
     }
 
-    pub fn jira_configs(&self, repo: &github::Repo, branch: &str) -> Vec<RepoJiraConfig> {
-        let configs = self
-            .lookup_info(repo)
-            .map(|r| r.jira_config)
-            .unwrap_or_default();
+    pub fn notify_commit_comments(&self, repo: &github::Repo) -> bool {
+        self.lookup_info(repo)
+            .map(|r| r.notify_commit_comments)
+            .unwrap_or(true)
+    }
 
-        self.filter_configs(configs, branch)
+    pub fn default_reviewers(&self, repo: &github::Repo) -> Vec<String> {
+        self.lookup_info(repo)
+            .map(|r| r.default_reviewers)
+            .unwrap_or_default()
     }
 
-    fn filter_configs(&self, configs: Vec<RepoJiraConfig>, branch: &str) -> Vec<RepoJiraConfig> {
-        let mut configs = configs;
-        configs.retain(|c| {
-            github::is_main_branch(branch) || self.matches_branch(branch, &c.release_branch_regex)
-        });
-        configs
+    pub fn suggested_reviewers(&self, repo: &github::Repo) -> Vec<String> {
+        self.lookup_info(repo)
+            .map(|r| r.suggested_reviewers)
+            .unwrap_or_default()
     }
 
-    fn matches_branch(&self, branch: &str, regex: &str) -> bool {
-        match regex::Regex::new(regex) {
-            Ok(r) => r.is_match(branch),
-            Err(e) => {
-                log::error!("Error parsing branch regex: '{}': {}", regex, e);
-                false
-            }
-        }
+    pub fn backport_authors(&self, repo: &github::Repo) -> Vec<String> {
+        self.lookup_info(repo)
+            .map(|r| r.backport_authors)
+            .unwrap_or_default()
     }
 
-    pub fn jira_projects(&self, repo: &github::Repo, branch: &str) -> Vec<String> {
-        self.jira_configs(repo, branch)
-            .into_iter()
-            .map(|c| c.jira_project)
-            .collect::<Vec<_>>()
+    pub fn skip_reviewer_refetch(&self, repo: &github::Repo) -> bool {
+        self.lookup_info(repo)
+            .map(|r| r.skip_reviewer_refetch)
+            .unwrap_or(false)
     }
 
-    pub fn release_branch_prefix(&self, repo: &github::Repo) -> String {
-        let default = "release/".to_string();
-        match self.lookup_info(repo).map(|r| r.release_branch_prefix) {
-            None => default,
-            Some(ref p) if p.is_empty() => default,
-            Some(p) => p,
-        }
+    pub fn merged_channel_only(&self, repo: &github::Repo) -> bool {
+        self.lookup_info(repo)
+            .map(|r| r.merged_channel_only)
+            .unwrap_or(false)
     }
 
-    pub fn get_all(&self) -> Result<Vec<RepoInfo>> {
+    // The next reviewers from the repo's reviewer pool, taken round-robin and skipping the author.
+    // The position in the pool is persisted so that successive PRs go to different reviewers.
+    pub fn next_pool_reviewers(
+        &mut self,
+        repo: &github::Repo,
+        author: &str,
+    ) -> Result<Vec<String>> {
+        let info = match self.lookup_info(repo) {
+            Some(i) if !i.reviewer_pool.is_empty() => i,
+            _ => return Ok(vec![]),
+        };
+        let pool = &info.reviewer_pool;
+        let count = info.auto_assign_count.unwrap_or(1) as usize;
+
+        let conn = self.db.connect()?;
+        let cursor: u32 = conn.query_row(
+            "SELECT reviewer_pool_cursor FROM repos WHERE id = :id",
+            named_params! {":id": &info.id},
+            |row| row.get(0),
+        )?;
+
+        let start = cursor as usize % pool.len();
+        let mut next = start;
+        let mut reviewers: Vec<String> = vec![];
+        for i in 0..pool.len() {
+            if reviewers.len() >= count {
+                break;
+            }
+            let index = (start + i) % pool.len();
+            let reviewer = &pool[index];
+            if reviewer != author && !reviewers.contains(reviewer) {
+                reviewers.push(reviewer.clone());
+                next = (index + 1) % pool.len();
+            }
+        }
+
+        conn.execute(
+            "UPDATE repos SET reviewer_pool_cursor = :cursor WHERE id = :id",
+            named_params! {":cursor": &(next as u32), ":id": &info.id},
+        )
+        .map_err(|e| anyhow!("Error updating reviewer pool for {}: {}", info.repo, e))?;
+
+        Ok(reviewers)
+    }
+
+    pub fn discussions_channel(&self, repo: &github::Repo) -> Option<String> {
+        self.lookup_info(repo)
+            .map(|r| r.discussions_channel)
+            .filter(|c| !c.is_empty())
+    }
+
+    pub fn version_script_min_commits(&self, repo: &github::Repo) -> u32 {
+        self.lookup_info(repo)
+            .map(|r| r.version_script_min_commits)
+            .unwrap_or_else(default_version_script_min_commits)
+    }
+
+    pub fn version_script_mode(&self, repo: &github::Repo) -> VersionScriptMode {
+        self.lookup_info(repo)
+            .map(|r| r.version_script_mode)
+            .unwrap_or_default()
+    }
+
+    pub fn is_frozen_branch(&self, repo: &github::Repo, branch: &str) -> bool {
+        self.lookup_info(repo).is_some_and(|r| {
+            r.frozen_branches
+                .iter()
+                .filter_map(|b| glob_to_regex(b))
+                .any(|re| re.is_match(branch))
+        })
+    }
+
+    pub fn has_version_script_paths(&self, repo: &github::Repo) -> bool {
+        self.lookup_info(repo)
+            .is_some_and(|r| !r.version_script_paths.is_empty())
+    }
+
+    // Whether any of the given changed file paths should trigger the version script
+    pub fn matches_version_script_paths(&self, repo: &github::Repo, paths: &[String]) -> bool {
+        let globs = match self.lookup_info(repo) {
+            Some(i) if !i.version_script_paths.is_empty() => i.version_script_paths,
+            _ => return true,
+        };
+        globs
+            .iter()
+            .filter_map(|g| glob_to_regex(g))
+            .any(|re| paths.iter().any(|p| re.is_match(p)))
+    }
+
+    pub fn stale_pr_days(&self, repo: &github::Repo) -> Option<u32> {
+        self.lookup_info(repo)
+            .and_then(|r| r.stale_pr_days)
+            .filter(|d| *d > 0)
+    }
+
+    // Whether PR events of this kind (e.g. "opened", "merged") are notified about
+    pub fn notifies_event(&self, repo: &github::Repo, event: &str) -> bool {
+        match self.lookup_info(repo).and_then(|r| r.notify_events) {
+            Some(events) => events.iter().any(|e| e == event),
+            None => true,
+        }
+    }
+
+    pub fn max_commits_for_jira(&self, repo: &github::Repo) -> Option<usize> {
+        self.lookup_info(repo).and_then(|r| r.max_commits_for_jira)
+    }
+
+    pub fn channel_for_branch(&self, repo: &github::Repo, branch: &str) -> Option<String> {
+        self.lookup_info(repo)
+            .and_then(|r| r.channel_for_branch(branch))
+    }
+
+    // Whether to skip notifications for the given user, given the global ignored users list
+    pub fn is_ignored_user(&self, repo: &github::Repo, login: &str, global: &[String]) -> bool {
+        let info = self.lookup_info(repo);
+        let in_repo_list = info
+            .as_ref()
+            .is_some_and(|r| r.ignored_users.iter().any(|u| u == login));
+        match info.map(|r| r.ignored_users_mode).unwrap_or_default() {
+            IgnoredUsersMode::Merge => in_repo_list || global.iter().any(|u| u == login),
+            IgnoredUsersMode::Replace => in_repo_list,
+        }
+    }
+
+    pub fn merge_strategy(&self, repo: &github::Repo) -> MergeStrategy {
+        self.lookup_info(repo)
+            .map(|r| r.merge_strategy)
+            .unwrap_or_default()
+    }
+
+    pub fn message_template(&self, repo: &github::Repo, event: &str) -> Option<String> {
+        self.lookup_info(repo)
+            .and_then(|r| r.message_templates)
+            .and_then(|mut t| t.remove(event))
+            .filter(|t| !t.is_empty())
+    }
+
+    pub fn jira_comment_visibility(&self, repo: &github::Repo) -> jira::api::CommentVisibility {
+        let public = self
+            .lookup_info(repo)
+            .map(|r| r.jira_comments_public)
+            .unwrap_or(false);
+
+        if public {
+            jira::api::CommentVisibility::Public
+        } else {
+            jira::api::CommentVisibility::Configured
+        }
+    }
+
+    pub fn jira_configs(&self, repo: &github::Repo, branch: &str) -> Vec<RepoJiraConfig> {
+        let info = match self.lookup_info(repo) {
+            Some(i) => i,
+            None => return vec![],
+        };
+
+        let mut configs = self.filter_configs(info.jira_config, branch);
+
+        // restrict to the first matching branch-scoped subset, if any
+        if let Some(subset) = info
+            .branch_jira_projects
+            .iter()
+            .find(|b| !b.branch_regex.is_empty() && self.matches_branch(branch, &b.branch_regex))
+        {
+            configs.retain(|c| subset.jira_projects.contains(&c.jira_project));
+        }
+
+        configs
+    }
+
+    fn filter_configs(&self, configs: Vec<RepoJiraConfig>, branch: &str) -> Vec<RepoJiraConfig> {
+        let mut configs = configs;
+        configs.retain(|c| {
+            github::is_main_branch(branch) || self.matches_branch(branch, &c.release_branch_regex)
+        });
+        configs
+    }
+
+    fn matches_branch(&self, branch: &str, regex: &str) -> bool {
+        match regex::Regex::new(regex) {
+            Ok(r) => r.is_match(branch),
+            Err(e) => {
+                log::error!("Error parsing branch regex: '{}': {}", regex, e);
+                false
+            }
+        }
+    }
+
+    pub fn jira_projects(&self, repo: &github::Repo, branch: &str) -> Vec<String> {
+        self.jira_configs(repo, branch)
+            .into_iter()
+            .map(|c| c.jira_project)
+            .collect::<Vec<_>>()
+    }
+
+    pub fn backport_label_prefix(&self, repo: &github::Repo) -> String {
+        self.lookup_info(repo)
+            .and_then(|r| r.backport_label_prefix)
+            .filter(|p| !p.is_empty())
+            .unwrap_or_else(|| "backport-".into())
+    }
+
+    pub fn release_branch_prefix(&self, repo: &github::Repo) -> String {
+        let default = "release/".to_string();
+        match self.lookup_info(repo).map(|r| r.release_branch_prefix) {
+            None => default,
+            Some(ref p) if p.is_empty() => default,
+            Some(p) => p,
+        }
+    }
+
+    pub fn jira_check_name(&self, repo: &github::Repo) -> String {
+        match self.lookup_info(repo).map(|r| r.jira_check_name) {
+            Some(ref n) if !n.is_empty() => n.clone(),
+            _ => jira::JIRA_REF_CONTEXT.to_string(),
+        }
+    }
+
+    pub fn jira_check_template(&self, repo: &github::Repo) -> jira::MissingRefTemplate {
+        match self.lookup_info(repo) {
+            Some(r) => jira::MissingRefTemplate::new(&r.jira_check_title, &r.jira_check_summary),
+            None => jira::MissingRefTemplate::default(),
+        }
+    }
+
+    pub fn has_path_labels(&self, repo: &github::Repo) -> bool {
+        self.lookup_info(repo)
+            .is_some_and(|r| !r.path_labels.is_empty())
+    }
+
+    // Labels configured for any of the given changed file paths, in config order
+    pub fn path_labels(&self, repo: &github::Repo, paths: &[String]) -> Vec<String> {
+        let info = match self.lookup_info(repo) {
+            Some(i) => i,
+            None => return vec![],
+        };
+
+        let mut labels: Vec<String> = vec![];
+        for entry in &info.path_labels {
+            let regex = match glob_to_regex(&entry.path_glob) {
+                Some(r) => r,
+                None => continue,
+            };
+            if paths.iter().any(|p| regex.is_match(p)) {
+                for label in &entry.labels {
+                    if !labels.contains(label) {
+                        labels.push(label.clone());
+                    }
+                }
+            }
+        }
+
+        labels
+    }
+
+    pub fn get_all(&self) -> Result<Vec<RepoInfo>> {
         let conn = self.db.connect()?;
         let mut stmt = conn.prepare("SELECT * FROM repos ORDER BY repo")?;
         let cols = db::Columns::from_stmt(&stmt)?;
@@ -336,6 +1174,19 @@ impl RepoConfig {
         Ok(repos)
     }
 
+    // Looks up the config stored under exactly this name, e.g. "some-org/some-repo" or "some-org"
+    pub fn lookup_by_name(&self, repo: &str) -> Result<Option<RepoInfo>> {
+        let conn = self.db.connect()?;
+        let mut stmt = conn.prepare("SELECT * FROM repos where repo = ?1")?;
+        let cols = db::Columns::from_stmt(&stmt)?;
+        let mut rows = stmt.query([repo])?;
+
+        match rows.next()? {
+            Some(row) => Ok(Some(self.map_row(&conn, row, &cols)?)),
+            None => Ok(None),
+        }
+    }
+
     fn lookup_info(&self, repo: &github::Repo) -> Option<RepoInfo> {
         match self.do_lookup_info(repo) {
             Ok(u) => u,
@@ -377,6 +1228,8 @@ impl RepoConfig {
     fn map_row(&self, conn: &Connection, row: &Row, cols: &db::Columns) -> Result<RepoInfo> {
         let id = cols.get(row, "id")?;
         let jira_config = self.load_jira_config(conn, id)?;
+        let branch_jira_projects = self.load_branch_jira_projects(conn, id)?;
+        let path_labels = self.load_path_labels(conn, id)?;
 
         Ok(RepoInfo {
             id: Some(id),
@@ -386,9 +1239,79 @@ impl RepoConfig {
             use_threads: db::to_bool(cols.get(row, "use_threads")?),
             jira_config,
             release_branch_prefix: cols.get(row, "release_branch_prefix")?,
+            jira_comments_public: db::to_bool(cols.get(row, "jira_comments_public")?),
+            default_reviewers: db::to_string_vec(cols.get(row, "default_reviewers")?),
+            notify_commit_comments: db::to_bool(cols.get(row, "notify_commit_comments")?),
+            jira_check_name: cols.get(row, "jira_check_name")?,
+            suggested_reviewers: db::to_string_vec(cols.get(row, "suggested_reviewers")?),
+            branch_jira_projects,
+            backport_authors: db::to_string_vec(cols.get(row, "backport_authors")?),
+            skip_reviewer_refetch: db::to_bool(cols.get(row, "skip_reviewer_refetch")?),
+            discussions_channel: cols.get(row, "discussions_channel")?,
+            version_script_min_commits: cols.get(row, "version_script_min_commits")?,
+            jira_check_title: cols.get(row, "jira_check_title")?,
+            jira_check_summary: cols.get(row, "jira_check_summary")?,
+            path_labels,
+            version_script_mode: VersionScriptMode::parse(
+                &cols.get::<String>(row, "version_script_mode")?,
+            ),
+            frozen_branches: db::to_string_vec(cols.get(row, "frozen_branches")?),
+            message_templates: to_message_templates(cols.get(row, "message_templates")?)?,
+            merge_strategy: MergeStrategy::parse(&cols.get::<String>(row, "merge_strategy")?),
+            channel_overrides: to_channel_overrides(cols.get(row, "channel_overrides")?)?,
+            version_script_paths: db::to_string_vec(cols.get(row, "version_script_paths")?),
+            stale_pr_days: cols.get(row, "stale_pr_days")?,
+            backport_label_prefix: cols.get(row, "backport_label_prefix")?,
+            ignored_users: db::to_string_vec(cols.get(row, "ignored_users")?),
+            ignored_users_mode: IgnoredUsersMode::parse(
+                &cols.get::<String>(row, "ignored_users_mode")?,
+            ),
+            merged_channel_only: db::to_bool(cols.get(row, "merged_channel_only")?),
+            reviewer_pool: db::to_string_vec(cols.get(row, "reviewer_pool")?),
+            auto_assign_count: cols.get(row, "auto_assign_count")?,
+            max_commits_for_jira: cols.get(row, "max_commits_for_jira")?,
+            notify_events: to_notify_events(cols.get(row, "notify_events")?),
         })
     }
 
+    fn load_path_labels(&self, conn: &Connection, id: i32) -> Result<Vec<PathLabels>> {
+        let mut stmt = conn
+            .prepare(r#"SELECT * FROM repos_path_labels where repo_id = :id ORDER BY position"#)?;
+        let cols = db::Columns::from_stmt(&stmt)?;
+        let mut rows = stmt.query(named_params! {":id": &id})?;
+
+        let mut result = vec![];
+        while let Ok(Some(row)) = rows.next() {
+            result.push(PathLabels {
+                path_glob: cols.get(row, "path_glob")?,
+                labels: db::to_string_vec(cols.get(row, "labels")?),
+            });
+        }
+
+        Ok(result)
+    }
+
+    fn load_branch_jira_projects(
+        &self,
+        conn: &Connection,
+        id: i32,
+    ) -> Result<Vec<BranchJiraProjects>> {
+        let mut stmt = conn
+            .prepare(r#"SELECT * FROM repos_branch_jiras where repo_id = :id ORDER BY position"#)?;
+        let cols = db::Columns::from_stmt(&stmt)?;
+        let mut rows = stmt.query(named_params! {":id": &id})?;
+
+        let mut result = vec![];
+        while let Ok(Some(row)) = rows.next() {
+            result.push(BranchJiraProjects {
+                branch_regex: cols.get(row, "branch_regex")?,
+                jira_projects: db::to_string_vec(cols.get(row, "jira_projects")?),
+            });
+        }
+
+        Ok(result)
+    }
+
     fn load_jira_config(&self, conn: &Connection, id: i32) -> Result<Vec<RepoJiraConfig>> {
         let mut stmt = conn.prepare(r#"SELECT * FROM repos_jiras where repo_id = :id"#)?;
         let cols = db::Columns::from_stmt(&stmt)?;
@@ -410,6 +1333,88 @@ impl RepoConfig {
     }
 }
 
+// Message templates are stored as a JSON object, or blank when there are none
+fn from_message_templates(templates: &Option<HashMap<String, String>>) -> Result<String> {
+    match templates {
+        Some(t) => serde_json::to_string(t)
+            .map_err(|e| anyhow!("Error serializing message templates: {}", e)),
+        None => Ok(String::new()),
+    }
+}
+
+fn to_message_templates(value: String) -> Result<Option<HashMap<String, String>>> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+    serde_json::from_str(&value)
+        .map(Some)
+        .map_err(|e| anyhow!("Error parsing message templates: {}", e))
+}
+
+// Notify events are stored as a comma separated list, or blank when unset
+fn from_notify_events(events: &Option<Vec<String>>) -> String {
+    match events {
+        Some(e) => db::from_string_vec(e),
+        None => String::new(),
+    }
+}
+
+fn to_notify_events(value: String) -> Option<Vec<String>> {
+    Some(db::to_string_vec(value)).filter(|e| !e.is_empty())
+}
+
+// Channel overrides are stored as a JSON list of [glob, channel] pairs, or blank when there are none
+fn from_channel_overrides(overrides: &[(String, String)]) -> Result<String> {
+    if overrides.is_empty() {
+        return Ok(String::new());
+    }
+    serde_json::to_string(overrides)
+        .map_err(|e| anyhow!("Error serializing channel overrides: {}", e))
+}
+
+fn to_channel_overrides(value: String) -> Result<Vec<(String, String)>> {
+    if value.is_empty() {
+        return Ok(vec![]);
+    }
+    serde_json::from_str(&value).map_err(|e| anyhow!("Error parsing channel overrides: {}", e))
+}
+
+// Converts a path glob into an anchored regex: "**" matches across directories, "*" and "?"
+// match within a single path component.
+fn glob_to_regex(glob: &str) -> Option<regex::Regex> {
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => {
+                if chars.peek() == Some(&'*') {
+                    chars.next();
+                    // "**/" also matches zero directories
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        pattern.push_str("(.*/)?");
+                    } else {
+                        pattern.push_str(".*");
+                    }
+                } else {
+                    pattern.push_str("[^/]*");
+                }
+            }
+            '?' => pattern.push_str("[^/]"),
+            _ => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+
+    match regex::Regex::new(&pattern) {
+        Ok(r) => Some(r),
+        Err(e) => {
+            log::error!("Error parsing path glob: '{}': {}", glob, e);
+            None
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -632,6 +1637,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_jira_comment_visibility() {
+        let (mut repos, _temp) = new_test();
+        repos
+            .insert_info(&RepoInfo::new("some-user/the-default", "reviews"))
+            .unwrap();
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/public-repo", "reviews").with_jira_comments_public(true),
+            )
+            .unwrap();
+
+        {
+            let repo =
+                github::Repo::parse("http://git.company.com/someone-else/some-other-repo").unwrap();
+            assert_eq!(
+                jira::api::CommentVisibility::Configured,
+                repos.jira_comment_visibility(&repo)
+            );
+        }
+
+        {
+            let repo = github::Repo::parse("http://git.company.com/some-user/the-default").unwrap();
+            assert_eq!(
+                jira::api::CommentVisibility::Configured,
+                repos.jira_comment_visibility(&repo)
+            );
+        }
+
+        {
+            let repo = github::Repo::parse("http://git.company.com/some-user/public-repo").unwrap();
+            assert_eq!(
+                jira::api::CommentVisibility::Public,
+                repos.jira_comment_visibility(&repo)
+            );
+        }
+    }
+
     #[test]
     fn test_jira_enabled() {
         let (mut repos, _temp) = new_test();
@@ -737,6 +1780,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_branch_jira_projects() {
+        let (mut repos, _temp) = new_test();
+
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/the-repo", "the-repo-reviews")
+                    .with_jira("SER")
+                    .with_jira("CLI")
+                    .with_jira("LIB")
+                    .with_branch_jira_projects("^team-cli/", &["CLI"])
+                    .with_branch_jira_projects("^team-", &["SER", "LIB"]),
+            )
+            .unwrap();
+
+        let repo = github::Repo::parse("http://git.company.com/some-user/the-repo").unwrap();
+
+        // unrestricted branches use the full list
+        assert_eq!(
+            vec!["CLI", "LIB", "SER"],
+            repos.jira_projects(&repo, "master")
+        );
+        assert_eq!(
+            vec!["CLI", "LIB", "SER"],
+            repos.jira_projects(&repo, "feature-branch")
+        );
+
+        // first matching entry wins
+        assert_eq!(vec!["CLI"], repos.jira_projects(&repo, "team-cli/feature"));
+        assert_eq!(
+            vec!["LIB", "SER"],
+            repos.jira_projects(&repo, "team-server/feature")
+        );
+
+        // survives an update
+        let info = repos.get_all().unwrap().remove(0);
+        assert_eq!(2, info.branch_jira_projects.len());
+        repos
+            .update(&info.with_branch_jira_projects("^master$", &["LIB"]))
+            .unwrap();
+        assert_eq!(vec!["LIB"], repos.jira_projects(&repo, "master"));
+        assert_eq!(vec!["CLI"], repos.jira_projects(&repo, "team-cli/feature"));
+    }
+
     #[test]
     fn test_repos_update() {
         let (mut repos, _temp) = new_test();
@@ -752,4 +1839,487 @@ mod tests {
         assert_eq!(1, all.len());
         assert_eq!("new-channel", all[0].channel);
     }
+
+    #[test]
+    fn test_default_reviewers() {
+        let (mut repos, _temp) = new_test();
+        repos
+            .insert_info(&RepoInfo::new("some-user/the-default", "reviews"))
+            .unwrap();
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/reviewed-repo", "reviews")
+                    .with_default_reviewers(vec!["joe".into(), "bob".into()]),
+            )
+            .unwrap();
+
+        {
+            let repo = github::Repo::parse("http://git.company.com/some-user/the-default").unwrap();
+            assert_eq!(Vec::<String>::new(), repos.default_reviewers(&repo));
+        }
+
+        {
+            let repo =
+                github::Repo::parse("http://git.company.com/some-user/reviewed-repo").unwrap();
+            assert_eq!(vec!["joe", "bob"], repos.default_reviewers(&repo));
+
+            let mut info = repos.lookup_info(&repo).unwrap();
+            info.default_reviewers = vec!["alice".into()];
+            repos.update(&info).unwrap();
+            assert_eq!(vec!["alice"], repos.default_reviewers(&repo));
+        }
+    }
+
+    #[test]
+    fn test_lookup_by_name() {
+        let (mut repos, _temp) = new_test();
+        repos.insert("some-user", "org-channel").unwrap();
+        repos.insert("some-user/the-repo", "repo-channel").unwrap();
+
+        let info = repos.lookup_by_name("some-user/the-repo").unwrap().unwrap();
+        assert_eq!("repo-channel", info.channel);
+        assert!(info.id.is_some());
+
+        let info = repos.lookup_by_name("some-user").unwrap().unwrap();
+        assert_eq!("org-channel", info.channel);
+
+        assert!(repos
+            .lookup_by_name("some-user/other-repo")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_next_pool_reviewers() {
+        let (mut repos, _temp) = new_test();
+        repos
+            .insert_info(&RepoInfo::new("some-user/the-default", "reviews"))
+            .unwrap();
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/pooled-repo", "reviews")
+                    .with_reviewer_pool(vec!["joe".into(), "bob".into(), "alice".into()], Some(2)),
+            )
+            .unwrap();
+
+        {
+            let repo = github::Repo::parse("http://git.company.com/some-user/the-default").unwrap();
+            assert_eq!(
+                Vec::<String>::new(),
+                repos.next_pool_reviewers(&repo, "joe").unwrap()
+            );
+        }
+
+        {
+            let repo = github::Repo::parse("http://git.company.com/some-user/pooled-repo").unwrap();
+            assert_eq!(
+                vec!["joe", "bob"],
+                repos.next_pool_reviewers(&repo, "sam").unwrap()
+            );
+            // the author is skipped
+            assert_eq!(
+                vec!["joe", "bob"],
+                repos.next_pool_reviewers(&repo, "alice").unwrap()
+            );
+            assert_eq!(
+                vec!["alice", "joe"],
+                repos.next_pool_reviewers(&repo, "sam").unwrap()
+            );
+
+            // the cursor survives config updates
+            let mut info = repos.lookup_info(&repo).unwrap();
+            info.auto_assign_count = None;
+            repos.update(&info).unwrap();
+            assert_eq!(
+                vec!["bob"],
+                repos.next_pool_reviewers(&repo, "sam").unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_notify_commit_comments() {
+        let (mut repos, _temp) = new_test();
+        repos
+            .insert_info(&RepoInfo::new("some-user/the-default", "reviews"))
+            .unwrap();
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/quiet-repo", "reviews")
+                    .with_notify_commit_comments(false),
+            )
+            .unwrap();
+
+        let repo = github::Repo::parse("http://git.company.com/some-user/the-default").unwrap();
+        assert!(repos.notify_commit_comments(&repo));
+
+        let repo = github::Repo::parse("http://git.company.com/some-user/quiet-repo").unwrap();
+        assert!(!repos.notify_commit_comments(&repo));
+    }
+
+    #[test]
+    fn test_jira_check_name() {
+        let (mut repos, _temp) = new_test();
+        repos
+            .insert_info(&RepoInfo::new("some-user/the-default", "reviews"))
+            .unwrap();
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/the-custom", "reviews")
+                    .with_jira_check_name("ci/jira-ref".into()),
+            )
+            .unwrap();
+
+        let repo = github::Repo::parse("http://git.company.com/some-user/the-default").unwrap();
+        assert_eq!("jira", repos.jira_check_name(&repo));
+
+        let repo = github::Repo::parse("http://git.company.com/some-user/the-custom").unwrap();
+        assert_eq!("ci/jira-ref", repos.jira_check_name(&repo));
+
+        let repo = github::Repo::parse("http://git.company.com/some-user/unknown").unwrap();
+        assert_eq!("jira", repos.jira_check_name(&repo));
+    }
+
+    #[test]
+    fn test_merge_strategy() {
+        let (mut repos, _temp) = new_test();
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/the-repo", "reviews")
+                    .with_merge_strategy(MergeStrategy::Squash),
+            )
+            .unwrap();
+
+        let repo = github::Repo::parse("http://git.company.com/some-user/the-repo").unwrap();
+        assert_eq!(MergeStrategy::Squash, repos.merge_strategy(&repo));
+
+        let other = github::Repo::parse("http://git.company.com/some-user/other-repo").unwrap();
+        assert_eq!(MergeStrategy::Merge, repos.merge_strategy(&other));
+    }
+
+    #[test]
+    fn test_message_templates() {
+        let (mut repos, _temp) = new_test();
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/the-repo", "reviews")
+                    .with_message_template("pr_opened", "{user} opened #{number}: {title}"),
+            )
+            .unwrap();
+        repos.insert("some-user/plain-repo", "reviews").unwrap();
+
+        let repo = github::Repo::parse("http://git.company.com/some-user/the-repo").unwrap();
+        assert_eq!(
+            Some("{user} opened #{number}: {title}".to_string()),
+            repos.message_template(&repo, "pr_opened")
+        );
+        assert_eq!(None, repos.message_template(&repo, "pr_merged"));
+
+        let plain = github::Repo::parse("http://git.company.com/some-user/plain-repo").unwrap();
+        assert_eq!(None, repos.message_template(&plain, "pr_opened"));
+        assert!(repos.get_all().unwrap()[0].message_templates.is_none());
+
+        // survives an update
+        let info = repos.get_all().unwrap().remove(1);
+        assert_eq!("some-user/the-repo", info.repo);
+        repos
+            .update(&info.with_message_template("pr_merged", "Merged: {title}"))
+            .unwrap();
+        assert_eq!(
+            Some("{user} opened #{number}: {title}".to_string()),
+            repos.message_template(&repo, "pr_opened")
+        );
+        assert_eq!(
+            Some("Merged: {title}".to_string()),
+            repos.message_template(&repo, "pr_merged")
+        );
+    }
+
+    #[test]
+    fn test_channel_overrides() {
+        let (mut repos, _temp) = new_test();
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/the-repo", "the-team")
+                    .with_channel_override("release/1.0", "legacy-releases")
+                    .with_channel_override("release/*", "releases")
+                    .with_channel_override("release/2.*", "never-matched"),
+            )
+            .unwrap();
+
+        let repo = github::Repo::parse("http://git.company.com/some-user/the-repo").unwrap();
+        let no_commits = Vec::<github::Commit>::new();
+
+        // first match wins
+        assert_eq!(
+            Some("legacy-releases".to_string()),
+            repos.channel_for_branch(&repo, "release/1.0")
+        );
+        assert_eq!(
+            Some("releases".to_string()),
+            repos.channel_for_branch(&repo, "release/2.0")
+        );
+        assert_eq!(None, repos.channel_for_branch(&repo, "feature/thing"));
+
+        assert_eq!(
+            vec!["releases"],
+            repos.lookup_channels(&repo, "release/2.0", &no_commits)
+        );
+        assert_eq!(
+            vec!["the-team"],
+            repos.lookup_channels(&repo, "feature/thing", &no_commits)
+        );
+
+        // survives an update
+        let info = repos.get_all().unwrap().remove(0);
+        assert_eq!(3, info.channel_overrides.len());
+        repos
+            .update(&info.with_channel_override("hotfix", "hotfixes"))
+            .unwrap();
+        assert_eq!(
+            Some("hotfixes".to_string()),
+            repos.channel_for_branch(&repo, "hotfix")
+        );
+
+        let other = github::Repo::parse("http://git.company.com/some-user/other-repo").unwrap();
+        assert_eq!(None, repos.channel_for_branch(&other, "release/1.0"));
+    }
+
+    #[test]
+    fn test_channel_overrides_jira_channels() {
+        let (mut repos, _temp) = new_test();
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/the-repo", "the-team")
+                    .with_channel_override("release/*", "releases")
+                    .with_jira_config(
+                        RepoJiraConfig::new("SER")
+                            .with_release_branch_regex("release/.*")
+                            .with_channel("server-reviews"),
+                    ),
+            )
+            .unwrap();
+
+        let repo = github::Repo::parse("http://git.company.com/some-user/the-repo").unwrap();
+        let mut server_commit = github::Commit::new();
+        server_commit.commit = github::CommitDetails {
+            message: "[SER-123] Do stuff".to_owned(),
+        };
+        let mut other_commit = github::Commit::new();
+        other_commit.commit = github::CommitDetails {
+            message: "[OTHER-123] Do stuff".to_owned(),
+        };
+
+        // JIRA project channels still take precedence over the branch channel
+        assert_eq!(
+            vec!["server-reviews"],
+            repos.lookup_channels(&repo, "release/1.0", &[server_commit])
+        );
+        assert_eq!(
+            vec!["releases"],
+            repos.lookup_channels(&repo, "release/1.0", &[other_commit])
+        );
+    }
+
+    #[test]
+    fn test_frozen_branches() {
+        let (mut repos, _temp) = new_test();
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/the-repo", "reviews")
+                    .with_frozen_branches(vec!["release/*".into(), "hotfix".into()]),
+            )
+            .unwrap();
+
+        let repo = github::Repo::parse("http://git.company.com/some-user/the-repo").unwrap();
+        assert!(repos.is_frozen_branch(&repo, "release/1.0"));
+        assert!(repos.is_frozen_branch(&repo, "hotfix"));
+        assert!(!repos.is_frozen_branch(&repo, "master"));
+        assert!(!repos.is_frozen_branch(&repo, "release/1.0/extra"));
+
+        let other = github::Repo::parse("http://git.company.com/some-user/other-repo").unwrap();
+        assert!(!repos.is_frozen_branch(&other, "release/1.0"));
+    }
+
+    #[test]
+    fn test_path_labels() {
+        let (mut repos, _temp) = new_test();
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/the-repo", "reviews")
+                    .with_path_labels("docs/**", &["docs"])
+                    .with_path_labels("src/*.rs", &["rust", "code"])
+                    .with_path_labels("**/Cargo.toml", &["deps", "code"]),
+            )
+            .unwrap();
+
+        let repo = github::Repo::parse("http://git.company.com/some-user/the-repo").unwrap();
+        assert!(repos.has_path_labels(&repo));
+
+        let paths = |p: &[&str]| p.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            vec!["docs"],
+            repos.path_labels(&repo, &paths(&["docs/guide/intro.md"]))
+        );
+        assert_eq!(
+            vec!["rust", "code", "deps"],
+            repos.path_labels(&repo, &paths(&["src/main.rs", "Cargo.toml"]))
+        );
+        assert_eq!(
+            vec!["deps", "code"],
+            repos.path_labels(&repo, &paths(&["src/nested/lib.rs", "lib/Cargo.toml"]))
+        );
+        assert_eq!(
+            Vec::<String>::new(),
+            repos.path_labels(&repo, &paths(&["README.md"]))
+        );
+
+        let repo = github::Repo::parse("http://git.company.com/some-user/other-repo").unwrap();
+        assert!(!repos.has_path_labels(&repo));
+    }
+
+    #[test]
+    fn test_version_script_paths() {
+        let (mut repos, _temp) = new_test();
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/the-repo", "reviews")
+                    .with_version_script_paths(vec!["src/**".into(), "Cargo.toml".into()]),
+            )
+            .unwrap();
+
+        let repo = github::Repo::parse("http://git.company.com/some-user/the-repo").unwrap();
+        assert!(repos.has_version_script_paths(&repo));
+        assert_eq!(
+            vec!["src/**", "Cargo.toml"],
+            repos.get_all().unwrap()[0].version_script_paths
+        );
+
+        let paths = |p: &[&str]| p.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(repos.matches_version_script_paths(&repo, &paths(&["src/server/main.rs"])));
+        assert!(repos.matches_version_script_paths(&repo, &paths(&["README.md", "Cargo.toml"])));
+        assert!(!repos.matches_version_script_paths(&repo, &paths(&["docs/Cargo.toml"])));
+        assert!(!repos.matches_version_script_paths(&repo, &paths(&["README.md"])));
+
+        // no paths configured: anything matches
+        let repo = github::Repo::parse("http://git.company.com/some-user/other-repo").unwrap();
+        assert!(!repos.has_version_script_paths(&repo));
+        assert!(repos.matches_version_script_paths(&repo, &paths(&["README.md"])));
+    }
+
+    #[test]
+    fn test_stale_pr_days() {
+        let (mut repos, _temp) = new_test();
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/the-repo", "reviews").with_stale_pr_days(Some(3)),
+            )
+            .unwrap();
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/zero-repo", "reviews").with_stale_pr_days(Some(0)),
+            )
+            .unwrap();
+        repos
+            .insert_info(&RepoInfo::new("some-user/other-repo", "reviews"))
+            .unwrap();
+
+        let repo = |name: &str| {
+            github::Repo::parse(&format!("http://git.company.com/some-user/{}", name)).unwrap()
+        };
+        assert_eq!(Some(3), repos.stale_pr_days(&repo("the-repo")));
+        assert_eq!(None, repos.stale_pr_days(&repo("zero-repo")));
+        assert_eq!(None, repos.stale_pr_days(&repo("other-repo")));
+    }
+
+    #[test]
+    fn test_notifies_event() {
+        let (mut repos, _temp) = new_test();
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/the-repo", "reviews")
+                    .with_notify_events(Some(vec!["review_requested".into(), "merged".into()])),
+            )
+            .unwrap();
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/empty-repo", "reviews").with_notify_events(Some(vec![])),
+            )
+            .unwrap();
+        repos
+            .insert_info(&RepoInfo::new("some-user/other-repo", "reviews"))
+            .unwrap();
+
+        let repo = |name: &str| {
+            github::Repo::parse(&format!("http://git.company.com/some-user/{}", name)).unwrap()
+        };
+        assert!(repos.notifies_event(&repo("the-repo"), "merged"));
+        assert!(repos.notifies_event(&repo("the-repo"), "review_requested"));
+        assert!(!repos.notifies_event(&repo("the-repo"), "opened"));
+
+        // an empty list is the same as unset
+        assert!(repos.notifies_event(&repo("empty-repo"), "opened"));
+        assert!(repos.notifies_event(&repo("other-repo"), "opened"));
+    }
+
+    #[test]
+    fn test_backport_label_prefix() {
+        let (mut repos, _temp) = new_test();
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/the-repo", "reviews")
+                    .with_backport_label_prefix("cherry-pick/"),
+            )
+            .unwrap();
+        repos
+            .insert_info(&RepoInfo::new("some-user/other-repo", "reviews"))
+            .unwrap();
+
+        let repo = |name: &str| {
+            github::Repo::parse(&format!("http://git.company.com/some-user/{}", name)).unwrap()
+        };
+        assert_eq!(
+            "cherry-pick/",
+            repos.backport_label_prefix(&repo("the-repo"))
+        );
+        assert_eq!(
+            "backport-",
+            repos.backport_label_prefix(&repo("other-repo"))
+        );
+    }
+
+    #[test]
+    fn test_ignored_users() {
+        let (mut repos, _temp) = new_test();
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/merge-repo", "reviews")
+                    .with_ignored_users(vec!["repo-bot".into()], IgnoredUsersMode::Merge),
+            )
+            .unwrap();
+        repos
+            .insert_info(
+                &RepoInfo::new("some-user/replace-repo", "reviews")
+                    .with_ignored_users(vec!["repo-bot".into()], IgnoredUsersMode::Replace),
+            )
+            .unwrap();
+        repos
+            .insert_info(&RepoInfo::new("some-user/other-repo", "reviews"))
+            .unwrap();
+
+        let repo = |name: &str| {
+            github::Repo::parse(&format!("http://git.company.com/some-user/{}", name)).unwrap()
+        };
+        let global = vec!["global-bot".to_string()];
+
+        assert!(repos.is_ignored_user(&repo("merge-repo"), "repo-bot", &global));
+        assert!(repos.is_ignored_user(&repo("merge-repo"), "global-bot", &global));
+        assert!(!repos.is_ignored_user(&repo("merge-repo"), "someone", &global));
+
+        assert!(repos.is_ignored_user(&repo("replace-repo"), "repo-bot", &global));
+        assert!(!repos.is_ignored_user(&repo("replace-repo"), "global-bot", &global));
+
+        assert!(!repos.is_ignored_user(&repo("other-repo"), "repo-bot", &global));
+        assert!(repos.is_ignored_user(&repo("other-repo"), "global-bot", &global));
+    }
 }
@@ -18,6 +18,11 @@ pub struct UserInfo {
     pub mute_direct_messages: bool,
     pub mute_team_direct_messages: bool,
     pub muted_repos: Vec<String>,
+    // reviews requested while out of office point to the backup reviewer
+    #[serde(default)]
+    pub out_of_office: bool,
+    #[serde(default)]
+    pub backup_reviewer: String,
 }
 
 #[derive(Clone)]
@@ -42,8 +47,17 @@ impl UserInfo {
             mute_direct_messages: false,
             mute_team_direct_messages: false,
             muted_repos,
+            out_of_office: false,
+            backup_reviewer: String::new(),
         }
     }
+
+    pub fn with_out_of_office(self, backup_reviewer: &str) -> UserInfo {
+        let mut info = self;
+        info.out_of_office = true;
+        info.backup_reviewer = backup_reviewer.to_string();
+        info
+    }
 }
 
 impl UserConfig {
@@ -58,7 +72,7 @@ impl UserConfig {
     pub fn insert_info(&mut self, user: &UserInfo) -> Result<()> {
         let conn = self.db.connect()?;
         conn.execute(
-            "INSERT INTO users (github_name, slack_name, slack_id, email, mute_direct_messages, muted_repos, mute_team_dm) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            "INSERT INTO users (github_name, slack_name, slack_id, email, mute_direct_messages, muted_repos, mute_team_dm, out_of_office, backup_reviewer) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
             [
                 &user.github,
                 &user.slack_name,
@@ -67,6 +81,8 @@ impl UserConfig {
                 &db::to_tinyint(user.mute_direct_messages) as &dyn ToSql,
                 &user.muted_repos.join(","),
                 &db::to_tinyint(user.mute_team_direct_messages) as &dyn ToSql,
+                &db::to_tinyint(user.out_of_office) as &dyn ToSql,
+                &user.backup_reviewer,
             ],
         )
         .map_err(|e| anyhow!("Error inserting user {}: {}", user.github, e))?;
@@ -77,7 +93,7 @@ impl UserConfig {
     pub fn update(&mut self, user: &UserInfo) -> Result<()> {
         let conn = self.db.connect()?;
         conn.execute(
-            "UPDATE users set github_name = ?1, slack_name = ?2, slack_id = ?3, email = ?4, mute_direct_messages = ?5, muted_repos = ?6, mute_team_dm = ?8  where id = ?7",
+            "UPDATE users set github_name = ?1, slack_name = ?2, slack_id = ?3, email = ?4, mute_direct_messages = ?5, muted_repos = ?6, mute_team_dm = ?8, out_of_office = ?9, backup_reviewer = ?10 where id = ?7",
             [
                 &user.github,
                 &user.slack_name,
@@ -86,7 +102,9 @@ impl UserConfig {
                 &db::to_tinyint(user.mute_direct_messages) as &dyn ToSql,
                 &user.muted_repos.join(","),
                 &user.id,
-                &db::to_tinyint(user.mute_team_direct_messages) as &dyn ToSql
+                &db::to_tinyint(user.mute_team_direct_messages) as &dyn ToSql,
+                &db::to_tinyint(user.out_of_office) as &dyn ToSql,
+                &user.backup_reviewer,
             ],
         ).map_err(|e| anyhow!("Error updating user {}: {}", user.github, e))?;
 
@@ -125,10 +143,29 @@ impl UserConfig {
         })
     }
 
+    // The backup reviewer to suggest in place of an out of office reviewer
+    pub fn backup_reviewer(&self, github_name: &str) -> Option<String> {
+        self.lookup_info(github_name)
+            .filter(|u| u.out_of_office && !u.backup_reviewer.is_empty())
+            .map(|u| u.backup_reviewer)
+    }
+
+    // The JIRA username for a user: their email, without the JIRA login suffix if it has one
+    pub fn jira_username(&self, github_name: &str, login_suffix: Option<&str>) -> Option<String> {
+        self.lookup_info(github_name)
+            .filter(|u| !u.email.is_empty())
+            .map(|u| match login_suffix {
+                Some(suffix) if !suffix.is_empty() => {
+                    u.email.strip_suffix(suffix).unwrap_or(&u.email).to_string()
+                }
+                _ => u.email,
+            })
+    }
+
     pub fn get_all(&self) -> Result<Vec<UserInfo>> {
         let conn = self.db.connect()?;
         let mut stmt = conn.prepare(
-            "SELECT id, slack_name, slack_id, email, github_name, mute_direct_messages, muted_repos, mute_team_dm FROM users ORDER BY github_name",
+            "SELECT id, slack_name, slack_id, email, github_name, mute_direct_messages, muted_repos, mute_team_dm, out_of_office, backup_reviewer FROM users ORDER BY github_name",
         )?;
         let found = stmt.query_map([], |row| {
             Ok(UserInfo {
@@ -145,6 +182,8 @@ impl UserConfig {
                     .map(|s| s.trim().to_owned())
                     .filter(|s| !s.is_empty())
                     .collect(),
+                out_of_office: db::to_bool(row.get(8)?),
+                backup_reviewer: row.get(9)?,
             })
         })?;
 
@@ -170,7 +209,7 @@ impl UserConfig {
         let github_name = github_name.to_string();
         let conn = self.db.connect()?;
         let mut stmt = conn.prepare(
-            "SELECT id, slack_name, slack_id, email, mute_direct_messages, muted_repos, mute_team_dm FROM users where github_name = ?1",
+            "SELECT id, slack_name, slack_id, email, mute_direct_messages, muted_repos, mute_team_dm, out_of_office, backup_reviewer FROM users where github_name = ?1",
         )?;
         let found = stmt.query_map([&github_name], |row| {
             Ok(UserInfo {
@@ -187,6 +226,8 @@ impl UserConfig {
                     .map(|s| s.trim().to_owned())
                     .filter(|s| !s.is_empty())
                     .collect(),
+                out_of_office: db::to_bool(row.get(7)?),
+                backup_reviewer: row.get(8)?,
             })
         })?;
 
@@ -284,4 +325,54 @@ mod tests {
             users.slack_direct_message("some-git-user", false, &"org1/repo3".into())
         );
     }
+
+    #[test]
+    fn test_backup_reviewer() {
+        let (mut users, _temp) = new_test();
+
+        users.insert("in-office", "in.office").unwrap();
+        let info = UserInfo::new("on-vacation", "on.vacation", "", "", Vec::new())
+            .with_out_of_office("the-backup");
+        users.insert_info(&info).unwrap();
+        let info =
+            UserInfo::new("no-backup", "no.backup", "", "", Vec::new()).with_out_of_office("");
+        users.insert_info(&info).unwrap();
+
+        assert_eq!(None, users.backup_reviewer("in-office"));
+        assert_eq!(
+            Some("the-backup".into()),
+            users.backup_reviewer("on-vacation")
+        );
+        assert_eq!(None, users.backup_reviewer("no-backup"));
+        assert_eq!(None, users.backup_reviewer("unknown-user"));
+
+        let mut info = users.lookup_info("on-vacation").unwrap();
+        info.out_of_office = false;
+        users.update(&info).unwrap();
+        assert_eq!(None, users.backup_reviewer("on-vacation"));
+    }
+
+    #[test]
+    fn test_jira_username() {
+        let (mut users, _temp) = new_test();
+
+        users.insert("no-email", "no.email").unwrap();
+        let info = UserInfo::new("joe", "joe", "", "joe@company.com", Vec::new());
+        users.insert_info(&info).unwrap();
+
+        assert_eq!(
+            Some("joe".into()),
+            users.jira_username("joe", Some("@company.com"))
+        );
+        assert_eq!(
+            Some("joe@company.com".into()),
+            users.jira_username("joe", Some("@other.com"))
+        );
+        assert_eq!(
+            Some("joe@company.com".into()),
+            users.jira_username("joe", None)
+        );
+        assert_eq!(None, users.jira_username("no-email", None));
+        assert_eq!(None, users.jira_username("unknown-user", None));
+    }
 }
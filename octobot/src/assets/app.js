@@ -14,7 +14,7 @@ app.config(function($stateProvider) {
         templateUrl : '/users.html'
     })
     .state('repos', {
-        url: '/repos',
+        url: '/repos?needle',
         controller: 'ReposController',
         templateUrl : '/repos.html'
     })
@@ -56,7 +56,7 @@ app.run(function($state, $rootScope, $timeout, sessionHttp, notificationService)
       sessionHttp.post('/auth/check', {}).then(function() {
         checkPromise = $timeout(checkSession, checkInterval);
       }).catch(function(e) {
-        if (e && e.status == 403) {
+        if (e && e.status == 401) {
           notificationService.showError("Session expired: Logged out");
         } else {
           checkPromise = $timeout(checkSession, checkInterval);
@@ -93,7 +93,7 @@ app.service('sessionHttp', function($http, $state) {
         session: sessionStorage['session'],
       },
     }).catch(function(e) {
-      catch_403(e);
+      catch_401(e);
       throw e;
     });
   }
@@ -104,7 +104,7 @@ app.service('sessionHttp', function($http, $state) {
         session: sessionStorage['session'],
       },
     }).catch(function(e) {
-      catch_403(e);
+      catch_401(e);
       throw e;
     });
   };
@@ -115,7 +115,7 @@ app.service('sessionHttp', function($http, $state) {
         session: sessionStorage['session'],
       },
     }).catch(function(e) {
-      catch_403(e);
+      catch_401(e);
       throw e;
     });
   };
@@ -126,7 +126,7 @@ app.service('sessionHttp', function($http, $state) {
         session: sessionStorage['session'],
       },
     }).catch(function(e) {
-      catch_403(e);
+      catch_401(e);
       throw e;
     });
   };
@@ -147,8 +147,8 @@ app.service('sessionHttp', function($http, $state) {
     }
   }
 
-  function catch_403(e) {
-    if (e && e.status == 403) {
+  function catch_401(e) {
+    if (e && e.status == 401) {
       self.logout();
       return true;
     }
@@ -244,6 +244,8 @@ app.controller('UsersController', function($scope, sessionHttp, notificationServ
       mute_direct_messages: false,
       mute_team_direct_messages: false,
       muted_repos: [],
+      out_of_office: false,
+      backup_reviewer: '',
     };
     $('#add-user-modal').modal('show');
   }
@@ -317,9 +319,11 @@ app.controller('UsersController', function($scope, sessionHttp, notificationServ
   init();
 });
 
-app.controller('ReposController', function($rootScope, $scope, sessionHttp, notificationService)  {
+app.controller('ReposController', function($rootScope, $scope, $stateParams, sessionHttp, notificationService)  {
 
   function init() {
+    $scope.needle = $stateParams.needle;
+
     $('#add-repo-modal').on('shown.bs.modal', function () {
       $('#add-repo-repo').focus()
     });
@@ -356,7 +360,24 @@ app.controller('ReposController', function($rootScope, $scope, sessionHttp, noti
     $scope.theRepo = {
       force_push_notify: true,
       use_threads: true,
+      notify_commit_comments: true,
+      skip_reviewer_refetch: false,
+      merged_channel_only: false,
+      version_script_min_commits: 1,
+      version_script_mode: 'script',
+      merge_strategy: 'merge',
+      ignored_users_mode: 'merge',
       jira_config: [],
+      default_reviewers: [],
+      reviewer_pool: [],
+      suggested_reviewers: [],
+      backport_authors: [],
+      frozen_branches: [],
+      branch_jira_projects: [],
+      path_labels: [],
+      channel_overrides: [],
+      version_script_paths: [],
+      ignored_users: [],
     };
     $('#add-repo-modal').modal('show');
   }
@@ -379,6 +400,39 @@ app.controller('ReposController', function($rootScope, $scope, sessionHttp, noti
    theRepo.jira_config.splice(index, 1);
   }
 
+  $scope.addBranchJIRAs = function(theRepo) {
+    if (!theRepo.branch_jira_projects) {
+      theRepo.branch_jira_projects = [];
+    }
+    theRepo.branch_jira_projects.push({ jira_projects: [] });
+  };
+
+  $scope.removeBranchJIRAs = function(theRepo, index) {
+   theRepo.branch_jira_projects.splice(index, 1);
+  }
+
+  $scope.addPathLabels = function(theRepo) {
+    if (!theRepo.path_labels) {
+      theRepo.path_labels = [];
+    }
+    theRepo.path_labels.push({ labels: [] });
+  };
+
+  $scope.removePathLabels = function(theRepo, index) {
+   theRepo.path_labels.splice(index, 1);
+  }
+
+  $scope.addChannelOverride = function(theRepo) {
+    if (!theRepo.channel_overrides) {
+      theRepo.channel_overrides = [];
+    }
+    theRepo.channel_overrides.push(['', '']);
+  };
+
+  $scope.removeChannelOverride = function(theRepo, index) {
+   theRepo.channel_overrides.splice(index, 1);
+  }
+
   function doAddRepo() {
     sessionHttp.post('/api/repos', $scope.theRepo).then(function(resp) {
       notificationService.showSuccess('Added repo succesfully');
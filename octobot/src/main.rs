@@ -5,6 +5,7 @@ use std::io::Write;
 use anyhow::anyhow;
 
 use octobot_lib::config;
+use octobot_lib::correlation;
 use octobot_lib::errors::*;
 
 use octobot::server;
@@ -48,6 +49,7 @@ fn run() -> Result<()> {
 
     let config =
         config::new(config_file.into()).map_err(|e| anyhow!("Error parsing config: {}", e))?;
+    correlation::set_logged(config.main.log_correlation_ids());
 
     server::main::start(config);
 
@@ -60,14 +62,19 @@ fn setup_logging() {
 
     let formatter = move |buf: &mut env_logger::fmt::Formatter, record: &log::Record| {
         let now = time::OffsetDateTime::now_utc();
+        let correlation_id = match correlation::logged() {
+            Some(id) => format!("[{}] ", id),
+            None => String::new(),
+        };
         writeln!(
             buf,
-            "[{},{:03}][{}:{}] - {} - {}",
+            "[{},{:03}][{}:{}] - {} - {}{}",
             now.format(&format).expect("cannot format time"),
             now.millisecond(),
             thread_id::get(),
             std::thread::current().name().unwrap_or(""),
             record.level(),
+            correlation_id,
             record.args()
         )
     };
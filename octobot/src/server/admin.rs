@@ -197,20 +197,40 @@ impl RepoAdmin {
         let repo: RepoInfo = parse_json(req).await?;
         config.repos_write().insert_info(&repo)?;
 
-        Ok(http_util::new_empty_resp(StatusCode::OK))
+        self.respond_with_repo(&repo.repo)
     }
 
     async fn update(&self, req: Request<Body>) -> Result<Response<Body>> {
         let config = self.config.clone();
-        let repo: RepoInfo = parse_json(req).await?;
+        let name = repo_name_from_path(req.uri().path());
+        let mut repo: RepoInfo = parse_json(req).await?;
+
+        // addressed by name: the stored id wins over anything in the body
+        if let Some(name) = name {
+            match config.repos().lookup_by_name(&name)? {
+                Some(existing) => repo.id = existing.id,
+                None => return Ok(http_util::new_empty_resp(StatusCode::NOT_FOUND)),
+            };
+        }
         config.repos_write().update(&repo)?;
 
-        Ok(http_util::new_empty_resp(StatusCode::OK))
+        self.respond_with_repo(&repo.repo)
     }
 
     async fn delete(&self, req: Request<Body>) -> Result<Response<Body>> {
         let config = self.config.clone();
 
+        if let Some(name) = repo_name_from_path(req.uri().path()) {
+            let repo = match config.repos().lookup_by_name(&name)? {
+                Some(r) => r,
+                None => return Ok(http_util::new_empty_resp(StatusCode::NOT_FOUND)),
+            };
+            if let Err(e) = config.repos_write().delete(repo.id.unwrap_or_default()) {
+                return Ok(self.respond_error(&format!("{}", e)));
+            }
+            return Ok(http_util::new_json_resp(serde_json::to_string(&repo)?));
+        }
+
         let query = util::parse_query(req.uri().query());
 
         let repo_id = match query.get("id").map(|id| id.parse::<i32>()) {
@@ -226,6 +246,23 @@ impl RepoAdmin {
 
         Ok(self.respond_with(StatusCode::OK, ""))
     }
+
+    fn respond_with_repo(&self, name: &str) -> Result<Response<Body>> {
+        let repo = self.config.repos().lookup_by_name(name)?;
+        match repo {
+            Some(repo) => Ok(http_util::new_json_resp(serde_json::to_string(&repo)?)),
+            None => Ok(self.respond_error(&format!("Repo {} not found after saving", name))),
+        }
+    }
+}
+
+// Extracts the repo name from `/api/repos/{owner}/{name}` (or `/api/repos/{owner}` for org-wide
+// configs)
+pub fn repo_name_from_path(path: &str) -> Option<String> {
+    path.strip_prefix("/api/repos/")
+        .map(|name| name.trim_end_matches('/'))
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
 }
 
 pub struct MergeVersions {
@@ -379,3 +416,128 @@ impl MergeVersions {
         http_util::new_json_resp(resp_json)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use octobot_lib::config_db::ConfigDatabase;
+    use tempfile::{tempdir, TempDir};
+
+    use crate::server::http::FilteredHandler;
+    use crate::server::login::LoginSessionFilter;
+    use crate::server::sessions::Sessions;
+
+    fn new_config() -> (Arc<Config>, TempDir) {
+        let temp_dir = tempdir().unwrap();
+        let db_file = temp_dir.path().join("db.sqlite3");
+        let db = ConfigDatabase::new(&db_file.to_string_lossy()).expect("create temp database");
+
+        (Arc::new(Config::new(db)), temp_dir)
+    }
+
+    fn new_req(method: &str, path: &str, body: String) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(path)
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    async fn resp_repo(resp: Response<Body>) -> RepoInfo {
+        assert_eq!(StatusCode::OK, resp.status());
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_repo_round_trip() {
+        let (config, _temp) = new_config();
+        let admin = |op| RepoAdmin::new(config.clone(), op);
+
+        let repo = RepoInfo::new("some-org/some-repo", "the-channel");
+        let resp = admin(Op::Create)
+            .handle(new_req(
+                "POST",
+                "/api/repos",
+                serde_json::to_string(&repo).unwrap(),
+            ))
+            .await
+            .unwrap();
+        let created = resp_repo(resp).await;
+        assert!(created.id.is_some());
+        assert_eq!("the-channel", created.channel);
+
+        // no id needed when addressed by name
+        let repo = RepoInfo::new("some-org/some-repo", "other-channel").with_use_threads(true);
+        let resp = admin(Op::Update)
+            .handle(new_req(
+                "PUT",
+                "/api/repos/some-org/some-repo",
+                serde_json::to_string(&repo).unwrap(),
+            ))
+            .await
+            .unwrap();
+        let updated = resp_repo(resp).await;
+        assert_eq!(created.id, updated.id);
+        assert_eq!("other-channel", updated.channel);
+        assert!(updated.use_threads);
+
+        let resp = admin(Op::Delete)
+            .handle(new_req(
+                "DELETE",
+                "/api/repos/some-org/some-repo",
+                String::new(),
+            ))
+            .await
+            .unwrap();
+        let deleted = resp_repo(resp).await;
+        assert_eq!(created.id, deleted.id);
+        assert!(config.repos().get_all().unwrap().is_empty());
+
+        let resp = admin(Op::Delete)
+            .handle(new_req(
+                "DELETE",
+                "/api/repos/some-org/some-repo",
+                String::new(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::NOT_FOUND, resp.status());
+    }
+
+    #[tokio::test]
+    async fn test_repo_requires_session() {
+        let (config, _temp) = new_config();
+        let sessions = Arc::new(Sessions::new());
+        let handler = FilteredHandler::new(
+            LoginSessionFilter::new(sessions.clone()),
+            RepoAdmin::new(config, Op::List),
+        );
+
+        let resp = handler
+            .handle(new_req("GET", "/api/repos", String::new()))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::UNAUTHORIZED, resp.status());
+
+        let mut req = new_req("GET", "/api/repos", String::new());
+        req.headers_mut()
+            .insert("session", sessions.new_session().parse().unwrap());
+        let resp = handler.handle(req).await.unwrap();
+        assert_eq!(StatusCode::OK, resp.status());
+    }
+
+    #[test]
+    fn test_repo_name_from_path() {
+        assert_eq!(
+            Some("some-org/some-repo".to_string()),
+            repo_name_from_path("/api/repos/some-org/some-repo")
+        );
+        assert_eq!(
+            Some("some-org".to_string()),
+            repo_name_from_path("/api/repos/some-org/")
+        );
+        assert_eq!(None, repo_name_from_path("/api/repos/"));
+        assert_eq!(None, repo_name_from_path("/api/repos"));
+    }
+}
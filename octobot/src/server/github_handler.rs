@@ -9,19 +9,23 @@ use regex::Regex;
 use serde_json;
 use tokio;
 
-use octobot_lib::config::Config;
+use octobot_lib::config::{BackportConflictPolicy, Config, GithubConfig, JiraConfig};
+use octobot_lib::correlation;
 use octobot_lib::errors::Result;
 use octobot_lib::github;
 use octobot_lib::github::api::Session;
 use octobot_lib::github::CommentLike;
 use octobot_lib::jira;
 use octobot_lib::metrics::{self, Metrics};
+use octobot_lib::repos::VersionScriptMode;
 use octobot_ops::force_push::{self, ForcePushRequest};
 use octobot_ops::git_clone_manager::GitCloneManager;
-use octobot_ops::messenger::{self, Messenger, Participants};
+use octobot_ops::jira_review::{self, JiraReviewRequest};
+use octobot_ops::messenger::{self, Messenger, Participants, UnmappedUsers};
 use octobot_ops::pr_merge::{self, PRMergeRequest};
 use octobot_ops::repo_version::{self, RepoVersionRequest};
 use octobot_ops::slack::{self, Slack, SlackAttachmentBuilder, SlackRequest};
+use octobot_ops::stale_prs::{self, StalePRNags};
 use octobot_ops::util;
 use octobot_ops::webhook_db::WebhookDatabase;
 use octobot_ops::worker::{TokioWorker, Worker};
@@ -39,16 +43,29 @@ pub struct GithubHandlerState {
     pr_merge_worker: Arc<dyn Worker<PRMergeRequest>>,
     repo_version_worker: Arc<dyn Worker<RepoVersionRequest>>,
     force_push_worker: Arc<dyn Worker<ForcePushRequest>>,
+    jira_review_worker: Arc<dyn Worker<JiraReviewRequest>>,
     slack_worker: Arc<dyn Worker<SlackRequest>>,
-    webhook_db: Arc<WebhookDatabase>,
+    pub webhook_db: Arc<WebhookDatabase>,
     metrics: Arc<Metrics>,
     git_clone_manager: Arc<GitCloneManager>,
+    pub recent_events: RecentEvents,
+    recent_backports: Arc<RecentBackports>,
+    unmapped_users: Arc<UnmappedUsers>,
+    review_threads: Arc<ReviewThreads>,
+    escalations: Arc<Escalations>,
+    jira_key_prs: Arc<JiraKeyPRs>,
+    pub redeliveries: PendingRedeliveries,
+    stale_pr_nags: StalePRNags,
 }
 
 pub struct GithubHandler {
     state: Arc<GithubHandlerState>,
 }
 
+pub struct ReprocessHandler {
+    state: Arc<GithubHandlerState>,
+}
+
 pub struct GithubEventHandler {
     pub messenger: Messenger,
     pub config: Arc<Config>,
@@ -61,7 +78,19 @@ pub struct GithubEventHandler {
     pub pr_merge: Arc<dyn Worker<PRMergeRequest>>,
     pub repo_version: Arc<dyn Worker<RepoVersionRequest>>,
     pub force_push: Arc<dyn Worker<ForcePushRequest>>,
+    pub jira_review: Arc<dyn Worker<JiraReviewRequest>>,
     pub team_members_cache: TeamsCache,
+    // PR number -> commits, so that each PR's commits are fetched at most once per event
+    pub commits_cache: Mutex<collections::HashMap<u32, Vec<github::Commit>>>,
+    pub recent_backports: Arc<RecentBackports>,
+    pub review_threads: Arc<ReviewThreads>,
+    pub escalations: Arc<Escalations>,
+    pub jira_key_prs: Arc<JiraKeyPRs>,
+    pub metrics: Arc<Metrics>,
+    // attached to queued worker requests so their logs can be tied back to this event
+    pub event_id: Option<String>,
+    // reprocessing a recorded event: don't backport again
+    pub replay: bool,
 }
 
 struct TeamCacheEntry {
@@ -103,6 +132,355 @@ impl TeamsCache {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct RecordedEvent {
+    pub event: String,
+    pub data: github::HookBody,
+}
+
+const MAX_INSTALLATION_EVENTS: usize = 20;
+
+// Keeps the most recent push/pull_request event for each repo so that it can be reprocessed
+// after configuration changes, as well as recent changes to the GitHub App's repositories.
+pub struct RecentEvents {
+    events: Mutex<collections::HashMap<String, RecordedEvent>>,
+    installation_events: Mutex<Vec<RecordedEvent>>,
+}
+
+impl RecentEvents {
+    pub fn new() -> RecentEvents {
+        RecentEvents {
+            events: Mutex::new(collections::HashMap::new()),
+            installation_events: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn record(&self, event: &str, data: &github::HookBody) {
+        if event == "installation_repositories" {
+            let mut events = self.installation_events.lock().unwrap();
+            events.push(RecordedEvent {
+                event: event.to_string(),
+                data: data.clone(),
+            });
+            if events.len() > MAX_INSTALLATION_EVENTS {
+                events.remove(0);
+            }
+            return;
+        }
+        if event != "push" && event != "pull_request" {
+            return;
+        }
+        if let Some(ref repo) = data.repository {
+            let mut events = self.events.lock().unwrap();
+            events.insert(
+                repo.full_name.clone(),
+                RecordedEvent {
+                    event: event.to_string(),
+                    data: data.clone(),
+                },
+            );
+        }
+    }
+
+    pub fn latest(&self, repo_full_name: &str) -> Option<RecordedEvent> {
+        self.events.lock().unwrap().get(repo_full_name).cloned()
+    }
+
+    // Recent installation_repositories events, oldest first
+    pub fn installation_events(&self) -> Vec<RecordedEvent> {
+        self.installation_events.lock().unwrap().clone()
+    }
+}
+
+impl Default for RecentEvents {
+    fn default() -> RecentEvents {
+        RecentEvents::new()
+    }
+}
+
+// Tracks backport labels which were recently turned into merge requests so that rapid label
+// churn (e.g. removing and re-adding the same label) doesn't enqueue duplicate backports.
+pub struct RecentBackports {
+    entries: Mutex<collections::HashMap<(String, u32, String), Instant>>,
+}
+
+impl RecentBackports {
+    pub fn new() -> RecentBackports {
+        RecentBackports {
+            entries: Mutex::new(collections::HashMap::new()),
+        }
+    }
+
+    // Returns true if this label has not been seen for the PR within the given window,
+    // and records it as seen.
+    pub fn check_and_record(
+        &self,
+        repo: &github::Repo,
+        pr_number: u32,
+        label: &str,
+        window: Duration,
+    ) -> bool {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, seen| now.duration_since(*seen) < window);
+
+        let key = (repo.full_name.clone(), pr_number, label.to_lowercase());
+        if entries.contains_key(&key) {
+            return false;
+        }
+        entries.insert(key, now);
+        true
+    }
+}
+
+impl Default for RecentBackports {
+    fn default() -> RecentBackports {
+        RecentBackports::new()
+    }
+}
+
+// Picks the candidate (other than the author) who is a requested reviewer or assignee on the
+// fewest of the given PRs. Ties go to the earliest candidate.
+fn least_loaded_reviewer(
+    candidates: &[String],
+    open_prs: &[github::PullRequest],
+    author: &str,
+) -> Option<String> {
+    candidates
+        .iter()
+        .filter(|c| c.as_str() != author)
+        .min_by_key(|c| {
+            open_prs
+                .iter()
+                .filter(|pr| {
+                    let requested = pr
+                        .requested_reviewers
+                        .as_ref()
+                        .is_some_and(|r| r.iter().any(|u| u.login() == c.as_str()));
+                    requested || pr.assignees.iter().any(|u| u.login() == c.as_str())
+                })
+                .count()
+        })
+        .cloned()
+}
+
+// Tracks the number of unresolved review conversations per PR in the webhook db, so that counts
+// survive restarts. Only conversations started or unresolved while octobot is running are known,
+// so counts start from zero.
+pub struct ReviewThreads {
+    webhook_db: Arc<WebhookDatabase>,
+}
+
+impl ReviewThreads {
+    pub fn new(webhook_db: Arc<WebhookDatabase>) -> ReviewThreads {
+        ReviewThreads { webhook_db }
+    }
+
+    // A conversation was started or unresolved
+    pub fn add(&self, repo: &github::Repo, pr_number: u32) {
+        if let Err(e) = self
+            .webhook_db
+            .add_review_thread(&repo.full_name, pr_number)
+        {
+            log::error!("{}", e);
+        }
+    }
+
+    // A conversation was resolved. Returns the number still unresolved, if any were known.
+    pub fn resolve(&self, repo: &github::Repo, pr_number: u32) -> Option<usize> {
+        match self
+            .webhook_db
+            .resolve_review_thread(&repo.full_name, pr_number)
+        {
+            Ok(remaining) => remaining,
+            Err(e) => {
+                log::error!("{}", e);
+                None
+            }
+        }
+    }
+
+    pub fn unresolved(&self, repo: &github::Repo, pr_number: u32) -> usize {
+        match self
+            .webhook_db
+            .unresolved_review_threads(&repo.full_name, pr_number)
+        {
+            Ok(count) => count.unwrap_or(0),
+            Err(e) => {
+                log::error!("{}", e);
+                0
+            }
+        }
+    }
+
+    pub fn clear(&self, repo: &github::Repo, pr_number: u32) {
+        if let Err(e) = self
+            .webhook_db
+            .clear_review_threads(&repo.full_name, pr_number)
+        {
+            log::error!("{}", e);
+        }
+    }
+}
+
+// PRs which have already been escalated for repeated changes-requested reviews
+pub struct Escalations {
+    escalated: Mutex<collections::HashSet<(String, u32)>>,
+}
+
+impl Escalations {
+    pub fn new() -> Escalations {
+        Escalations {
+            escalated: Mutex::new(collections::HashSet::new()),
+        }
+    }
+
+    // Returns true (once) for each PR
+    pub fn check_and_record(&self, repo: &github::Repo, pr_number: u32) -> bool {
+        self.escalated
+            .lock()
+            .unwrap()
+            .insert((repo.full_name.clone(), pr_number))
+    }
+}
+
+impl Default for Escalations {
+    fn default() -> Escalations {
+        Escalations::new()
+    }
+}
+
+// The open PRs fixing each JIRA, to notice PRs that might transition the same JIRA
+pub struct JiraKeyPRs {
+    prs: Mutex<collections::HashMap<String, Vec<KeyPR>>>,
+}
+
+struct KeyPR {
+    repo: String,
+    number: u32,
+    html_url: String,
+}
+
+impl KeyPR {
+    fn is(&self, repo: &github::Repo, pr_number: u32) -> bool {
+        self.repo == repo.full_name && self.number == pr_number
+    }
+}
+
+impl JiraKeyPRs {
+    pub fn new() -> JiraKeyPRs {
+        JiraKeyPRs {
+            prs: Mutex::new(collections::HashMap::new()),
+        }
+    }
+
+    // Records the PR as fixing the given JIRAs, returning the URLs of other open PRs fixing each
+    // of them, if any
+    pub fn record(
+        &self,
+        repo: &github::Repo,
+        pr_number: u32,
+        html_url: &str,
+        keys: &[String],
+    ) -> Vec<(String, Vec<String>)> {
+        let mut prs = self.prs.lock().unwrap();
+        let mut shared = vec![];
+        for key in keys {
+            let key_prs = prs.entry(key.clone()).or_default();
+            let others = key_prs
+                .iter()
+                .filter(|p| !p.is(repo, pr_number))
+                .map(|p| p.html_url.clone())
+                .collect::<Vec<_>>();
+            if !others.is_empty() {
+                shared.push((key.clone(), others));
+            }
+            if !key_prs.iter().any(|p| p.is(repo, pr_number)) {
+                key_prs.push(KeyPR {
+                    repo: repo.full_name.clone(),
+                    number: pr_number,
+                    html_url: html_url.to_string(),
+                });
+            }
+        }
+        shared
+    }
+
+    pub fn clear(&self, repo: &github::Repo, pr_number: u32) {
+        let mut prs = self.prs.lock().unwrap();
+        for key_prs in prs.values_mut() {
+            key_prs.retain(|p| !p.is(repo, pr_number));
+        }
+        prs.retain(|_, key_prs| !key_prs.is_empty());
+    }
+}
+
+impl Default for JiraKeyPRs {
+    fn default() -> JiraKeyPRs {
+        JiraKeyPRs::new()
+    }
+}
+
+// Delivery guids of webhooks octobot asked github to redeliver, so that their notifications
+// can be suppressed when they arrive.
+pub struct PendingRedeliveries {
+    guids: Mutex<collections::HashSet<String>>,
+}
+
+impl PendingRedeliveries {
+    pub fn new() -> PendingRedeliveries {
+        PendingRedeliveries {
+            guids: Mutex::new(collections::HashSet::new()),
+        }
+    }
+
+    pub fn mark(&self, guid: &str) {
+        self.guids.lock().unwrap().insert(guid.to_string());
+    }
+
+    // Returns true (once) if the guid was marked for redelivery
+    pub fn take(&self, guid: &str) -> bool {
+        self.guids.lock().unwrap().remove(guid)
+    }
+}
+
+impl Default for PendingRedeliveries {
+    fn default() -> PendingRedeliveries {
+        PendingRedeliveries::new()
+    }
+}
+
+// The backport target named by a "<prefix><target>" label, e.g. "backport-1.0"
+fn parse_backport_label(label: &str, prefix: &str) -> Option<String> {
+    let re = Regex::new(&format!("(?i){}(.+)", regex::escape(prefix))).unwrap();
+    re.captures(label).map(|c| c[1].to_string())
+}
+
+// Response for a webhook delivery which has already been processed
+fn duplicate_webhook_resp(config: &GithubConfig, event_id: &str) -> Response<Body> {
+    match config.duplicate_webhook_response() {
+        Some(body) => {
+            info!("Ignoring duplicate webhook event: {}", event_id);
+            http_util::new_msg_resp(StatusCode::OK, body)
+        }
+        None => {
+            let msg = format!("Duplicate webhook event: {}", event_id);
+            error!("{}", msg);
+            http_util::new_bad_req_resp(msg)
+        }
+    }
+}
+
+// Events octobot needs webhooks to be subscribed to
+const REQUIRED_WEBHOOK_EVENTS: &[&str] = &[
+    "pull_request",
+    "pull_request_review",
+    "pull_request_review_comment",
+    "issue_comment",
+    "commit_comment",
+    "push",
+];
+
 const MAX_CONCURRENT_JOBS: usize = 20;
 const MAX_COMMITS_FOR_JIRA_CONSIDERATION: usize = 20;
 
@@ -132,6 +510,7 @@ impl GithubHandlerState {
                 github_app.clone(),
                 git_clone_manager.clone(),
                 slack_worker.clone(),
+                jira_session.clone(),
                 metrics.clone(),
             ),
         );
@@ -141,6 +520,7 @@ impl GithubHandlerState {
                 config.clone(),
                 github_app.clone(),
                 jira_session.clone(),
+                Some(webhook_db.clone()),
                 git_clone_manager.clone(),
                 slack_worker.clone(),
                 metrics.clone(),
@@ -154,6 +534,16 @@ impl GithubHandlerState {
                 metrics.clone(),
             ),
         );
+        let jira_review_worker = TokioWorker::new_worker(
+            runtime.clone(),
+            jira_review::new_runner(
+                config.clone(),
+                jira_session.clone(),
+                slack_worker.clone(),
+                webhook_db.clone(),
+            ),
+        );
+        let review_threads = Arc::new(ReviewThreads::new(webhook_db.clone()));
 
         GithubHandlerState {
             config,
@@ -163,13 +553,49 @@ impl GithubHandlerState {
             pr_merge_worker,
             repo_version_worker,
             force_push_worker,
+            jira_review_worker,
             slack_worker,
             webhook_db,
             metrics,
             git_clone_manager,
+            recent_events: RecentEvents::new(),
+            recent_backports: Arc::new(RecentBackports::new()),
+            unmapped_users: Arc::new(UnmappedUsers::new()),
+            review_threads,
+            escalations: Arc::new(Escalations::new()),
+            jira_key_prs: Arc::new(JiraKeyPRs::new()),
+            redeliveries: PendingRedeliveries::new(),
+            stale_pr_nags: StalePRNags::new(),
         }
     }
 
+    // Requests queued or running in the background workers
+    pub fn pending_jobs(&self) -> usize {
+        self.pr_merge_worker.pending()
+            + self.repo_version_worker.pending()
+            + self.force_push_worker.pending()
+            + self.jira_review_worker.pending()
+            + self.slack_worker.pending()
+    }
+
+    // Resubmits queued JIRA reviews whose retry is due
+    pub fn retry_jira_reviews(&self, now: std::time::SystemTime) {
+        jira_review::retry_pending(&self.webhook_db, self.jira_review_worker.as_ref(), now);
+    }
+
+    pub async fn nag_stale_prs(&self) {
+        let messenger = messenger::new(self.config.clone(), self.slack_worker.clone());
+        stale_prs::nag_stale_prs(
+            &self.config,
+            self.github_app.as_ref(),
+            &messenger,
+            &self.stale_pr_nags,
+            &self.webhook_db,
+            SystemTime::now(),
+        )
+        .await;
+    }
+
     pub fn clean(&self) {
         let hour = Duration::from_secs(3600);
         let day = 24 * hour;
@@ -235,9 +661,7 @@ impl Handler for GithubHandler {
                 log::trace!("Recorded new webhook event: {}", event_id);
             }
             Ok(false) => {
-                let msg = format!("Duplicate webhook event: {}", event_id);
-                error!("{}", msg);
-                return http_util::new_bad_req_resp(msg);
+                return duplicate_webhook_resp(&self.state.config.github, &event_id);
             }
         };
 
@@ -257,13 +681,6 @@ impl Handler for GithubHandler {
         }
 
         let headers = req.headers().clone();
-        let github_app = self.state.github_app.clone();
-        let config = self.state.config.clone();
-        let jira_session = self.state.jira_session.clone();
-        let pr_merge = self.state.pr_merge_worker.clone();
-        let repo_version = self.state.repo_version_worker.clone();
-        let force_push = self.state.force_push_worker.clone();
-        let slack = self.state.slack_worker.clone();
 
         let body = match hyper::body::to_bytes(req.into_body()).await {
             Ok(b) => b,
@@ -274,24 +691,113 @@ impl Handler for GithubHandler {
         };
 
         let verifier = GithubWebhookVerifier {
-            secret: config.github.webhook_secret.clone(),
+            secret: self.state.config.github.webhook_secret.clone(),
         };
         if !verifier.is_req_valid(&headers, &body) {
             return http_util::new_msg_resp(StatusCode::FORBIDDEN, "Invalid signature");
         }
 
-        let mut data: github::HookBody = match serde_json::from_slice(&body) {
+        let data: github::HookBody = match serde_json::from_slice(&body) {
             Ok(h) => h,
             Err(e) => {
                 error!(
                     "Error parsing json: {}\n---\n{}\n---\n",
                     e,
-                    String::from_utf8_lossy(&body)
+                    util::redact_secrets(
+                        &String::from_utf8_lossy(&body),
+                        &self.state.config.main.redact_patterns()
+                    )
                 );
                 return http_util::new_bad_req_resp(format!("Error parsing JSON: {}", e));
             }
         };
 
+        self.state.recent_events.record(&event, &data);
+
+        let redelivery = self.state.redeliveries.take(&event_id);
+        if redelivery {
+            info!(
+                "Suppressing notifications for redelivered webhook {}",
+                event_id
+            );
+        }
+
+        correlation::scope(
+            Some(event_id.clone()),
+            self.state
+                .process_event(event, data, redelivery, false, Some(event_id)),
+        )
+        .await
+    }
+}
+
+impl GithubHandlerState {
+    // Reprocess a previously recorded event using the current configuration. Its notifications,
+    // GitHub reviewers, labels and comments, force-push comparisons and backports already went out
+    // the first time, so only JIRA and check updates are redone. Pushes resolve their JIRAs again
+    // without rerunning the version script.
+    pub async fn replay_event(&self, event: String, data: github::HookBody) -> Response<Body> {
+        self.process_event(event, data, true, true, None).await
+    }
+
+    fn handle_installation_repositories(
+        &self,
+        data: &github::HookBody,
+        suppress_notifications: bool,
+    ) -> Response<Body> {
+        let names = |repos: &Option<Vec<github::InstallationRepository>>| -> Vec<String> {
+            repos
+                .iter()
+                .flatten()
+                .map(|r| r.full_name.clone())
+                .collect()
+        };
+        let added = names(&data.repositories_added);
+        let removed = names(&data.repositories_removed);
+
+        info!(
+            "GitHub App installation repositories changed by {}: added [{}], removed [{}]",
+            data.sender.login(),
+            added.join(", "),
+            removed.join(", ")
+        );
+
+        messenger::new(self.config.clone(), self.slack_worker.clone())
+            .with_suppressed(suppress_notifications)
+            .send_installation_change(&data.sender, &added, &removed);
+
+        http_util::new_msg_resp(StatusCode::OK, "installation_repositories")
+    }
+
+    // Process a webhook event. Slack notifications are dropped if `suppress_notifications` is set,
+    // and backports are skipped for a `replay`.
+    async fn process_event(
+        &self,
+        event: String,
+        mut data: github::HookBody,
+        suppress_notifications: bool,
+        replay: bool,
+        event_id: Option<String>,
+    ) -> Response<Body> {
+        let github_app = self.github_app.clone();
+        let config = self.config.clone();
+        let jira_session = self.jira_session.clone();
+        let pr_merge = self.pr_merge_worker.clone();
+        let repo_version = self.repo_version_worker.clone();
+        let force_push = self.force_push_worker.clone();
+        let jira_review = self.jira_review_worker.clone();
+        let slack = self.slack_worker.clone();
+
+        if event == "installation_repositories" {
+            return self.handle_installation_repositories(&data, suppress_notifications);
+        }
+
+        // before the repository check: organization and app webhooks ping without one
+        if event == "ping" {
+            let (status, msg) = handle_ping(&data);
+            return http_util::new_msg_resp(status, msg);
+        }
+
         // Installation events have no repository, ignore
         let repository = match data.repository {
             Some(ref r) => r.clone(),
@@ -341,41 +847,18 @@ impl Handler for GithubHandler {
         }
 
         // refetch PR if present to get requested reviewers: they don't come on each webhook :cry:
-        let mut changed_pr = None;
         if let Some(ref pull_request) = data.pull_request {
-            if pull_request.requested_reviewers.is_none() || pull_request.reviews.is_none() {
-                match github_session
-                    .get_pull_request(
-                        repository.owner.login(),
-                        &repository.name,
-                        pull_request.number,
-                    )
-                    .await
-                {
-                    Ok(mut refetched_pr) => {
-                        if refetched_pr.draft != pull_request.draft {
-                            warn!(
-                                "Refetched pull request had mismatched draft: {:?} != {:?}",
-                                refetched_pr.draft, pull_request.draft
-                            );
-                            refetched_pr.draft = pull_request.draft;
-                        }
-                        if refetched_pr.head.sha != pull_request.head.sha {
-                            warn!(
-                                "Refetched pull request had different HEAD commit hash: {:?} != {:?}",
-                                refetched_pr.head.sha, pull_request.head.sha
-                            );
-                        }
-
-                        changed_pr = Some(refetched_pr);
-                    }
-                    Err(e) => error!("Error refetching pull request to get reviewers: {}", e),
-                };
+            let refetched_pr = refetch_pull_request(
+                github_session.as_ref(),
+                &self.config,
+                &repository,
+                pull_request,
+            )
+            .await;
+            if let Some(refetched_pr) = refetched_pr {
+                data.pull_request = Some(refetched_pr);
             }
         }
-        if let Some(changed_pr) = changed_pr {
-            data.pull_request = Some(changed_pr);
-        }
 
         let handler = GithubEventHandler {
             event: event.clone(),
@@ -383,13 +866,24 @@ impl Handler for GithubHandler {
             repository,
             action,
             config: config.clone(),
-            messenger: messenger::new(config.clone(), slack),
+            messenger: messenger::new(config.clone(), slack)
+                .with_unmapped_users(self.unmapped_users.clone())
+                .with_suppressed(suppress_notifications),
             github_session,
             jira_session,
             pr_merge,
             repo_version,
             force_push,
+            jira_review,
             team_members_cache: TeamsCache::new(Duration::from_secs(3600)),
+            commits_cache: Mutex::new(collections::HashMap::new()),
+            recent_backports: self.recent_backports.clone(),
+            review_threads: self.review_threads.clone(),
+            escalations: self.escalations.clone(),
+            jira_key_prs: self.jira_key_prs.clone(),
+            metrics: self.metrics.clone(),
+            event_id,
+            replay,
         };
 
         match handler.handle_event().await {
@@ -399,28 +893,176 @@ impl Handler for GithubHandler {
     }
 }
 
+impl ReprocessHandler {
+    pub fn new(state: Arc<GithubHandlerState>) -> Box<ReprocessHandler> {
+        Box::new(ReprocessHandler { state })
+    }
+}
+
+#[async_trait::async_trait]
+impl Handler for ReprocessHandler {
+    async fn handle(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let query = util::parse_query(req.uri().query());
+
+        let repo = match query.get("repo") {
+            None => return Ok(http_util::new_bad_req_resp("No `repo` param specified")),
+            Some(r) => r,
+        };
+
+        let recorded = match self.state.recent_events.latest(repo) {
+            None => {
+                return Ok(http_util::new_msg_resp(
+                    StatusCode::NOT_FOUND,
+                    format!("No recent events for {}", repo),
+                ))
+            }
+            Some(e) => e,
+        };
+
+        info!("Reprocessing recent {} event for {}", recorded.event, repo);
+
+        Ok(self.state.replay_event(recorded.event, recorded.data).await)
+    }
+}
+
 type EventResponse = (StatusCode, String);
 
+/// Check that a newly added webhook is subscribed to the events octobot needs. Repository,
+/// organization and app webhooks all ping, but only repository pings name a repository.
+pub fn handle_ping(data: &github::HookBody) -> EventResponse {
+    if let Some(ref hook) = data.hook {
+        let missing = hook.missing_events(REQUIRED_WEBHOOK_EVENTS);
+        if !missing.is_empty() {
+            warn!(
+                "Webhook {} for {} is not subscribed to required events: {}",
+                hook.id,
+                data.repository
+                    .as_ref()
+                    .map_or("an organization or app", |r| r.full_name.as_str()),
+                missing.join(", ")
+            );
+            return (
+                StatusCode::OK,
+                format!("ping [missing events: {}]", missing.join(", ")),
+            );
+        }
+    }
+
+    (StatusCode::OK, "ping".into())
+}
+
+/// Refetch a pull request to fill in its requested reviewers and reviews, which don't come on
+/// every webhook. Returns None if no refetch was needed, it was disabled for the repo, or it failed.
+pub async fn refetch_pull_request(
+    github_session: &dyn github::api::Session,
+    config: &Config,
+    repo: &github::Repo,
+    pull_request: &github::PullRequest,
+) -> Option<github::PullRequest> {
+    if pull_request.requested_reviewers.is_some() && pull_request.reviews.is_some() {
+        return None;
+    }
+    if config.repos().skip_reviewer_refetch(repo) {
+        return None;
+    }
+
+    match github_session
+        .get_pull_request(repo.owner.login(), &repo.name, pull_request.number)
+        .await
+    {
+        Ok(mut refetched_pr) => {
+            if refetched_pr.draft != pull_request.draft {
+                warn!(
+                    "Refetched pull request had mismatched draft: {:?} != {:?}",
+                    refetched_pr.draft, pull_request.draft
+                );
+                refetched_pr.draft = pull_request.draft;
+            }
+            if refetched_pr.head.sha != pull_request.head.sha {
+                warn!(
+                    "Refetched pull request had different HEAD commit hash: {:?} != {:?}",
+                    refetched_pr.head.sha, pull_request.head.sha
+                );
+            }
+
+            Some(refetched_pr)
+        }
+        Err(e) => {
+            error!("Error refetching pull request to get reviewers: {}", e);
+            None
+        }
+    }
+}
+
+// How an event was handled, for metrics
+fn event_outcome(resp: &Option<EventResponse>) -> &'static str {
+    match resp {
+        None => "unhandled",
+        Some((status, _)) if !status.is_success() => "error",
+        Some((_, msg)) if msg.ends_with("[ignored]") => "ignored",
+        Some(_) => "handled",
+    }
+}
+
 impl GithubEventHandler {
     pub async fn handle_event(&self) -> Option<EventResponse> {
+        let _scoped_timer = self
+            .metrics
+            .webhook_duration
+            .with_label_values(&[&self.event])
+            .start_timer();
+
+        let resp = self.dispatch_event().await;
+
+        self.metrics
+            .webhook_events
+            .with_label_values(&[&self.event, event_outcome(&resp)])
+            .inc();
+        resp
+    }
+
+    async fn dispatch_event(&self) -> Option<EventResponse> {
         info!(
             "Received event: {}{}{}",
             self.event,
             if self.action.is_empty() { "" } else { "." },
             self.action
         );
+        // commits may have changed since any previous event handled by this handler
+        self.commits_cache.lock().unwrap().clear();
+
         if self.event == "ping" {
-            Some(self.handle_ping())
+            Some(handle_ping(&self.data))
+        } else if self.config.github.ignored_events().contains(&self.event) {
+            Some(self.handle_ignored_event())
         } else if self.event == "pull_request" {
             Some(self.handle_pr().await)
         } else if self.event == "pull_request_review_comment" {
             Some(self.handle_pr_review_comment().await)
         } else if self.event == "pull_request_review" {
             Some(self.handle_pr_review().await)
+        } else if self.event == "pull_request_review_thread" {
+            Some(self.handle_pr_review_thread().await)
         } else if self.event == "commit_comment" {
             Some(self.handle_commit_comment().await)
         } else if self.event == "issue_comment" {
             Some(self.handle_issue_comment().await)
+        } else if self.event == "issues" {
+            Some(self.handle_issues())
+        } else if self.event == "discussion" {
+            Some(self.handle_discussion())
+        } else if self.event == "discussion_comment" {
+            Some(self.handle_discussion_comment())
+        } else if self.event == "workflow_run" {
+            Some(self.handle_workflow_run().await)
+        } else if self.event == "secret_scanning_alert" {
+            Some(self.handle_secret_scanning_alert())
+        } else if self.event == "check_run" {
+            Some(self.handle_check_run().await)
+        } else if self.event == "release" {
+            Some(self.handle_release().await)
+        } else if self.event == "deployment_status" {
+            Some(self.handle_deployment_status())
         } else if self.event == "push" {
             Some(self.handle_push().await)
         } else {
@@ -428,6 +1070,14 @@ impl GithubEventHandler {
         }
     }
 
+    // PRs with more commits than this are too big to act on their JIRAs
+    fn max_commits_for_jira(&self) -> usize {
+        self.config
+            .repos()
+            .max_commits_for_jira(&self.repository)
+            .unwrap_or(MAX_COMMITS_FOR_JIRA_CONSIDERATION)
+    }
+
     // This defaults to using the github name if no slack name is configured, since this is not
     // used for actually sending messages, but just for referring to users in slack messages.
     fn slack_user_name(&self, user: &github::User) -> String {
@@ -441,7 +1091,8 @@ impl GithubEventHandler {
         users.iter().map(|u| self.slack_user_name(u)).collect()
     }
 
-    async fn pull_request_commits(
+    // The PR's commits, fetched at most once per event
+    pub async fn pull_request_commits(
         &self,
         pull_request: &dyn github::PullRequestLike,
     ) -> Vec<github::Commit> {
@@ -449,6 +1100,15 @@ impl GithubEventHandler {
             return vec![];
         }
 
+        if let Some(commits) = self
+            .commits_cache
+            .lock()
+            .unwrap()
+            .get(&pull_request.number())
+        {
+            return commits.clone();
+        }
+
         match self
             .github_session
             .get_pull_request_commits(
@@ -458,7 +1118,13 @@ impl GithubEventHandler {
             )
             .await
         {
-            Ok(commits) => commits,
+            Ok(commits) => {
+                self.commits_cache
+                    .lock()
+                    .unwrap()
+                    .insert(pull_request.number(), commits.clone());
+                commits
+            }
             Err(e) => {
                 error!("Error looking up PR commits: {}", e);
                 vec![]
@@ -545,18 +1211,80 @@ impl GithubEventHandler {
         reviewer_names
     }
 
-    fn handle_ping(&self) -> EventResponse {
-        (StatusCode::OK, "ping".into())
+    // Notes for requested reviewers who are out of office and have a backup
+    fn backup_reviewer_attachments(
+        &self,
+        pull_request: &github::PullRequest,
+    ) -> Vec<slack::SlackAttachment> {
+        let backups: Vec<(github::User, String)> = {
+            let users = self.config.users();
+            pull_request
+                .requested_reviewers
+                .iter()
+                .flatten()
+                .filter_map(|r| users.backup_reviewer(r.login()).map(|b| (r.clone(), b)))
+                .collect()
+        };
+
+        backups
+            .into_iter()
+            .map(|(reviewer, backup)| {
+                SlackAttachmentBuilder::new(&format!(
+                    "{} is out of office. Backup reviewer: {}",
+                    self.slack_user_name(&reviewer),
+                    self.slack_user_name(&github::User::new(&backup))
+                ))
+                .build()
+            })
+            .collect()
+    }
+
+    // PRs may route their notifications to a different (allowed) channel with a
+    // "Slack-Channel: #foo" trailer in the PR body.
+    fn channel_override_messenger(&self, pull_request: &github::PullRequest) -> Option<Messenger> {
+        let channel = pull_request
+            .body
+            .as_ref()
+            .and_then(|b| util::parse_slack_channel_trailer(b))?;
+
+        if self
+            .config
+            .slack
+            .allowed_channel_overrides
+            .contains(&channel)
+        {
+            Some(self.messenger.with_channel_override(&channel))
+        } else {
+            warn!(
+                "PR {} requested disallowed slack channel '{}'; using default channels",
+                pull_request.html_url, channel
+            );
+            None
+        }
+    }
+
+    fn handle_ignored_event(&self) -> EventResponse {
+        self.metrics
+            .ignored_webhook_events
+            .with_label_values(&[&self.event])
+            .inc();
+        (StatusCode::OK, format!("{} [ignored]", self.event))
     }
 
     async fn handle_pr(&self) -> EventResponse {
         enum NotifyMode {
             All,
             Channel,
+            Participants,
             None,
         }
 
         if let Some(ref pull_request) = self.data.pull_request {
+            if self.action == "closed" {
+                self.jira_key_prs
+                    .clear(&self.repository, pull_request.number);
+            }
+
             let verb: Option<String>;
             let notify_mode;
             if self.action == "opened" {
@@ -566,12 +1294,19 @@ impl GithubEventHandler {
                 ));
                 notify_mode = NotifyMode::Channel;
             } else if self.action == "closed" {
+                self.review_threads
+                    .clear(&self.repository, pull_request.number);
                 if pull_request.merged == Some(true) {
                     verb = Some("merged".to_string());
-                } else {
+                    notify_mode = if self.config.repos().merged_channel_only(&self.repository) {
+                        NotifyMode::Channel
+                    } else {
+                        NotifyMode::All
+                    };
+                } else {
                     verb = Some("closed".to_string());
+                    notify_mode = NotifyMode::All;
                 }
-                notify_mode = NotifyMode::All;
             } else if self.action == "reopened" {
                 verb = Some("reopened".to_string());
                 notify_mode = NotifyMode::Channel;
@@ -581,6 +1316,13 @@ impl GithubEventHandler {
             } else if self.action == "ready_for_review" {
                 verb = Some("is ready for review".to_string());
                 notify_mode = NotifyMode::All;
+            } else if self.action == "converted_to_draft" {
+                // no JIRA processing: it happens again once the PR is ready for review
+                verb = Some(format!(
+                    "converted to draft by {}",
+                    self.slack_user_name(&self.data.sender)
+                ));
+                notify_mode = NotifyMode::Channel;
             } else if self.action == "assigned" {
                 let assignees_str = self.slack_user_names(&pull_request.assignees).join(", ");
                 verb = Some(format!("assigned to {}", assignees_str));
@@ -603,11 +1345,44 @@ impl GithubEventHandler {
             } else if self.action == "synchronize" {
                 verb = Some("synchronize".to_string());
                 notify_mode = NotifyMode::None;
+            } else if self.action == "enqueued" {
+                verb = Some("added to merge queue".to_string());
+                notify_mode = NotifyMode::None;
+            } else if self.action == "auto_merge_enabled" {
+                verb = Some(format!(
+                    "auto-merge enabled by {}",
+                    self.slack_user_name(&self.data.sender)
+                ));
+                notify_mode = NotifyMode::Channel;
+            } else if self.action == "auto_merge_disabled" {
+                verb = Some(format!(
+                    "auto-merge disabled by {}",
+                    self.slack_user_name(&self.data.sender)
+                ));
+                notify_mode = NotifyMode::Channel;
+            } else if self.action == "milestoned" || self.action == "demilestoned" {
+                verb = Some(self.action.clone());
+                notify_mode = NotifyMode::None;
             } else {
                 verb = None;
                 notify_mode = NotifyMode::None;
             }
 
+            // repos may only want to hear about some events in their channel, but participants
+            // are still notified and the JIRA workflow still runs
+            let notify_mode = if self
+                .config
+                .repos()
+                .notifies_event(&self.repository, &self.pr_event_kind())
+            {
+                notify_mode
+            } else {
+                match notify_mode {
+                    NotifyMode::All => NotifyMode::Participants,
+                    _ => NotifyMode::None,
+                }
+            };
+
             // early exit if we have nothing to do here.
             if verb.is_none() && self.action != "labeled" {
                 return (StatusCode::OK, "pr".into());
@@ -615,10 +1390,37 @@ impl GithubEventHandler {
 
             let commits = self.pull_request_commits(&pull_request).await;
 
+            let channel_messenger = self.channel_override_messenger(pull_request);
+            let messenger = channel_messenger.as_ref().unwrap_or(&self.messenger);
+            let blocks_messenger = if self.action == "opened" && self.config.slack.use_blocks {
+                Some(messenger.with_blocks())
+            } else {
+                None
+            };
+            let messenger = blocks_messenger.as_ref().unwrap_or(messenger);
+
             if let Some(ref verb) = verb {
                 let branch_name = &pull_request.base.ref_name;
 
-                let attachments = vec![SlackAttachmentBuilder::new("")
+                if self.action == "edited" {
+                    let previous_base = self.data.changes.as_ref().and_then(|c| c.previous_base());
+                    if let Some(previous_base) = previous_base {
+                        if previous_base != branch_name {
+                            self.notify_base_changed(
+                                messenger,
+                                pull_request,
+                                previous_base,
+                                &commits,
+                            );
+                        }
+                    }
+                }
+
+                let size_text = match self.pr_size(pull_request) {
+                    Some(size) if self.config.slack.include_pr_size => format!("Size: {}", size),
+                    _ => String::new(),
+                };
+                let mut attachments = vec![SlackAttachmentBuilder::new(&size_text)
                     .title(format!(
                         "Pull Request #{}: \"{}\"",
                         pull_request.number,
@@ -627,11 +1429,41 @@ impl GithubEventHandler {
                     .title_link(pull_request.html_url.as_str())
                     .build()];
 
-                if !pull_request.is_draft() {
-                    let msg = format!("Pull Request {}", verb);
+                if self.action == "review_requested" {
+                    attachments.extend(self.backup_reviewer_attachments(pull_request));
+                }
+
+                if self.action == "opened" && !pull_request.is_draft() {
+                    if let Some(details) = self.jira_issue_details(branch_name, &commits).await {
+                        attachments.push(SlackAttachmentBuilder::new(&details).build());
+                    }
+                }
+
+                if self.action == "opened" && !pull_request.is_draft() {
+                    if let Some(reviewer) = self.suggest_reviewer(pull_request).await {
+                        attachments.push(
+                            SlackAttachmentBuilder::new(&format!(
+                                "Suggested reviewer: {}",
+                                self.slack_user_name(&github::User::new(&reviewer))
+                            ))
+                            .build(),
+                        );
+                    }
+                }
+
+                // drafts are quiet, except to announce becoming one
+                if !pull_request.is_draft() || self.action == "converted_to_draft" {
+                    let msg = match self.pr_message_template() {
+                        Some(template) => self.render_pr_message(&template, pull_request),
+                        None => format!("Pull Request {}", verb),
+                    };
+                    let msg = match self.config.slack.author_team(pull_request.user.login()) {
+                        Some(team) => format!("{} (author team: {})", msg, team),
+                        None => msg,
+                    };
                     let thread_guid = self.build_thread_guid(pull_request.number);
                     match notify_mode {
-                        NotifyMode::Channel => self.messenger.send_to_channel(
+                        NotifyMode::Channel => messenger.send_to_channel(
                             &msg,
                             &attachments,
                             &self.repository,
@@ -641,7 +1473,7 @@ impl GithubEventHandler {
                             self.action == "opened",
                         ),
 
-                        NotifyMode::All => self.messenger.send_to_all(
+                        NotifyMode::All => messenger.send_to_all(
                             &msg,
                             &attachments,
                             &pull_request.user,
@@ -653,10 +1485,49 @@ impl GithubEventHandler {
                             vec![thread_guid],
                         ),
 
+                        NotifyMode::Participants => messenger.send_to_participants(
+                            &msg,
+                            &attachments,
+                            &pull_request.user,
+                            &self.data.sender,
+                            &self.repository,
+                            self.all_participants(&pull_request, &commits).await,
+                        ),
+
                         NotifyMode::None => (),
                     };
                 }
 
+                if self.action == "opened" && !pull_request.is_draft() && !self.replay {
+                    self.request_default_reviewers(pull_request).await;
+                }
+
+                if self.action == "opened" || self.action == "synchronize" {
+                    if !self.replay {
+                        self.apply_path_labels(pull_request).await;
+                        self.apply_size_label(pull_request).await;
+                    }
+
+                    if self
+                        .config
+                        .repos()
+                        .is_frozen_branch(&self.repository, branch_name)
+                    {
+                        let msg = format!(
+                            "Pull Request #{} targets frozen branch {}",
+                            pull_request.number, branch_name
+                        );
+                        messenger.send_to_owner(
+                            &msg,
+                            &attachments,
+                            &pull_request.user,
+                            &self.repository,
+                            branch_name,
+                            &commits,
+                        );
+                    }
+                }
+
                 let jira_projects = self
                     .config
                     .repos()
@@ -664,17 +1535,18 @@ impl GithubEventHandler {
 
                 let is_pull_request_first_ready =
                     self.action == "opened" || self.action == "ready_for_review";
+                let max_commits = self.max_commits_for_jira();
 
-                // Mark JIRAs in review for PR open
-                if is_pull_request_first_ready {
+                // Mark JIRAs in review for PR open. Drafts wait until they are marked ready.
+                if is_pull_request_first_ready && !pull_request.is_draft() {
                     if let Some(ref jira_config) = self.config.jira {
                         if let Some(ref jira_session) = self.jira_session {
-                            if commits.len() > MAX_COMMITS_FOR_JIRA_CONSIDERATION {
+                            if commits.len() > max_commits {
                                 let msg = format!(
                                     "Too many commits on Pull Request #{}. Ignoring JIRAs.",
                                     pull_request.number
                                 );
-                                self.messenger.send_to_owner(
+                                messenger.send_to_owner(
                                     &msg,
                                     &attachments,
                                     &pull_request.user,
@@ -682,13 +1554,202 @@ impl GithubEventHandler {
                                     branch_name,
                                     &commits,
                                 );
+                            } else if jira_config.skips_review_for_branch(branch_name) {
+                                info!(
+                                    "Skipping JIRA review for Pull Request #{}: base branch {} is excluded",
+                                    pull_request.number, branch_name
+                                );
+                            } else if jira_config.skip_revert_reviews() && pull_request.is_revert()
+                            {
+                                info!(
+                                    "Skipping JIRA review for Pull Request #{}: it is a revert",
+                                    pull_request.number
+                                );
                             } else {
-                                jira::workflow::submit_for_review(
+                                let visibility = self
+                                    .config
+                                    .repos()
+                                    .jira_comment_visibility(&self.repository);
+
+                                let release_branch_prefix =
+                                    self.config.repos().release_branch_prefix(&self.repository);
+
+                                if jira_config.review_retries() > 0 {
+                                    // retried in the background if JIRA is unavailable
+                                    let req = jira_review::req(
+                                        pull_request,
+                                        &commits,
+                                        &jira_projects,
+                                        &release_branch_prefix,
+                                        visibility,
+                                    )
+                                    .with_event_id(self.event_id.clone())
+                                    .with_suppressed_notifications(self.messenger.is_suppressed());
+                                    self.jira_review.send(req);
+                                } else {
+                                    let transitions = jira::workflow::submit_for_review(
+                                        pull_request,
+                                        &commits,
+                                        &jira_projects,
+                                        &release_branch_prefix,
+                                        jira_session.deref(),
+                                        jira_config,
+                                        visibility,
+                                    )
+                                    .await
+                                    .applied;
+                                    self.messenger.send_transition_audit(
+                                        &transitions,
+                                        &format!("review of PR#{}", pull_request.number),
+                                    );
+
+                                    if jira_config.sync_assignee() {
+                                        let assignee = self.config.users().jira_username(
+                                            pull_request.user.login(),
+                                            jira_config.login_suffix.as_deref(),
+                                        );
+                                        if let Some(assignee) = assignee {
+                                            jira::workflow::assign_fixed_issues(
+                                                &commits,
+                                                &jira_projects,
+                                                &assignee,
+                                                jira_session.deref(),
+                                            )
+                                            .await;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                // Comment on the PR with the JIRAs it references
+                if is_pull_request_first_ready
+                    && !self.replay
+                    && !jira_projects.is_empty()
+                    && commits.len() <= max_commits
+                {
+                    if let (Some(ref jira_config), Some(ref jira_session)) =
+                        (&self.config.jira, &self.jira_session)
+                    {
+                        if jira_config.pr_summary_comment() {
+                            self.comment_jira_summaries(
+                                pull_request,
+                                &commits,
+                                &jira_projects,
+                                jira_session.deref(),
+                                jira_config,
+                            )
+                            .await;
+                        }
+                    }
+                }
+
+                // Warn about JIRA references that will be ignored
+                if is_pull_request_first_ready && !jira_projects.is_empty() {
+                    if let Some(ref jira_config) = self.config.jira {
+                        if jira_config.warn_unconfigured_projects() {
+                            let keys = jira::workflow::get_unconfigured_jira_keys(
+                                &commits,
+                                &jira_projects,
+                            );
+                            if !keys.is_empty() {
+                                let msg = format!(
+                                    "Pull Request #{} references JIRAs from projects not configured for this repo: {}",
+                                    pull_request.number,
+                                    keys.join(", ")
+                                );
+                                messenger.send_to_owner(
+                                    &msg,
+                                    &attachments,
+                                    &pull_request.user,
+                                    &self.repository,
+                                    branch_name,
+                                    &commits,
+                                );
+                            }
+                        }
+                    }
+                }
+
+                // Warn about JIRAs other open PRs also fix, since their transitions may conflict
+                if is_pull_request_first_ready
+                    && !jira_projects.is_empty()
+                    && commits.len() <= max_commits
+                    && self
+                        .config
+                        .jira
+                        .as_ref()
+                        .is_some_and(|c| c.warn_shared_keys())
+                {
+                    let keys = jira::workflow::get_fixed_jira_keys(&commits, &jira_projects);
+                    let shared = self.jira_key_prs.record(
+                        &self.repository,
+                        pull_request.number,
+                        &pull_request.html_url,
+                        &keys,
+                    );
+                    for (key, others) in shared {
+                        let msg = format!(
+                            "Pull Request #{} fixes {}, which other open PRs also fix: {}",
+                            pull_request.number,
+                            key,
+                            others.join(", ")
+                        );
+                        messenger.send_to_owner(
+                            &msg,
+                            &attachments,
+                            &pull_request.user,
+                            &self.repository,
+                            branch_name,
+                            &commits,
+                        );
+                    }
+                }
+
+                // Mark JIRAs as pending merge when entering the merge queue
+                if self.action == "enqueued" && commits.len() <= max_commits {
+                    if let Some(ref jira_config) = self.config.jira {
+                        if let Some(ref jira_session) = self.jira_session {
+                            let visibility = self
+                                .config
+                                .repos()
+                                .jira_comment_visibility(&self.repository);
+
+                            let transitions = jira::workflow::mark_queued(
+                                pull_request,
+                                &commits,
+                                &jira_projects,
+                                jira_session.deref(),
+                                jira_config,
+                                visibility,
+                            )
+                            .await;
+                            self.messenger.send_transition_audit(
+                                &transitions,
+                                &format!("merge queue entry of PR#{}", pull_request.number),
+                            );
+                        }
+                    }
+                }
+
+                // Note on JIRAs that their fix is queued once auto-merge is enabled
+                if self.action == "auto_merge_enabled" && commits.len() <= max_commits {
+                    if let Some(ref jira_config) = self.config.jira {
+                        if let Some(ref jira_session) = self.jira_session {
+                            if jira_config.auto_merge_comments() {
+                                let visibility = self
+                                    .config
+                                    .repos()
+                                    .jira_comment_visibility(&self.repository);
+
+                                jira::workflow::mark_auto_merge_enabled(
                                     pull_request,
                                     &commits,
                                     &jira_projects,
                                     jira_session.deref(),
-                                    jira_config,
+                                    visibility,
                                 )
                                 .await;
                             }
@@ -696,27 +1757,71 @@ impl GithubEventHandler {
                     }
                 }
 
+                // Keep JIRA fix-versions in sync with the PR milestone
+                if (self.action == "milestoned" || self.action == "demilestoned")
+                    && commits.len() <= max_commits
+                {
+                    if let Some(ref jira_config) = self.config.jira {
+                        if let Some(ref jira_session) = self.jira_session {
+                            if let Some(ref milestone) = self.data.milestone {
+                                if jira_config.sync_milestone_versions() {
+                                    self.sync_milestone_version(
+                                        &commits,
+                                        &jira_projects,
+                                        jira_session.deref(),
+                                        &milestone.title,
+                                    )
+                                    .await;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                // Drafts get checked once they are marked ready for review
+                let defer_check =
+                    pull_request.is_draft() && self.config.github.defer_draft_jira_check();
+
                 // Check for jira reference on ready for review and PR title rename
                 // (since JIRA check ignore is based on PR title)
-                if is_pull_request_first_ready
+                if (is_pull_request_first_ready
                     || self.action == "edited"
-                    || self.action == "synchronize"
+                    || self.action == "synchronize")
+                    && !defer_check
                 {
                     // Mark if no JIRA references
-                    jira::check_jira_refs(
+                    let check_name = self.config.repos().jira_check_name(&self.repository);
+                    let template = self.config.repos().jira_check_template(&self.repository);
+                    jira::check_jira_refs_with_template(
                         pull_request,
                         &commits,
                         &jira_projects,
+                        &check_name,
+                        &template,
                         self.github_session.deref(),
+                        self.config.github.check_run_retries(),
                     )
                     .await;
                 }
             }
 
             let release_branch_prefix = self.config.repos().release_branch_prefix(&self.repository);
-            if self.action == "labeled" {
+            if self.replay {
+                info!(
+                    "Not backporting {}#{} again for a replayed event",
+                    self.repository.full_name, pull_request.number
+                );
+            } else if self.action == "labeled" {
                 if let Some(ref label) = self.data.label {
-                    self.merge_pull_request(pull_request, label, &release_branch_prefix, &commits);
+                    let labels = pull_request.labels.as_deref().unwrap_or(&[]);
+                    if !self.skip_conflicting_backports(pull_request, labels, &commits) {
+                        self.merge_pull_request(
+                            pull_request,
+                            label,
+                            &release_branch_prefix,
+                            &commits,
+                        );
+                    }
                 }
             } else if verb == Some("merged".to_string()) {
                 self.merge_pull_request_all_labels(pull_request, &release_branch_prefix, &commits)
@@ -731,6 +1836,14 @@ impl GithubEventHandler {
         if let Some(ref pull_request) = self.data.pull_request {
             if let Some(ref comment) = self.data.comment {
                 if self.action == "created" {
+                    // replies continue an existing conversation
+                    if comment.in_reply_to_id.is_none()
+                        && self.config.slack.notify_conversations_resolved
+                    {
+                        self.review_threads
+                            .add(&self.repository, pull_request.number);
+                    }
+
                     let branch_name = &pull_request.base.ref_name;
                     let commits = self.pull_request_commits(&pull_request).await;
 
@@ -743,6 +1856,59 @@ impl GithubEventHandler {
         (StatusCode::OK, "pr_review_comment".into())
     }
 
+    async fn handle_pr_review_thread(&self) -> EventResponse {
+        if !self.config.slack.notify_conversations_resolved {
+            return (StatusCode::OK, "pr_review_thread [ignored]".into());
+        }
+
+        if let Some(ref pull_request) = self.data.pull_request {
+            if let Some(ref thread) = self.data.thread {
+                info!(
+                    "Review thread {} {} on PR #{}",
+                    thread.node_id, self.action, pull_request.number
+                );
+            }
+
+            if self.action == "unresolved" {
+                self.review_threads
+                    .add(&self.repository, pull_request.number);
+            } else if self.action == "resolved"
+                && self
+                    .review_threads
+                    .resolve(&self.repository, pull_request.number)
+                    == Some(0)
+            {
+                let branch_name = &pull_request.base.ref_name;
+                let commits = self.pull_request_commits(&pull_request).await;
+
+                let msg = format!(
+                    "All review conversations resolved on PR \"{}\"",
+                    util::make_link(pull_request.html_url.as_str(), pull_request.title.as_str())
+                );
+
+                self.messenger.send_to_channel(
+                    &msg,
+                    &[],
+                    &self.repository,
+                    branch_name,
+                    &commits,
+                    vec![self.build_thread_guid(pull_request.number)],
+                    false,
+                );
+                self.messenger.send_to_participants(
+                    &msg,
+                    &[],
+                    &pull_request.user,
+                    &self.data.sender,
+                    &self.repository,
+                    Participants::new(),
+                );
+            }
+        }
+
+        (StatusCode::OK, "pr_review_thread".into())
+    }
+
     async fn handle_pr_review(&self) -> EventResponse {
         if let Some(ref pull_request) = self.data.pull_request {
             if let Some(ref review) = self.data.review {
@@ -804,6 +1970,10 @@ impl GithubEventHandler {
                         &commits,
                         vec![self.build_thread_guid(pull_request.number)],
                     );
+
+                    if review.state == "changes_requested" {
+                        self.escalate_changes_requested(pull_request).await;
+                    }
                 }
             }
         }
@@ -811,6 +1981,53 @@ impl GithubEventHandler {
         (StatusCode::OK, "pr_review".into())
     }
 
+    // Posts to the escalation channel once a PR collects enough changes-requested reviews
+    async fn escalate_changes_requested(&self, pull_request: &github::PullRequest) {
+        let (channel, threshold) = match self.config.slack.changes_requested_escalation() {
+            Some((c, t)) => (c.to_string(), t),
+            None => return,
+        };
+
+        let reviews = match self
+            .github_session
+            .get_pull_request_reviews(
+                self.repository.owner.login(),
+                &self.repository.name,
+                pull_request.number,
+            )
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                error!(
+                    "Error looking up reviews for PR #{}: {}",
+                    pull_request.number, e
+                );
+                return;
+            }
+        };
+
+        let count = reviews
+            .iter()
+            .filter(|r| r.state.eq_ignore_ascii_case("changes_requested"))
+            .count();
+        if count < threshold as usize {
+            return;
+        }
+
+        if self
+            .escalations
+            .check_and_record(&self.repository, pull_request.number)
+        {
+            self.messenger.send_changes_requested_escalation(
+                &channel,
+                pull_request,
+                &self.repository,
+                count,
+            );
+        }
+    }
+
     async fn do_pull_request_comment(
         &self,
         pull_request: &dyn github::PullRequestLike,
@@ -824,10 +2041,8 @@ impl GithubEventHandler {
 
         if comment.user().login() == self.github_session.bot_name()
             || self
-                .config
-                .slack
-                .ignored_users
-                .contains(&comment.user().login().to_string())
+                .messenger
+                .is_ignored_user(comment.user(), &self.repository)
         {
             info!(
                 "Ignoring message from bot ({}): {}",
@@ -866,11 +2081,15 @@ impl GithubEventHandler {
     }
 
     async fn handle_commit_comment(&self) -> EventResponse {
+        if !self.config.repos().notify_commit_comments(&self.repository) {
+            return (StatusCode::OK, "commit_comment [ignored]".into());
+        }
+
         if let Some(ref comment) = self.data.comment {
             if self.action == "created" {
                 if let Some(ref commit_id) = comment.commit_id {
                     let commit: &str = &commit_id[0..7];
-                    let commit_url = format!("{}/commit/{}", self.repository.html_url, commit_id);
+                    let commit_url = self.repository.commit_url(commit_id);
                     let commit_path = if let Some(ref path) = comment.path {
                         path.to_string()
                     } else {
@@ -935,48 +2154,850 @@ impl GithubEventHandler {
         (StatusCode::OK, "commit_comment".into())
     }
 
-    async fn handle_issue_comment(&self) -> EventResponse {
-        if let Some(ref comment) = self.data.comment {
-            if self.action == "created" {
-                // Check to see if we remapped this "issue" to a PR
-                if let Some(ref pr) = self.data.pull_request {
-                    let branch_name = &pr.base.ref_name;
-                    let commits = self.pull_request_commits(&pr).await;
+    async fn handle_release(&self) -> EventResponse {
+        let release = match self.data.release {
+            Some(ref r) if self.action == "published" => r,
+            _ => return (StatusCode::OK, "release [ignored]".into()),
+        };
 
-                    self.do_pull_request_comment(&pr, &comment, branch_name, &commits)
-                        .await;
-                } else if let Some(ref issue) = self.data.issue {
-                    // issues do not have branches or commits -> main channel is fine.
-                    let branch_name = "";
-                    let commits = vec![];
+        let kind = if release.prerelease {
+            "Pre-release"
+        } else {
+            "Release"
+        };
+        let msg = format!(
+            "{} {} published by {}",
+            kind,
+            release.tag_name,
+            self.slack_user_name(&self.data.sender)
+        );
+        let attachments =
+            vec![
+                SlackAttachmentBuilder::new(release.body.as_deref().unwrap_or("").trim())
+                    .title(format!("{}: {}", kind, release.display_name()))
+                    .title_link(release.html_url.as_str())
+                    .build(),
+            ];
+
+        let branch_name = release.target_branch().unwrap_or("");
+        self.messenger.send_to_channel(
+            &msg,
+            &attachments,
+            &self.repository,
+            branch_name,
+            &Vec::<github::Commit>::new(),
+            vec![],
+            false,
+        );
 
-                    self.do_pull_request_comment(&issue, &comment, branch_name, &commits)
-                        .await;
-                }
+        // pre-releases haven't shipped yet
+        if release.prerelease {
+            return (StatusCode::OK, "release".into());
+        }
+
+        // Move JIRAs fixed in this version to the released states
+        if let Some(ref jira_config) = self.config.jira {
+            if let Some(ref jira_session) = self.jira_session {
+                // releases tagged from a commit are taken to be from the default branch
+                let branch_name = match release
+                    .target_branch()
+                    .or(self.repository.default_branch.as_deref())
+                {
+                    Some(b) => b,
+                    None => {
+                        info!(
+                            "Skipping JIRAs for release {} of '{}': unknown branch",
+                            release.tag_name, self.repository.full_name
+                        );
+                        return (StatusCode::OK, "release".into());
+                    }
+                };
+                let jira_projects = self
+                    .config
+                    .repos()
+                    .jira_projects(&self.repository, branch_name);
+
+                let transitions = jira::workflow::mark_released(
+                    release.version(),
+                    &jira_projects,
+                    jira_session.deref(),
+                    jira_config,
+                )
+                .await;
+                self.messenger
+                    .send_transition_audit(&transitions, &format!("release {}", release.tag_name));
             }
         }
-        (StatusCode::OK, "issue_comment".into())
+
+        (StatusCode::OK, "release".into())
     }
 
-    async fn handle_push(&self) -> EventResponse {
-        if self.data.deleted() || self.data.created() {
-            // ignore
-            return (StatusCode::OK, "push [ignored]".into());
-        }
+    fn handle_deployment_status(&self) -> EventResponse {
+        let status = match self.data.deployment_status {
+            Some(ref s) => s,
+            None => return (StatusCode::OK, "deployment_status [ignored]".into()),
+        };
 
-        if !self.data.ref_name().is_empty()
-            && !self.data.after().is_empty()
-            && !self.data.before().is_empty()
-        {
-            let branch_name = self.data.ref_name().replace("refs/heads/", "");
+        // only announce finished deployments
+        let color = match status.state.as_str() {
+            "success" => "good",
+            "failure" | "error" => "danger",
+            _ => return (StatusCode::OK, "deployment_status [ignored]".into()),
+        };
 
-            let release_branch_prefix = self.config.repos().release_branch_prefix(&self.repository);
-            let is_versioned_branch = github::is_main_branch(&branch_name)
-                || branch_name.starts_with(&release_branch_prefix);
+        let msg = format!("Deployment to {} {}", status.environment, status.state);
+        let mut attachment =
+            SlackAttachmentBuilder::new(status.description.as_deref().unwrap_or("").trim());
+        attachment
+            .title(format!("Deployment to {}", status.environment))
+            .color(color);
+        if let Some(ref url) = status.target_url {
+            if !url.is_empty() {
+                attachment.title_link(url.as_str());
+            }
+        }
 
-            // only lookup PRs for non-main branches
-            if !is_versioned_branch {
-                let prs = match self
+        self.messenger.send_to_channel(
+            &msg,
+            &[attachment.build()],
+            &self.repository,
+            "",
+            &Vec::<github::Commit>::new(),
+            vec![],
+            false,
+        );
+
+        (StatusCode::OK, "deployment_status".into())
+    }
+
+    fn handle_secret_scanning_alert(&self) -> EventResponse {
+        match self.data.alert {
+            Some(ref alert) if self.action == "created" => {
+                self.messenger
+                    .send_secret_scanning_alert(&self.repository, alert);
+                (StatusCode::OK, "secret_scanning_alert".into())
+            }
+            _ => (StatusCode::OK, "secret_scanning_alert [ignored]".into()),
+        }
+    }
+
+    // "Re-run" on the JIRA check: re-evaluate the PR commits without requiring a push
+    async fn handle_check_run(&self) -> EventResponse {
+        let run = match self.data.check_run {
+            Some(ref r) if self.action == "rerequested" => r,
+            _ => return (StatusCode::OK, "check_run [ignored]".into()),
+        };
+
+        let (check_name, template) = {
+            let repos = self.config.repos();
+            (
+                repos.jira_check_name(&self.repository),
+                repos.jira_check_template(&self.repository),
+            )
+        };
+        if run.name != check_name {
+            return (StatusCode::OK, "check_run [ignored]".into());
+        }
+
+        for pr in &run.pull_requests {
+            let pull_request = match self
+                .github_session
+                .get_pull_request(
+                    self.repository.owner.login(),
+                    &self.repository.name,
+                    pr.number,
+                )
+                .await
+            {
+                Ok(p) => p,
+                Err(e) => {
+                    error!("Error looking up PR #{}: {}", pr.number, e);
+                    continue;
+                }
+            };
+
+            // a newer push already has its own check run
+            if pull_request.head.sha != run.head_sha {
+                info!(
+                    "Skipping JIRA check re-run for PR #{}: {} is no longer the head",
+                    pr.number, run.head_sha
+                );
+                continue;
+            }
+
+            let commits = self.pull_request_commits(&&pull_request).await;
+            let jira_projects = self
+                .config
+                .repos()
+                .jira_projects(&self.repository, &pull_request.base.ref_name);
+
+            jira::check_jira_refs_with_template(
+                &pull_request,
+                &commits,
+                &jira_projects,
+                &check_name,
+                &template,
+                self.github_session.deref(),
+                self.config.github.check_run_retries(),
+            )
+            .await;
+        }
+
+        (StatusCode::OK, "check_run".into())
+    }
+
+    async fn handle_workflow_run(&self) -> EventResponse {
+        let run = match self.data.workflow_run {
+            Some(ref r) if self.action == "completed" && r.is_failure() => r,
+            _ => return (StatusCode::OK, "workflow_run [ignored]".into()),
+        };
+
+        let comment_on_jiras = match (&self.config.jira, &self.jira_session) {
+            (Some(ref jira_config), Some(_)) => jira_config.workflow_failure_comments(),
+            _ => false,
+        };
+        if !self.config.github.notify_workflow_failures() && !comment_on_jiras {
+            return (StatusCode::OK, "workflow_run".into());
+        }
+
+        let branch_name = run.head_branch.as_deref().unwrap_or("");
+        // the branch the run's PRs target, which is what channels and JIRA projects are keyed on
+        let base_branch = run
+            .pull_requests
+            .first()
+            .map(|pr| pr.base.ref_name.as_str())
+            .unwrap_or(branch_name);
+
+        // gather commits of the run's PRs for channel routing and JIRA references
+        let mut commits = vec![];
+        for pr in &run.pull_requests {
+            match self
+                .github_session
+                .get_pull_request_commits(
+                    self.repository.owner.login(),
+                    &self.repository.name,
+                    pr.number,
+                )
+                .await
+            {
+                Ok(c) => commits.extend(c),
+                Err(e) => error!("Error looking up PR #{} commits: {}", pr.number, e),
+            }
+        }
+
+        if self.config.github.notify_workflow_failures() {
+            let msg = format!("Workflow \"{}\" failed on branch {}", run.name, branch_name);
+            let short_sha: String = run.head_sha.chars().take(7).collect();
+            let attachments = vec![SlackAttachmentBuilder::new("")
+                .title(format!("Workflow run for {}", short_sha))
+                .title_link(run.html_url.as_str())
+                .color("danger")
+                .build()];
+            let thread_guids = run
+                .pull_requests
+                .iter()
+                .map(|pr| self.build_thread_guid(pr.number))
+                .collect();
+
+            self.messenger.send_to_channel(
+                &msg,
+                &attachments,
+                &self.repository,
+                base_branch,
+                &commits,
+                thread_guids,
+                false,
+            );
+        }
+
+        if let (Some(ref jira_config), Some(ref jira_session)) =
+            (&self.config.jira, &self.jira_session)
+        {
+            if comment_on_jiras && !commits.is_empty() {
+                let (jira_projects, visibility) = {
+                    let repos = self.config.repos();
+                    (
+                        repos.jira_projects(&self.repository, base_branch),
+                        repos.jira_comment_visibility(&self.repository),
+                    )
+                };
+                jira::workflow::comment_workflow_failure(
+                    run,
+                    &commits,
+                    &jira_projects,
+                    jira_session.deref(),
+                    jira_config,
+                    visibility,
+                )
+                .await;
+            }
+        }
+
+        (StatusCode::OK, "workflow_run".into())
+    }
+
+    // Type and priority of the JIRAs referenced by a PR, if configured
+    async fn jira_issue_details(
+        &self,
+        branch_name: &str,
+        commits: &[github::Commit],
+    ) -> Option<String> {
+        let jira_session = match (&self.config.jira, &self.jira_session) {
+            (Some(c), Some(s)) if c.issue_details_in_notifications() => s,
+            _ => return None,
+        };
+        if commits.len() > self.max_commits_for_jira() {
+            return None;
+        }
+
+        let jira_projects = self
+            .config
+            .repos()
+            .jira_projects(&self.repository, branch_name);
+        jira::workflow::issue_details(commits, &jira_projects, jira_session.deref()).await
+    }
+
+    async fn comment_jira_summaries(
+        &self,
+        pull_request: &github::PullRequest,
+        commits: &[github::Commit],
+        jira_projects: &[String],
+        jira_session: &dyn jira::api::Session,
+        jira_config: &JiraConfig,
+    ) {
+        let comment = match jira::workflow::pr_summary_comment(
+            commits,
+            jira_projects,
+            jira_session,
+            jira_config,
+        )
+        .await
+        {
+            Some(c) => c,
+            None => return,
+        };
+
+        if let Err(e) = self
+            .github_session
+            .comment_pull_request(
+                self.repository.owner.login(),
+                &self.repository.name,
+                pull_request.number,
+                &comment,
+            )
+            .await
+        {
+            error!(
+                "Error commenting JIRA summaries on PR #{}: {}",
+                pull_request.number, e
+            );
+        }
+    }
+
+    fn handle_discussion(&self) -> EventResponse {
+        let channel = match self.config.repos().discussions_channel(&self.repository) {
+            Some(c) => c,
+            None => return (StatusCode::OK, "discussion [ignored]".into()),
+        };
+
+        if let Some(ref discussion) = self.data.discussion {
+            if self.action == "created" {
+                let msg = format!(
+                    "New discussion {}",
+                    util::make_link(
+                        &discussion.html_url,
+                        &format!("#{}: \"{}\"", discussion.number, discussion.title)
+                    )
+                );
+                let attachments = vec![SlackAttachmentBuilder::new(discussion.body())
+                    .title(format!("{} said:", self.slack_user_name(&discussion.user)))
+                    .title_link(discussion.html_url.as_str())
+                    .build()];
+
+                self.send_to_discussions_channel(&channel, &msg, &attachments);
+            }
+        }
+
+        (StatusCode::OK, "discussion".into())
+    }
+
+    fn handle_discussion_comment(&self) -> EventResponse {
+        let channel = match self.config.repos().discussions_channel(&self.repository) {
+            Some(c) => c,
+            None => return (StatusCode::OK, "discussion_comment [ignored]".into()),
+        };
+
+        if let (Some(ref discussion), Some(ref comment)) =
+            (&self.data.discussion, &self.data.comment)
+        {
+            if self.action == "created" {
+                let msg = format!(
+                    "Comment on discussion {}",
+                    util::make_link(
+                        &discussion.html_url,
+                        &format!("#{}: \"{}\"", discussion.number, discussion.title)
+                    )
+                );
+                let attachments = vec![SlackAttachmentBuilder::new(comment.body())
+                    .title(format!("{} said:", self.slack_user_name(&comment.user)))
+                    .title_link(comment.html_url.as_str())
+                    .build()];
+
+                self.send_to_discussions_channel(&channel, &msg, &attachments);
+            }
+        }
+
+        (StatusCode::OK, "discussion_comment".into())
+    }
+
+    fn send_to_discussions_channel(
+        &self,
+        channel: &str,
+        msg: &str,
+        attachments: &[slack::SlackAttachment],
+    ) {
+        if self
+            .messenger
+            .is_ignored_user(&self.data.sender, &self.repository)
+        {
+            info!(
+                "Ignoring discussion message from ignored user: {}",
+                self.data.sender.login()
+            );
+            return;
+        }
+
+        self.messenger
+            .with_channel_override(channel)
+            .send_to_channel(
+                msg,
+                attachments,
+                &self.repository,
+                "",
+                &Vec::<github::Commit>::new(),
+                vec![],
+                false,
+            );
+    }
+
+    async fn handle_issue_comment(&self) -> EventResponse {
+        if let Some(ref comment) = self.data.comment {
+            if self.action == "created" {
+                // Check to see if we remapped this "issue" to a PR
+                if let Some(ref pr) = self.data.pull_request {
+                    let branch_name = &pr.base.ref_name;
+                    let commits = self.pull_request_commits(&pr).await;
+
+                    self.do_pull_request_comment(&pr, &comment, branch_name, &commits)
+                        .await;
+                } else if let Some(ref issue) = self.data.issue {
+                    // issues do not have branches or commits -> main channel is fine.
+                    let branch_name = "";
+                    let commits = vec![];
+
+                    self.do_pull_request_comment(&issue, &comment, branch_name, &commits)
+                        .await;
+                }
+            }
+        }
+        (StatusCode::OK, "issue_comment".into())
+    }
+
+    fn handle_issues(&self) -> EventResponse {
+        let issue = match self.data.issue {
+            Some(ref i) => i,
+            None => return (StatusCode::OK, "issues".into()),
+        };
+        if !["opened", "closed", "reopened"].contains(&self.action.as_str()) {
+            return (StatusCode::OK, "issues [ignored]".into());
+        }
+        if self
+            .messenger
+            .is_ignored_user(&self.data.sender, &self.repository)
+        {
+            info!(
+                "Ignoring issue message from ignored user: {}",
+                self.data.sender.login()
+            );
+            return (StatusCode::OK, "issues".into());
+        }
+
+        let msg = format!(
+            "Issue {} {} by {}",
+            util::make_link(
+                &issue.html_url,
+                &format!("#{}: \"{}\"", issue.number, issue.title)
+            ),
+            self.action,
+            self.slack_user_name(&self.data.sender)
+        );
+
+        // issues do not have branches or commits -> main channel is fine.
+        self.messenger.send_to_channel(
+            &msg,
+            &[],
+            &self.repository,
+            "",
+            &Vec::<github::Commit>::new(),
+            vec![self.build_thread_guid(issue.number)],
+            self.action == "opened",
+        );
+
+        (StatusCode::OK, "issues".into())
+    }
+
+    async fn request_default_reviewers(&self, pull_request: &github::PullRequest) {
+        let has_reviewers = pull_request
+            .requested_reviewers
+            .as_ref()
+            .is_some_and(|r| !r.is_empty())
+            || pull_request
+                .requested_teams
+                .as_ref()
+                .is_some_and(|t| !t.is_empty());
+        if has_reviewers {
+            return;
+        }
+
+        // authors cannot review their own PRs
+        let mut reviewers = self
+            .config
+            .repos()
+            .default_reviewers(&self.repository)
+            .into_iter()
+            .filter(|r| r != pull_request.user.login())
+            .collect::<Vec<_>>();
+
+        let pool_reviewers = self
+            .config
+            .repos_write()
+            .next_pool_reviewers(&self.repository, pull_request.user.login());
+        match pool_reviewers {
+            Ok(pool_reviewers) => {
+                for r in pool_reviewers {
+                    if !reviewers.contains(&r) {
+                        reviewers.push(r);
+                    }
+                }
+            }
+            Err(e) => error!(
+                "Error picking pool reviewers for PR #{}: {}",
+                pull_request.number, e
+            ),
+        }
+
+        if reviewers.is_empty() {
+            return;
+        }
+
+        if let Err(e) = self
+            .github_session
+            .request_review(
+                self.repository.owner.login(),
+                &self.repository.name,
+                pull_request.number,
+                reviewers,
+            )
+            .await
+        {
+            error!(
+                "Error requesting default reviewers for PR #{}: {}",
+                pull_request.number, e
+            );
+        }
+    }
+
+    fn pr_size(&self, pull_request: &github::PullRequest) -> Option<&'static str> {
+        let changed_lines = pull_request.changed_lines()?;
+        Some(github::pr_size(
+            changed_lines,
+            &self.config.github.pr_size_thresholds(),
+        ))
+    }
+
+    // Lets the channel know a PR was retargeted, along with the JIRA projects for its new base
+    fn notify_base_changed(
+        &self,
+        messenger: &Messenger,
+        pull_request: &github::PullRequest,
+        previous_base: &str,
+        commits: &[github::Commit],
+    ) {
+        let branch_name = &pull_request.base.ref_name;
+        let jira_projects = self
+            .config
+            .repos()
+            .jira_projects(&self.repository, branch_name);
+        let projects_text = if jira_projects.is_empty() {
+            String::new()
+        } else {
+            format!("JIRA projects: {}", jira_projects.join(", "))
+        };
+
+        let msg = format!(
+            "Pull Request base changed from {} to {}",
+            previous_base, branch_name
+        );
+        let attachments = vec![SlackAttachmentBuilder::new(&projects_text)
+            .title(format!(
+                "Pull Request #{}: \"{}\"",
+                pull_request.number,
+                pull_request.title.as_str()
+            ))
+            .title_link(pull_request.html_url.as_str())
+            .build()];
+
+        messenger.send_to_channel(
+            &msg,
+            &attachments,
+            &self.repository,
+            branch_name,
+            commits,
+            vec![self.build_thread_guid(pull_request.number)],
+            false,
+        );
+    }
+
+    // The kind of PR event: its action, except closing a merged PR is "merged"
+    fn pr_event_kind(&self) -> String {
+        let merged = self
+            .data
+            .pull_request
+            .as_ref()
+            .is_some_and(|pr| pr.merged == Some(true));
+        if self.action == "closed" && merged {
+            "merged".to_string()
+        } else {
+            self.action.clone()
+        }
+    }
+
+    // The repo's override for this PR event's notification, e.g. "pr_opened" or "pr_merged"
+    fn pr_message_template(&self) -> Option<String> {
+        let event = format!("pr_{}", self.pr_event_kind());
+
+        self.config
+            .repos()
+            .message_template(&self.repository, &event)
+    }
+
+    fn render_pr_message(&self, template: &str, pull_request: &github::PullRequest) -> String {
+        template
+            .replace("{user}", &self.slack_user_name(&self.data.sender))
+            .replace("{title}", &pull_request.title)
+            .replace("{number}", &pull_request.number.to_string())
+    }
+
+    // Labels the PR with its size, e.g. "size/M", replacing any label for a previous size.
+    async fn apply_size_label(&self, pull_request: &github::PullRequest) {
+        if !self.config.github.pr_size_labels() {
+            return;
+        }
+        let size = match self.pr_size(pull_request) {
+            Some(s) => s,
+            None => return,
+        };
+
+        let label = format!("size/{}", size);
+        let labels = pull_request.labels.as_deref().unwrap_or(&[]);
+        if labels.iter().any(|l| l.name == label) {
+            return;
+        }
+
+        for old_label in labels.iter().filter(|l| l.name.starts_with("size/")) {
+            if let Err(e) = self
+                .github_session
+                .remove_pull_request_label(
+                    self.repository.owner.login(),
+                    &self.repository.name,
+                    pull_request.number,
+                    &old_label.name,
+                )
+                .await
+            {
+                error!(
+                    "Error removing size label from PR #{}: {}",
+                    pull_request.number, e
+                );
+            }
+        }
+
+        if let Err(e) = self
+            .github_session
+            .add_pull_request_labels(
+                self.repository.owner.login(),
+                &self.repository.name,
+                pull_request.number,
+                vec![label],
+            )
+            .await
+        {
+            error!(
+                "Error adding size label to PR #{}: {}",
+                pull_request.number, e
+            );
+        }
+    }
+
+    // Applies the labels configured for the paths this PR changes
+    async fn apply_path_labels(&self, pull_request: &github::PullRequest) {
+        if !self.config.repos().has_path_labels(&self.repository) {
+            return;
+        }
+
+        let files = match self
+            .github_session
+            .get_pull_request_files(
+                self.repository.owner.login(),
+                &self.repository.name,
+                pull_request.number,
+            )
+            .await
+        {
+            Ok(f) => f,
+            Err(e) => {
+                error!(
+                    "Error looking up files for PR #{}: {}",
+                    pull_request.number, e
+                );
+                return;
+            }
+        };
+
+        let paths = files.into_iter().map(|f| f.filename).collect::<Vec<_>>();
+        let existing = pull_request.labels.as_deref().unwrap_or(&[]);
+        let labels = self
+            .config
+            .repos()
+            .path_labels(&self.repository, &paths)
+            .into_iter()
+            .filter(|l| !existing.iter().any(|e| &e.name == l))
+            .collect::<Vec<_>>();
+        if labels.is_empty() {
+            return;
+        }
+
+        if let Err(e) = self
+            .github_session
+            .add_pull_request_labels(
+                self.repository.owner.login(),
+                &self.repository.name,
+                pull_request.number,
+                labels,
+            )
+            .await
+        {
+            error!(
+                "Error adding path labels to PR #{}: {}",
+                pull_request.number, e
+            );
+        }
+    }
+
+    // Suggests the candidate with the fewest open PRs awaiting their review, for PRs opened
+    // without any reviewers. Only a suggestion: nobody is requested.
+    async fn suggest_reviewer(&self, pull_request: &github::PullRequest) -> Option<String> {
+        let has_reviewers = pull_request
+            .requested_reviewers
+            .as_ref()
+            .is_some_and(|r| !r.is_empty())
+            || pull_request
+                .requested_teams
+                .as_ref()
+                .is_some_and(|t| !t.is_empty());
+        if has_reviewers {
+            return None;
+        }
+
+        let (candidates, default_reviewers) = {
+            let repos = self.config.repos();
+            (
+                repos.suggested_reviewers(&self.repository),
+                repos.default_reviewers(&self.repository),
+            )
+        };
+        // default reviewers are requested instead
+        if candidates.is_empty() || !default_reviewers.is_empty() {
+            return None;
+        }
+
+        let open_prs = match self
+            .github_session
+            .get_pull_requests(
+                self.repository.owner.login(),
+                &self.repository.name,
+                Some("open"),
+                None,
+            )
+            .await
+        {
+            Ok(prs) => prs,
+            Err(e) => {
+                error!("Error looking up open PRs to suggest a reviewer: {}", e);
+                return None;
+            }
+        };
+
+        least_loaded_reviewer(&candidates, &open_prs, pull_request.user.login())
+    }
+
+    async fn sync_milestone_version(
+        &self,
+        commits: &[github::Commit],
+        jira_projects: &[String],
+        jira_session: &dyn jira::api::Session,
+        milestone: &str,
+    ) {
+        if self.action == "milestoned" {
+            jira::workflow::assign_milestone_version(
+                commits,
+                jira_projects,
+                jira_session,
+                milestone,
+            )
+            .await;
+        } else {
+            jira::workflow::remove_milestone_version(
+                commits,
+                jira_projects,
+                jira_session,
+                milestone,
+            )
+            .await;
+        }
+    }
+
+    async fn handle_push(&self) -> EventResponse {
+        if self.data.deleted() || self.data.created() {
+            // ignore
+            return (StatusCode::OK, "push [ignored]".into());
+        }
+
+        if !self.data.ref_name().is_empty()
+            && !self.data.after().is_empty()
+            && !self.data.before().is_empty()
+        {
+            let branch_name = self.data.ref_name().replace("refs/heads/", "");
+
+            let release_branch_prefix = self.config.repos().release_branch_prefix(&self.repository);
+            let is_versioned_branch = github::is_main_branch(&branch_name)
+                || branch_name.starts_with(&release_branch_prefix);
+
+            if self.config.github.skip_release_merges()
+                && branch_name.starts_with(&release_branch_prefix)
+                && self.is_release_merge_push(&release_branch_prefix)
+            {
+                info!(
+                    "Skipping merge of release branch into '{}' ({})",
+                    branch_name,
+                    self.data.after()
+                );
+                return (StatusCode::OK, "push [release merge, ignored]".into());
+            }
+
+            // only lookup PRs for non-main branches
+            if !is_versioned_branch {
+                let prs = match self
                     .github_session
                     .get_pull_requests(
                         self.repository.owner.login(),
@@ -1032,12 +3053,103 @@ impl GithubEventHandler {
                         branch_name
                     );
 
+                    let blocks_messenger = if self.config.slack.use_blocks {
+                        Some(self.messenger.with_blocks())
+                    } else {
+                        None
+                    };
+                    let messenger = blocks_messenger.as_ref().unwrap_or(&self.messenger);
+
+                    let mut ready_prs = vec![];
                     for pull_request in &prs {
                         if pull_request.is_draft() {
                             info!("Skipping WIP PR #{}", pull_request.number);
                             continue;
                         }
 
+                        let commits = self.pull_request_commits(&pull_request).await;
+                        ready_prs.push((pull_request, commits));
+                    }
+
+                    let batch_messages =
+                        self.config.slack.batch_push_notifications && ready_prs.len() > 1;
+
+                    let notify_force_push = self.data.forced()
+                        && self.config.repos().notify_force_push(&self.repository);
+                    let summarize_force_push = notify_force_push
+                        && self.config.slack.summarize_force_push(ready_prs.len());
+
+                    // Post one channel message listing all PRs affected by the force-push
+                    // instead of notifying each PR
+                    if summarize_force_push
+                        && !messenger.is_ignored_user(&self.data.sender, &self.repository)
+                    {
+                        let pr_attachments = ready_prs
+                            .iter()
+                            .map(|(pull_request, _)| {
+                                SlackAttachmentBuilder::new("")
+                                    .title(format!(
+                                        "Pull Request #{}: \"{}\"",
+                                        pull_request.number,
+                                        pull_request.title.as_str()
+                                    ))
+                                    .title_link(pull_request.html_url.as_str())
+                                    .build()
+                            })
+                            .collect::<Vec<_>>();
+
+                        messenger.send_to_channel(
+                            &format!(
+                                "{} force-pushed branch {}, affecting {} pull requests",
+                                self.slack_user_name(&self.data.sender),
+                                branch_name,
+                                ready_prs.len()
+                            ),
+                            &pr_attachments,
+                            &self.repository,
+                            &branch_name,
+                            &Vec::<github::Commit>::new(),
+                            vec![],
+                            false,
+                        );
+                    }
+
+                    // Post one channel message listing all PRs instead of one per PR
+                    if batch_messages
+                        && !messenger.is_ignored_user(&self.data.sender, &self.repository)
+                    {
+                        let mut batch_attachments = ready_prs
+                            .iter()
+                            .map(|(pull_request, _)| {
+                                SlackAttachmentBuilder::new("")
+                                    .title(format!(
+                                        "Pull Request #{}: \"{}\"",
+                                        pull_request.number,
+                                        pull_request.title.as_str()
+                                    ))
+                                    .title_link(pull_request.html_url.as_str())
+                                    .build()
+                            })
+                            .collect::<Vec<_>>();
+                        batch_attachments.extend(attachments.iter().cloned());
+
+                        let all_commits = ready_prs
+                            .iter()
+                            .flat_map(|(_, commits)| commits.iter().cloned())
+                            .collect::<Vec<_>>();
+
+                        messenger.send_to_channel(
+                            &message,
+                            &batch_attachments,
+                            &self.repository,
+                            &branch_name,
+                            &all_commits,
+                            vec![],
+                            false,
+                        );
+                    }
+
+                    for (pull_request, commits) in ready_prs {
                         let mut attachments = attachments.clone();
                         attachments.insert(
                             0,
@@ -1051,29 +3163,39 @@ impl GithubEventHandler {
                                 .build(),
                         );
 
-                        let commits = self.pull_request_commits(&pull_request).await;
-
-                        self.messenger.send_to_all(
-                            &message,
-                            &attachments,
-                            &pull_request.user,
-                            &self.data.sender,
-                            &self.repository,
-                            self.all_participants(&pull_request, &commits).await,
-                            &branch_name,
-                            &commits,
-                            vec![self.build_thread_guid(pull_request.number)],
-                        );
+                        // the channel already got the batch or force-push summary
+                        let participants = self.all_participants(&pull_request, &commits).await;
+                        if batch_messages || summarize_force_push {
+                            messenger.send_to_participants(
+                                &message,
+                                &attachments,
+                                &pull_request.user,
+                                &self.data.sender,
+                                &self.repository,
+                                participants,
+                            );
+                        } else {
+                            messenger.send_to_all(
+                                &message,
+                                &attachments,
+                                &pull_request.user,
+                                &self.data.sender,
+                                &self.repository,
+                                participants,
+                                &branch_name,
+                                &commits,
+                                vec![self.build_thread_guid(pull_request.number)],
+                            );
+                        }
 
-                        if self.data.forced()
-                            && self.config.repos().notify_force_push(&self.repository)
-                        {
+                        if notify_force_push && !self.replay {
                             let msg = force_push::req(
                                 &self.repository,
                                 pull_request,
                                 self.data.before(),
                                 self.data.after(),
-                            );
+                            )
+                            .with_event_id(self.event_id.clone());
                             self.force_push.send(msg);
                         }
 
@@ -1084,11 +3206,16 @@ impl GithubEventHandler {
                             .jira_projects(&self.repository, &pull_request.base.ref_name);
 
                         // Mark if no JIRA references
-                        jira::check_jira_refs(
+                        let check_name = self.config.repos().jira_check_name(&self.repository);
+                        let template = self.config.repos().jira_check_template(&self.repository);
+                        jira::check_jira_refs_with_template(
                             pull_request,
                             &commits,
                             &jira_projects,
+                            &check_name,
+                            &template,
                             self.github_session.deref(),
+                            self.config.github.check_run_retries(),
                         )
                         .await;
                     }
@@ -1102,15 +3229,51 @@ impl GithubEventHandler {
                 .jira_projects(&self.repository, &branch_name)
                 .is_empty();
 
+            // plain resolves don't run a script, so aren't subject to the minimum
+            let min_commits = {
+                let repos = self.config.repos();
+                match repos.version_script_mode(&self.repository) {
+                    VersionScriptMode::Resolve => 0,
+                    _ => repos.version_script_min_commits(&self.repository) as usize,
+                }
+            };
+
             // Mark JIRAs as merged
             if is_versioned_branch && has_jira_projects {
                 if let Some(ref commits) = self.data.commits {
+                    // pushes that aren't versioned still resolve their JIRAs
+                    let resolve_only = if self.replay {
+                        info!(
+                            "Skipping version script for replayed push to '{}'",
+                            branch_name
+                        );
+                        true
+                    } else if commits.len() < min_commits {
+                        info!(
+                            "Skipping version script for push of {} commit(s) to '{}': fewer than {}",
+                            commits.len(),
+                            branch_name,
+                            min_commits
+                        );
+                        true
+                    } else if !self.push_touches_version_script_paths().await {
+                        info!(
+                            "Skipping version script for push to '{}': no changes to version script paths",
+                            branch_name
+                        );
+                        true
+                    } else {
+                        false
+                    };
+
                     let msg = repo_version::req(
                         &self.repository,
                         &branch_name,
                         self.data.after(),
                         commits,
-                    );
+                    )
+                    .with_event_id(self.event_id.clone())
+                    .with_resolve_only(resolve_only);
                     self.repo_version.send(msg);
                 }
             }
@@ -1119,6 +3282,50 @@ impl GithubEventHandler {
         (StatusCode::OK, "push".into())
     }
 
+    // Whether this push changes any of the repo's version script paths. Fails open so that a
+    // lookup error doesn't skip versioning.
+    async fn push_touches_version_script_paths(&self) -> bool {
+        let has_paths = {
+            let repos = self.config.repos();
+            repos.version_script_mode(&self.repository) != VersionScriptMode::Resolve
+                && repos.has_version_script_paths(&self.repository)
+        };
+        if !has_paths {
+            return true;
+        }
+
+        match self
+            .github_session
+            .get_compare_files(
+                self.repository.owner.login(),
+                &self.repository.name,
+                self.data.before(),
+                self.data.after(),
+            )
+            .await
+        {
+            Ok(files) => {
+                let paths = files.into_iter().map(|f| f.filename).collect::<Vec<_>>();
+                self.config
+                    .repos()
+                    .matches_version_script_paths(&self.repository, &paths)
+            }
+            Err(e) => {
+                error!("Error looking up files changed by push: {}", e);
+                true
+            }
+        }
+    }
+
+    // Whether the pushed HEAD commit merges another release branch
+    fn is_release_merge_push(&self, release_branch_prefix: &str) -> bool {
+        self.data
+            .commits
+            .as_ref()
+            .and_then(|commits| commits.last())
+            .is_some_and(|c| github::is_release_merge(&c.message, release_branch_prefix))
+    }
+
     async fn merge_pull_request_all_labels(
         &self,
         pull_request: &github::PullRequest,
@@ -1156,11 +3363,76 @@ impl GithubEventHandler {
             }
         };
 
+        if self.skip_conflicting_backports(pull_request, &labels, commits) {
+            return;
+        }
+
         for label in &labels {
             self.merge_pull_request(pull_request, label, release_branch_prefix, commits);
         }
     }
 
+    // Warns the owner if a merged PR has both backport labels and the no-backport label.
+    // Returns true if backports should be skipped per the configured conflict policy.
+    fn skip_conflicting_backports(
+        &self,
+        pull_request: &github::PullRequest,
+        labels: &[github::Label],
+        commits: &[github::Commit],
+    ) -> bool {
+        if !pull_request.is_merged() {
+            return false;
+        }
+
+        let no_backport_label = self.config.github.no_backport_label();
+        let backport_label_prefix = self.config.repos().backport_label_prefix(&self.repository);
+        let no_backport = labels
+            .iter()
+            .any(|l| l.name.eq_ignore_ascii_case(&no_backport_label));
+        let backport_labels = labels
+            .iter()
+            .filter(|l| parse_backport_label(&l.name, &backport_label_prefix).is_some())
+            .map(|l| l.name.as_str())
+            .collect::<Vec<_>>();
+        if !no_backport || backport_labels.is_empty() {
+            return false;
+        }
+
+        let skip = match self.config.github.backport_conflict_policy() {
+            BackportConflictPolicy::PreferNoBackport => true,
+            BackportConflictPolicy::PreferBackport => false,
+        };
+        let msg = format!(
+            "Pull Request #{} has conflicting labels '{}' and '{}': {}",
+            pull_request.number,
+            no_backport_label,
+            backport_labels.join("', '"),
+            if skip {
+                "skipping backports"
+            } else {
+                "backporting anyway"
+            }
+        );
+        warn!("{}", msg);
+        self.messenger.send_to_owner(
+            &msg,
+            &[SlackAttachmentBuilder::new("")
+                .title(format!(
+                    "Pull Request #{}: \"{}\"",
+                    pull_request.number, pull_request.title
+                ))
+                .title_link(pull_request.html_url.clone())
+                .color("warning")
+                .build()],
+            &pull_request.user,
+            &self.repository,
+            &pull_request.base.ref_name,
+            commits,
+        );
+
+        skip
+    }
+
     fn merge_pull_request(
         &self,
         pull_request: &github::PullRequest,
@@ -1172,9 +3444,9 @@ impl GithubEventHandler {
             return;
         }
 
-        let re = Regex::new(r"(?i)backport-(.+)").unwrap();
-        let backport = match re.captures(&label.name) {
-            Some(c) => c[1].to_string(),
+        let backport_label_prefix = self.config.repos().backport_label_prefix(&self.repository);
+        let backport = match parse_backport_label(&label.name, &backport_label_prefix) {
+            Some(b) => b,
             None => return,
         };
         let target_branch = if github::is_main_branch(&backport) {
@@ -1183,16 +3455,79 @@ impl GithubEventHandler {
             release_branch_prefix.to_string() + &backport
         };
 
+        if !self.recent_backports.check_and_record(
+            &self.repository,
+            pull_request.number,
+            &label.name,
+            self.config.github.backport_label_window(),
+        ) {
+            info!(
+                "Ignoring recently processed backport label '{}' on {}#{}",
+                label.name, self.repository.full_name, pull_request.number
+            );
+            return;
+        }
+
+        let disallowed_authors = self.disallowed_backport_authors(commits);
+        if !disallowed_authors.is_empty() {
+            let msg = format!(
+                "Not backporting PR #{} to {}: commits by authors not allowed to be backported: {}",
+                pull_request.number,
+                target_branch,
+                disallowed_authors.join(", ")
+            );
+            info!("{}", msg);
+            self.messenger.send_to_owner(
+                &msg,
+                &[SlackAttachmentBuilder::new("")
+                    .title(format!(
+                        "Source PR: #{}: \"{}\"",
+                        pull_request.number, pull_request.title
+                    ))
+                    .title_link(pull_request.html_url.clone())
+                    .color("warning")
+                    .build()],
+                &pull_request.user,
+                &self.repository,
+                &target_branch,
+                commits,
+            );
+            return;
+        }
+
+        let merge_strategy = self.config.repos().merge_strategy(&self.repository);
         let req = pr_merge::req(
             &self.repository,
             pull_request,
             &target_branch,
             release_branch_prefix,
             commits,
-        );
+            merge_strategy,
+        )
+        .with_event_id(self.event_id.clone());
         self.pr_merge.send(req);
     }
 
+    // Commit authors missing from the repo's backport allowlist. Empty if there is no allowlist.
+    fn disallowed_backport_authors(&self, commits: &[github::Commit]) -> Vec<String> {
+        let allowed = self.config.repos().backport_authors(&self.repository);
+        if allowed.is_empty() {
+            return vec![];
+        }
+
+        let mut disallowed = collections::BTreeSet::new();
+        for commit in commits {
+            let login = match commit.author {
+                Some(ref u) => u.login(),
+                None => "(unknown author)",
+            };
+            if !allowed.iter().any(|a| a == login) {
+                disallowed.insert(login.to_string());
+            }
+        }
+        disallowed.into_iter().collect()
+    }
+
     fn build_thread_guid(&self, number: u32) -> String {
         format!(
             "{}/{}/{}",
@@ -1202,3 +3537,33 @@ impl GithubEventHandler {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use octobot_lib::config::ConfigModel;
+
+    async fn resp_body(resp: Response<Body>) -> String {
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        String::from_utf8_lossy(&body).into_owned()
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_webhook_resp() {
+        let config = ConfigModel::new().github;
+
+        let resp = duplicate_webhook_resp(&config, "abc-123");
+        assert_eq!(StatusCode::BAD_REQUEST, resp.status());
+        assert_eq!("Duplicate webhook event: abc-123", resp_body(resp).await);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_webhook_resp_configured() {
+        let mut config = ConfigModel::new().github;
+        config.duplicate_webhook_response = Some("duplicate, ignored".into());
+
+        let resp = duplicate_webhook_resp(&config, "abc-123");
+        assert_eq!(StatusCode::OK, resp.status());
+        assert_eq!("duplicate, ignored", resp_body(resp).await);
+    }
+}
@@ -0,0 +1,144 @@
+use std::sync::Arc;
+
+use hyper::{Body, Request, Response, StatusCode};
+use log::error;
+use serde_json::json;
+
+use octobot_lib::config::Config;
+use octobot_lib::errors::*;
+use octobot_lib::github::api::{GithubSessionFactory, Session};
+use octobot_lib::jira;
+use octobot_ops::webhook_db::WebhookDatabase;
+
+use crate::http_util;
+use crate::server::http::Handler;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Health {
+    Ok,
+    Skipped,
+    Error,
+}
+
+impl Health {
+    fn of(name: &str, result: Result<()>) -> Health {
+        match result {
+            Ok(()) => Health::Ok,
+            Err(e) => {
+                error!("Health check failed for {}: {}", name, e);
+                Health::Error
+            }
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Health::Ok => "ok",
+            Health::Skipped => "skipped",
+            Health::Error => "error",
+        }
+    }
+}
+
+// Reports connectivity to each subsystem for liveness/readiness probes
+pub struct HealthHandler {
+    config: Arc<Config>,
+    github_app: Arc<dyn GithubSessionFactory>,
+    jira_session: Option<Arc<dyn jira::api::Session>>,
+    webhook_db: Arc<WebhookDatabase>,
+}
+
+impl HealthHandler {
+    pub fn new(
+        config: Arc<Config>,
+        github_app: Arc<dyn GithubSessionFactory>,
+        jira_session: Option<Arc<dyn jira::api::Session>>,
+        webhook_db: Arc<WebhookDatabase>,
+    ) -> Box<HealthHandler> {
+        Box::new(HealthHandler {
+            config,
+            github_app,
+            jira_session,
+            webhook_db,
+        })
+    }
+
+    async fn check_github(&self) -> Result<()> {
+        self.github_app.new_service_session().await?.ping().await
+    }
+
+    async fn check_jira(&self) -> Health {
+        if self.config.jira.is_none() {
+            return Health::Skipped;
+        }
+        let result = match self.jira_session {
+            Some(ref jira) => jira.ping().await,
+            None => Err(anyhow::anyhow!("No JIRA session")),
+        };
+        Health::of("jira", result)
+    }
+}
+
+// A 503 if any subsystem is failing, so that readiness gating works
+fn health_response(checks: &[(&str, Health)]) -> (StatusCode, serde_json::Value) {
+    let mut body = serde_json::Map::new();
+    for (name, health) in checks {
+        body.insert(name.to_string(), json!(health.as_str()));
+    }
+
+    let status = if checks.iter().any(|(_, h)| *h == Health::Error) {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+
+    (status, serde_json::Value::Object(body))
+}
+
+#[async_trait::async_trait]
+impl Handler for HealthHandler {
+    async fn handle(&self, _: Request<Body>) -> Result<Response<Body>> {
+        let checks = [
+            ("github", Health::of("github", self.check_github().await)),
+            ("jira", self.check_jira().await),
+            ("config_db", Health::of("config_db", self.config.check_db())),
+            (
+                "webhook_db",
+                Health::of("webhook_db", self.webhook_db.check()),
+            ),
+        ];
+
+        let (status, body) = health_response(&checks);
+        let mut resp = http_util::new_json_resp(serde_json::to_string(&body)?);
+        *resp.status_mut() = status;
+        Ok(resp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_response_ok() {
+        let (status, body) = health_response(&[
+            ("github", Health::Ok),
+            ("jira", Health::Skipped),
+            ("config_db", Health::Ok),
+        ]);
+
+        assert_eq!(StatusCode::OK, status);
+        assert_eq!(
+            json!({"github": "ok", "jira": "skipped", "config_db": "ok"}),
+            body
+        );
+    }
+
+    #[test]
+    fn test_health_response_failure() {
+        let (status, body) = health_response(&[("github", Health::Error), ("jira", Health::Ok)]);
+
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, status);
+        assert_eq!(json!({"github": "error", "jira": "ok"}), body);
+    }
+}
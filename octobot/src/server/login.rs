@@ -115,7 +115,7 @@ impl Handler for LoginHandler {
 }
 
 fn invalid_session() -> Response<Body> {
-    http_util::new_msg_resp(StatusCode::FORBIDDEN, "Invalid session")
+    http_util::new_msg_resp(StatusCode::UNAUTHORIZED, "Invalid session")
 }
 
 #[async_trait]
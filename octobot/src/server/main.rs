@@ -3,8 +3,9 @@ use std::sync::Arc;
 
 use hyper::server::Server;
 use hyper::service::{make_service_fn, service_fn};
-use log::{error, info};
+use log::{error, info, warn};
 use octobot_ops::webhook_db::WebhookDatabase;
+use octobot_ops::worker;
 
 use crate::runtime;
 use crate::server::github_handler::GithubHandlerState;
@@ -13,7 +14,7 @@ use crate::server::sessions::Sessions;
 use octobot_lib::config::Config;
 use octobot_lib::github;
 use octobot_lib::jira;
-use octobot_lib::jira::api::JiraSession;
+use octobot_lib::jira::api::{DryRunSession, JiraSession};
 use octobot_lib::metrics;
 
 use octobot_lib::github::api::Session;
@@ -30,11 +31,15 @@ pub fn start(config: Config) {
 async fn run_server(config: Config, metrics: Arc<metrics::Metrics>) {
     let config = Arc::new(config);
 
-    let slack_api = Arc::new(octobot_ops::slack::Slack::new(
-        config.slack.bot_token.clone(),
-        config.slack_db_path(),
-        metrics.clone(),
-    ));
+    let slack_api = Arc::new(
+        octobot_ops::slack::Slack::new(
+            config.slack.bot_token.clone(),
+            config.slack_db_path(),
+            config.slack_retry_db_path(),
+            metrics.clone(),
+        )
+        .with_max_messages_per_minute(config.slack.max_messages_per_minute()),
+    );
 
     {
         let slack = slack_api.clone();
@@ -48,10 +53,11 @@ async fn run_server(config: Config, metrics: Arc<metrics::Metrics>) {
 
     let github_api: Arc<dyn github::api::GithubSessionFactory> = if config.github.app_id.is_some() {
         match github::api::GithubApp::new(
-            &config.github.host,
+            &config.github.prefixed_host(),
             config.github.app_id.expect("expected an app_id"),
             &config.github.app_key().expect("expected an app_key"),
             Some(metrics.clone()),
+            config.github.extra_headers(),
         )
         .await
         {
@@ -60,13 +66,14 @@ async fn run_server(config: Config, metrics: Arc<metrics::Metrics>) {
         }
     } else {
         match github::api::GithubOauthApp::new(
-            &config.github.host,
+            &config.github.prefixed_host(),
             config
                 .github
                 .api_token
                 .as_ref()
                 .expect("expected an api_token"),
             Some(metrics.clone()),
+            config.github.extra_headers(),
         )
         .await
         {
@@ -82,6 +89,10 @@ async fn run_server(config: Config, metrics: Arc<metrics::Metrics>) {
 
     let jira_api: Option<Arc<dyn jira::api::Session>> = if let Some(ref jira_config) = config.jira {
         match JiraSession::new(jira_config, Some(metrics.clone())).await {
+            Ok(s) if jira_config.dry_run() => {
+                warn!("JIRA dry run enabled: changes to JIRA will be logged but not made");
+                Some(Arc::new(DryRunSession::new(Arc::new(s))))
+            }
             Ok(s) => Some(Arc::new(s)),
             Err(e) => panic!("Error initiating jira session: {}", e),
         }
@@ -143,71 +154,148 @@ async fn run_server(config: Config, metrics: Arc<metrics::Metrics>) {
         }
     });
 
-    let server = Server::bind(&http_addr).serve(main_service);
+    let slack_retries = {
+        let slack = slack_api.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
+            loop {
+                interval.tick().await;
+                slack.retry_pending(std::time::SystemTime::now()).await;
+            }
+        })
+    };
+
+    let jira_review_retries = {
+        let github_handler_state = github_handler_state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
+            loop {
+                interval.tick().await;
+                github_handler_state.retry_jira_reviews(std::time::SystemTime::now());
+            }
+        })
+    };
+
+    let stale_prs = {
+        let github_handler_state = github_handler_state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+            loop {
+                interval.tick().await;
+                github_handler_state.nag_stale_prs().await;
+            }
+        })
+    };
+
+    let server = Server::bind(&http_addr)
+        .serve(main_service)
+        .with_graceful_shutdown(shutdown_signal());
     info!("Listening (HTTP) on {}", http_addr);
 
-    let webhook_redeliver = tokio::spawn(async move {
-        let webhook_db = webhook_db.clone();
+    let webhook_redeliver = {
+        let config = config.clone();
+        let github_handler_state = github_handler_state.clone();
+        tokio::spawn(async move {
+            let webhook_db = webhook_db.clone();
+            let suppress_notifications = config.slack.suppress_redelivery_notifications;
 
-        // Wait some time for service to startup before redelivering
-        tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+            // Wait some time for service to startup before redelivering
+            tokio::time::sleep(std::time::Duration::from_secs(10)).await;
 
-        if let Some(guid) = latest_webhook_guid {
-            log::info!("Starting webhook redelivery");
+            if let Some(guid) = latest_webhook_guid {
+                log::info!("Starting webhook redelivery");
 
-            let session = match github_api.new_service_session().await {
-                Ok(s) => s,
-                Err(e) => {
-                    log::error!("Failed to get session to redeliver webhooks: {}", e);
-                    return;
-                }
-            };
-
-            let max_count = 10_000;
-            let webhooks = match session.get_webhook_deliveries_since(&guid, max_count).await {
-                Ok(v) => v,
-                Err(e) => {
-                    log::error!("Failed to lookup webhook deliveries: {}", e);
-                    return;
-                }
-            };
-
-            for d in webhooks {
-                if d.status_code != 200 && d.status_code != 400 {
-                    if webhook_db.has_guid(&d.guid) {
-                        log::debug!(
-                            "Skipping webhook redelivery guid {} -- {}",
-                            d.guid,
-                            d.status_code
-                        );
-                    } else {
-                        log::info!(
-                            "Redelivering webhook guid {} due to HTTP {}",
-                            d.guid,
-                            d.status_code
-                        );
-                        if let Err(e) = session.redeliver_webhook(d.id).await {
-                            log::error!("Failed to redeliver webhook guid: {}", e);
+                let session = match github_api.new_service_session().await {
+                    Ok(s) => s,
+                    Err(e) => {
+                        log::error!("Failed to get session to redeliver webhooks: {}", e);
+                        return;
+                    }
+                };
+
+                let max_count = 10_000;
+                let webhooks = match session.get_webhook_deliveries_since(&guid, max_count).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        log::error!("Failed to lookup webhook deliveries: {}", e);
+                        return;
+                    }
+                };
+
+                for d in webhooks {
+                    if d.status_code != 200 && d.status_code != 400 {
+                        if webhook_db.has_guid(&d.guid) {
+                            log::debug!(
+                                "Skipping webhook redelivery guid {} -- {}",
+                                d.guid,
+                                d.status_code
+                            );
+                        } else {
+                            log::info!(
+                                "Redelivering webhook guid {} due to HTTP {}",
+                                d.guid,
+                                d.status_code
+                            );
+                            if suppress_notifications {
+                                github_handler_state.redeliveries.mark(&d.guid);
+                            }
+                            if let Err(e) = session.redeliver_webhook(d.id).await {
+                                log::error!("Failed to redeliver webhook guid: {}", e);
+                                github_handler_state.redeliveries.take(&d.guid);
+                            }
                         }
                     }
                 }
-            }
 
-            log::info!("Finished webhook redelivery");
-        } else {
-            log::info!("No recent webhook delivery to search for");
-        }
-    });
+                log::info!("Finished webhook redelivery");
+            } else {
+                log::info!("No recent webhook delivery to search for");
+            }
+        })
+    };
 
     if let Err(e) = server.await {
         error!("server error: {}", e);
     }
 
-    if let Err(e) = jobs.await {
-        error!("jobs error: {}", e);
+    // no new webhooks are accepted at this point: stop background jobs and let queued work finish
+    jobs.abort();
+    stale_prs.abort();
+    slack_retries.abort();
+    jira_review_retries.abort();
+    webhook_redeliver.abort();
+
+    let timeout = config.main.shutdown_timeout();
+    info!(
+        "Waiting up to {}s for {} pending jobs",
+        timeout.as_secs(),
+        github_handler_state.pending_jobs()
+    );
+    let remaining = worker::wait_for_drain(|| github_handler_state.pending_jobs(), timeout).await;
+    if remaining > 0 {
+        warn!("Shutting down with {} jobs remaining", remaining);
+    } else {
+        info!("All jobs finished; shutting down");
     }
+}
+
+#[cfg(unix)]
+async fn shutdown_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate()).expect("SIGTERM handler");
+    let mut interrupt = signal(SignalKind::interrupt()).expect("SIGINT handler");
+    tokio::select! {
+        _ = terminate.recv() => (),
+        _ = interrupt.recv() => (),
+    }
+    info!("Received shutdown signal");
+}
 
-    if let Err(e) = webhook_redeliver.await {
-        error!("webhook redeliver error: {}", e);
+#[cfg(not(unix))]
+async fn shutdown_signal() {
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        error!("Error waiting for shutdown signal: {}", e);
     }
+    info!("Received shutdown signal");
 }
@@ -1,8 +1,9 @@
 mod admin;
 pub mod github_handler;
 mod github_verify;
+mod health_handler;
 mod html_handler;
-mod http;
+pub mod http;
 pub mod login;
 pub mod main;
 pub mod metrics;
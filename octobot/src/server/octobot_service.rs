@@ -9,7 +9,8 @@ use octobot_ops::util;
 
 use crate::server::admin;
 use crate::server::admin::{Op, RepoAdmin, UserAdmin};
-use crate::server::github_handler::{GithubHandler, GithubHandlerState};
+use crate::server::github_handler::{GithubHandler, GithubHandlerState, ReprocessHandler};
+use crate::server::health_handler::HealthHandler;
 use crate::server::html_handler::HtmlHandler;
 use crate::server::http::{FilteredHandler, Handler, NotFoundHandler};
 use crate::server::login::{LoginHandler, LoginSessionFilter, LogoutHandler, SessionCheckHandler};
@@ -111,6 +112,15 @@ impl OctobotService {
                     (&Method::DELETE, "/api/repo") => {
                         RepoAdmin::new(self.config.clone(), Op::Delete)
                     }
+                    (&Method::PUT, path) if admin::repo_name_from_path(path).is_some() => {
+                        RepoAdmin::new(self.config.clone(), Op::Update)
+                    }
+                    (&Method::DELETE, path) if admin::repo_name_from_path(path).is_some() => {
+                        RepoAdmin::new(self.config.clone(), Op::Delete)
+                    }
+                    (&Method::POST, "/api/repo/reprocess") => {
+                        ReprocessHandler::new(self.github_handler_state.clone())
+                    }
 
                     (&Method::POST, "/api/merge-versions") => {
                         admin::MergeVersions::new(self.config.clone())
@@ -162,6 +172,14 @@ impl OctobotService {
                 MetricsScrapeHandler::new(self.config.clone(), self.metrics.clone())
             }
 
+            // liveness/readiness
+            (&Method::GET, "/health") => HealthHandler::new(
+                self.config.clone(),
+                self.github_handler_state.github_app.clone(),
+                self.github_handler_state.jira_session.clone(),
+                self.github_handler_state.webhook_db.clone(),
+            ),
+
             _ => Box::new(NotFoundHandler),
         }
     }
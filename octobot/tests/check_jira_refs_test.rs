@@ -1,5 +1,7 @@
 mod mocks;
 
+use anyhow::anyhow;
+
 use mocks::mock_github::MockGithub;
 
 use octobot_lib::github;
@@ -23,6 +25,7 @@ fn expect_pass(git: &MockGithub, pr: &github::PullRequest) {
 }
 
 fn expect_pass_commit(git: &MockGithub, pr: &github::PullRequest, commit: &str) {
+    git.mock_get_check_runs(pr, commit, "jira", Ok(vec![]));
     git.mock_create_check_run(
         pr,
         &github::CheckRun::new("jira", commit, None).completed(github::Conclusion::Success),
@@ -35,6 +38,7 @@ fn expect_failure(git: &MockGithub, pr: &github::PullRequest) {
         github::CheckRun::new("jira", &pr.head.sha, None).completed(github::Conclusion::Neutral);
     run.output = Some(github::CheckOutput::new("Missing JIRA reference", ""));
 
+    git.mock_get_check_runs(pr, &pr.head.sha, "jira", Ok(vec![]));
     git.mock_create_check_run(pr, &run, Ok(1));
 }
 
@@ -43,6 +47,7 @@ fn expect_skip(git: &MockGithub, pr: &github::PullRequest) {
         github::CheckRun::new("jira", &pr.head.sha, None).completed(github::Conclusion::Neutral);
     run.output = Some(github::CheckOutput::new("Skipped JIRA check", ""));
 
+    git.mock_get_check_runs(pr, &pr.head.sha, "jira", Ok(vec![]));
     git.mock_create_check_run(pr, &run, Ok(1));
 }
 
@@ -56,7 +61,7 @@ async fn test_check_jira_refs_no_projects() {
 
     // No assertions -- it shouldn't do anything
 
-    jira::check_jira_refs(&pr, &commits, &projects, &git).await;
+    jira::check_jira_refs(&pr, &commits, &projects, "jira", &git, 2).await;
 }
 
 #[tokio::test]
@@ -69,7 +74,7 @@ async fn test_check_jira_refs_chore_commit() {
 
     expect_skip(&git, &pr);
 
-    jira::check_jira_refs(&pr, &commits, &projects, &git).await;
+    jira::check_jira_refs(&pr, &commits, &projects, "jira", &git, 2).await;
 }
 
 #[tokio::test]
@@ -82,7 +87,7 @@ async fn test_check_jira_refs_chore_commit_scope() {
 
     expect_skip(&git, &pr);
 
-    jira::check_jira_refs(&pr, &commits, &projects, &git).await;
+    jira::check_jira_refs(&pr, &commits, &projects, "jira", &git, 2).await;
 }
 
 #[tokio::test]
@@ -95,7 +100,7 @@ async fn test_check_jira_refs_build_commit() {
 
     expect_skip(&git, &pr);
 
-    jira::check_jira_refs(&pr, &commits, &projects, &git).await;
+    jira::check_jira_refs(&pr, &commits, &projects, "jira", &git, 2).await;
 }
 
 #[tokio::test]
@@ -108,7 +113,7 @@ async fn test_check_jira_refs_mismatch() {
 
     expect_failure(&git, &pr);
 
-    jira::check_jira_refs(&pr, &commits, &projects, &git).await;
+    jira::check_jira_refs(&pr, &commits, &projects, "jira", &git, 2).await;
 }
 
 #[tokio::test]
@@ -121,7 +126,7 @@ async fn test_check_jira_refs_pass() {
 
     expect_pass(&git, &pr);
 
-    jira::check_jira_refs(&pr, &commits, &projects, &git).await;
+    jira::check_jira_refs(&pr, &commits, &projects, "jira", &git, 2).await;
 }
 
 #[tokio::test]
@@ -134,7 +139,7 @@ async fn test_check_jira_refs_requires_only_one_ref() {
 
     expect_pass(&git, &pr);
 
-    jira::check_jira_refs(&pr, &commits, &projects, &git).await;
+    jira::check_jira_refs(&pr, &commits, &projects, "jira", &git, 2).await;
 }
 
 #[tokio::test]
@@ -150,7 +155,7 @@ async fn test_check_jira_refs_checks_all_commits() {
 
     expect_pass(&git, &pr);
 
-    jira::check_jira_refs(&pr, &commits, &projects, &git).await;
+    jira::check_jira_refs(&pr, &commits, &projects, "jira", &git, 2).await;
 }
 
 #[tokio::test]
@@ -170,5 +175,146 @@ async fn test_check_jira_refs_pr_head_mismatch() {
 
     expect_pass_commit(&git, &pr, &commits[1].sha);
 
-    jira::check_jira_refs(&pr, &commits, &projects, &git).await;
+    jira::check_jira_refs(&pr, &commits, &projects, "jira", &git, 2).await;
+}
+
+#[tokio::test]
+async fn test_check_jira_refs_updates_existing_run() {
+    let git = MockGithub::new();
+
+    let pr = new_pr("Do stuff");
+    let commits = vec![new_commit("[SERVER-123] Do stuff")];
+    let projects = vec!["SERVER".into()];
+
+    let mut existing = github::CheckRun::new("jira", &pr.head.sha, None);
+    existing.id = Some(55);
+    git.mock_get_check_runs(&pr, &pr.head.sha, "jira", Ok(vec![existing]));
+    git.mock_update_check_run(
+        &pr,
+        55,
+        &github::CheckRun::new("jira", &pr.head.sha, None).completed(github::Conclusion::Success),
+        Ok(()),
+    );
+
+    jira::check_jira_refs(&pr, &commits, &projects, "jira", &git, 2).await;
+}
+
+#[tokio::test]
+async fn test_check_jira_refs_custom_check_name() {
+    let git = MockGithub::new();
+
+    let pr = new_pr("Do stuff");
+    let commits = vec![new_commit("[SERVER-123] Do stuff")];
+    let projects = vec!["SERVER".into()];
+
+    let mut existing = github::CheckRun::new("ci/jira-ref", &pr.head.sha, None);
+    existing.id = Some(57);
+    git.mock_get_check_runs(&pr, &pr.head.sha, "ci/jira-ref", Ok(vec![existing]));
+    git.mock_update_check_run(
+        &pr,
+        57,
+        &github::CheckRun::new("ci/jira-ref", &pr.head.sha, None)
+            .completed(github::Conclusion::Success),
+        Ok(()),
+    );
+
+    jira::check_jira_refs(&pr, &commits, &projects, "ci/jira-ref", &git, 2).await;
+}
+
+#[tokio::test]
+async fn test_check_jira_refs_custom_check_name_skip() {
+    let git = MockGithub::new();
+
+    let pr = new_pr("chore: Do stuff");
+    let commits = vec![new_commit("did stuff")];
+    let projects = vec!["SERVER".into()];
+
+    let mut run = github::CheckRun::new("ci/jira-ref", &pr.head.sha, None)
+        .completed(github::Conclusion::Neutral);
+    run.output = Some(github::CheckOutput::new("Skipped JIRA check", ""));
+
+    git.mock_get_check_runs(&pr, &pr.head.sha, "ci/jira-ref", Ok(vec![]));
+    git.mock_create_check_run(&pr, &run, Ok(1));
+
+    jira::check_jira_refs(&pr, &commits, &projects, "ci/jira-ref", &git, 2).await;
+}
+
+#[tokio::test]
+async fn test_check_jira_refs_retries_on_failure() {
+    let git = MockGithub::new();
+
+    let pr = new_pr("Do stuff");
+    let commits = vec![new_commit("[SERVER-123] Do stuff")];
+    let projects = vec!["SERVER".into()];
+
+    let run =
+        github::CheckRun::new("jira", &pr.head.sha, None).completed(github::Conclusion::Success);
+
+    // first attempt fails to create the run
+    git.mock_get_check_runs(&pr, &pr.head.sha, "jira", Ok(vec![]));
+    git.mock_create_check_run(&pr, &run, Err(anyhow!("bad gateway")));
+
+    // second attempt finds it was actually created and updates it
+    let mut existing = github::CheckRun::new("jira", &pr.head.sha, None);
+    existing.id = Some(56);
+    git.mock_get_check_runs(&pr, &pr.head.sha, "jira", Ok(vec![existing]));
+    git.mock_update_check_run(&pr, 56, &run, Ok(()));
+
+    jira::check_jira_refs(&pr, &commits, &projects, "jira", &git, 2).await;
+}
+
+#[tokio::test]
+async fn test_check_jira_refs_gives_up_after_retries() {
+    let git = MockGithub::new();
+
+    let pr = new_pr("Do stuff");
+    let commits = vec![new_commit("[SERVER-123] Do stuff")];
+    let projects = vec!["SERVER".into()];
+
+    git.mock_get_check_runs(&pr, &pr.head.sha, "jira", Err(anyhow!("timeout")));
+    git.mock_get_check_runs(&pr, &pr.head.sha, "jira", Err(anyhow!("timeout")));
+
+    // mock asserts no further calls are made
+    jira::check_jira_refs(&pr, &commits, &projects, "jira", &git, 1).await;
+}
+
+#[tokio::test]
+async fn test_check_jira_refs_custom_template() {
+    let git = MockGithub::new();
+
+    let pr = new_pr("Do stuff");
+    let commits = vec![new_commit("Do stuff")];
+    let projects = vec!["SERVER".into(), "CLIENT".into()];
+
+    let mut run =
+        github::CheckRun::new("jira", &pr.head.sha, None).completed(github::Conclusion::Neutral);
+    run.output = Some(github::CheckOutput::new("Needs one of SERVER, CLIENT", ""));
+    git.mock_get_check_runs(&pr, &pr.head.sha, "jira", Ok(vec![]));
+    git.mock_create_check_run(&pr, &run, Ok(1));
+
+    let template = jira::MissingRefTemplate::new("Needs one of {projects}", "");
+    jira::check_jira_refs_with_template(&pr, &commits, &projects, "jira", &template, &git, 2).await;
+}
+
+#[test]
+fn test_missing_ref_template_render() {
+    let projects = vec!["SERVER".to_string(), "CLIENT".to_string()];
+
+    let (title, summary) = jira::MissingRefTemplate::default().render(&projects);
+    assert_eq!("Missing JIRA reference", title);
+    assert_eq!(
+        "Expected a JIRA reference in a commit message for at least one of the following projects: SERVER, CLIENT",
+        summary
+    );
+
+    let template = jira::MissingRefTemplate::new(
+        "No {projects} ticket",
+        "Commit messages must match `{regex}`",
+    );
+    let (title, summary) = template.render(&projects);
+    assert_eq!("No SERVER, CLIENT ticket", title);
+    assert_eq!(
+        r"Commit messages must match `\b(SERVER|CLIENT)-[0-9]+\b`",
+        summary
+    );
 }
@@ -366,3 +366,32 @@ fn test_get_commit_desc() {
         git.git.get_commit_desc("HEAD").unwrap()
     );
 }
+
+#[test]
+fn test_fetch_prune_removes_deleted_remote_branches() {
+    let git = TempGit::new();
+
+    git.run_git(&["checkout", "-b", "release/1.0"]);
+    git.run_git(&["push", "origin", "release/1.0"]);
+    git.run_git(&["tag", "1.0.0"]);
+    git.run_git(&["push", "origin", "1.0.0"]);
+    git.run_git(&["checkout", "master"]);
+
+    assert!(git.git.has_remote_branch("release/1.0").unwrap());
+
+    // rename the branch on the remote, as if done from another clone
+    git.run_git(&[
+        "--git-dir=../remote",
+        "branch",
+        "-m",
+        "release/1.0",
+        "release/1.0-old",
+    ]);
+    git.run_git(&["--git-dir=../remote", "tag", "-d", "1.0.0"]);
+
+    git.git.fetch_prune().unwrap();
+
+    assert!(!git.git.has_remote_branch("release/1.0").unwrap());
+    assert!(git.git.has_remote_branch("release/1.0-old").unwrap());
+    assert_eq!("", git.run_git(&["tag", "-l", "1.0.0"]));
+}
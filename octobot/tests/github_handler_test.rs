@@ -1,4 +1,5 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use anyhow::anyhow;
@@ -9,19 +10,29 @@ use mocks::mock_github::MockGithub;
 use mocks::mock_jira::MockJira;
 use mocks::mock_slack::MockSlack;
 use mocks::mock_worker::LockedMockWorker;
-use octobot::server::github_handler::{GithubEventHandler, TeamsCache};
-use octobot_lib::config::{Config, JiraConfig};
+use octobot::server::github_handler::{
+    handle_ping, refetch_pull_request, Escalations, GithubEventHandler, JiraKeyPRs,
+    PendingRedeliveries, RecentBackports, RecentEvents, ReviewThreads, TeamsCache,
+};
+use octobot::server::http::Handler;
+use octobot::server::metrics::MetricsScrapeHandler;
+use octobot_lib::config::{BackportConflictPolicy, Config, JiraConfig, MetricsConfig};
 use octobot_lib::config_db::ConfigDatabase;
 use octobot_lib::github::api::Session;
 use octobot_lib::github::*;
 use octobot_lib::jira;
-use octobot_lib::repos;
+use octobot_lib::jira::api::CommentVisibility;
+use octobot_lib::metrics::Metrics;
+use octobot_lib::passwd;
+use octobot_lib::repos::{self, MergeStrategy};
 use octobot_lib::slack::SlackRecipient;
 use octobot_ops::force_push::{self, ForcePushRequest};
+use octobot_ops::jira_review::{self, JiraReviewRequest};
 use octobot_ops::messenger;
 use octobot_ops::pr_merge::{self, PRMergeRequest};
 use octobot_ops::repo_version::{self, RepoVersionRequest};
-use octobot_ops::slack::{self, SlackAttachmentBuilder};
+use octobot_ops::slack::{self, SlackAttachmentBuilder, SlackBlockBuilder, SlackRequest};
+use octobot_ops::webhook_db::WebhookDatabase;
 
 mod mocks;
 
@@ -42,9 +53,20 @@ struct GithubHandlerTest {
     pr_merge: LockedMockWorker<PRMergeRequest>,
     repo_version: LockedMockWorker<RepoVersionRequest>,
     force_push: LockedMockWorker<ForcePushRequest>,
+    jira_review: LockedMockWorker<JiraReviewRequest>,
 }
 
 impl GithubHandlerTest {
+    // Sets up the handler the way GithubHandlerState::replay_event does
+    fn replay(&mut self, event: String, data: HookBody) {
+        self.handler.event = event;
+        self.handler.action = data.action.clone().unwrap_or_default();
+        self.handler.data = data;
+        self.handler.replay = true;
+        self.handler.messenger =
+            messenger::new(self.config.clone(), self.slack.new_sender()).with_suppressed(true);
+    }
+
     fn expect_will_merge_branches(
         &mut self,
         release_branch_prefix: &str,
@@ -55,13 +77,17 @@ impl GithubHandlerTest {
         let pr = &self.handler.data.pull_request.as_ref().unwrap();
 
         for branch in branches {
-            self.pr_merge.expect_req(pr_merge::req(
-                repo,
-                pr,
-                &branch,
-                release_branch_prefix,
-                &commits,
-            ));
+            self.pr_merge.expect_req(
+                pr_merge::req(
+                    repo,
+                    pr,
+                    &branch,
+                    release_branch_prefix,
+                    &commits,
+                    MergeStrategy::Merge,
+                )
+                .with_event_id(self.handler.event_id.clone()),
+            );
         }
     }
 
@@ -73,8 +99,10 @@ impl GithubHandlerTest {
     ) {
         let repo = &self.handler.repository;
 
-        self.force_push
-            .expect_req(force_push::req(repo, pr, before_hash, after_hash));
+        self.force_push.expect_req(
+            force_push::req(repo, pr, before_hash, after_hash)
+                .with_event_id(self.handler.event_id.clone()),
+        );
     }
 
     fn expect_will_run_version_script(
@@ -85,8 +113,10 @@ impl GithubHandlerTest {
     ) {
         let repo = &self.handler.repository;
 
-        self.repo_version
-            .expect_req(repo_version::req(repo, branch, commit_hash, commits));
+        self.repo_version.expect_req(
+            repo_version::req(repo, branch, commit_hash, commits)
+                .with_event_id(self.handler.event_id.clone()),
+        );
     }
 
     fn mock_pull_request_commits(&self) -> Vec<Commit> {
@@ -115,15 +145,27 @@ fn new_test() -> GithubHandlerTest {
 }
 
 fn new_test_with(jira: Option<JiraConfig>) -> GithubHandlerTest {
+    new_test_with_config(jira, |_| {})
+}
+
+fn new_test_with_config<F>(jira: Option<JiraConfig>, update: F) -> GithubHandlerTest
+where
+    F: FnOnce(&mut Config),
+{
     let github = Arc::new(MockGithub::new());
     let slack = MockSlack::new(vec![]);
     let pr_merge = LockedMockWorker::new("pr-merge");
     let repo_version = LockedMockWorker::new("repo-version");
     let force_push = LockedMockWorker::new("force-push");
+    let jira_review = LockedMockWorker::new("jira-review");
 
     let temp_dir = tempdir().unwrap();
     let db_file = temp_dir.path().join("db.sqlite3");
     let db = ConfigDatabase::new(&db_file.to_string_lossy()).expect("create temp database");
+    let webhook_db = Arc::new(
+        WebhookDatabase::new(&temp_dir.path().join("webhook.sqlite3").to_string_lossy())
+            .expect("create temp webhook database"),
+    );
 
     let mut data = HookBody::new();
 
@@ -183,13 +225,16 @@ fn new_test_with(jira: Option<JiraConfig>) -> GithubHandlerTest {
         .expect("Failed to add some-user/some-repo");
 
     config.slack.ignored_users = vec!["ignore-me[bot]".into()];
+    config.slack.allowed_channel_overrides = vec!["the-other-channel".into()];
     config.jira = jira;
+    update(&mut config);
     let config = Arc::new(config);
 
     let slack_sender = slack.new_sender();
     let pr_merge_sender = pr_merge.new_sender();
     let repo_version_sender = repo_version.new_sender();
     let force_push_sender = force_push.new_sender();
+    let jira_review_sender = jira_review.new_sender();
 
     GithubHandlerTest {
         github: github.clone(),
@@ -200,6 +245,7 @@ fn new_test_with(jira: Option<JiraConfig>) -> GithubHandlerTest {
         pr_merge,
         repo_version,
         force_push,
+        jira_review,
         handler: GithubEventHandler {
             event: "ping".to_string(),
             data,
@@ -212,13 +258,36 @@ fn new_test_with(jira: Option<JiraConfig>) -> GithubHandlerTest {
             pr_merge: pr_merge_sender,
             repo_version: repo_version_sender,
             force_push: force_push_sender,
+            jira_review: jira_review_sender,
             team_members_cache: TeamsCache::new(Duration::new(3600, 0)),
+            commits_cache: Mutex::new(HashMap::new()),
+            recent_backports: Arc::new(RecentBackports::new()),
+            review_threads: Arc::new(ReviewThreads::new(webhook_db)),
+            escalations: Arc::new(Escalations::new()),
+            jira_key_prs: Arc::new(JiraKeyPRs::new()),
+            metrics: Metrics::new(),
+            event_id: None,
+            replay: false,
         },
     }
 }
 
 fn new_test_with_jira() -> GithubHandlerTest {
-    let jira = Some(JiraConfig {
+    new_test_with_jira_config(|_| {})
+}
+
+fn new_test_with_jira_config<F>(update: F) -> GithubHandlerTest
+where
+    F: FnOnce(&mut JiraConfig),
+{
+    new_test_with_jira_and_config(|c| update(c.jira.as_mut().unwrap()))
+}
+
+fn new_test_with_jira_and_config<F>(update: F) -> GithubHandlerTest
+where
+    F: FnOnce(&mut Config),
+{
+    let jira = JiraConfig {
         host: "the-jira-host".into(),
         username: "the-jira-user".into(),
         password: "the-jira-pass".into(),
@@ -230,8 +299,32 @@ fn new_test_with_jira() -> GithubHandlerTest {
         pending_versions_field: Some("the-pending-versions".into()),
         restrict_comment_visibility_to_role: None,
         login_suffix: None,
-    });
-    let mut test = new_test_with(jira);
+        queued_states: Some(vec!["the-queue".into()]),
+        skip_review_branches: None,
+        include_commit_count: None,
+        include_deploy_environment: None,
+        warn_unconfigured_projects: None,
+        sync_milestone_versions: None,
+        transition_audit_channel: None,
+        dedupe_comments: None,
+        pr_summary_comment: None,
+        backport_comments: None,
+        workflow_failure_comments: None,
+        release_branch_review_transitions: None,
+        released_states: None,
+        max_quoted_title_length: None,
+        skip_revert_reviews: None,
+        use_remote_links: None,
+        auto_merge_comments: None,
+        review_retries: None,
+        review_retry_delay_secs: None,
+        issue_details_in_notifications: None,
+        sync_assignee: None,
+        warn_shared_keys: None,
+        dry_run: None,
+        extra_headers: None,
+    };
+    let mut test = new_test_with_config(Some(jira), update);
 
     let jira = Arc::new(MockJira::new());
     test.jira = Some(jira.clone());
@@ -267,6 +360,11 @@ fn some_pr() -> Option<PullRequest> {
             repo: the_repo(),
         },
         draft: None,
+        labels: None,
+        created_at: None,
+        updated_at: None,
+        additions: None,
+        deletions: None,
     })
 }
 
@@ -305,10 +403,12 @@ fn expect_jira_ref_fail_pr(git: &MockGithub, pr: &PullRequest, commits: &[Commit
         CheckRun::new("jira", &commits.last().unwrap().sha, None).completed(Conclusion::Neutral);
     run.output = Some(CheckOutput::new("Missing JIRA reference", ""));
 
+    git.mock_get_check_runs(pr, &run.head_sha, "jira", Ok(vec![]));
     git.mock_create_check_run(pr, &run, Ok(1));
 }
 
 fn expect_jira_ref_pass_pr(git: &MockGithub, pr: &PullRequest, commits: &[Commit]) {
+    git.mock_get_check_runs(pr, &commits.last().unwrap().sha, "jira", Ok(vec![]));
     git.mock_create_check_run(
         pr,
         &CheckRun::new("jira", &commits.last().unwrap().sha, None).completed(Conclusion::Success),
@@ -325,6 +425,161 @@ async fn test_ping() {
     assert_eq!((StatusCode::OK, "ping".into()), resp);
 }
 
+#[tokio::test]
+async fn test_ping_all_events() {
+    let mut test = new_test();
+    test.handler.event = "ping".to_string();
+    test.handler.data.hook = Some(Hook::new(123, &["*"]));
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "ping".into()), resp);
+}
+
+#[tokio::test]
+async fn test_ping_missing_events() {
+    let mut test = new_test();
+    test.handler.event = "ping".to_string();
+    test.handler.data.hook = Some(Hook::new(
+        123,
+        &["pull_request", "pull_request_review", "issue_comment"],
+    ));
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!(
+        (
+            StatusCode::OK,
+            "ping [missing events: pull_request_review_comment, commit_comment, push]".into()
+        ),
+        resp
+    );
+}
+
+#[test]
+fn test_ping_organization_and_app_hooks() {
+    // neither names a repository, and hook ids no longer fit in 32 bits
+    let org_ping: HookBody = serde_json::from_value(serde_json::json!({
+        "zen": "Keep it logically awesome.",
+        "hook_id": 5000000001u64,
+        "hook": {
+            "type": "Organization",
+            "id": 5000000001u64,
+            "name": "web",
+            "active": true,
+            "events": ["pull_request", "push"],
+            "config": {"content_type": "json", "url": "https://octobot/hooks/github"},
+        },
+        "organization": {"login": "some-org", "id": 1},
+        "sender": {"login": "the-admin", "id": 7},
+    }))
+    .unwrap();
+    assert_eq!(
+        (
+            StatusCode::OK,
+            "ping [missing events: pull_request_review, pull_request_review_comment, \
+             issue_comment, commit_comment]"
+                .into()
+        ),
+        handle_ping(&org_ping)
+    );
+
+    let app_ping: HookBody = serde_json::from_value(serde_json::json!({
+        "zen": "Design for failure.",
+        "hook_id": 5000000002u64,
+        "hook": {
+            "type": "App",
+            "id": 5000000002u64,
+            "name": "web",
+            "active": true,
+            "events": ["*"],
+            "app_id": 42,
+        },
+        "sender": {"login": "the-admin", "id": 7},
+    }))
+    .unwrap();
+    assert_eq!((StatusCode::OK, "ping".into()), handle_ping(&app_ping));
+}
+
+#[tokio::test]
+async fn test_star_event_ignored() {
+    let mut test = new_test();
+    test.handler.event = "star".into();
+    test.handler.action = "created".into();
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "star [ignored]".into()), resp);
+    assert_eq!(
+        1,
+        test.handler
+            .metrics
+            .ignored_webhook_events
+            .with_label_values(&["star"])
+            .get()
+    );
+}
+
+#[tokio::test]
+async fn test_ignored_events_configurable() {
+    let mut test = new_test_with_config(None, |c| {
+        c.github.ignored_events = Some(vec!["fork".into()])
+    });
+
+    test.handler.event = "fork".into();
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "fork [ignored]".into()), resp);
+
+    // no longer ignored by design
+    test.handler.event = "star".into();
+    assert!(test.handler.handle_event().await.is_none());
+    assert_eq!(
+        0,
+        test.handler
+            .metrics
+            .ignored_webhook_events
+            .with_label_values(&["star"])
+            .get()
+    );
+}
+
+#[tokio::test]
+async fn test_webhook_event_metrics() {
+    let mut test = new_test_with_config(None, |c| {
+        c.metrics = Some(MetricsConfig {
+            salt: "the-salt".into(),
+            pass_hash: passwd::store_password("the-pass", "the-salt"),
+        })
+    });
+
+    test.handler.event = "star".into();
+    test.handler.handle_event().await.unwrap();
+    test.handler.event = "issues".into();
+    test.handler.action = "opened".into();
+    test.handler.handle_event().await.unwrap();
+    test.handler.handle_event().await.unwrap();
+    test.handler.event = "gollum".into();
+    assert!(test.handler.handle_event().await.is_none());
+
+    let req = hyper::Request::get("/metrics")
+        .header(hyper::header::AUTHORIZATION, "Bearer the-pass")
+        .body(hyper::Body::empty())
+        .unwrap();
+    let resp = MetricsScrapeHandler::new(test.config.clone(), test.handler.metrics.clone())
+        .handle(req)
+        .await
+        .unwrap();
+    assert_eq!(StatusCode::OK, resp.status());
+
+    let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+    let body = String::from_utf8_lossy(&body);
+    for line in [
+        r#"octobot_webhook_events{event="star",outcome="ignored"} 1"#,
+        r#"octobot_webhook_events{event="issues",outcome="handled"} 2"#,
+        r#"octobot_webhook_events{event="gollum",outcome="unhandled"} 1"#,
+        r#"octobot_webhook_duration_count{event="issues"} 2"#,
+    ] {
+        assert!(body.contains(line), "Missing {} in:\n{}", line, body);
+    }
+}
+
 #[tokio::test]
 async fn test_commit_comment_with_path() {
     let mut test = new_test();
@@ -336,6 +591,7 @@ async fn test_commit_comment_with_path() {
         body: Some("I think this file should change".into()),
         html_url: "http://the-comment".into(),
         user: User::new("joe-reviewer"),
+        in_reply_to_id: None,
     });
     test.handler.data.sender = User::new("joe-reviewer");
     let mut pr1 = some_pr().unwrap();
@@ -380,6 +636,109 @@ async fn test_commit_comment_with_path() {
     assert_eq!((StatusCode::OK, "commit_comment".into()), resp);
 }
 
+#[tokio::test]
+async fn test_commit_comment_disabled() {
+    let mut test = new_test();
+    test.handler.event = "commit_comment".into();
+    test.handler.action = "created".into();
+    test.handler.data.comment = Some(Comment {
+        commit_id: Some("abcdef00001111".into()),
+        path: Some("src/main.rs".into()),
+        body: Some("I think this file should change".into()),
+        html_url: "http://the-comment".into(),
+        user: User::new("joe-reviewer"),
+        in_reply_to_id: None,
+    });
+    test.handler.data.sender = User::new("joe-reviewer");
+
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(&info.with_notify_commit_comments(false))
+            .unwrap();
+    }
+
+    // no PR lookups or slack messages expected
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "commit_comment [ignored]".into()), resp);
+}
+
+fn some_discussion() -> Discussion {
+    let mut discussion = Discussion::new(5, "How do I build this?");
+    discussion.html_url = "http://the-discussion".into();
+    discussion.user = User::new("joe-sender");
+    discussion
+}
+
+#[tokio::test]
+async fn test_discussion_comment() {
+    let mut test = new_test();
+    test.handler.event = "discussion_comment".into();
+    test.handler.action = "created".into();
+    test.handler.data.discussion = Some(some_discussion());
+    test.handler.data.comment = Some(Comment {
+        commit_id: None,
+        path: None,
+        body: Some("Run cargo build".into()),
+        html_url: "http://the-discussion-comment".into(),
+        user: User::new("joe-reviewer"),
+        in_reply_to_id: None,
+    });
+    test.handler.data.sender = User::new("joe-reviewer");
+
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(&info.with_discussions_channel("the-discussions-channel".into()))
+            .unwrap();
+    }
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-discussions-channel"),
+        &format!(
+            "Comment on discussion <http://the-discussion|#5: \"How do I build this?\"> {}",
+            REPO_MSG
+        ),
+        &[SlackAttachmentBuilder::new("Run cargo build")
+            .title("joe.reviewer said:")
+            .title_link("http://the-discussion-comment")
+            .build()],
+        None,
+        false,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "discussion_comment".into()), resp);
+}
+
+#[tokio::test]
+async fn test_discussion_comment_disabled() {
+    let mut test = new_test();
+    test.handler.event = "discussion_comment".into();
+    test.handler.action = "created".into();
+    test.handler.data.discussion = Some(some_discussion());
+    test.handler.data.comment = Some(Comment {
+        commit_id: None,
+        path: None,
+        body: Some("Run cargo build".into()),
+        html_url: "http://the-discussion-comment".into(),
+        user: User::new("joe-reviewer"),
+        in_reply_to_id: None,
+    });
+    test.handler.data.sender = User::new("joe-reviewer");
+
+    // no discussions channel configured: no slack messages expected
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!(
+        (StatusCode::OK, "discussion_comment [ignored]".into()),
+        resp
+    );
+}
+
 #[tokio::test]
 async fn test_commit_comment_with_path_that_is_included_in_multiple_prs() {
     let mut test = new_test();
@@ -391,6 +750,7 @@ async fn test_commit_comment_with_path_that_is_included_in_multiple_prs() {
         body: Some("I think this file should change".into()),
         html_url: "http://the-comment".into(),
         user: User::new("joe-reviewer"),
+        in_reply_to_id: None,
     });
     test.handler.data.sender = User::new("joe-reviewer");
     let pr = some_pr();
@@ -429,6 +789,7 @@ async fn test_commit_comment_no_path() {
         body: Some("I think this file should change".into()),
         html_url: "http://the-comment".into(),
         user: User::new("joe-reviewer"),
+        in_reply_to_id: None,
     });
     test.handler.data.sender = User::new("joe-reviewer");
 
@@ -456,6 +817,76 @@ async fn test_commit_comment_no_path() {
     assert_eq!((StatusCode::OK, "commit_comment".into()), resp);
 }
 
+fn some_issue() -> Option<Issue> {
+    Some(Issue {
+        number: 5,
+        title: "The Issue".into(),
+        html_url: "http://the-issue".into(),
+        user: User::new("the-pr-owner"),
+        assignees: vec![User::new("assign1")],
+    })
+}
+
+#[tokio::test]
+async fn test_issues_opened() {
+    let mut test = new_test();
+    test.handler.event = "issues".into();
+    test.handler.action = "opened".into();
+    test.handler.data.issue = some_issue();
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!(
+            "Issue <http://the-issue|#5: \"The Issue\"> opened by the.pr.owner {}",
+            REPO_MSG
+        ),
+        &[],
+        Some("some-user/some-repo/5".to_string()),
+        true,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "issues".into()), resp);
+}
+
+#[tokio::test]
+async fn test_issues_closed() {
+    let mut test = new_test();
+    test.handler.event = "issues".into();
+    test.handler.action = "closed".into();
+    test.handler.data.issue = some_issue();
+    test.handler.data.sender = User::new("joe-reviewer");
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!(
+            "Issue <http://the-issue|#5: \"The Issue\"> closed by joe.reviewer {}",
+            REPO_MSG
+        ),
+        &[],
+        Some("some-user/some-repo/5".to_string()),
+        false,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "issues".into()), resp);
+}
+
+#[tokio::test]
+async fn test_issues_assigned_ignored() {
+    let mut test = new_test();
+    test.handler.event = "issues".into();
+    test.handler.action = "assigned".into();
+    test.handler.data.issue = some_issue();
+    test.handler.data.sender = User::new("joe-reviewer");
+
+    test.slack.expect(vec![]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "issues [ignored]".into()), resp);
+}
+
 #[tokio::test]
 async fn test_issue_comment() {
     let mut test = new_test();
@@ -474,6 +905,7 @@ async fn test_issue_comment() {
         body: Some("I think this file should change, cc: @mentioned-participant".into()),
         html_url: "http://the-comment".into(),
         user: User::new("joe-reviewer"),
+        in_reply_to_id: None,
     });
     test.handler.data.sender = User::new("joe-reviewer");
 
@@ -532,6 +964,7 @@ async fn test_pull_request_comment() {
         body: Some("I think this file should change, cc: @mentioned-participant".into()),
         html_url: "http://the-comment".into(),
         user: User::new("joe-reviewer"),
+        in_reply_to_id: None,
     });
     test.handler.data.sender = User::new("joe-reviewer");
     test.mock_pull_request_commits();
@@ -651,6 +1084,164 @@ async fn test_pull_request_review_commented() {
     assert_eq!((StatusCode::OK, "pr_review [comment]".into()), resp);
 }
 
+fn new_review_comment(in_reply_to_id: Option<u64>) -> Comment {
+    // empty so that no notifications are sent for it
+    Comment {
+        commit_id: Some("abcdef00001111".into()),
+        path: Some("src/main.rs".into()),
+        body: Some("".into()),
+        html_url: "http://the-comment".into(),
+        user: User::new("joe-reviewer"),
+        in_reply_to_id,
+    }
+}
+
+#[tokio::test]
+async fn test_pull_request_review_thread_counts() {
+    let mut test = new_test_with_config(None, |c| c.slack.notify_conversations_resolved = true);
+    test.handler.event = "pull_request_review_comment".into();
+    test.handler.action = "created".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("joe-reviewer");
+
+    // two new conversations and a reply
+    for comment in [
+        new_review_comment(None),
+        new_review_comment(Some(1)),
+        new_review_comment(None),
+    ] {
+        test.mock_pull_request_commits();
+        test.handler.data.comment = Some(comment);
+        let resp = test.handler.handle_event().await.unwrap();
+        assert_eq!((StatusCode::OK, "pr_review_comment".into()), resp);
+    }
+    assert_eq!(2, test.handler.review_threads.unresolved(&the_repo(), 32));
+
+    test.handler.event = "pull_request_review_thread".into();
+    test.handler.data.comment = None;
+    test.handler.data.thread = Some(ReviewThread::new("thread-1"));
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    // resolve one: nothing to say yet
+    test.handler.action = "resolved".into();
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr_review_thread".into()), resp);
+    assert_eq!(1, test.handler.review_threads.unresolved(&the_repo(), 32));
+
+    // unresolve it again, then resolve both
+    test.handler.action = "unresolved".into();
+    test.handler.handle_event().await.unwrap();
+    assert_eq!(2, test.handler.review_threads.unresolved(&the_repo(), 32));
+
+    test.handler.action = "resolved".into();
+    test.handler.handle_event().await.unwrap();
+    assert_eq!(1, test.handler.review_threads.unresolved(&the_repo(), 32));
+
+    test.mock_pull_request_commits();
+    let msg = "All review conversations resolved on PR \"<http://the-pr|The PR>\"";
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &[],
+        Some("some-user/some-repo/32".to_string()),
+        false,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr_review_thread".into()), resp);
+    assert_eq!(0, test.handler.review_threads.unresolved(&the_repo(), 32));
+}
+
+#[tokio::test]
+async fn test_pull_request_review_thread_resolved_notifies_owner() {
+    let mut test = new_test_with_config(None, |c| c.slack.notify_conversations_resolved = true);
+    test.handler.event = "pull_request_review_thread".into();
+    test.handler.action = "resolved".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.thread = Some(ReviewThread::new("thread-1"));
+    test.handler.data.sender = User::new("joe-reviewer");
+    test.handler.review_threads.add(&the_repo(), 32);
+    test.mock_pull_request_commits();
+
+    let msg = "All review conversations resolved on PR \"<http://the-pr|The PR>\"";
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &[],
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &[],
+            None,
+            false,
+        ),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr_review_thread".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_review_thread_counts_survive_restart() {
+    let mut test = new_test_with_config(None, |c| c.slack.notify_conversations_resolved = true);
+    test.handler.event = "pull_request_review_thread".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.thread = Some(ReviewThread::new("thread-1"));
+    test.handler.data.sender = User::new("joe-reviewer");
+    test.handler.review_threads.add(&the_repo(), 32);
+    test.handler.review_threads.add(&the_repo(), 32);
+
+    // a restart picks the counts back up from the webhook db
+    let webhook_db = WebhookDatabase::new(
+        &test
+            ._temp_dir
+            .path()
+            .join("webhook.sqlite3")
+            .to_string_lossy(),
+    )
+    .unwrap();
+    test.handler.review_threads = Arc::new(ReviewThreads::new(Arc::new(webhook_db)));
+    assert_eq!(2, test.handler.review_threads.unresolved(&the_repo(), 32));
+
+    // one conversation is still open: nothing to say yet
+    test.handler.action = "resolved".into();
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr_review_thread".into()), resp);
+    assert_eq!(1, test.handler.review_threads.unresolved(&the_repo(), 32));
+}
+
+#[tokio::test]
+async fn test_pull_request_review_thread_resolved_untracked() {
+    let mut test = new_test_with_config(None, |c| c.slack.notify_conversations_resolved = true);
+    test.handler.event = "pull_request_review_thread".into();
+    test.handler.action = "resolved".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.thread = Some(ReviewThread::new("thread-1"));
+    test.handler.data.sender = User::new("joe-reviewer");
+
+    // conversations octobot never saw: no message
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr_review_thread".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_review_thread_disabled() {
+    let mut test = new_test();
+    test.handler.event = "pull_request_review_thread".into();
+    test.handler.action = "resolved".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.thread = Some(ReviewThread::new("thread-1"));
+    test.handler.data.sender = User::new("joe-reviewer");
+    test.handler.review_threads.add(&the_repo(), 32);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr_review_thread [ignored]".into()), resp);
+}
+
 #[tokio::test]
 async fn test_pull_request_comments_ignore_empty_messages() {
     let mut test = new_test();
@@ -663,6 +1254,7 @@ async fn test_pull_request_comments_ignore_empty_messages() {
         body: Some("".into()),
         html_url: "http://the-comment".into(),
         user: User::new("joe-reviewer"),
+        in_reply_to_id: None,
     });
     test.handler.data.sender = User::new("joe-reviewer");
     test.mock_pull_request_commits();
@@ -685,6 +1277,7 @@ async fn test_pull_request_comments_ignore_octobot() {
         body: Some("I think this file should change, cc: @mentioned-participant".into()),
         html_url: "http://the-comment".into(),
         user: User::new("octobot[bot]"),
+        in_reply_to_id: None,
     });
     test.handler.data.sender = User::new("joe-reviewer");
     test.mock_pull_request_commits();
@@ -707,6 +1300,7 @@ async fn test_pull_request_comments_ignore_user() {
         body: Some("I think this file should change, cc: @mentioned-participant".into()),
         html_url: "http://the-comment".into(),
         user: User::new("ignore-me[bot]"),
+        in_reply_to_id: None,
     });
     test.handler.data.sender = User::new("joe-reviewer");
     test.mock_pull_request_commits();
@@ -718,28 +1312,35 @@ async fn test_pull_request_comments_ignore_user() {
 }
 
 #[tokio::test]
-async fn test_pull_request_review_approved() {
+async fn test_pull_request_comments_repo_allows_ignored_user() {
     let mut test = new_test();
-    test.handler.event = "pull_request_review".into();
-    test.handler.action = "submitted".into();
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(&info.with_ignored_users(vec![], repos::IgnoredUsersMode::Replace))
+            .unwrap();
+    }
+
+    test.handler.event = "pull_request_review_comment".into();
+    test.handler.action = "created".into();
     test.handler.data.pull_request = some_pr();
-    test.handler.data.review = Some(Review {
-        state: "approved".into(),
-        body: Some("I like it! cc: @mentioned-participant".into()),
-        html_url: "http://the-comment".into(),
-        user: User::new("joe-reviewer"),
+    test.handler.data.comment = Some(Comment {
+        commit_id: Some("abcdef00001111".into()),
+        path: Some("src/main.rs".into()),
+        body: Some("Coverage dropped by 2%".into()),
+        html_url: "http://the-comment".into(),
+        user: User::new("ignore-me[bot]"),
+        in_reply_to_id: None,
     });
     test.handler.data.sender = User::new("joe-reviewer");
     test.mock_pull_request_commits();
 
-    let attach = vec![
-        SlackAttachmentBuilder::new("I like it! cc: @mentioned-participant")
-            .title("Review: Approved")
-            .title_link("http://the-comment")
-            .color("good")
-            .build(),
-    ];
-    let msg = "joe.reviewer approved PR \"<http://the-pr|The PR>\"";
+    let attach = vec![SlackAttachmentBuilder::new("Coverage dropped by 2%")
+        .title("ignore-me[bot] said:")
+        .title_link("http://the-comment")
+        .build()];
+    let msg = "Comment on \"<http://the-pr|The PR>\"";
 
     test.slack.expect(vec![
         slack::req(
@@ -763,13 +1364,6 @@ async fn test_pull_request_review_approved() {
             None,
             false,
         ),
-        slack::req(
-            SlackRecipient::user_mention("mentioned.participant"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
         slack::req(
             SlackRecipient::user_mention("the.pr.owner"),
             msg,
@@ -780,33 +1374,33 @@ async fn test_pull_request_review_approved() {
     ]);
 
     let resp = test.handler.handle_event().await.unwrap();
-    assert_eq!((StatusCode::OK, "pr_review".into()), resp);
+    assert_eq!((StatusCode::OK, "pr_review_comment".into()), resp);
 }
 
 #[tokio::test]
-async fn test_pull_request_review_changes_requested() {
+async fn test_pull_request_review_approved() {
     let mut test = new_test();
     test.handler.event = "pull_request_review".into();
     test.handler.action = "submitted".into();
     test.handler.data.pull_request = some_pr();
     test.handler.data.review = Some(Review {
-        state: "changes_requested".into(),
-        body: Some("It needs some work! cc: @mentioned-participant".into()),
+        state: "approved".into(),
+        body: Some("I like it! cc: @mentioned-participant".into()),
         html_url: "http://the-comment".into(),
         user: User::new("joe-reviewer"),
     });
     test.handler.data.sender = User::new("joe-reviewer");
     test.mock_pull_request_commits();
 
-    let attach =
-        vec![
-            SlackAttachmentBuilder::new("It needs some work! cc: @mentioned-participant")
-                .title("Review: Changes Requested")
-                .title_link("http://the-comment")
-                .color("danger")
-                .build(),
-        ];
-    let msg = "joe.reviewer requested changes to PR \"<http://the-pr|The PR>\"";
+    let attach = vec![
+        SlackAttachmentBuilder::new("I like it! cc: @mentioned-participant")
+            .title("Review: Approved")
+            .title_link("http://the-comment")
+            .color("good")
+            .build(),
+    ];
+    let msg = "joe.reviewer approved PR \"<http://the-pr|The PR>\"";
+
     test.slack.expect(vec![
         slack::req(
             SlackRecipient::by_name("the-reviews-channel"),
@@ -850,49 +1444,29 @@ async fn test_pull_request_review_changes_requested() {
 }
 
 #[tokio::test]
-async fn test_pull_request_opened() {
-    let mut test = new_test();
-    test.handler.event = "pull_request".into();
-    test.handler.action = "opened".into();
-    test.handler.data.pull_request = some_pr();
-    test.handler.data.sender = User::new("the-pr-owner");
-    test.mock_pull_request_commits();
-
-    expect_jira_ref_fail(&test.github);
-
-    let attach = vec![SlackAttachmentBuilder::new("")
-        .title("Pull Request #32: \"The PR\"")
-        .title_link("http://the-pr")
-        .build()];
-    let msg = "Pull Request opened by the.pr.owner";
-
-    test.slack.expect(vec![slack::req(
-        SlackRecipient::by_name("the-reviews-channel"),
-        &format!("{} {}", msg, REPO_MSG),
-        &attach,
-        Some("some-user/some-repo/32".to_string()),
-        true,
-    )]);
-
-    let resp = test.handler.handle_event().await.unwrap();
-    assert_eq!((StatusCode::OK, "pr".into()), resp);
-}
-
-#[tokio::test]
-async fn test_pull_request_closed() {
+async fn test_pull_request_review_changes_requested() {
     let mut test = new_test();
-    test.handler.event = "pull_request".into();
-    test.handler.action = "closed".into();
+    test.handler.event = "pull_request_review".into();
+    test.handler.action = "submitted".into();
     test.handler.data.pull_request = some_pr();
-    test.handler.data.sender = User::new("the-pr-closer");
+    test.handler.data.review = Some(Review {
+        state: "changes_requested".into(),
+        body: Some("It needs some work! cc: @mentioned-participant".into()),
+        html_url: "http://the-comment".into(),
+        user: User::new("joe-reviewer"),
+    });
+    test.handler.data.sender = User::new("joe-reviewer");
     test.mock_pull_request_commits();
 
-    let attach = vec![SlackAttachmentBuilder::new("")
-        .title("Pull Request #32: \"The PR\"")
-        .title_link("http://the-pr")
-        .build()];
-    let msg = "Pull Request closed";
-
+    let attach =
+        vec![
+            SlackAttachmentBuilder::new("It needs some work! cc: @mentioned-participant")
+                .title("Review: Changes Requested")
+                .title_link("http://the-comment")
+                .color("danger")
+                .build(),
+        ];
+    let msg = "joe.reviewer requested changes to PR \"<http://the-pr|The PR>\"";
     test.slack.expect(vec![
         slack::req(
             SlackRecipient::by_name("the-reviews-channel"),
@@ -916,7 +1490,7 @@ async fn test_pull_request_closed() {
             false,
         ),
         slack::req(
-            SlackRecipient::user_mention("joe.reviewer"),
+            SlackRecipient::user_mention("mentioned.participant"),
             msg,
             &attach,
             None,
@@ -932,53 +1506,44 @@ async fn test_pull_request_closed() {
     ]);
 
     let resp = test.handler.handle_event().await.unwrap();
-    assert_eq!((StatusCode::OK, "pr".into()), resp);
+    assert_eq!((StatusCode::OK, "pr_review".into()), resp);
 }
 
-#[tokio::test]
-async fn test_pull_request_reopened() {
-    let mut test = new_test();
-    test.handler.event = "pull_request".into();
-    test.handler.action = "reopened".into();
-    test.handler.data.pull_request = some_pr();
-    test.handler.data.sender = User::new("the-pr-closer");
-    test.mock_pull_request_commits();
-
-    let attach = vec![SlackAttachmentBuilder::new("")
-        .title("Pull Request #32: \"The PR\"")
-        .title_link("http://the-pr")
-        .build()];
-    let msg = "Pull Request reopened";
-
-    test.slack.expect(vec![slack::req(
-        SlackRecipient::by_name("the-reviews-channel"),
-        &format!("{} {}", msg, REPO_MSG),
-        &attach,
-        Some("some-user/some-repo/32".to_string()),
-        false,
-    )]);
-
-    let resp = test.handler.handle_event().await.unwrap();
-    assert_eq!((StatusCode::OK, "pr".into()), resp);
+fn changes_requested_review(user: &str) -> Review {
+    Review {
+        state: "CHANGES_REQUESTED".into(),
+        body: None,
+        html_url: String::new(),
+        user: User::new(user),
+    }
 }
 
-#[tokio::test]
-async fn test_pull_request_ready_for_review() {
-    let mut test = new_test();
-    test.handler.event = "pull_request".into();
-    test.handler.action = "ready_for_review".into();
+fn new_changes_requested_test(threshold: u32) -> GithubHandlerTest {
+    let mut test = new_test_with_config(None, |c| {
+        c.slack.escalation_channel = Some("the-leads-channel".into());
+        c.slack.changes_requested_escalation_threshold = Some(threshold);
+    });
+    test.handler.event = "pull_request_review".into();
+    test.handler.action = "submitted".into();
     test.handler.data.pull_request = some_pr();
-    test.handler.data.sender = User::new("the-pr-closer");
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.assignees = vec![];
+    }
+    test.handler.data.review = Some(Review {
+        state: "changes_requested".into(),
+        body: Some("Still needs work".into()),
+        html_url: "http://the-comment".into(),
+        user: User::new("joe-reviewer"),
+    });
+    test.handler.data.sender = User::new("joe-reviewer");
     test.mock_pull_request_commits();
 
-    expect_jira_ref_fail(&test.github);
-
-    let attach = vec![SlackAttachmentBuilder::new("")
-        .title("Pull Request #32: \"The PR\"")
-        .title_link("http://the-pr")
+    let attach = vec![SlackAttachmentBuilder::new("Still needs work")
+        .title("Review: Changes Requested")
+        .title_link("http://the-comment")
+        .color("danger")
         .build()];
-    let msg = "Pull Request is ready for review";
-
+    let msg = "joe.reviewer requested changes to PR \"<http://the-pr|The PR>\"";
     test.slack.expect(vec![
         slack::req(
             SlackRecipient::by_name("the-reviews-channel"),
@@ -987,13 +1552,6 @@ async fn test_pull_request_ready_for_review() {
             Some("some-user/some-repo/32".to_string()),
             false,
         ),
-        slack::req(
-            SlackRecipient::user_mention("assign1"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
         slack::req(
             SlackRecipient::user_mention("bob.author"),
             msg,
@@ -1001,13 +1559,6 @@ async fn test_pull_request_ready_for_review() {
             None,
             false,
         ),
-        slack::req(
-            SlackRecipient::user_mention("joe.reviewer"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
         slack::req(
             SlackRecipient::user_mention("the.pr.owner"),
             msg,
@@ -1017,1267 +1568,5985 @@ async fn test_pull_request_ready_for_review() {
         ),
     ]);
 
-    let resp = test.handler.handle_event().await.unwrap();
-    assert_eq!((StatusCode::OK, "pr".into()), resp);
+    test
 }
 
 #[tokio::test]
-async fn test_pull_request_edited() {
-    let mut test = new_test();
-    test.handler.event = "pull_request".into();
-    test.handler.action = "edited".into();
-    test.handler.data.pull_request = some_pr();
-    test.handler.data.sender = User::new("the-pr-closer");
-    test.mock_pull_request_commits();
-
-    expect_jira_ref_fail(&test.github);
+async fn test_pull_request_review_changes_requested_escalation() {
+    let mut test = new_changes_requested_test(2);
 
-    // no slack mocks
+    test.github.mock_get_pull_request_reviews(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(vec![
+            changes_requested_review("joe-reviewer"),
+            Review::new("looks ok", User::new("smith-reviewer")),
+            changes_requested_review("joe-reviewer"),
+        ]),
+    );
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-leads-channel"),
+        "Changes have been requested 2 times on PR \"<http://the-pr|The PR>\" in <http://the-github-host/some-user/some-repo|some-user/some-repo>",
+        &[],
+        None,
+        false,
+    )]);
 
     let resp = test.handler.handle_event().await.unwrap();
-    assert_eq!((StatusCode::OK, "pr".into()), resp);
+    assert_eq!((StatusCode::OK, "pr_review".into()), resp);
 }
 
 #[tokio::test]
-async fn test_pull_request_synchronize() {
-    let mut test = new_test();
-    test.handler.event = "pull_request".into();
-    test.handler.action = "synchronize".into();
-    test.handler.data.pull_request = some_pr();
-    test.handler.data.sender = User::new("the-pr-closer");
-    test.mock_pull_request_commits();
+async fn test_pull_request_review_changes_requested_below_escalation() {
+    let test = new_changes_requested_test(3);
 
-    expect_jira_ref_fail(&test.github);
+    test.github.mock_get_pull_request_reviews(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(vec![
+            changes_requested_review("joe-reviewer"),
+            changes_requested_review("joe-reviewer"),
+        ]),
+    );
+    // no escalation
 
-    // no slack mocks
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr_review".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_review_changes_requested_escalated_once() {
+    let test = new_changes_requested_test(2);
+    assert!(test.handler.escalations.check_and_record(&the_repo(), 32));
+
+    test.github.mock_get_pull_request_reviews(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(vec![
+            changes_requested_review("joe-reviewer"),
+            changes_requested_review("joe-reviewer"),
+            changes_requested_review("joe-reviewer"),
+        ]),
+    );
+    // already escalated
 
     let resp = test.handler.handle_event().await.unwrap();
-    assert_eq!((StatusCode::OK, "pr".into()), resp);
+    assert_eq!((StatusCode::OK, "pr_review".into()), resp);
 }
 
 #[tokio::test]
-async fn test_pull_request_assigned() {
+async fn test_pull_request_opened_default_reviewers() {
     let mut test = new_test();
     test.handler.event = "pull_request".into();
-    test.handler.action = "assigned".into();
+    test.handler.action = "opened".into();
     test.handler.data.pull_request = some_pr();
     if let Some(ref mut pr) = test.handler.data.pull_request {
-        pr.assignees.push(User::new("assign2"));
+        pr.requested_reviewers = Some(vec![]);
     }
-    test.handler.data.sender = User::new("the-pr-closer");
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(&info.with_default_reviewers(vec![
+                "the-pr-owner".into(),
+                "joe-reviewer".into(),
+                "smith-reviewer".into(),
+            ]))
+            .unwrap();
+    }
+
     test.mock_pull_request_commits();
+    expect_jira_ref_fail(&test.github);
 
     let attach = vec![SlackAttachmentBuilder::new("")
         .title("Pull Request #32: \"The PR\"")
         .title_link("http://the-pr")
         .build()];
-    let msg = "Pull Request assigned to assign1, assign2";
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("Pull Request opened by the.pr.owner {}", REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
 
-    test.slack.expect(vec![
-        slack::req(
-            SlackRecipient::by_name("the-reviews-channel"),
-            &format!("{} {}", msg, REPO_MSG),
-            &attach,
-            Some("some-user/some-repo/32".to_string()),
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("assign1"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("assign2"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("bob.author"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("joe.reviewer"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("the.pr.owner"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-    ]);
+    // author is never requested
+    test.github.mock_request_review(
+        "some-user",
+        "some-repo",
+        32,
+        vec!["joe-reviewer".into(), "smith-reviewer".into()],
+        Ok(()),
+    );
 
     let resp = test.handler.handle_event().await.unwrap();
     assert_eq!((StatusCode::OK, "pr".into()), resp);
 }
 
 #[tokio::test]
-async fn test_pull_request_unassigned() {
+async fn test_pull_request_opened_reviewer_pool() {
     let mut test = new_test();
     test.handler.event = "pull_request".into();
-    test.handler.action = "unassigned".into();
+    test.handler.action = "opened".into();
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(&info.with_reviewer_pool(
+                vec![
+                    "the-pr-owner".into(),
+                    "joe-reviewer".into(),
+                    "smith-reviewer".into(),
+                ],
+                None,
+            ))
+            .unwrap();
+    }
+
+    // each PR goes to the next reviewer in the pool, never the author
+    for (number, reviewer) in [(32, "joe-reviewer"), (33, "smith-reviewer")] {
+        let mut pr = some_pr().unwrap();
+        pr.number = number;
+        pr.requested_reviewers = None;
+        test.handler.data.pull_request = Some(pr.clone());
+
+        let commits = some_commits();
+        test.github.mock_get_pull_request_commits(
+            "some-user",
+            "some-repo",
+            number,
+            Ok(commits.clone()),
+        );
+        expect_jira_ref_fail_pr(&test.github, &pr, &commits);
+
+        let attach = vec![SlackAttachmentBuilder::new("")
+            .title(format!("Pull Request #{}: \"The PR\"", number))
+            .title_link("http://the-pr")
+            .build()];
+        test.slack.expect(vec![slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("Pull Request opened by the.pr.owner {}", REPO_MSG),
+            &attach,
+            Some(format!("some-user/some-repo/{}", number)),
+            true,
+        )]);
+
+        test.github.mock_request_review(
+            "some-user",
+            "some-repo",
+            number,
+            vec![reviewer.into()],
+            Ok(()),
+        );
+
+        let resp = test.handler.handle_event().await.unwrap();
+        assert_eq!((StatusCode::OK, "pr".into()), resp);
+    }
+}
+
+#[tokio::test]
+async fn test_pull_request_opened_default_reviewers_only_author() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
     test.handler.data.pull_request = some_pr();
-    test.handler.data.sender = User::new("the-pr-closer");
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.requested_reviewers = None;
+    }
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(&info.with_default_reviewers(vec!["the-pr-owner".into()]))
+            .unwrap();
+    }
+
     test.mock_pull_request_commits();
+    expect_jira_ref_fail(&test.github);
 
     let attach = vec![SlackAttachmentBuilder::new("")
         .title("Pull Request #32: \"The PR\"")
         .title_link("http://the-pr")
         .build()];
-    let msg = "Pull Request unassigned";
-
     test.slack.expect(vec![slack::req(
         SlackRecipient::by_name("the-reviews-channel"),
-        &format!("{} {}", msg, REPO_MSG),
+        &format!("Pull Request opened by the.pr.owner {}", REPO_MSG),
         &attach,
         Some("some-user/some-repo/32".to_string()),
-        false,
+        true,
     )]);
 
+    // no review request expected
+
     let resp = test.handler.handle_event().await.unwrap();
     assert_eq!((StatusCode::OK, "pr".into()), resp);
 }
 
 #[tokio::test]
-async fn test_pull_request_review_requested() {
+async fn test_pull_request_opened() {
     let mut test = new_test();
     test.handler.event = "pull_request".into();
-    test.handler.action = "review_requested".into();
+    test.handler.action = "opened".into();
     test.handler.data.pull_request = some_pr();
-    if let Some(ref mut pr) = test.handler.data.pull_request {
-        pr.requested_reviewers = Some(vec![User::new("joe-reviewer"), User::new("smith-reviewer")]);
-        pr.requested_teams = Some(vec![Team::new(100, "team-awesome")]);
-    }
-    test.handler.data.sender = User::new("the-pr-closer");
+    test.handler.data.sender = User::new("the-pr-owner");
     test.mock_pull_request_commits();
-    test.mock_get_team_members(100);
+
+    expect_jira_ref_fail(&test.github);
 
     let attach = vec![SlackAttachmentBuilder::new("")
         .title("Pull Request #32: \"The PR\"")
         .title_link("http://the-pr")
         .build()];
-    let msg = "Pull Request by the.pr.owner submitted for review to joe.reviewer, smith.reviewer, @team-awesome";
+    let msg = "Pull Request opened by the.pr.owner";
 
-    test.slack.expect(vec![
-        slack::req(
-            SlackRecipient::by_name("the-reviews-channel"),
-            &format!("{} {}", msg, REPO_MSG),
-            &attach,
-            Some("some-user/some-repo/32".to_string()),
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("assign1"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("bob.author"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("joe.reviewer"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("smith.reviewer"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("team.member1"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("team.member2"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("the.pr.owner"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-    ]);
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
 
     let resp = test.handler.handle_event().await.unwrap();
     assert_eq!((StatusCode::OK, "pr".into()), resp);
 }
 
 #[tokio::test]
-async fn test_pull_request_review_no_username() {
-    let mut test = new_test();
+async fn test_pull_request_opened_blocks() {
+    let mut test = new_test_with_config(None, |c| c.slack.use_blocks = true);
     test.handler.event = "pull_request".into();
-    test.handler.action = "review_requested".into();
+    test.handler.action = "opened".into();
     test.handler.data.pull_request = some_pr();
-    if let Some(ref mut pr) = test.handler.data.pull_request {
-        pr.requested_reviewers = Some(vec![User::new("some-unknown-reviewer")]);
-        pr.requested_teams = Some(vec![Team::new(100, "some-unknown-team")]);
-    }
-    test.handler.data.sender = User::new("the-pr-closer");
+    test.handler.data.sender = User::new("the-pr-owner");
     test.mock_pull_request_commits();
-    test.github
-        .mock_get_team_members(&the_repo(), 100, Err(anyhow!("nope")));
+
+    expect_jira_ref_fail(&test.github);
 
     let attach = vec![SlackAttachmentBuilder::new("")
         .title("Pull Request #32: \"The PR\"")
         .title_link("http://the-pr")
         .build()];
-    let msg = "Pull Request by the.pr.owner submitted for review to some-unknown-reviewer, @some-unknown-team";
+    let msg = format!("Pull Request opened by the.pr.owner {}", REPO_MSG);
 
-    test.slack.expect(vec![
-        slack::req(
-            SlackRecipient::by_name("the-reviews-channel"),
-            &format!("{} {}", msg, REPO_MSG),
-            &attach,
-            Some("some-user/some-repo/32".to_string()),
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("assign1"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("bob.author"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("the.pr.owner"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-    ]);
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &msg,
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )
+    .with_blocks(
+        SlackBlockBuilder::new()
+            .section(&msg)
+            .section("*<http://the-pr|Pull Request #32: \"The PR\">*")
+            .build(),
+    )]);
 
     let resp = test.handler.handle_event().await.unwrap();
     assert_eq!((StatusCode::OK, "pr".into()), resp);
 }
 
 #[tokio::test]
-async fn test_pull_request_other() {
+async fn test_pull_request_opened_branch_channel() {
     let mut test = new_test();
     test.handler.event = "pull_request".into();
-    test.handler.action = "some-other-action".into();
+    test.handler.action = "opened".into();
     test.handler.data.pull_request = some_pr();
-    test.handler.data.sender = User::new("the-pr-closer");
+    test.handler.data.sender = User::new("the-pr-owner");
+    test.mock_pull_request_commits();
 
-    // should not do anything!
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(
+                &info
+                    .with_channel_override("release/*", "the-releases-channel")
+                    .with_channel_override("mast*", "the-master-channel"),
+            )
+            .unwrap();
+    }
+
+    expect_jira_ref_fail(&test.github);
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request opened by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-master-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
 
     let resp = test.handler.handle_event().await.unwrap();
     assert_eq!((StatusCode::OK, "pr".into()), resp);
 }
 
 #[tokio::test]
-async fn test_pull_request_labeled_not_merged() {
+async fn test_pull_request_opened_message_template() {
     let mut test = new_test();
     test.handler.event = "pull_request".into();
-    test.handler.action = "labeled".into();
+    test.handler.action = "opened".into();
     test.handler.data.pull_request = some_pr();
-    if let Some(ref mut pr) = test.handler.data.pull_request {
-        pr.merged = Some(false);
-    }
     test.handler.data.sender = User::new("the-pr-owner");
     test.mock_pull_request_commits();
 
-    // labeled but not merged --> noop
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(&info.with_message_template("pr_opened", "New PR from {user}: {title}"))
+            .unwrap();
+    }
 
-    let resp = test.handler.handle_event().await.unwrap();
-    assert_eq!((StatusCode::OK, "pr".into()), resp);
-}
+    expect_jira_ref_fail(&test.github);
 
-#[tokio::test]
-async fn test_pull_request_merged_error_getting_labels() {
-    let mut test = new_test();
-    test.handler.event = "pull_request".into();
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "New PR from the.pr.owner: The PR";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+fn some_release(prerelease: bool) -> Option<Release> {
+    let mut release = Release::new("v1.2.0");
+    release.name = Some("The Release".into());
+    release.body = Some("* Fixed things\n".into());
+    release.html_url = "http://the-release".into();
+    release.prerelease = prerelease;
+    release.target_commitish = Some("master".into());
+    Some(release)
+}
+
+#[tokio::test]
+async fn test_release_published() {
+    let mut test = new_test();
+    test.handler.event = "release".into();
+    test.handler.action = "published".into();
+    test.handler.data.release = some_release(false);
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    let attach = vec![SlackAttachmentBuilder::new("* Fixed things")
+        .title("Release: The Release")
+        .title_link("http://the-release")
+        .build()];
+    let msg = "Release v1.2.0 published by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        None,
+        false,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "release".into()), resp);
+}
+
+#[tokio::test]
+async fn test_release_published_prerelease() {
+    let mut test = new_test();
+    test.handler.event = "release".into();
+    test.handler.action = "published".into();
+    test.handler.data.release = some_release(true);
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    let attach = vec![SlackAttachmentBuilder::new("* Fixed things")
+        .title("Pre-release: The Release")
+        .title_link("http://the-release")
+        .build()];
+    let msg = "Pre-release v1.2.0 published by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        None,
+        false,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "release".into()), resp);
+}
+
+#[tokio::test]
+async fn test_deployment_status_failure() {
+    let mut test = new_test();
+    test.handler.event = "deployment_status".into();
+    test.handler.action = "created".into();
+    let mut status = DeploymentStatus::new("failure", "production");
+    status.description = Some("Rollout timed out".into());
+    status.target_url = Some("http://the-deployment".into());
+    test.handler.data.deployment_status = Some(status);
+
+    let attach = vec![SlackAttachmentBuilder::new("Rollout timed out")
+        .title("Deployment to production")
+        .title_link("http://the-deployment")
+        .color("danger")
+        .build()];
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("Deployment to production failure {}", REPO_MSG),
+        &attach,
+        None,
+        false,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "deployment_status".into()), resp);
+}
+
+#[tokio::test]
+async fn test_deployment_status_success() {
+    let mut test = new_test();
+    test.handler.event = "deployment_status".into();
+    test.handler.action = "created".into();
+    test.handler.data.deployment_status = Some(DeploymentStatus::new("success", "staging"));
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Deployment to staging")
+        .color("good")
+        .build()];
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("Deployment to staging success {}", REPO_MSG),
+        &attach,
+        None,
+        false,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "deployment_status".into()), resp);
+}
+
+#[tokio::test]
+async fn test_deployment_status_pending_ignored() {
+    let mut test = new_test();
+    test.handler.event = "deployment_status".into();
+    test.handler.action = "created".into();
+    test.handler.data.deployment_status = Some(DeploymentStatus::new("pending", "production"));
+
+    // no slack mocks
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "deployment_status [ignored]".into()), resp);
+}
+
+#[tokio::test]
+async fn test_release_other_action_ignored() {
+    let mut test = new_test();
+    test.handler.event = "release".into();
+    test.handler.action = "created".into();
+    test.handler.data.release = some_release(false);
+
+    // no slack mocks
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "release [ignored]".into()), resp);
+}
+
+#[tokio::test]
+async fn test_release_published_transitions_jiras() {
+    let mut test = new_test_with_jira_config(|j| {
+        j.released_states = Some(vec!["the-released".into()]);
+    });
+    test.handler.event = "release".into();
+    test.handler.action = "published".into();
+    test.handler.data.release = some_release(false);
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    let attach = vec![SlackAttachmentBuilder::new("* Fixed things")
+        .title("Release: The Release")
+        .title_link("http://the-release")
+        .build()];
+    let msg = "Release v1.2.0 published by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        None,
+        false,
+    )]);
+
+    if let Some(ref jira) = test.jira {
+        jira.mock_find_issues_with_fix_version("CLI", "1.2.0", Ok(vec![]));
+
+        jira.mock_find_issues_with_fix_version("SER", "1.2.0", Ok(vec!["SER-1".into()]));
+        jira.mock_get_issue("SER-1", Ok(new_issue("SER-1")));
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("004", "the-released")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("004"), Ok(()));
+    }
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "release".into()), resp);
+}
+
+#[tokio::test]
+async fn test_release_published_prerelease_leaves_jiras() {
+    let mut test = new_test_with_jira_config(|j| {
+        j.released_states = Some(vec!["the-released".into()]);
+    });
+    test.handler.event = "release".into();
+    test.handler.action = "published".into();
+    test.handler.data.release = some_release(true);
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    let attach = vec![SlackAttachmentBuilder::new("* Fixed things")
+        .title("Pre-release: The Release")
+        .title_link("http://the-release")
+        .build()];
+    let msg = "Pre-release v1.2.0 published by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        None,
+        false,
+    )]);
+
+    // no jira mocks: will fail if called
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "release".into()), resp);
+}
+
+#[tokio::test]
+async fn test_release_published_from_sha_transitions_jiras() {
+    let mut test = new_test_with_jira_config(|j| {
+        j.released_states = Some(vec!["the-released".into()]);
+    });
+    test.handler.event = "release".into();
+    test.handler.action = "published".into();
+    test.handler.data.release = some_release(false);
+    if let Some(ref mut release) = test.handler.data.release {
+        release.target_commitish = Some("0123456789abcdef0123456789abcdef01234567".into());
+    }
+    test.handler.repository.default_branch = Some("master".into());
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    // only release branches match by regex: the SHA must not be mistaken for one
+    {
+        let mut repos = test.config.repos_write();
+        let mut info = repos.get_all().unwrap().remove(0);
+        for config in info.jira_config.iter_mut() {
+            config.release_branch_regex = "^release/".into();
+        }
+        repos.update(&info).unwrap();
+    }
+
+    let attach = vec![SlackAttachmentBuilder::new("* Fixed things")
+        .title("Release: The Release")
+        .title_link("http://the-release")
+        .build()];
+    let msg = "Release v1.2.0 published by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        None,
+        false,
+    )]);
+
+    // the SHA isn't a branch: uses the default branch's JIRA projects
+    if let Some(ref jira) = test.jira {
+        jira.mock_find_issues_with_fix_version("CLI", "1.2.0", Ok(vec![]));
+
+        jira.mock_find_issues_with_fix_version("SER", "1.2.0", Ok(vec!["SER-1".into()]));
+        jira.mock_get_issue("SER-1", Ok(new_issue("SER-1")));
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("004", "the-released")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("004"), Ok(()));
+    }
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "release".into()), resp);
+}
+
+#[tokio::test]
+async fn test_release_published_from_sha_unknown_branch_leaves_jiras() {
+    let mut test = new_test_with_jira_config(|j| {
+        j.released_states = Some(vec!["the-released".into()]);
+    });
+    test.handler.event = "release".into();
+    test.handler.action = "published".into();
+    test.handler.data.release = some_release(false);
+    if let Some(ref mut release) = test.handler.data.release {
+        release.target_commitish = Some("0123456789abcdef0123456789abcdef01234567".into());
+    }
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    let attach = vec![SlackAttachmentBuilder::new("* Fixed things")
+        .title("Release: The Release")
+        .title_link("http://the-release")
+        .build()];
+    let msg = "Release v1.2.0 published by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        None,
+        false,
+    )]);
+
+    // no default branch to fall back on: no jira mocks, will fail if called
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "release".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_opened_custom_jira_check_name() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+    let commits = test.mock_pull_request_commits();
+
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(&info.with_jira_check_name("ci/jira-ref".into()))
+            .unwrap();
+    }
+
+    let mut run = CheckRun::new("ci/jira-ref", &commits.last().unwrap().sha, None)
+        .completed(Conclusion::Neutral);
+    run.output = Some(CheckOutput::new("Missing JIRA reference", ""));
+    let pr = test.handler.data.pull_request.clone().unwrap();
+    test.github
+        .mock_get_check_runs(&pr, &run.head_sha, "ci/jira-ref", Ok(vec![]));
+    test.github.mock_create_check_run(&pr, &run, Ok(1));
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request opened by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+fn expect_suggested_reviewer(test: &mut GithubHandlerTest, reviewer: &str) {
+    let attach = vec![
+        SlackAttachmentBuilder::new("")
+            .title("Pull Request #32: \"The PR\"")
+            .title_link("http://the-pr")
+            .build(),
+        SlackAttachmentBuilder::new(&format!("Suggested reviewer: {}", reviewer)).build(),
+    ];
+    let msg = "Pull Request opened by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
+}
+
+fn open_pr_for(reviewers: &[&str], assignees: &[&str]) -> PullRequest {
+    let mut pr = PullRequest::new();
+    pr.requested_reviewers = Some(reviewers.iter().map(|r| User::new(r)).collect());
+    pr.assignees = assignees.iter().map(|a| User::new(a)).collect();
+    pr
+}
+
+#[tokio::test]
+async fn test_pull_request_opened_suggests_least_loaded_reviewer() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.requested_reviewers = Some(vec![]);
+    }
+    test.handler.data.sender = User::new("the-pr-owner");
+    test.mock_pull_request_commits();
+
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(&info.with_suggested_reviewers(vec![
+                "the-pr-owner".into(),
+                "busy-reviewer".into(),
+                "idle-reviewer".into(),
+            ]))
+            .unwrap();
+    }
+
+    test.github.mock_get_pull_requests(
+        "some-user",
+        "some-repo",
+        Some("open"),
+        None,
+        Ok(vec![
+            open_pr_for(&["busy-reviewer"], &[]),
+            open_pr_for(&[], &["busy-reviewer"]),
+            open_pr_for(&["idle-reviewer"], &["busy-reviewer"]),
+        ]),
+    );
+
+    expect_jira_ref_fail(&test.github);
+    expect_suggested_reviewer(&mut test, "idle-reviewer");
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_opened_suggested_reviewer_tie() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.requested_reviewers = Some(vec![]);
+    }
+    test.handler.data.sender = User::new("the-pr-owner");
+    test.mock_pull_request_commits();
+
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(&info.with_suggested_reviewers(vec!["reviewer-b".into(), "reviewer-a".into()]))
+            .unwrap();
+    }
+
+    test.github.mock_get_pull_requests(
+        "some-user",
+        "some-repo",
+        Some("open"),
+        None,
+        Ok(vec![
+            open_pr_for(&["reviewer-a"], &[]),
+            open_pr_for(&["reviewer-b"], &[]),
+        ]),
+    );
+
+    expect_jira_ref_fail(&test.github);
+    expect_suggested_reviewer(&mut test, "reviewer-b");
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_opened_with_reviewers_no_suggestion() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.requested_reviewers = Some(vec![User::new("joe-reviewer")]);
+    }
+    test.handler.data.sender = User::new("the-pr-owner");
+    test.mock_pull_request_commits();
+
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(&info.with_suggested_reviewers(vec!["idle-reviewer".into()]))
+            .unwrap();
+    }
+
+    // no open PR lookup expected
+    expect_jira_ref_fail(&test.github);
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request opened by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_opened_size() {
+    let mut test = new_test_with_config(None, |c| {
+        c.slack.include_pr_size = true;
+        c.github.pr_size_labels = Some(true);
+        c.github.pr_size_thresholds = Some(vec![10, 50, 100, 200]);
+    });
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.additions = Some(40);
+        pr.deletions = Some(10);
+    }
+    test.handler.data.sender = User::new("the-pr-owner");
+    test.mock_pull_request_commits();
+
+    test.github.mock_add_pull_request_labels(
+        "some-user",
+        "some-repo",
+        32,
+        vec!["size/S".into()],
+        Ok(()),
+    );
+    expect_jira_ref_fail(&test.github);
+
+    let attach = vec![SlackAttachmentBuilder::new("Size: S")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request opened by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_synchronize_size_label_exists() {
+    let mut test = new_test_with_config(None, |c| {
+        c.github.pr_size_labels = Some(true);
+    });
+    test.handler.event = "pull_request".into();
+    test.handler.action = "synchronize".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.additions = Some(1000);
+        pr.deletions = Some(1);
+        pr.labels = Some(vec![Label::new("size/XL")]);
+    }
+    test.handler.data.sender = User::new("the-pr-owner");
+    test.mock_pull_request_commits();
+
+    // already labeled: no label added
+    expect_jira_ref_fail(&test.github);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_synchronize_size_label_resized() {
+    let mut test = new_test_with_config(None, |c| {
+        c.github.pr_size_labels = Some(true);
+    });
+    test.handler.event = "pull_request".into();
+    test.handler.action = "synchronize".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.additions = Some(1000);
+        pr.deletions = Some(1);
+        pr.labels = Some(vec![Label::new("bug"), Label::new("size/S")]);
+    }
+    test.handler.data.sender = User::new("the-pr-owner");
+    test.mock_pull_request_commits();
+
+    // the PR grew: its old size label is replaced
+    test.github
+        .mock_remove_pull_request_label("some-user", "some-repo", 32, "size/S", Ok(()));
+    test.github.mock_add_pull_request_labels(
+        "some-user",
+        "some-repo",
+        32,
+        vec!["size/XL".into()],
+        Ok(()),
+    );
+    expect_jira_ref_fail(&test.github);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_opened_author_team() {
+    let mut test = new_test_with_config(None, |c| {
+        c.slack.include_author_team = true;
+        c.slack
+            .author_teams
+            .insert("the-pr-owner".into(), "the-team".into());
+    });
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+    test.mock_pull_request_commits();
+
+    expect_jira_ref_fail(&test.github);
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request opened by the.pr.owner (author team: the-team)";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_opened_author_team_disabled() {
+    let mut test = new_test_with_config(None, |c| {
+        c.slack
+            .author_teams
+            .insert("the-pr-owner".into(), "the-team".into());
+    });
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+    test.mock_pull_request_commits();
+
+    expect_jira_ref_fail(&test.github);
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request opened by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_opened_channel_override() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.body = Some("The body\n\nSlack-Channel: #the-other-channel".into());
+    }
+    test.handler.data.sender = User::new("the-pr-owner");
+    test.mock_pull_request_commits();
+
+    expect_jira_ref_fail(&test.github);
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request opened by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-other-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_opened_disallowed_channel_override() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.body = Some("The body\n\nSlack-Channel: #some-secret-channel".into());
+    }
+    test.handler.data.sender = User::new("the-pr-owner");
+    test.mock_pull_request_commits();
+
+    expect_jira_ref_fail(&test.github);
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request opened by the.pr.owner";
+
+    // falls back to the default channel
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_closed() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "closed".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-closer");
+    test.mock_pull_request_commits();
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request closed";
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_reopened() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "reopened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-closer");
+    test.mock_pull_request_commits();
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request reopened";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        false,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_auto_merge_enabled() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "auto_merge_enabled".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+    test.mock_pull_request_commits();
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request auto-merge enabled by the.pr.owner";
+
+    // channel only: no participant DMs, and no jira check
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        false,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_auto_merge_disabled() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "auto_merge_disabled".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-closer");
+    test.mock_pull_request_commits();
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request auto-merge disabled by the-pr-closer";
+
+    // channel only: no participant DMs, and no jira check
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        false,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_ready_for_review() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "ready_for_review".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-closer");
+    test.mock_pull_request_commits();
+
+    expect_jira_ref_fail(&test.github);
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request is ready for review";
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_edited() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "edited".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-closer");
+    test.mock_pull_request_commits();
+
+    expect_jira_ref_fail(&test.github);
+
+    // no slack mocks
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_edited_base_changed() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "edited".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.base.ref_name = "release/1.0".into();
+    }
+    test.handler.data.sender = User::new("the-pr-owner");
+    test.handler.data.changes = Some(
+        serde_json::from_value(serde_json::json!({
+            "base": {
+                "ref": { "from": "master" },
+                "sha": { "from": "abcdef0123456789" }
+            }
+        }))
+        .unwrap(),
+    );
+    test.mock_pull_request_commits();
+
+    expect_jira_ref_fail_pr(
+        &test.github,
+        test.handler.data.pull_request.as_ref().unwrap(),
+        &some_commits(),
+    );
+
+    let attach = vec![SlackAttachmentBuilder::new("JIRA projects: CLI, SER")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request base changed from master to release/1.0";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        false,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_edited_title_only() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "edited".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+    test.handler.data.changes = Some(
+        serde_json::from_value(serde_json::json!({
+            "title": { "from": "The old PR" }
+        }))
+        .unwrap(),
+    );
+    test.mock_pull_request_commits();
+
+    expect_jira_ref_fail(&test.github);
+
+    // no slack mocks
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_synchronize() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "synchronize".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-closer");
+    test.mock_pull_request_commits();
+
+    expect_jira_ref_fail(&test.github);
+
+    // no slack mocks
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_synchronize_path_labels() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "synchronize".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.labels = Some(vec![Label::new("docs")]);
+    }
+    test.handler.data.sender = User::new("the-pr-closer");
+    test.mock_pull_request_commits();
+
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(
+                &info
+                    .with_path_labels("docs/**", &["docs"])
+                    .with_path_labels("src/**/*.rs", &["rust"])
+                    .with_path_labels("ui/**", &["frontend"]),
+            )
+            .unwrap();
+    }
+
+    test.github.mock_get_pull_request_files(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(vec![
+            PullRequestFile::new("docs/README.md"),
+            PullRequestFile::new("src/server/main.rs"),
+        ]),
+    );
+    // existing "docs" label is not re-added
+    test.github.mock_add_pull_request_labels(
+        "some-user",
+        "some-repo",
+        32,
+        vec!["rust".into()],
+        Ok(()),
+    );
+
+    expect_jira_ref_fail(&test.github);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_synchronize_path_labels_already_applied() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "synchronize".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.labels = Some(vec![Label::new("docs")]);
+    }
+    test.handler.data.sender = User::new("the-pr-closer");
+    test.mock_pull_request_commits();
+
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(&info.with_path_labels("docs/**", &["docs"]))
+            .unwrap();
+    }
+
+    test.github.mock_get_pull_request_files(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(vec![PullRequestFile::new("docs/README.md")]),
+    );
+    // no labels added
+
+    expect_jira_ref_fail(&test.github);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_synchronize_frozen_branch() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "synchronize".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.base.ref_name = "release/1.0".into();
+    }
+    test.handler.data.sender = User::new("the-pr-owner");
+    test.mock_pull_request_commits();
+
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(&info.with_frozen_branches(vec!["release/*".into()]))
+            .unwrap();
+    }
+
+    expect_jira_ref_fail_pr(
+        &test.github,
+        test.handler.data.pull_request.as_ref().unwrap(),
+        &some_commits(),
+    );
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request #32 targets frozen branch release/1.0";
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_synchronize_draft() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "synchronize".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.draft = Some(true);
+    }
+    test.handler.data.sender = User::new("the-pr-closer");
+    test.mock_pull_request_commits();
+
+    // still checked by default
+    expect_jira_ref_fail(&test.github);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_synchronize_draft_deferred() {
+    let mut test = new_test_with_config(None, |c| c.github.defer_draft_jira_check = Some(true));
+    test.handler.event = "pull_request".into();
+    test.handler.action = "synchronize".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.draft = Some(true);
+    }
+    test.handler.data.sender = User::new("the-pr-closer");
+    test.mock_pull_request_commits();
+
+    // no jira check: no github check run mocks
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_ready_for_review_draft_deferred() {
+    let mut test = new_test_with_config(None, |c| c.github.defer_draft_jira_check = Some(true));
+    test.handler.event = "pull_request".into();
+    test.handler.action = "ready_for_review".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.draft = Some(false);
+    }
+    test.handler.data.sender = User::new("the-pr-owner");
+    test.mock_pull_request_commits();
+
+    expect_jira_ref_fail(&test.github);
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request is ready for review";
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_assigned() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "assigned".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.assignees.push(User::new("assign2"));
+    }
+    test.handler.data.sender = User::new("the-pr-closer");
+    test.mock_pull_request_commits();
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request assigned to assign1, assign2";
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign2"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_unassigned() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "unassigned".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-closer");
+    test.mock_pull_request_commits();
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request unassigned";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        false,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_review_requested() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "review_requested".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.requested_reviewers = Some(vec![User::new("joe-reviewer"), User::new("smith-reviewer")]);
+        pr.requested_teams = Some(vec![Team::new(100, "team-awesome")]);
+    }
+    test.handler.data.sender = User::new("the-pr-closer");
+    test.mock_pull_request_commits();
+    test.mock_get_team_members(100);
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request by the.pr.owner submitted for review to joe.reviewer, smith.reviewer, @team-awesome";
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("smith.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("team.member1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("team.member2"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_review_requested_out_of_office() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "review_requested".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.requested_reviewers = Some(vec![User::new("joe-reviewer")]);
+    }
+    test.handler.data.sender = User::new("the-pr-closer");
+    test.mock_pull_request_commits();
+
+    {
+        let mut users = test.config.users_write();
+        let info = users.lookup_info("joe-reviewer").unwrap();
+        users
+            .update(&info.with_out_of_office("smith-reviewer"))
+            .unwrap();
+    }
+
+    let attach = vec![
+        SlackAttachmentBuilder::new("")
+            .title("Pull Request #32: \"The PR\"")
+            .title_link("http://the-pr")
+            .build(),
+        SlackAttachmentBuilder::new(
+            "joe.reviewer is out of office. Backup reviewer: smith.reviewer",
+        )
+        .build(),
+    ];
+    let msg = "Pull Request by the.pr.owner submitted for review to joe.reviewer";
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_review_no_username() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "review_requested".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.requested_reviewers = Some(vec![User::new("some-unknown-reviewer")]);
+        pr.requested_teams = Some(vec![Team::new(100, "some-unknown-team")]);
+    }
+    test.handler.data.sender = User::new("the-pr-closer");
+    test.mock_pull_request_commits();
+    test.github
+        .mock_get_team_members(&the_repo(), 100, Err(anyhow!("nope")));
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request by the.pr.owner submitted for review to some-unknown-reviewer, @some-unknown-team";
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_other() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "some-other-action".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-closer");
+
+    // should not do anything!
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_labeled_not_merged() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "labeled".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(false);
+    }
+    test.handler.data.sender = User::new("the-pr-owner");
+    test.mock_pull_request_commits();
+
+    // labeled but not merged --> noop
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_merged_error_getting_labels() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "closed".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(true);
+    }
+    test.handler.data.sender = User::new("the-pr-merger");
+
+    test.mock_pull_request_commits();
+    test.github.mock_get_pull_request_labels(
+        "some-user",
+        "some-repo",
+        32,
+        Err(anyhow!("whooops.")),
+    );
+
+    let msg1 = "Pull Request merged";
+    let attach1 = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+
+    let msg2 = "Error getting Pull Request labels";
+    let attach2 = vec![SlackAttachmentBuilder::new("whooops.")
+        .color("danger")
+        .build()];
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg1, REPO_MSG),
+            &attach1,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg1,
+            &attach1,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg1,
+            &attach1,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg1,
+            &attach1,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg1,
+            &attach1,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg2, REPO_MSG),
+            &attach2,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg2,
+            &attach2,
+            None,
+            false,
+        ),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_merged_no_labels() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "closed".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(true);
+    }
+    test.handler.data.sender = User::new("the-pr-merger");
+
+    test.mock_pull_request_commits();
+    test.github
+        .mock_get_pull_request_labels("some-user", "some-repo", 32, Ok(vec![]));
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request merged";
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+fn set_merged_channel_only(test: &GithubHandlerTest) {
+    let mut repos = test.config.repos_write();
+    let info = repos.get_all().unwrap().remove(0);
+    repos.update(&info.with_merged_channel_only(true)).unwrap();
+}
+
+#[tokio::test]
+async fn test_pull_request_merged_channel_only() {
+    let mut test = new_test();
+    set_merged_channel_only(&test);
+    test.handler.event = "pull_request".into();
+    test.handler.action = "closed".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(true);
+    }
+    test.handler.data.sender = User::new("the-pr-merger");
+
+    test.mock_pull_request_commits();
+    test.github
+        .mock_get_pull_request_labels("some-user", "some-repo", 32, Ok(vec![]));
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request merged";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        false,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_closed_merged_channel_only() {
+    let mut test = new_test();
+    set_merged_channel_only(&test);
+    test.handler.event = "pull_request".into();
+    test.handler.action = "closed".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-closer");
+
+    test.mock_pull_request_commits();
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request closed";
+
+    // closing without merging still messages participants
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+fn set_notify_events(test: &GithubHandlerTest, events: &[&str]) {
+    let mut repos = test.config.repos_write();
+    let info = repos.get_all().unwrap().remove(0);
+    let events = events.iter().map(|e| e.to_string()).collect();
+    repos
+        .update(&info.with_notify_events(Some(events)))
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_pull_request_merged_notify_events() {
+    let mut test = new_test();
+    set_notify_events(&test, &["merged"]);
+    test.handler.event = "pull_request".into();
+    test.handler.action = "closed".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(true);
+    }
+    test.handler.data.sender = User::new("the-pr-merger");
+
+    test.mock_pull_request_commits();
+    test.github
+        .mock_get_pull_request_labels("some-user", "some-repo", 32, Ok(vec![]));
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request merged";
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_closed_notify_events() {
+    let mut test = new_test();
+    set_notify_events(&test, &["merged"]);
+    test.handler.event = "pull_request".into();
+    test.handler.action = "closed".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-closer");
+
+    test.mock_pull_request_commits();
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request closed";
+
+    // closing without merging is not in the list: participants still hear about it, the channel doesn't
+    let dm = |user: &str| {
+        slack::req(
+            SlackRecipient::user_mention(user),
+            msg,
+            &attach,
+            None,
+            false,
+        )
+    };
+    test.slack.expect(vec![
+        dm("assign1"),
+        dm("bob.author"),
+        dm("joe.reviewer"),
+        dm("the.pr.owner"),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_reopened_notify_events() {
+    let mut test = new_test();
+    set_notify_events(&test, &["merged"]);
+    test.handler.event = "pull_request".into();
+    test.handler.action = "reopened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    test.mock_pull_request_commits();
+
+    // no slack mocks: reopening only notifies the channel, which doesn't want it
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_merged_message_template() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "closed".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(true);
+    }
+    test.handler.data.sender = User::new("the-pr-merger");
+
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(
+                &info
+                    .with_message_template("pr_merged", "#{number} \"{title}\" shipped by {user}")
+                    .with_message_template("pr_closed", "Closed: {title}"),
+            )
+            .unwrap();
+    }
+
+    test.mock_pull_request_commits();
+    test.github
+        .mock_get_pull_request_labels("some-user", "some-repo", 32, Ok(vec![]));
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "#32 \"The PR\" shipped by the-pr-merger";
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_merged_backport_labels() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "closed".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(true);
+    }
+    test.handler.data.sender = User::new("the-pr-merger");
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request merged";
+
+    let commits = test.mock_pull_request_commits();
+    test.github.mock_get_pull_request_labels(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(vec![
+            Label::new("other"),
+            Label::new("backport-1.0"),
+            Label::new("BACKPORT-2.0"),
+            Label::new("BACKport-other-thing"),
+            Label::new("non-matching"),
+        ]),
+    );
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    test.expect_will_merge_branches(
+        "release/",
+        vec![
+            "release/1.0".into(),
+            "release/2.0".into(),
+            "release/other-thing".into(),
+        ],
+        commits,
+    );
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_merged_backport_labels_custom_pattern() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "closed".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(true);
+    }
+    test.handler.data.sender = User::new("the-pr-merger");
+
+    test.config
+        .repos_write()
+        .insert_info(
+            &repos::RepoInfo::new("some-user/custom-branches-repo", "the-reviews-channel")
+                .with_release_branch_prefix("the-other-prefix-".into())
+                .with_use_threads(true),
+        )
+        .expect("Failed to add repo");
+
+    // change the repo to an unconfigured one
+    test.handler.repository = Repo::parse(&format!(
+        "http://{}/some-user/custom-branches-repo",
+        test.github.github_host()
+    ))
+    .unwrap();
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request merged";
+
+    let commits = some_commits();
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "custom-branches-repo",
+        32,
+        Ok(commits.clone()),
+    );
+
+    test.github.mock_get_pull_request_labels(
+        "some-user",
+        "custom-branches-repo",
+        32,
+        Ok(vec![
+            Label::new("other"),
+            Label::new("backport-1.0"),
+            Label::new("BACKPORT-2.0"),
+            Label::new("BACKport-other-thing"),
+            Label::new("non-matching"),
+        ]),
+    );
+
+    let repo_msg =
+        "(<http://the-github-host/some-user/custom-branches-repo|some-user/custom-branches-repo>)";
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, repo_msg),
+            &attach,
+            Some("some-user/custom-branches-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    test.expect_will_merge_branches(
+        "the-other-prefix-",
+        vec![
+            "the-other-prefix-1.0".into(),
+            "the-other-prefix-2.0".into(),
+            "the-other-prefix-other-thing".into(),
+        ],
+        commits,
+    );
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+async fn run_merged_backport_label_prefix(
+    label_prefix: Option<&str>,
+    labels: Vec<Label>,
+    branches: Vec<String>,
+) {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "closed".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(true);
+    }
+    test.handler.data.sender = User::new("the-pr-merger");
+
+    let mut info = repos::RepoInfo::new("some-user/label-prefix-repo", "the-reviews-channel")
+        .with_use_threads(true);
+    if let Some(prefix) = label_prefix {
+        info = info.with_backport_label_prefix(prefix);
+    }
+    test.config
+        .repos_write()
+        .insert_info(&info)
+        .expect("Failed to add repo");
+
+    test.handler.repository = Repo::parse(&format!(
+        "http://{}/some-user/label-prefix-repo",
+        test.github.github_host()
+    ))
+    .unwrap();
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request merged";
+
+    let commits = some_commits();
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "label-prefix-repo",
+        32,
+        Ok(commits.clone()),
+    );
+    test.github
+        .mock_get_pull_request_labels("some-user", "label-prefix-repo", 32, Ok(labels));
+
+    let repo_msg =
+        "(<http://the-github-host/some-user/label-prefix-repo|some-user/label-prefix-repo>)";
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, repo_msg),
+            &attach,
+            Some("some-user/label-prefix-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    test.expect_will_merge_branches("release/", branches, commits);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_merged_backport_labels_custom_prefix() {
+    // "." in the prefix must match literally, and the old prefix no longer applies
+    run_merged_backport_label_prefix(
+        Some("cherry.pick/"),
+        vec![
+            Label::new("other"),
+            Label::new("cherry.pick/1.0"),
+            Label::new("CHERRY.PICK/2.0"),
+            Label::new("cherryXpick/3.0"),
+            Label::new("backport-4.0"),
+        ],
+        vec!["release/1.0".into(), "release/2.0".into()],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_pull_request_merged_backport_labels_default_prefix() {
+    run_merged_backport_label_prefix(
+        None,
+        vec![
+            Label::new("other"),
+            Label::new("backport-1.0"),
+            Label::new("BACKPORT-2.0"),
+            Label::new("cherry.pick/3.0"),
+        ],
+        vec!["release/1.0".into(), "release/2.0".into()],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_pull_request_merged_conflicting_backport_labels() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
     test.handler.action = "closed".into();
     test.handler.data.pull_request = some_pr();
-    if let Some(ref mut pr) = test.handler.data.pull_request {
-        pr.merged = Some(true);
-    }
-    test.handler.data.sender = User::new("the-pr-merger");
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(true);
+    }
+    test.handler.data.sender = User::new("the-pr-merger");
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request merged";
+
+    test.mock_pull_request_commits();
+    test.github.mock_get_pull_request_labels(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(vec![Label::new("backport-1.0"), Label::new("No-Backport")]),
+    );
+
+    let warn_attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .color("warning")
+        .build()];
+    let warn_msg = "Pull Request #32 has conflicting labels 'no-backport' and 'backport-1.0': skipping backports";
+
+    let mut expected = vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        false,
+    )];
+    for user in &["assign1", "bob.author", "joe.reviewer", "the.pr.owner"] {
+        expected.push(slack::req(
+            SlackRecipient::user_mention(user),
+            msg,
+            &attach,
+            None,
+            false,
+        ));
+    }
+    expected.push(slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", warn_msg, REPO_MSG),
+        &warn_attach,
+        None,
+        false,
+    ));
+    expected.push(slack::req(
+        SlackRecipient::user_mention("the.pr.owner"),
+        warn_msg,
+        &warn_attach,
+        None,
+        false,
+    ));
+    test.slack.expect(expected);
+
+    // no backports
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_labeled_conflicting_backport_labels_prefer_backport() {
+    let mut test = new_test_with_config(None, |c| {
+        c.github.backport_conflict_policy = Some(BackportConflictPolicy::PreferBackport);
+    });
+    test.handler.event = "pull_request".into();
+    test.handler.action = "labeled".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(true);
+        pr.labels = Some(vec![
+            Label::new("no-backport"),
+            Label::new("backport-7.123"),
+        ]);
+    }
+    test.handler.data.label = Some(Label::new("backport-7.123"));
+    test.handler.data.sender = User::new("the-pr-merger");
+
+    let commits = test.mock_pull_request_commits();
+
+    let warn_attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .color("warning")
+        .build()];
+    let warn_msg = "Pull Request #32 has conflicting labels 'no-backport' and 'backport-7.123': backporting anyway";
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", warn_msg, REPO_MSG),
+            &warn_attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            warn_msg,
+            &warn_attach,
+            None,
+            false,
+        ),
+    ]);
+
+    test.expect_will_merge_branches("release/", vec!["release/7.123".into()], commits);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_merged_retroactively_labeled() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "labeled".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(true);
+    }
+    test.handler.data.label = Some(Label::new("backport-7.123"));
+    test.handler.data.sender = User::new("the-pr-merger");
+
+    let commits = test.mock_pull_request_commits();
+
+    test.expect_will_merge_branches("release/", vec!["release/7.123".into()], commits);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_merged_labeled_event_id() {
+    let mut test = new_test();
+    test.handler.event_id = Some("delivery-1234".into());
+    test.handler.event = "pull_request".into();
+    test.handler.action = "labeled".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(true);
+    }
+    test.handler.data.label = Some(Label::new("backport-7.123"));
+    test.handler.data.sender = User::new("the-pr-merger");
+
+    let commits = test.mock_pull_request_commits();
+
+    // the worker request carries the id of the webhook that queued it
+    let mut expected = pr_merge::req(
+        &test.handler.repository,
+        test.handler.data.pull_request.as_ref().unwrap(),
+        "release/7.123",
+        "release/",
+        &commits,
+        MergeStrategy::Merge,
+    );
+    expected.event_id = Some("delivery-1234".into());
+    test.pr_merge.expect_req(expected);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_merged_backport_external_author() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "labeled".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(true);
+    }
+    test.handler.data.label = Some(Label::new("backport-7.123"));
+    test.handler.data.sender = User::new("the-pr-merger");
+
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(&info.with_backport_authors(vec!["the-pr-owner".into()]))
+            .unwrap();
+    }
+
+    test.mock_pull_request_commits();
+
+    // bob-author is not allowlisted: no merge request
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Source PR: #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .color("warning")
+        .build()];
+    let msg = "Not backporting PR #32 to release/7.123: commits by authors not allowed to be backported: bob-author";
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_merged_backport_allowlisted_authors() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "labeled".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(true);
+    }
+    test.handler.data.label = Some(Label::new("backport-7.123"));
+    test.handler.data.sender = User::new("the-pr-merger");
+
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(&info.with_backport_authors(vec!["the-pr-owner".into(), "bob-author".into()]))
+            .unwrap();
+    }
+
+    let commits = test.mock_pull_request_commits();
+
+    test.expect_will_merge_branches("release/", vec!["release/7.123".into()], commits);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_merged_duplicate_backport_label() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "labeled".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(true);
+    }
+    test.handler.data.label = Some(Label::new("backport-7.123"));
+    test.handler.data.sender = User::new("the-pr-merger");
+
+    let commits = test.mock_pull_request_commits();
+    test.mock_pull_request_commits();
+
+    // only one merge request, even though the label is seen twice
+    test.expect_will_merge_branches("release/", vec!["release/7.123".into()], commits);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_merged_duplicate_backport_label_no_window() {
+    let mut test = new_test_with_config(None, |c| c.github.backport_label_window_secs = Some(0));
+    test.handler.event = "pull_request".into();
+    test.handler.action = "labeled".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(true);
+    }
+    test.handler.data.label = Some(Label::new("backport-7.123"));
+    test.handler.data.sender = User::new("the-pr-merger");
+
+    let commits = test.mock_pull_request_commits();
+    test.mock_pull_request_commits();
+
+    test.expect_will_merge_branches(
+        "release/",
+        vec!["release/7.123".into(), "release/7.123".into()],
+        commits,
+    );
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_merged_master_branch() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "labeled".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(true);
+    }
+    test.handler.data.label = Some(Label::new("backport-master"));
+    test.handler.data.sender = User::new("the-pr-merger");
+
+    let commits = test.mock_pull_request_commits();
+
+    test.expect_will_merge_branches("release/", vec!["master".into()], commits);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_merged_develop_branch() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "labeled".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(true);
+    }
+    test.handler.data.label = Some(Label::new("backport-develop"));
+    test.handler.data.sender = User::new("the-pr-merger");
+
+    let commits = test.mock_pull_request_commits();
+
+    test.expect_will_merge_branches("release/", vec!["develop".into()], commits);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_merged_main_branch() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "labeled".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.merged = Some(true);
+    }
+    test.handler.data.label = Some(Label::new("backport-main"));
+    test.handler.data.sender = User::new("the-pr-merger");
+
+    let commits = test.mock_pull_request_commits();
+
+    test.expect_will_merge_branches("release/", vec!["main".into()], commits);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_push_no_pr() {
+    let mut test = new_test();
+    test.handler.event = "push".into();
+    test.handler.data.ref_name = Some("refs/heads/some-branch".into());
+    test.handler.data.before = Some("abcdef0000".into());
+    test.handler.data.after = Some("1111abcdef".into());
+
+    test.github
+        .mock_get_pull_requests("some-user", "some-repo", Some("open"), None, Ok(vec![]));
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "push".into()), resp);
+}
+
+#[tokio::test]
+async fn test_push_with_pr() {
+    let mut test = new_test();
+    test.handler.event = "push".into();
+    test.handler.data.ref_name = Some("refs/heads/some-branch".into());
+    test.handler.data.before = Some("the-before-commit".into());
+    test.handler.data.after = Some("the-after-commit".into());
+
+    test.handler.data.commits = Some(vec![
+        PushCommit {
+            id: "aaaaaa000000".into(),
+            tree_id: "".into(),
+            message: "add stuff".into(),
+            url: "http://commit1".into(),
+        },
+        PushCommit {
+            id: "1111abcdef".into(),
+            tree_id: "".into(),
+            message: "fix stuff".into(),
+            url: "http://commit2".into(),
+        },
+    ]);
+
+    // Note: github shouldn't really let you have two PR's for a single branch.
+    // This multiple PR support is theoretical only, but also allows us to test the
+    // race condition between the hook event and the PRs api.
+    let mut pr1 = some_pr().unwrap();
+    pr1.head.sha = "the-before-commit".into();
+
+    let mut pr2 = pr1.clone();
+    pr2.head.sha = "the-after-commit".into();
+    pr2.number = 99;
+    pr2.assignees = vec![User::new("assign2")];
+    pr2.requested_reviewers = None;
+
+    // no jira references here: should fail
+    expect_jira_ref_fail_pr(&test.github, &pr1, &some_commits());
+    expect_jira_ref_fail_pr(&test.github, &pr2, &some_commits());
+
+    test.github
+        .mock_get_pull_request_commits("some-user", "some-repo", 32, Ok(some_commits()));
+    test.github
+        .mock_get_pull_request_commits("some-user", "some-repo", 99, Ok(some_commits()));
+
+    test.github.mock_get_pull_requests(
+        "some-user",
+        "some-repo",
+        Some("open"),
+        None,
+        Ok(vec![pr1, pr2]),
+    );
+
+    let msg = "joe.sender pushed 2 commit(s) to branch some-branch";
+    let attach_common = vec![
+        SlackAttachmentBuilder::new("<http://commit1|aaaaaa0>: add stuff").build(),
+        SlackAttachmentBuilder::new("<http://commit2|1111abc>: fix stuff").build(),
+    ];
+
+    let mut attach1 = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    attach1.append(&mut attach_common.clone());
+
+    let mut attach2 = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #99: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    attach2.append(&mut attach_common.clone());
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach1,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach1,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach1,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach1,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach1,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach2,
+            Some("some-user/some-repo/99".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign2"),
+            msg,
+            &attach2,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach2,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach2,
+            None,
+            false,
+        ),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "push".into()), resp);
+}
+
+#[tokio::test]
+async fn test_push_with_pr_blocks() {
+    let mut test = new_test_with_config(None, |c| c.slack.use_blocks = true);
+    test.handler.event = "push".into();
+    test.handler.data.ref_name = Some("refs/heads/some-branch".into());
+    test.handler.data.before = Some("the-before-commit".into());
+    test.handler.data.after = Some("the-after-commit".into());
+
+    test.handler.data.commits = Some(vec![
+        PushCommit {
+            id: "aaaaaa000000".into(),
+            tree_id: "".into(),
+            message: "add stuff".into(),
+            url: "http://commit1".into(),
+        },
+        PushCommit {
+            id: "1111abcdef".into(),
+            tree_id: "".into(),
+            message: "fix stuff".into(),
+            url: "http://commit2".into(),
+        },
+    ]);
+
+    let mut pr = some_pr().unwrap();
+    pr.head.sha = "the-after-commit".into();
+
+    expect_jira_ref_fail_pr(&test.github, &pr, &some_commits());
+    test.github
+        .mock_get_pull_request_commits("some-user", "some-repo", 32, Ok(some_commits()));
+    test.github
+        .mock_get_pull_requests("some-user", "some-repo", Some("open"), None, Ok(vec![pr]));
+
+    let msg = "joe.sender pushed 2 commit(s) to branch some-branch";
+    let channel_msg = format!("{} {}", msg, REPO_MSG);
+    let attach = vec![
+        SlackAttachmentBuilder::new("")
+            .title("Pull Request #32: \"The PR\"")
+            .title_link("http://the-pr")
+            .build(),
+        SlackAttachmentBuilder::new("<http://commit1|aaaaaa0>: add stuff").build(),
+        SlackAttachmentBuilder::new("<http://commit2|1111abc>: fix stuff").build(),
+    ];
+    let blocks = |msg: &str| {
+        SlackBlockBuilder::new()
+            .section(msg)
+            .section("*<http://the-pr|Pull Request #32: \"The PR\">*")
+            .context("<http://commit1|aaaaaa0>: add stuff")
+            .context("<http://commit2|1111abc>: fix stuff")
+            .build()
+    };
+
+    let dm = |user: &str| {
+        slack::req(
+            SlackRecipient::user_mention(user),
+            msg,
+            &attach,
+            None,
+            false,
+        )
+        .with_blocks(blocks(msg))
+    };
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &channel_msg,
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        )
+        .with_blocks(blocks(&channel_msg)),
+        dm("assign1"),
+        dm("bob.author"),
+        dm("joe.reviewer"),
+        dm("the.pr.owner"),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "push".into()), resp);
+}
+
+#[tokio::test]
+async fn test_push_with_pr_batched() {
+    let mut test = new_test_with_config(None, |c| c.slack.batch_push_notifications = true);
+    test.handler.event = "push".into();
+    test.handler.data.ref_name = Some("refs/heads/some-branch".into());
+    test.handler.data.before = Some("the-before-commit".into());
+    test.handler.data.after = Some("the-after-commit".into());
+
+    test.handler.data.commits = Some(vec![PushCommit {
+        id: "1111abcdef".into(),
+        tree_id: "".into(),
+        message: "fix stuff".into(),
+        url: "http://commit1".into(),
+    }]);
+
+    let mut pr1 = some_pr().unwrap();
+    pr1.head.sha = "the-after-commit".into();
+
+    let mut pr2 = pr1.clone();
+    pr2.number = 99;
+    pr2.title = "The other PR".into();
+    pr2.html_url = "http://the-other-pr".into();
+    pr2.assignees = vec![User::new("assign2")];
+    pr2.requested_reviewers = None;
+
+    expect_jira_ref_fail_pr(&test.github, &pr1, &some_commits());
+    expect_jira_ref_fail_pr(&test.github, &pr2, &some_commits());
+
+    test.github
+        .mock_get_pull_request_commits("some-user", "some-repo", 32, Ok(some_commits()));
+    test.github
+        .mock_get_pull_request_commits("some-user", "some-repo", 99, Ok(some_commits()));
+
+    test.github.mock_get_pull_requests(
+        "some-user",
+        "some-repo",
+        Some("open"),
+        None,
+        Ok(vec![pr1, pr2]),
+    );
+
+    let msg = "joe.sender pushed 1 commit(s) to branch some-branch";
+    let commit_attach = SlackAttachmentBuilder::new("<http://commit1|1111abc>: fix stuff").build();
+    let pr1_attach = SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build();
+    let pr2_attach = SlackAttachmentBuilder::new("")
+        .title("Pull Request #99: \"The other PR\"")
+        .title_link("http://the-other-pr")
+        .build();
+
+    let batch_attach = vec![
+        pr1_attach.clone(),
+        pr2_attach.clone(),
+        commit_attach.clone(),
+    ];
+    let attach1 = vec![pr1_attach, commit_attach.clone()];
+    let attach2 = vec![pr2_attach, commit_attach];
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &batch_attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach1,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach1,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach1,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach1,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign2"),
+            msg,
+            &attach2,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach2,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach2,
+            None,
+            false,
+        ),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "push".into()), resp);
+}
+
+#[tokio::test]
+async fn test_push_force_notify() {
+    let mut test = new_test();
+
+    test.handler.event = "push".into();
+    test.handler.data.ref_name = Some("refs/heads/some-branch".into());
+    test.handler.data.before = Some("abcdef0000".into());
+    test.handler.data.after = Some("1111abcdef".into());
+    test.handler.data.forced = Some(true);
+    test.handler.data.compare = Some("http://compare-url".into());
+
+    let mut pr = some_pr().unwrap();
+    pr.head.sha = "abcdef0000".into();
+    test.github.mock_get_pull_requests(
+        "some-user",
+        "some-repo",
+        Some("open"),
+        None,
+        Ok(vec![pr.clone()]),
+    );
+
+    test.mock_pull_request_commits();
+
+    expect_jira_ref_fail_pr(&test.github, &pr, &some_commits());
+
+    let msg = "joe.sender pushed 0 commit(s) to branch some-branch";
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    test.expect_will_force_push_notify(&pr, "abcdef0000", "1111abcdef");
+
+    let resp = test.handler.handle_event().await.expect("handled event");
+    assert_eq!((StatusCode::OK, "push".into()), resp);
+}
+
+// Sets up a force-push to a branch that is the head of two PRs, expecting the usual per-PR
+// push notifications. Returns the two PRs.
+fn setup_force_push_two_prs(test: &mut GithubHandlerTest) -> (PullRequest, PullRequest) {
+    test.handler.event = "push".into();
+    test.handler.data.ref_name = Some("refs/heads/some-branch".into());
+    test.handler.data.before = Some("abcdef0000".into());
+    test.handler.data.after = Some("1111abcdef".into());
+    test.handler.data.forced = Some(true);
+
+    let mut pr1 = some_pr().unwrap();
+    pr1.head.sha = "abcdef0000".into();
+
+    let mut pr2 = pr1.clone();
+    pr2.number = 99;
+    pr2.title = "The other PR".into();
+    pr2.html_url = "http://the-other-pr".into();
+    pr2.assignees = vec![User::new("assign2")];
+    pr2.requested_reviewers = None;
+
+    test.github.mock_get_pull_requests(
+        "some-user",
+        "some-repo",
+        Some("open"),
+        None,
+        Ok(vec![pr1.clone(), pr2.clone()]),
+    );
+    test.github
+        .mock_get_pull_request_commits("some-user", "some-repo", 32, Ok(some_commits()));
+    test.github
+        .mock_get_pull_request_commits("some-user", "some-repo", 99, Ok(some_commits()));
+
+    expect_jira_ref_fail_pr(&test.github, &pr1, &some_commits());
+    expect_jira_ref_fail_pr(&test.github, &pr2, &some_commits());
+
+    (pr1, pr2)
+}
+
+// The per-PR push messages; `channel` is false when a summary replaces the channel posts
+fn force_push_two_prs_slack_reqs(channel: bool) -> Vec<SlackRequest> {
+    let msg = "joe.sender pushed 0 commit(s) to branch some-branch";
+    let attach1 = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let attach2 = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #99: \"The other PR\"")
+        .title_link("http://the-other-pr")
+        .build()];
+
+    let mut reqs = vec![];
+    if channel {
+        reqs.push(slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach1,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ));
+    }
+    for user in &["assign1", "bob.author", "joe.reviewer", "the.pr.owner"] {
+        reqs.push(slack::req(
+            SlackRecipient::user_mention(user),
+            msg,
+            &attach1,
+            None,
+            false,
+        ));
+    }
+    if channel {
+        reqs.push(slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach2,
+            Some("some-user/some-repo/99".to_string()),
+            false,
+        ));
+    }
+    for user in &["assign2", "bob.author", "the.pr.owner"] {
+        reqs.push(slack::req(
+            SlackRecipient::user_mention(user),
+            msg,
+            &attach2,
+            None,
+            false,
+        ));
+    }
+    reqs
+}
+
+#[tokio::test]
+async fn test_push_force_notify_summary() {
+    let mut test = new_test_with_config(None, |c| {
+        c.slack.force_push_summary_threshold = Some(1);
+    });
+    let (pr1, pr2) = setup_force_push_two_prs(&mut test);
+
+    let mut reqs = vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!(
+            "joe.sender force-pushed branch some-branch, affecting 2 pull requests {}",
+            REPO_MSG
+        ),
+        &[
+            SlackAttachmentBuilder::new("")
+                .title("Pull Request #32: \"The PR\"")
+                .title_link("http://the-pr")
+                .build(),
+            SlackAttachmentBuilder::new("")
+                .title("Pull Request #99: \"The other PR\"")
+                .title_link("http://the-other-pr")
+                .build(),
+        ],
+        None,
+        false,
+    )];
+    // participants are still notified directly, and each PR still gets its diff comparison
+    reqs.extend(force_push_two_prs_slack_reqs(false));
+    test.slack.expect(reqs);
+
+    test.expect_will_force_push_notify(&pr1, "abcdef0000", "1111abcdef");
+    test.expect_will_force_push_notify(&pr2, "abcdef0000", "1111abcdef");
+
+    let resp = test.handler.handle_event().await.expect("handled event");
+    assert_eq!((StatusCode::OK, "push".into()), resp);
+}
+
+#[tokio::test]
+async fn test_push_force_notify_below_summary_threshold() {
+    let mut test = new_test_with_config(None, |c| {
+        c.slack.force_push_summary_threshold = Some(2);
+    });
+    let (pr1, pr2) = setup_force_push_two_prs(&mut test);
+
+    test.slack.expect(force_push_two_prs_slack_reqs(true));
+
+    test.expect_will_force_push_notify(&pr1, "abcdef0000", "1111abcdef");
+    test.expect_will_force_push_notify(&pr2, "abcdef0000", "1111abcdef");
+
+    let resp = test.handler.handle_event().await.expect("handled event");
+    assert_eq!((StatusCode::OK, "push".into()), resp);
+}
+
+#[tokio::test]
+async fn test_push_force_notify_wip() {
+    let mut test = new_test();
+
+    test.handler.event = "push".into();
+    test.handler.data.ref_name = Some("refs/heads/some-branch".into());
+    test.handler.data.before = Some("abcdef0000".into());
+    test.handler.data.after = Some("1111abcdef".into());
+    test.handler.data.forced = Some(true);
+
+    let mut pr = some_pr().unwrap();
+    pr.head.sha = "abcdef0000".into();
+    pr.title = "WIP: Awesome new feature".into();
+    test.github
+        .mock_get_pull_requests("some-user", "some-repo", Some("open"), None, Ok(vec![pr]));
+
+    // Note: no expectations here.
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "push".into()), resp);
+}
+
+#[tokio::test]
+async fn test_push_force_notify_ignored() {
+    let mut test = new_test();
+
+    test.handler.event = "push".into();
+    test.handler.data.ref_name = Some("refs/heads/some-branch".into());
+    test.handler.data.before = Some("abcdef0000".into());
+    test.handler.data.after = Some("1111abcdef".into());
+    test.handler.data.forced = Some(true);
+
+    // change the repo to an unconfigured one
+    test.handler.repository = Repo::parse(&format!(
+        "http://{}/some-other-user/some-other-repo",
+        test.github.github_host()
+    ))
+    .unwrap();
+
+    let mut pr = some_pr().unwrap();
+    pr.head.sha = "1111abcdef".into();
+    test.github.mock_get_pull_requests(
+        "some-other-user",
+        "some-other-repo",
+        Some("open"),
+        None,
+        Ok(vec![pr.clone()]),
+    );
+    test.github.mock_get_pull_request_commits(
+        "some-other-user",
+        "some-other-repo",
+        32,
+        Ok(some_commits()),
+    );
+
+    let msg = "joe.sender pushed 0 commit(s) to branch some-branch";
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    // Note: no expectations here.
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "push".into()), resp);
+}
+
+#[tokio::test]
+async fn test_pull_request_commits_cached() {
+    let test = new_test();
+    let pr = some_pr().unwrap();
+
+    // only mocked once: a second lookup would panic
+    let commits = test.mock_pull_request_commits();
+
+    assert_eq!(commits, test.handler.pull_request_commits(&&pr).await);
+    assert_eq!(commits, test.handler.pull_request_commits(&&pr).await);
+}
+
+#[tokio::test]
+async fn test_pull_request_commits_cached_per_event() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "synchronize".into();
+    test.handler.data.pull_request = some_pr();
+    let pr = some_pr().unwrap();
+
+    test.mock_pull_request_commits();
+    test.handler.pull_request_commits(&&pr).await;
+
+    // a new event fetches the commits again
+    test.mock_pull_request_commits();
+    expect_jira_ref_fail(&test.github);
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_team_members_cache() {
+    let mut test = new_test();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "review_requested".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.requested_teams = Some(vec![Team::new(100, "team-awesome")]);
+    }
+    test.handler.data.sender = User::new("the-pr-closer");
+    test.mock_pull_request_commits();
+    test.mock_get_team_members(100);
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request by the.pr.owner submitted for review to joe.reviewer, @team-awesome";
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("team.member1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("team.member2"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+
+    // Second request. get_team_members is not mocked on this request.
+    test.mock_pull_request_commits();
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request by the.pr.owner submitted for review to joe.reviewer, @team-awesome";
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("team.member1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("team.member2"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+    let another_resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), another_resp);
+}
+
+fn new_issue(key: &str) -> jira::Issue {
+    jira::Issue::new(key)
+}
+
+fn new_transition(id: &str, name: &str) -> jira::Transition {
+    jira::Transition {
+        id: id.into(),
+        name: name.into(),
+        to: jira::TransitionTo {
+            id: String::new(),
+            name: format!("{}-inner", name),
+        },
+        fields: None,
+    }
+}
+
+fn new_transition_req(id: &str) -> jira::TransitionRequest {
+    jira::TransitionRequest {
+        transition: jira::IDOrName {
+            id: Some(id.into()),
+            name: None,
+        },
+        fields: None,
+    }
+}
+
+fn some_jira_commits() -> Vec<Commit> {
+    vec![Commit {
+        sha: "ffeedd00110011".into(),
+        html_url: "http://commit/ffeedd00110011".into(),
+        author: Some(User::new("bob-author")),
+        commit: CommitDetails {
+            message: "Fix [SER-1] Add the feature\n\nThe body ([OTHER-123])".into(),
+        },
+    }]
+}
+
+fn many_jira_commits() -> Vec<Commit> {
+    jira_commits(21)
+}
+
+fn jira_commits(count: usize) -> Vec<Commit> {
+    let commit = Commit {
+        sha: "ffeedd00110011".into(),
+        html_url: "http://commit/ffeedd00110011".into(),
+        author: Some(User::new("bob-author")),
+        commit: CommitDetails {
+            message: "Fix [SER-1] Add the feature\n\nThe body ([OTHER-123])".into(),
+        },
+    };
+
+    vec![commit; count]
+}
+
+fn some_jira_push_commits() -> Vec<PushCommit> {
+    vec![PushCommit {
+        id: "ffeedd00110011".into(),
+        tree_id: "ffeedd00110011".into(),
+        url: "http://commit/ffeedd00110011".into(),
+        message: "Fix [SER-1] Add the feature\n\nThe body ([OTHER-123])".into(),
+    }]
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_opened() {
+    let mut test = new_test_with_jira();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request opened by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
+
+    expect_jira_ref_pass_pr(
+        &test.github,
+        some_pr().as_ref().unwrap(),
+        &some_jira_commits(),
+    );
+
+    if let Some(ref jira) = test.jira {
+        jira.mock_comment_issue(
+            "SER-1",
+            "Review submitted for branch master: http://the-pr",
+            Ok(()),
+        );
+
+        jira.mock_get_issue("SER-1", Ok(new_issue("SER-1")));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("001", "the-progress")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("001"), Ok(()));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("002", "the-review")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("002"), Ok(()));
+    }
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_opened_queues_review() {
+    let mut test = new_test_with_jira_config(|c| c.review_retries = Some(3));
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request opened by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
+
+    expect_jira_ref_pass_pr(
+        &test.github,
+        some_pr().as_ref().unwrap(),
+        &some_jira_commits(),
+    );
+
+    // no JIRA calls: the review is submitted by the worker
+    test.jira_review.expect_req(jira_review::req(
+        some_pr().as_ref().unwrap(),
+        &some_jira_commits(),
+        &["CLI".to_string(), "SER".to_string()],
+        "release/",
+        CommentVisibility::Configured,
+    ));
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_converted_to_draft() {
+    let mut test = new_test_with_jira();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "converted_to_draft".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.draft = Some(true);
+    }
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request converted to draft by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        false,
+    )]);
+
+    // no jira check or jira mocks: will fail if called
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_converted_to_draft_then_ready() {
+    let mut test = new_test_with_jira();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "converted_to_draft".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.draft = Some(true);
+    }
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!(
+            "Pull Request converted to draft by the.pr.owner {}",
+            REPO_MSG
+        ),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        false,
+    )]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+
+    // marking it ready again re-runs the review workflow
+    test.handler.action = "ready_for_review".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    let msg = "Pull Request is ready for review";
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    expect_jira_ref_pass_pr(
+        &test.github,
+        some_pr().as_ref().unwrap(),
+        &some_jira_commits(),
+    );
+
+    if let Some(ref jira) = test.jira {
+        jira.mock_comment_issue(
+            "SER-1",
+            "Review submitted for branch master: http://the-pr",
+            Ok(()),
+        );
+
+        jira.mock_get_issue("SER-1", Ok(new_issue("SER-1")));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("001", "the-progress")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("001"), Ok(()));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("002", "the-review")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("002"), Ok(()));
+    }
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_opened_draft_then_ready() {
+    let mut test = new_test_with_jira();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.draft = Some(true);
+    }
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    let mut draft_pr = some_pr().unwrap();
+    draft_pr.draft = Some(true);
+    expect_jira_ref_pass_pr(&test.github, &draft_pr, &some_jira_commits());
+
+    // drafts are quiet, and no jira mocks: will fail if JIRAs are moved to review
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+
+    // marking it ready moves the JIRAs to review
+    test.handler.action = "ready_for_review".into();
+    test.handler.data.pull_request = some_pr();
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request is ready for review";
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("assign1"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("bob.author"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("joe.reviewer"),
+            msg,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    expect_jira_ref_pass_pr(
+        &test.github,
+        some_pr().as_ref().unwrap(),
+        &some_jira_commits(),
+    );
+
+    if let Some(ref jira) = test.jira {
+        jira.mock_comment_issue(
+            "SER-1",
+            "Review submitted for branch master: http://the-pr",
+            Ok(()),
+        );
+
+        jira.mock_get_issue("SER-1", Ok(new_issue("SER-1")));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("001", "the-progress")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("001"), Ok(()));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("002", "the-review")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("002"), Ok(()));
+    }
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_opened_notify_events() {
+    let mut test = new_test_with_jira();
+    set_notify_events(&test, &["merged"]);
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    expect_jira_ref_pass_pr(
+        &test.github,
+        some_pr().as_ref().unwrap(),
+        &some_jira_commits(),
+    );
+
+    // no slack mocks, but JIRAs are still moved to review
+
+    if let Some(ref jira) = test.jira {
+        jira.mock_comment_issue(
+            "SER-1",
+            "Review submitted for branch master: http://the-pr",
+            Ok(()),
+        );
+
+        jira.mock_get_issue("SER-1", Ok(new_issue("SER-1")));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("001", "the-progress")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("001"), Ok(()));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("002", "the-review")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("002"), Ok(()));
+    }
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_opened_summary_comment() {
+    let mut test = new_test_with_jira_config(|c| c.pr_summary_comment = Some(true));
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request opened by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
+
+    expect_jira_ref_pass_pr(
+        &test.github,
+        some_pr().as_ref().unwrap(),
+        &some_jira_commits(),
+    );
+
+    if let Some(ref jira) = test.jira {
+        jira.mock_comment_issue(
+            "SER-1",
+            "Review submitted for branch master: http://the-pr",
+            Ok(()),
+        );
+
+        let mut issue = new_issue("SER-1");
+        issue.fields.status = Some(jira::Status {
+            name: "the-review".into(),
+        });
+        jira.mock_get_issue("SER-1", Ok(issue.clone()));
+
+        issue.fields.summary = Some("Add the feature".into());
+        jira.mock_get_issue("SER-1", Ok(issue));
+    }
+
+    test.github.mock_comment_pull_request(
+        "some-user",
+        "some-repo",
+        32,
+        "Referenced JIRAs:\n* [SER-1](https://the-jira-host/browse/SER-1): Add the feature (the-review)",
+        Ok(()),
+    );
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_opened_issue_details() {
+    let mut test = new_test_with_jira_config(|c| c.issue_details_in_notifications = Some(true));
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    let attach = vec![
+        SlackAttachmentBuilder::new("")
+            .title("Pull Request #32: \"The PR\"")
+            .title_link("http://the-pr")
+            .build(),
+        SlackAttachmentBuilder::new("SER-1: Bug, priority Critical").build(),
+    ];
+    let msg = "Pull Request opened by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
+
+    expect_jira_ref_pass_pr(
+        &test.github,
+        some_pr().as_ref().unwrap(),
+        &some_jira_commits(),
+    );
+
+    if let Some(ref jira) = test.jira {
+        let mut issue = new_issue("SER-1");
+        issue.fields.issue_type = Some(jira::IssueType { name: "Bug".into() });
+        issue.fields.priority = Some(jira::Priority {
+            name: "Critical".into(),
+        });
+        jira.mock_get_issue("SER-1", Ok(issue));
+
+        jira.mock_comment_issue(
+            "SER-1",
+            "Review submitted for branch master: http://the-pr",
+            Ok(()),
+        );
+        let mut issue = new_issue("SER-1");
+        issue.fields.status = Some(jira::Status {
+            name: "the-review".into(),
+        });
+        jira.mock_get_issue("SER-1", Ok(issue));
+    }
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_opened_issue_details_missing() {
+    let mut test = new_test_with_jira_config(|c| c.issue_details_in_notifications = Some(true));
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    // no type or priority to show, so the notification is unchanged
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request opened by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
+
+    expect_jira_ref_pass_pr(
+        &test.github,
+        some_pr().as_ref().unwrap(),
+        &some_jira_commits(),
+    );
+
+    if let Some(ref jira) = test.jira {
+        jira.mock_get_issue("SER-1", Err(anyhow::anyhow!("JIRA unavailable")));
+
+        jira.mock_comment_issue(
+            "SER-1",
+            "Review submitted for branch master: http://the-pr",
+            Ok(()),
+        );
+        let mut issue = new_issue("SER-1");
+        issue.fields.status = Some(jira::Status {
+            name: "the-review".into(),
+        });
+        jira.mock_get_issue("SER-1", Ok(issue));
+    }
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+fn expect_jira_review_submitted(test: &GithubHandlerTest) {
+    if let Some(ref jira) = test.jira {
+        jira.mock_comment_issue(
+            "SER-1",
+            "Review submitted for branch master: http://the-pr",
+            Ok(()),
+        );
+        let mut issue = new_issue("SER-1");
+        issue.fields.status = Some(jira::Status {
+            name: "the-review".into(),
+        });
+        jira.mock_get_issue("SER-1", Ok(issue));
+    }
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_opened_shared_key() {
+    let mut test = new_test_with_jira_config(|c| c.warn_shared_keys = Some(true));
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    // another open PR already fixes SER-1
+    test.handler.jira_key_prs.record(
+        &the_repo(),
+        31,
+        "http://the-other-pr",
+        &["SER-1".to_string()],
+    );
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request opened by the.pr.owner";
+    let warning =
+        "Pull Request #32 fixes SER-1, which other open PRs also fix: http://the-other-pr";
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            true,
+        ),
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", warning, REPO_MSG),
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            warning,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    expect_jira_ref_pass_pr(
+        &test.github,
+        some_pr().as_ref().unwrap(),
+        &some_jira_commits(),
+    );
+    expect_jira_review_submitted(&test);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_opened_shared_key_closed() {
+    let mut test = new_test_with_jira_config(|c| c.warn_shared_keys = Some(true));
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    // the other PR fixing SER-1 has since been closed
+    test.handler.jira_key_prs.record(
+        &the_repo(),
+        31,
+        "http://the-other-pr",
+        &["SER-1".to_string()],
+    );
+    test.handler.jira_key_prs.clear(&the_repo(), 31);
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request opened by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
+
+    expect_jira_ref_pass_pr(
+        &test.github,
+        some_pr().as_ref().unwrap(),
+        &some_jira_commits(),
+    );
+    expect_jira_review_submitted(&test);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+
+    // the opened PR is now tracked, so a later PR fixing SER-1 would be warned about
+    assert_eq!(
+        vec![("SER-1".to_string(), vec!["http://the-pr".to_string()])],
+        test.handler
+            .jira_key_prs
+            .record(&the_repo(), 33, "http://pr-33", &["SER-1".to_string()])
+    );
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_opened_transition_audit() {
+    let mut test =
+        new_test_with_jira_config(|c| c.transition_audit_channel = Some("jira-audit".into()));
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request opened by the.pr.owner";
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", msg, REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            true,
+        ),
+        slack::req(
+            SlackRecipient::by_name("jira-audit"),
+            "SER-1: Unknown → the-progress-inner (by octobot on review of PR#32)",
+            &[],
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::by_name("jira-audit"),
+            "SER-1: the-progress-inner → the-review-inner (by octobot on review of PR#32)",
+            &[],
+            None,
+            false,
+        ),
+    ]);
+
+    expect_jira_ref_pass_pr(
+        &test.github,
+        some_pr().as_ref().unwrap(),
+        &some_jira_commits(),
+    );
+
+    if let Some(ref jira) = test.jira {
+        jira.mock_comment_issue(
+            "SER-1",
+            "Review submitted for branch master: http://the-pr",
+            Ok(()),
+        );
+
+        jira.mock_get_issue("SER-1", Ok(new_issue("SER-1")));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("001", "the-progress")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("001"), Ok(()));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("002", "the-review")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("002"), Ok(()));
+    }
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_opened_suppressed_notifications() {
+    let mut test =
+        new_test_with_jira_config(|c| c.transition_audit_channel = Some("jira-audit".into()));
+    test.handler.messenger =
+        messenger::new(test.config.clone(), test.slack.new_sender()).with_suppressed(true);
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    // no slack messages, but JIRA is still updated
+    test.slack.expect(vec![]);
+
+    expect_jira_ref_pass_pr(
+        &test.github,
+        some_pr().as_ref().unwrap(),
+        &some_jira_commits(),
+    );
+
+    if let Some(ref jira) = test.jira {
+        jira.mock_comment_issue(
+            "SER-1",
+            "Review submitted for branch master: http://the-pr",
+            Ok(()),
+        );
+
+        jira.mock_get_issue("SER-1", Ok(new_issue("SER-1")));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("001", "the-progress")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("001"), Ok(()));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("002", "the-review")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("002"), Ok(()));
+    }
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_opened_public_comments() {
+    let mut test = new_test_with_jira();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos.update(&info.with_jira_comments_public(true)).unwrap();
+    }
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request opened by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
+
+    expect_jira_ref_pass_pr(
+        &test.github,
+        some_pr().as_ref().unwrap(),
+        &some_jira_commits(),
+    );
+
+    if let Some(ref jira) = test.jira {
+        jira.mock_comment_issue_with_visibility(
+            "SER-1",
+            "Review submitted for branch master: http://the-pr",
+            CommentVisibility::Public,
+            Ok(()),
+        );
+
+        jira.mock_get_issue("SER-1", Ok(new_issue("SER-1")));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("001", "the-progress")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("001"), Ok(()));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("002", "the-review")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("002"), Ok(()));
+    }
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_milestoned() {
+    let mut test = new_test_with_jira_config(|c| c.sync_milestone_versions = Some(true));
+    test.handler.event = "pull_request".into();
+    test.handler.action = "milestoned".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.milestone = Some(Milestone::new("1.2.3"));
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    if let Some(ref jira) = test.jira {
+        jira.mock_assign_fix_version("SER-1", "1.2.3", Ok(()));
+    }
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_demilestoned() {
+    let mut test = new_test_with_jira_config(|c| c.sync_milestone_versions = Some(true));
+    test.handler.event = "pull_request".into();
+    test.handler.action = "demilestoned".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.milestone = Some(Milestone::new("1.2.3"));
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    if let Some(ref jira) = test.jira {
+        jira.mock_remove_fix_version("SER-1", "1.2.3", Ok(()));
+    }
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_milestoned_disabled() {
+    let mut test = new_test_with_jira();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "milestoned".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.milestone = Some(Milestone::new("1.2.3"));
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    // no jira expectations
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_opened_warn_unconfigured_projects() {
+    let mut test = new_test_with_jira_config(|c| c.warn_unconfigured_projects = Some(true));
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let warning =
+        "Pull Request #32 references JIRAs from projects not configured for this repo: OTHER-123";
+
+    test.slack.expect(vec![
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("Pull Request opened by the.pr.owner {}", REPO_MSG),
+            &attach,
+            Some("some-user/some-repo/32".to_string()),
+            true,
+        ),
+        slack::req(
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!("{} {}", warning, REPO_MSG),
+            &attach,
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.pr.owner"),
+            warning,
+            &attach,
+            None,
+            false,
+        ),
+    ]);
+
+    expect_jira_ref_pass_pr(
+        &test.github,
+        some_pr().as_ref().unwrap(),
+        &some_jira_commits(),
+    );
+
+    if let Some(ref jira) = test.jira {
+        jira.mock_comment_issue(
+            "SER-1",
+            "Review submitted for branch master: http://the-pr",
+            Ok(()),
+        );
+
+        jira.mock_get_issue("SER-1", Ok(new_issue("SER-1")));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("001", "the-progress")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("001"), Ok(()));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("002", "the-review")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("002"), Ok(()));
+    }
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_opened_skip_review_branch() {
+    let mut test = new_test_with_jira_config(|c| {
+        c.skip_review_branches = Some(vec!["the-integration-.*".into()])
+    });
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.base.ref_name = "the-integration-branch".into();
+    }
+    let pr = test.handler.data.pull_request.clone().unwrap();
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request opened by the.pr.owner";
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
+
+    // still checks for JIRA references, but does not submit for review
+    expect_jira_ref_pass_pr(&test.github, &pr, &some_jira_commits());
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_opened_skip_revert_review() {
+    let mut test = new_test_with_jira_config(|c| c.skip_revert_reviews = Some(true));
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    if let Some(ref mut pr) = test.handler.data.pull_request {
+        pr.body = Some("Reverts some-user/some-repo#30".into());
+    }
+    let pr = test.handler.data.pull_request.clone().unwrap();
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request opened by the.pr.owner";
+
+    // still notifies and checks for JIRA references, but does not submit for review
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
+    expect_jira_ref_pass_pr(&test.github, &pr, &some_jira_commits());
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_reprocess_recent_event_with_new_projects() {
+    let mut test = new_test_with_jira();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.action = Some("opened".into());
+    test.handler.data.repository = Some(test.handler.repository.clone());
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
+
+    // start out with no JIRA projects configured
+    {
+        let mut repos = test.config.repos_write();
+        let mut info = repos.get_all().unwrap().remove(0);
+        info.jira_config = vec![];
+        repos.update(&info).unwrap();
+    }
+
+    let recent_events = RecentEvents::new();
+    recent_events.record(&test.handler.event, &test.handler.data);
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request opened by the.pr.owner";
+    let expected_msg = slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    );
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+    test.slack.expect(vec![expected_msg]);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+
+    // now configure the JIRA project and reprocess the recorded event
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos.update(&info.with_jira("SER")).unwrap();
+    }
+
+    let recorded = recent_events.latest("some-user/some-repo").unwrap();
+    assert_eq!("pull_request", recorded.event);
+    test.replay(recorded.event, recorded.data);
+
+    // the PR was already announced: only JIRA is updated
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+
+    expect_jira_ref_pass_pr(
+        &test.github,
+        some_pr().as_ref().unwrap(),
+        &some_jira_commits(),
+    );
+
+    if let Some(ref jira) = test.jira {
+        jira.mock_comment_issue(
+            "SER-1",
+            "Review submitted for branch master: http://the-pr",
+            Ok(()),
+        );
+
+        jira.mock_get_issue("SER-1", Ok(new_issue("SER-1")));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("001", "the-progress")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("001"), Ok(()));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("002", "the-review")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("002"), Ok(()));
+    }
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_replay_merged_pr_skips_notifications_and_backports() {
+    let mut test = new_test();
+    let mut data = test.handler.data.clone();
+    data.action = Some("closed".into());
+    data.repository = Some(test.handler.repository.clone());
+    data.pull_request = some_pr();
+    if let Some(ref mut pr) = data.pull_request {
+        pr.merged = Some(true);
+        pr.labels = Some(vec![Label::new("backport-1.0")]);
+    }
+    data.sender = User::new("the-pr-merger");
+    test.replay("pull_request".into(), data);
+
+    // no slack messages, no label lookups and no backport requests
+    test.mock_pull_request_commits();
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_replay_opened_pr_skips_github_updates() {
+    let mut test = new_test_with_jira_and_config(|c| {
+        c.github.pr_size_labels = Some(true);
+        c.jira.as_mut().unwrap().pr_summary_comment = Some(true);
+    });
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(
+                &info
+                    .with_default_reviewers(vec!["joe-reviewer".into()])
+                    .with_path_labels("docs/**", &["docs"]),
+            )
+            .unwrap();
+    }
+
+    let mut data = test.handler.data.clone();
+    data.action = Some("opened".into());
+    data.repository = Some(test.handler.repository.clone());
+    data.pull_request = some_pr();
+    if let Some(ref mut pr) = data.pull_request {
+        pr.requested_reviewers = Some(vec![]);
+        pr.additions = Some(40);
+        pr.deletions = Some(10);
+    }
+    data.sender = User::new("the-pr-owner");
+    test.replay("pull_request".into(), data);
+
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
+    expect_jira_ref_pass_pr(
+        &test.github,
+        some_pr().as_ref().unwrap(),
+        &some_jira_commits(),
+    );
+
+    // only the JIRA is updated: no reviewers requested, no labels and no PR comment
+    if let Some(ref jira) = test.jira {
+        jira.mock_comment_issue(
+            "SER-1",
+            "Review submitted for branch master: http://the-pr",
+            Ok(()),
+        );
+
+        let mut issue = new_issue("SER-1");
+        issue.fields.status = Some(jira::Status {
+            name: "the-review".into(),
+        });
+        jira.mock_get_issue("SER-1", Ok(issue));
+    }
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_replay_force_push_skips_comparison() {
+    let mut test = new_test();
+    setup_force_push_two_prs(&mut test);
+    let data = test.handler.data.clone();
+    test.replay("push".into(), data);
+
+    // no slack messages and no force-push comparisons
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "push".into()), resp);
+}
+
+#[tokio::test]
+async fn test_replay_push_skips_version_script() {
+    let mut test = new_test();
+
+    let commits = some_jira_push_commits();
+    let mut data = test.handler.data.clone();
+    data.repository = Some(test.handler.repository.clone());
+    data.ref_name = Some("refs/heads/master".into());
+    data.before = Some("abcdef0000".into());
+    data.after = Some("1111abcdef".into());
+    data.commits = Some(commits.clone());
+    test.replay("push".into(), data);
+
+    // JIRAs are resolved again, but the version script isn't rerun
+    test.repo_version.expect_req(
+        repo_version::req(&the_repo(), "master", "1111abcdef", &commits).with_resolve_only(true),
+    );
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "push".into()), resp);
+}
+
+#[test]
+fn test_recent_events_keeps_latest_push_or_pr() {
+    let recent_events = RecentEvents::new();
+
+    let mut data = HookBody::new();
+    data.repository = Some(the_repo());
+
+    data.after = Some("first".into());
+    recent_events.record("push", &data);
+    data.after = Some("second".into());
+    recent_events.record("push", &data);
+    data.after = Some("ignored".into());
+    recent_events.record("issue_comment", &data);
+
+    let recorded = recent_events.latest("some-user/some-repo").unwrap();
+    assert_eq!("push", recorded.event);
+    assert_eq!(Some("second".to_string()), recorded.data.after);
+
+    assert!(recent_events.latest("some-user/other-repo").is_none());
+}
+
+#[test]
+fn test_recent_events_records_installation_repositories() {
+    let recent_events = RecentEvents::new();
+
+    let data: HookBody = serde_json::from_str(
+        r#"{
+            "action": "added",
+            "sender": {"id": 7, "login": "the-admin"},
+            "repositories_added": [
+                {"id": 1, "full_name": "some-user/new-repo", "private": true}
+            ],
+            "repositories_removed": []
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(None, data.repository);
+    recent_events.record("installation_repositories", &data);
+
+    let recorded = recent_events.installation_events();
+    assert_eq!(1, recorded.len());
+    assert_eq!("installation_repositories", recorded[0].event);
+    assert_eq!(
+        Some(vec![InstallationRepository::new("some-user/new-repo")]),
+        recorded[0].data.repositories_added
+    );
+
+    // repo events are tracked separately
+    assert!(recent_events.latest("some-user/new-repo").is_none());
+}
+
+#[test]
+fn test_pending_redeliveries_taken_once() {
+    let redeliveries = PendingRedeliveries::new();
+    redeliveries.mark("guid-1");
+
+    assert!(!redeliveries.take("guid-2"));
+    assert!(redeliveries.take("guid-1"));
+    assert!(!redeliveries.take("guid-1"));
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_enqueued() {
+    let mut test = new_test_with_jira();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "enqueued".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
 
-    test.mock_pull_request_commits();
-    test.github.mock_get_pull_request_labels(
+    test.github.mock_get_pull_request_commits(
         "some-user",
         "some-repo",
         32,
-        Err(anyhow!("whooops.")),
+        Ok(some_jira_commits()),
     );
 
-    let msg1 = "Pull Request merged";
-    let attach1 = vec![SlackAttachmentBuilder::new("")
-        .title("Pull Request #32: \"The PR\"")
-        .title_link("http://the-pr")
-        .build()];
+    // no slack messages
+    if let Some(ref jira) = test.jira {
+        jira.mock_comment_issue(
+            "SER-1",
+            "Added to merge queue for branch master: http://the-pr",
+            Ok(()),
+        );
 
-    let msg2 = "Error getting Pull Request labels";
-    let attach2 = vec![SlackAttachmentBuilder::new("whooops.")
-        .color("danger")
-        .build()];
+        jira.mock_get_issue("SER-1", Ok(new_issue("SER-1")));
 
-    test.slack.expect(vec![
-        slack::req(
-            SlackRecipient::by_name("the-reviews-channel"),
-            &format!("{} {}", msg1, REPO_MSG),
-            &attach1,
-            Some("some-user/some-repo/32".to_string()),
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("assign1"),
-            msg1,
-            &attach1,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("bob.author"),
-            msg1,
-            &attach1,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("joe.reviewer"),
-            msg1,
-            &attach1,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("the.pr.owner"),
-            msg1,
-            &attach1,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::by_name("the-reviews-channel"),
-            &format!("{} {}", msg2, REPO_MSG),
-            &attach2,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("the.pr.owner"),
-            msg2,
-            &attach2,
-            None,
-            false,
-        ),
-    ]);
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("005", "the-queue")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("005"), Ok(()));
+    }
 
     let resp = test.handler.handle_event().await.unwrap();
     assert_eq!((StatusCode::OK, "pr".into()), resp);
 }
 
 #[tokio::test]
-async fn test_pull_request_merged_no_labels() {
-    let mut test = new_test();
+async fn test_jira_pull_request_auto_merge_enabled() {
+    let mut test = new_test_with_jira_config(|c| c.auto_merge_comments = Some(true));
     test.handler.event = "pull_request".into();
-    test.handler.action = "closed".into();
+    test.handler.action = "auto_merge_enabled".into();
     test.handler.data.pull_request = some_pr();
-    if let Some(ref mut pr) = test.handler.data.pull_request {
-        pr.merged = Some(true);
-    }
-    test.handler.data.sender = User::new("the-pr-merger");
+    test.handler.data.sender = User::new("the-pr-owner");
 
-    test.mock_pull_request_commits();
-    test.github
-        .mock_get_pull_request_labels("some-user", "some-repo", 32, Ok(vec![]));
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
 
     let attach = vec![SlackAttachmentBuilder::new("")
         .title("Pull Request #32: \"The PR\"")
         .title_link("http://the-pr")
         .build()];
-    let msg = "Pull Request merged";
+    let msg = "Pull Request auto-merge enabled by the.pr.owner";
 
-    test.slack.expect(vec![
-        slack::req(
-            SlackRecipient::by_name("the-reviews-channel"),
-            &format!("{} {}", msg, REPO_MSG),
-            &attach,
-            Some("some-user/some-repo/32".to_string()),
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("assign1"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("bob.author"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("joe.reviewer"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("the.pr.owner"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-    ]);
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        false,
+    )]);
+
+    if let Some(ref jira) = test.jira {
+        let comment = "Queued for auto-merge into branch master: http://the-pr";
+        jira.mock_get_issue_property("SER-1", "octobot.comment-hashes", Ok(None));
+        jira.mock_comment_issue("SER-1", comment, Ok(()));
+        jira.mock_set_issue_property(
+            "SER-1",
+            "octobot.comment-hashes",
+            serde_json::json!([jira::workflow::comment_hash(comment)]),
+            Ok(()),
+        );
+    }
 
     let resp = test.handler.handle_event().await.unwrap();
     assert_eq!((StatusCode::OK, "pr".into()), resp);
 }
 
 #[tokio::test]
-async fn test_pull_request_merged_backport_labels() {
-    let mut test = new_test();
+async fn test_jira_pull_request_auto_merge_enabled_not_configured() {
+    let mut test = new_test_with_jira();
     test.handler.event = "pull_request".into();
-    test.handler.action = "closed".into();
+    test.handler.action = "auto_merge_enabled".into();
     test.handler.data.pull_request = some_pr();
-    if let Some(ref mut pr) = test.handler.data.pull_request {
-        pr.merged = Some(true);
-    }
-    test.handler.data.sender = User::new("the-pr-merger");
-
-    let attach = vec![SlackAttachmentBuilder::new("")
-        .title("Pull Request #32: \"The PR\"")
-        .title_link("http://the-pr")
-        .build()];
-    let msg = "Pull Request merged";
+    test.handler.data.sender = User::new("the-pr-owner");
 
-    let commits = test.mock_pull_request_commits();
-    test.github.mock_get_pull_request_labels(
+    test.github.mock_get_pull_request_commits(
         "some-user",
         "some-repo",
         32,
-        Ok(vec![
-            Label::new("other"),
-            Label::new("backport-1.0"),
-            Label::new("BACKPORT-2.0"),
-            Label::new("BACKport-other-thing"),
-            Label::new("non-matching"),
-        ]),
+        Ok(some_jira_commits()),
     );
 
-    test.slack.expect(vec![
-        slack::req(
-            SlackRecipient::by_name("the-reviews-channel"),
-            &format!("{} {}", msg, REPO_MSG),
-            &attach,
-            Some("some-user/some-repo/32".to_string()),
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("assign1"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("bob.author"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("joe.reviewer"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("the.pr.owner"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-    ]);
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+    let msg = "Pull Request auto-merge enabled by the.pr.owner";
 
-    test.expect_will_merge_branches(
-        "release/",
-        vec![
-            "release/1.0".into(),
-            "release/2.0".into(),
-            "release/other-thing".into(),
-        ],
-        commits,
-    );
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("{} {}", msg, REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        false,
+    )]);
 
+    // no JIRA comments
     let resp = test.handler.handle_event().await.unwrap();
     assert_eq!((StatusCode::OK, "pr".into()), resp);
 }
 
 #[tokio::test]
-async fn test_pull_request_merged_backport_labels_custom_pattern() {
-    let mut test = new_test();
+async fn test_jira_pull_request_enqueued_transition_audit() {
+    let mut test =
+        new_test_with_jira_config(|c| c.transition_audit_channel = Some("jira-audit".into()));
     test.handler.event = "pull_request".into();
-    test.handler.action = "closed".into();
+    test.handler.action = "enqueued".into();
     test.handler.data.pull_request = some_pr();
-    if let Some(ref mut pr) = test.handler.data.pull_request {
-        pr.merged = Some(true);
-    }
-    test.handler.data.sender = User::new("the-pr-merger");
+    test.handler.data.sender = User::new("the-pr-owner");
 
-    test.config
-        .repos_write()
-        .insert_info(
-            &repos::RepoInfo::new("some-user/custom-branches-repo", "the-reviews-channel")
-                .with_release_branch_prefix("the-other-prefix-".into())
-                .with_use_threads(true),
-        )
-        .expect("Failed to add repo");
+    test.github.mock_get_pull_request_commits(
+        "some-user",
+        "some-repo",
+        32,
+        Ok(some_jira_commits()),
+    );
 
-    // change the repo to an unconfigured one
-    test.handler.repository = Repo::parse(&format!(
-        "http://{}/some-user/custom-branches-repo",
-        test.github.github_host()
-    ))
-    .unwrap();
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("jira-audit"),
+        "SER-1: Unknown → the-queue-inner (by octobot on merge queue entry of PR#32)",
+        &[],
+        None,
+        false,
+    )]);
 
-    let attach = vec![SlackAttachmentBuilder::new("")
-        .title("Pull Request #32: \"The PR\"")
-        .title_link("http://the-pr")
-        .build()];
-    let msg = "Pull Request merged";
+    if let Some(ref jira) = test.jira {
+        jira.mock_comment_issue(
+            "SER-1",
+            "Added to merge queue for branch master: http://the-pr",
+            Ok(()),
+        );
+
+        jira.mock_get_issue("SER-1", Ok(new_issue("SER-1")));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("005", "the-queue")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("005"), Ok(()));
+    }
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "pr".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_pull_request_opened_too_many_commits() {
+    let mut test = new_test_with_jira();
+    test.handler.event = "pull_request".into();
+    test.handler.action = "opened".into();
+    test.handler.data.pull_request = some_pr();
+    test.handler.data.sender = User::new("the-pr-owner");
 
-    let commits = some_commits();
     test.github.mock_get_pull_request_commits(
         "some-user",
-        "custom-branches-repo",
+        "some-repo",
         32,
-        Ok(commits.clone()),
+        Ok(many_jira_commits()),
     );
 
-    test.github.mock_get_pull_request_labels(
-        "some-user",
-        "custom-branches-repo",
-        32,
-        Ok(vec![
-            Label::new("other"),
-            Label::new("backport-1.0"),
-            Label::new("BACKPORT-2.0"),
-            Label::new("BACKport-other-thing"),
-            Label::new("non-matching"),
-        ]),
+    expect_jira_ref_pass_pr(
+        &test.github,
+        some_pr().as_ref().unwrap(),
+        &some_jira_commits(),
     );
 
-    let repo_msg =
-        "(<http://the-github-host/some-user/custom-branches-repo|some-user/custom-branches-repo>)";
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
 
     test.slack.expect(vec![
         slack::req(
             SlackRecipient::by_name("the-reviews-channel"),
-            &format!("{} {}", msg, repo_msg),
-            &attach,
-            Some("some-user/custom-branches-repo/32".to_string()),
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("assign1"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("bob.author"),
-            msg,
+            &format!("Pull Request opened by the.pr.owner {}", REPO_MSG),
             &attach,
-            None,
-            false,
+            Some("some-user/some-repo/32".to_string()),
+            true,
         ),
         slack::req(
-            SlackRecipient::user_mention("joe.reviewer"),
-            msg,
+            SlackRecipient::by_name("the-reviews-channel"),
+            &format!(
+                "Too many commits on Pull Request #32. Ignoring JIRAs. {}",
+                REPO_MSG
+            ),
             &attach,
             None,
             false,
         ),
         slack::req(
             SlackRecipient::user_mention("the.pr.owner"),
-            msg,
+            &"Too many commits on Pull Request #32. Ignoring JIRAs.".to_string(),
             &attach,
             None,
             false,
         ),
     ]);
 
-    test.expect_will_merge_branches(
-        "the-other-prefix-",
-        vec![
-            "the-other-prefix-1.0".into(),
-            "the-other-prefix-2.0".into(),
-            "the-other-prefix-other-thing".into(),
-        ],
-        commits,
-    );
+    // do not set jira expectations
 
     let resp = test.handler.handle_event().await.unwrap();
     assert_eq!((StatusCode::OK, "pr".into()), resp);
 }
 
 #[tokio::test]
-async fn test_pull_request_merged_retroactively_labeled() {
-    let mut test = new_test();
+async fn test_jira_pull_request_opened_max_commits_override() {
+    let mut test = new_test_with_jira();
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(&info.with_max_commits_for_jira(Some(50)))
+            .unwrap();
+    }
     test.handler.event = "pull_request".into();
-    test.handler.action = "labeled".into();
+    test.handler.action = "opened".into();
     test.handler.data.pull_request = some_pr();
-    if let Some(ref mut pr) = test.handler.data.pull_request {
-        pr.merged = Some(true);
-    }
-    test.handler.data.label = Some(Label::new("backport-7.123"));
-    test.handler.data.sender = User::new("the-pr-merger");
+    test.handler.data.sender = User::new("the-pr-owner");
 
-    let commits = test.mock_pull_request_commits();
+    test.github
+        .mock_get_pull_request_commits("some-user", "some-repo", 32, Ok(jira_commits(30)));
 
-    test.expect_will_merge_branches("release/", vec!["release/7.123".into()], commits);
+    expect_jira_ref_pass_pr(
+        &test.github,
+        some_pr().as_ref().unwrap(),
+        &some_jira_commits(),
+    );
+
+    let attach = vec![SlackAttachmentBuilder::new("")
+        .title("Pull Request #32: \"The PR\"")
+        .title_link("http://the-pr")
+        .build()];
+
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        &format!("Pull Request opened by the.pr.owner {}", REPO_MSG),
+        &attach,
+        Some("some-user/some-repo/32".to_string()),
+        true,
+    )]);
+
+    if let Some(ref jira) = test.jira {
+        jira.mock_comment_issue(
+            "SER-1",
+            "Review submitted for branch master: http://the-pr",
+            Ok(()),
+        );
+
+        jira.mock_get_issue("SER-1", Ok(new_issue("SER-1")));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("001", "the-progress")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("001"), Ok(()));
+
+        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("002", "the-review")]));
+        jira.mock_transition_issue("SER-1", &new_transition_req("002"), Ok(()));
+    }
 
     let resp = test.handler.handle_event().await.unwrap();
     assert_eq!((StatusCode::OK, "pr".into()), resp);
 }
 
 #[tokio::test]
-async fn test_pull_request_merged_master_branch() {
-    let mut test = new_test();
-    test.handler.event = "pull_request".into();
-    test.handler.action = "labeled".into();
-    test.handler.data.pull_request = some_pr();
-    if let Some(ref mut pr) = test.handler.data.pull_request {
-        pr.merged = Some(true);
-    }
-    test.handler.data.label = Some(Label::new("backport-master"));
-    test.handler.data.sender = User::new("the-pr-merger");
-
-    let commits = test.mock_pull_request_commits();
+async fn test_jira_push_master() {
+    let mut test = new_test_with_jira();
+    test.handler.event = "push".into();
+    test.handler.data.ref_name = Some("refs/heads/master".into());
+    test.handler.data.before = Some("abcdef0000".into());
+    test.handler.data.after = Some("1111abcdef".into());
+    let commits = some_jira_push_commits();
+    test.handler.data.commits = Some(commits.clone());
 
-    test.expect_will_merge_branches("release/", vec!["master".into()], commits);
+    test.expect_will_run_version_script("master", "1111abcdef", &commits);
 
     let resp = test.handler.handle_event().await.unwrap();
-    assert_eq!((StatusCode::OK, "pr".into()), resp);
+    assert_eq!((StatusCode::OK, "push".into()), resp);
 }
 
 #[tokio::test]
-async fn test_pull_request_merged_develop_branch() {
-    let mut test = new_test();
-    test.handler.event = "pull_request".into();
-    test.handler.action = "labeled".into();
-    test.handler.data.pull_request = some_pr();
-    if let Some(ref mut pr) = test.handler.data.pull_request {
-        pr.merged = Some(true);
-    }
-    test.handler.data.label = Some(Label::new("backport-develop"));
-    test.handler.data.sender = User::new("the-pr-merger");
+async fn test_jira_push_develop() {
+    let mut test = new_test_with_jira();
+    test.handler.event = "push".into();
+    test.handler.data.ref_name = Some("refs/heads/develop".into());
+    test.handler.data.before = Some("abcdef0000".into());
+    test.handler.data.after = Some("1111abcdef".into());
+    let commits = some_jira_push_commits();
+    test.handler.data.commits = Some(commits.clone());
 
-    let commits = test.mock_pull_request_commits();
+    test.expect_will_run_version_script("develop", "1111abcdef", &commits);
 
-    test.expect_will_merge_branches("release/", vec!["develop".into()], commits);
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "push".into()), resp);
+}
+
+#[tokio::test]
+async fn test_jira_push_release() {
+    let mut test = new_test_with_jira();
+    test.handler.event = "push".into();
+    test.handler.data.ref_name = Some("refs/heads/release/55".into());
+    test.handler.data.before = Some("abcdef0000".into());
+    test.handler.data.after = Some("1111abcdef".into());
+    let commits = some_jira_push_commits();
+    test.handler.data.commits = Some(commits.clone());
+
+    test.expect_will_run_version_script("release/55", "1111abcdef", &commits);
 
     let resp = test.handler.handle_event().await.unwrap();
-    assert_eq!((StatusCode::OK, "pr".into()), resp);
+    assert_eq!((StatusCode::OK, "push".into()), resp);
 }
 
 #[tokio::test]
-async fn test_pull_request_merged_main_branch() {
-    let mut test = new_test();
-    test.handler.event = "pull_request".into();
-    test.handler.action = "labeled".into();
-    test.handler.data.pull_request = some_pr();
-    if let Some(ref mut pr) = test.handler.data.pull_request {
-        pr.merged = Some(true);
-    }
-    test.handler.data.label = Some(Label::new("backport-main"));
-    test.handler.data.sender = User::new("the-pr-merger");
+async fn test_jira_push_other_branch() {
+    let mut test = new_test_with_jira();
+    test.handler.event = "push".into();
+    test.handler.data.ref_name = Some("refs/heads/some-branch".into());
+    test.handler.data.before = Some("abcdef0000".into());
+    test.handler.data.after = Some("1111abcdef".into());
 
-    let commits = test.mock_pull_request_commits();
+    test.handler.data.commits = Some(some_jira_push_commits());
+
+    test.github
+        .mock_get_pull_requests("some-user", "some-repo", Some("open"), None, Ok(vec![]));
 
-    test.expect_will_merge_branches("release/", vec!["main".into()], commits);
+    // no jira mocks: will fail if called
 
     let resp = test.handler.handle_event().await.unwrap();
-    assert_eq!((StatusCode::OK, "pr".into()), resp);
+    assert_eq!((StatusCode::OK, "push".into()), resp);
 }
 
 #[tokio::test]
-async fn test_push_no_pr() {
-    let mut test = new_test();
+async fn test_jira_disabled() {
+    let mut test = new_test_with_jira();
     test.handler.event = "push".into();
-    test.handler.data.ref_name = Some("refs/heads/some-branch".into());
+    test.handler.data.ref_name = Some("refs/heads/master".into());
     test.handler.data.before = Some("abcdef0000".into());
     test.handler.data.after = Some("1111abcdef".into());
+    test.handler.data.commits = Some(some_jira_push_commits());
 
-    test.github
-        .mock_get_pull_requests("some-user", "some-repo", Some("open"), None, Ok(vec![]));
+    // change the repo to an unconfigured one
+    test.handler.repository = Repo::parse(&format!(
+        "http://{}/some-other-user/some-other-repo",
+        test.github.github_host()
+    ))
+    .unwrap();
+
+    // no jira mocks: will fail if called
 
     let resp = test.handler.handle_event().await.unwrap();
     assert_eq!((StatusCode::OK, "push".into()), resp);
 }
 
 #[tokio::test]
-async fn test_push_with_pr() {
-    let mut test = new_test();
+async fn test_jira_push_triggers_version_script() {
+    let mut test = new_test_with_jira();
+
+    test.config
+        .repos_write()
+        .insert_info(
+            &repos::RepoInfo::new("some-user/versioning-repo", "the-reviews-channel")
+                .with_jira("PRJ"),
+        )
+        .expect("Failed to add repo");
+
+    // change the repo to an unconfigured one
+    test.handler.repository = Repo::parse(&format!(
+        "http://{}/some-user/versioning-repo",
+        test.github.github_host()
+    ))
+    .unwrap();
+
     test.handler.event = "push".into();
-    test.handler.data.ref_name = Some("refs/heads/some-branch".into());
-    test.handler.data.before = Some("the-before-commit".into());
-    test.handler.data.after = Some("the-after-commit".into());
+    test.handler.data.ref_name = Some("refs/heads/master".into());
+    test.handler.data.before = Some("abcdef0000".into());
+    test.handler.data.after = Some("1111abcdef".into());
+    let commits = some_jira_push_commits();
+    test.handler.data.commits = Some(commits.clone());
 
-    test.handler.data.commits = Some(vec![
-        PushCommit {
-            id: "aaaaaa000000".into(),
-            tree_id: "".into(),
-            message: "add stuff".into(),
-            url: "http://commit1".into(),
-        },
-        PushCommit {
-            id: "1111abcdef".into(),
-            tree_id: "".into(),
-            message: "fix stuff".into(),
-            url: "http://commit2".into(),
-        },
-    ]);
+    test.expect_will_run_version_script("master", "1111abcdef", &commits);
 
-    // Note: github shouldn't really let you have two PR's for a single branch.
-    // This multiple PR support is theoretical only, but also allows us to test the
-    // race condition between the hook event and the PRs api.
-    let mut pr1 = some_pr().unwrap();
-    pr1.head.sha = "the-before-commit".into();
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "push".into()), resp);
+}
 
-    let mut pr2 = pr1.clone();
-    pr2.head.sha = "the-after-commit".into();
-    pr2.number = 99;
-    pr2.assignees = vec![User::new("assign2")];
-    pr2.requested_reviewers = None;
+#[tokio::test]
+async fn test_push_below_version_script_min_commits() {
+    let mut test = new_test();
 
-    // no jira references here: should fail
-    expect_jira_ref_fail_pr(&test.github, &pr1, &some_commits());
-    expect_jira_ref_fail_pr(&test.github, &pr2, &some_commits());
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(&info.with_version_script_min_commits(2))
+            .unwrap();
+    }
 
-    test.github
-        .mock_get_pull_request_commits("some-user", "some-repo", 32, Ok(some_commits()));
-    test.github
-        .mock_get_pull_request_commits("some-user", "some-repo", 99, Ok(some_commits()));
+    test.handler.event = "push".into();
+    test.handler.data.ref_name = Some("refs/heads/master".into());
+    test.handler.data.before = Some("abcdef0000".into());
+    test.handler.data.after = Some("1111abcdef".into());
+    let commits = some_jira_push_commits();
+    test.handler.data.commits = Some(commits.clone());
 
-    test.github.mock_get_pull_requests(
-        "some-user",
-        "some-repo",
-        Some("open"),
-        None,
-        Ok(vec![pr1, pr2]),
+    // single commit push: its JIRAs are resolved without running the version script
+    test.repo_version.expect_req(
+        repo_version::req(&the_repo(), "master", "1111abcdef", &commits).with_resolve_only(true),
     );
 
-    let msg = "joe.sender pushed 2 commit(s) to branch some-branch";
-    let attach_common = vec![
-        SlackAttachmentBuilder::new("<http://commit1|aaaaaa0>: add stuff").build(),
-        SlackAttachmentBuilder::new("<http://commit2|1111abc>: fix stuff").build(),
-    ];
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "push".into()), resp);
+}
 
-    let mut attach1 = vec![SlackAttachmentBuilder::new("")
-        .title("Pull Request #32: \"The PR\"")
-        .title_link("http://the-pr")
-        .build()];
-    attach1.append(&mut attach_common.clone());
+#[tokio::test]
+async fn test_push_at_version_script_min_commits() {
+    let mut test = new_test();
 
-    let mut attach2 = vec![SlackAttachmentBuilder::new("")
-        .title("Pull Request #99: \"The PR\"")
-        .title_link("http://the-pr")
-        .build()];
-    attach2.append(&mut attach_common.clone());
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(&info.with_version_script_min_commits(2))
+            .unwrap();
+    }
 
-    test.slack.expect(vec![
-        slack::req(
-            SlackRecipient::by_name("the-reviews-channel"),
-            &format!("{} {}", msg, REPO_MSG),
-            &attach1,
-            Some("some-user/some-repo/32".to_string()),
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("assign1"),
-            msg,
-            &attach1,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("bob.author"),
-            msg,
-            &attach1,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("joe.reviewer"),
-            msg,
-            &attach1,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("the.pr.owner"),
-            msg,
-            &attach1,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::by_name("the-reviews-channel"),
-            &format!("{} {}", msg, REPO_MSG),
-            &attach2,
-            Some("some-user/some-repo/99".to_string()),
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("assign2"),
-            msg,
-            &attach2,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("bob.author"),
-            msg,
-            &attach2,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("the.pr.owner"),
-            msg,
-            &attach2,
-            None,
-            false,
-        ),
-    ]);
+    test.handler.event = "push".into();
+    test.handler.data.ref_name = Some("refs/heads/master".into());
+    test.handler.data.before = Some("abcdef0000".into());
+    test.handler.data.after = Some("1111abcdef".into());
+    let mut commits = some_jira_push_commits();
+    commits.extend(some_jira_push_commits());
+    test.handler.data.commits = Some(commits.clone());
+
+    test.expect_will_run_version_script("master", "1111abcdef", &commits);
 
     let resp = test.handler.handle_event().await.unwrap();
     assert_eq!((StatusCode::OK, "push".into()), resp);
 }
 
+fn set_version_script_paths(test: &GithubHandlerTest, paths: &[&str]) {
+    let mut repos = test.config.repos_write();
+    let info = repos.get_all().unwrap().remove(0);
+    repos
+        .update(&info.with_version_script_paths(paths.iter().map(|p| p.to_string()).collect()))
+        .unwrap();
+}
+
 #[tokio::test]
-async fn test_push_force_notify() {
+async fn test_push_docs_only_skips_version_script() {
     let mut test = new_test();
+    set_version_script_paths(&test, &["src/**", "Cargo.toml"]);
 
     test.handler.event = "push".into();
-    test.handler.data.ref_name = Some("refs/heads/some-branch".into());
+    test.handler.data.ref_name = Some("refs/heads/master".into());
     test.handler.data.before = Some("abcdef0000".into());
     test.handler.data.after = Some("1111abcdef".into());
-    test.handler.data.forced = Some(true);
-    test.handler.data.compare = Some("http://compare-url".into());
+    let commits = some_jira_push_commits();
+    test.handler.data.commits = Some(commits.clone());
 
-    let mut pr = some_pr().unwrap();
-    pr.head.sha = "abcdef0000".into();
-    test.github.mock_get_pull_requests(
+    test.github.mock_get_compare_files(
         "some-user",
         "some-repo",
-        Some("open"),
-        None,
-        Ok(vec![pr.clone()]),
-    );
-
-    test.mock_pull_request_commits();
-
-    expect_jira_ref_fail_pr(&test.github, &pr, &some_commits());
-
-    let msg = "joe.sender pushed 0 commit(s) to branch some-branch";
-    let attach = vec![SlackAttachmentBuilder::new("")
-        .title("Pull Request #32: \"The PR\"")
-        .title_link("http://the-pr")
-        .build()];
-    test.slack.expect(vec![
-        slack::req(
-            SlackRecipient::by_name("the-reviews-channel"),
-            &format!("{} {}", msg, REPO_MSG),
-            &attach,
-            Some("some-user/some-repo/32".to_string()),
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("assign1"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("bob.author"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("joe.reviewer"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("the.pr.owner"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-    ]);
+        "abcdef0000",
+        "1111abcdef",
+        Ok(vec![
+            PullRequestFile::new("README.md"),
+            PullRequestFile::new("docs/src/setup.md"),
+        ]),
+    );
 
-    test.expect_will_force_push_notify(&pr, "abcdef0000", "1111abcdef");
+    // docs-only push: its JIRAs are resolved without running the version script
+    test.repo_version.expect_req(
+        repo_version::req(&the_repo(), "master", "1111abcdef", &commits).with_resolve_only(true),
+    );
 
-    let resp = test.handler.handle_event().await.expect("handled event");
+    let resp = test.handler.handle_event().await.unwrap();
     assert_eq!((StatusCode::OK, "push".into()), resp);
 }
 
 #[tokio::test]
-async fn test_push_force_notify_wip() {
+async fn test_push_matching_paths_runs_version_script() {
     let mut test = new_test();
+    set_version_script_paths(&test, &["src/**", "Cargo.toml"]);
 
     test.handler.event = "push".into();
-    test.handler.data.ref_name = Some("refs/heads/some-branch".into());
+    test.handler.data.ref_name = Some("refs/heads/master".into());
     test.handler.data.before = Some("abcdef0000".into());
     test.handler.data.after = Some("1111abcdef".into());
-    test.handler.data.forced = Some(true);
+    let commits = some_jira_push_commits();
+    test.handler.data.commits = Some(commits.clone());
 
-    let mut pr = some_pr().unwrap();
-    pr.head.sha = "abcdef0000".into();
-    pr.title = "WIP: Awesome new feature".into();
-    test.github
-        .mock_get_pull_requests("some-user", "some-repo", Some("open"), None, Ok(vec![pr]));
-    test.mock_pull_request_commits();
+    test.github.mock_get_compare_files(
+        "some-user",
+        "some-repo",
+        "abcdef0000",
+        "1111abcdef",
+        Ok(vec![
+            PullRequestFile::new("README.md"),
+            PullRequestFile::new("src/server/main.rs"),
+        ]),
+    );
 
-    // Note: no expectations here.
+    test.expect_will_run_version_script("master", "1111abcdef", &commits);
 
     let resp = test.handler.handle_event().await.unwrap();
     assert_eq!((StatusCode::OK, "push".into()), resp);
 }
 
 #[tokio::test]
-async fn test_push_force_notify_ignored() {
+async fn test_push_version_script_paths_lookup_error() {
     let mut test = new_test();
+    set_version_script_paths(&test, &["src/**"]);
 
     test.handler.event = "push".into();
-    test.handler.data.ref_name = Some("refs/heads/some-branch".into());
+    test.handler.data.ref_name = Some("refs/heads/master".into());
     test.handler.data.before = Some("abcdef0000".into());
     test.handler.data.after = Some("1111abcdef".into());
-    test.handler.data.forced = Some(true);
-
-    // change the repo to an unconfigured one
-    test.handler.repository = Repo::parse(&format!(
-        "http://{}/some-other-user/some-other-repo",
-        test.github.github_host()
-    ))
-    .unwrap();
+    let commits = some_jira_push_commits();
+    test.handler.data.commits = Some(commits.clone());
 
-    let mut pr = some_pr().unwrap();
-    pr.head.sha = "1111abcdef".into();
-    test.github.mock_get_pull_requests(
-        "some-other-user",
-        "some-other-repo",
-        Some("open"),
-        None,
-        Ok(vec![pr.clone()]),
-    );
-    test.github.mock_get_pull_request_commits(
-        "some-other-user",
-        "some-other-repo",
-        32,
-        Ok(some_commits()),
+    test.github.mock_get_compare_files(
+        "some-user",
+        "some-repo",
+        "abcdef0000",
+        "1111abcdef",
+        Err(anyhow!("whoops")),
     );
-    expect_jira_ref_fail_pr(&test.github, &pr, &some_commits());
-
-    let msg = "joe.sender pushed 0 commit(s) to branch some-branch";
-    let attach = vec![SlackAttachmentBuilder::new("")
-        .title("Pull Request #32: \"The PR\"")
-        .title_link("http://the-pr")
-        .build()];
-    test.slack.expect(vec![
-        slack::req(
-            SlackRecipient::user_mention("assign1"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("bob.author"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("joe.reviewer"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("the.pr.owner"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-    ]);
 
-    // Note: no expectations here.
+    // can't tell what changed: run the script anyway
+    test.expect_will_run_version_script("master", "1111abcdef", &commits);
 
     let resp = test.handler.handle_event().await.unwrap();
     assert_eq!((StatusCode::OK, "push".into()), resp);
 }
 
 #[tokio::test]
-async fn test_team_members_cache() {
+async fn test_push_version_script_event_id() {
     let mut test = new_test();
-    test.handler.event = "pull_request".into();
-    test.handler.action = "review_requested".into();
-    test.handler.data.pull_request = some_pr();
-    if let Some(ref mut pr) = test.handler.data.pull_request {
-        pr.requested_teams = Some(vec![Team::new(100, "team-awesome")]);
-    }
-    test.handler.data.sender = User::new("the-pr-closer");
-    test.mock_pull_request_commits();
-    test.mock_pull_request_commits();
-    test.mock_get_team_members(100);
-
-    let attach = vec![SlackAttachmentBuilder::new("")
-        .title("Pull Request #32: \"The PR\"")
-        .title_link("http://the-pr")
-        .build()];
-    let msg = "Pull Request by the.pr.owner submitted for review to joe.reviewer, @team-awesome";
-    test.slack.expect(vec![
-        slack::req(
-            SlackRecipient::by_name("the-reviews-channel"),
-            &format!("{} {}", msg, REPO_MSG),
-            &attach,
-            Some("some-user/some-repo/32".to_string()),
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("assign1"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("bob.author"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("joe.reviewer"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("team.member1"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("team.member2"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("the.pr.owner"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-    ]);
-
-    let resp = test.handler.handle_event().await.unwrap();
-    assert_eq!((StatusCode::OK, "pr".into()), resp);
-
-    // Second request. get_team_members is not mocked on this request.
-    test.mock_pull_request_commits();
-    let attach = vec![SlackAttachmentBuilder::new("")
-        .title("Pull Request #32: \"The PR\"")
-        .title_link("http://the-pr")
-        .build()];
-    let msg = "Pull Request by the.pr.owner submitted for review to joe.reviewer, @team-awesome";
-    test.slack.expect(vec![
-        slack::req(
-            SlackRecipient::by_name("the-reviews-channel"),
-            &format!("{} {}", msg, REPO_MSG),
-            &attach,
-            Some("some-user/some-repo/32".to_string()),
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("assign1"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("bob.author"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("joe.reviewer"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("team.member1"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("team.member2"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("the.pr.owner"),
-            msg,
-            &attach,
-            None,
-            false,
-        ),
-    ]);
-    let another_resp = test.handler.handle_event().await.unwrap();
-    assert_eq!((StatusCode::OK, "pr".into()), another_resp);
-}
+    test.handler.event_id = Some("delivery-1234".into());
 
-fn new_issue(key: &str) -> jira::Issue {
-    jira::Issue {
-        key: key.into(),
-        status: None,
-    }
-}
+    test.handler.event = "push".into();
+    test.handler.data.ref_name = Some("refs/heads/master".into());
+    test.handler.data.before = Some("abcdef0000".into());
+    test.handler.data.after = Some("1111abcdef".into());
+    let commits = some_jira_push_commits();
+    test.handler.data.commits = Some(commits.clone());
 
-fn new_transition(id: &str, name: &str) -> jira::Transition {
-    jira::Transition {
-        id: id.into(),
-        name: name.into(),
-        to: jira::TransitionTo {
-            id: String::new(),
-            name: format!("{}-inner", name),
-        },
-        fields: None,
-    }
-}
+    // the worker request carries the id of the webhook that queued it
+    let mut expected = repo_version::req(&the_repo(), "master", "1111abcdef", &commits);
+    expected.event_id = Some("delivery-1234".into());
+    test.repo_version.expect_req(expected);
 
-fn new_transition_req(id: &str) -> jira::TransitionRequest {
-    jira::TransitionRequest {
-        transition: jira::IDOrName {
-            id: Some(id.into()),
-            name: None,
-        },
-        fields: None,
-    }
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "push".into()), resp);
 }
 
-fn some_jira_commits() -> Vec<Commit> {
-    vec![Commit {
-        sha: "ffeedd00110011".into(),
-        html_url: "http://commit/ffeedd00110011".into(),
-        author: Some(User::new("bob-author")),
-        commit: CommitDetails {
-            message: "Fix [SER-1] Add the feature\n\nThe body ([OTHER-123])".into(),
-        },
-    }]
-}
+#[tokio::test]
+async fn test_push_resolve_mode_ignores_version_script_min_commits() {
+    let mut test = new_test();
 
-fn many_jira_commits() -> Vec<Commit> {
-    let commit = Commit {
-        sha: "ffeedd00110011".into(),
-        html_url: "http://commit/ffeedd00110011".into(),
-        author: Some(User::new("bob-author")),
-        commit: CommitDetails {
-            message: "Fix [SER-1] Add the feature\n\nThe body ([OTHER-123])".into(),
-        },
-    };
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(
+                &info
+                    .with_version_script_min_commits(2)
+                    .with_version_script_mode(repos::VersionScriptMode::Resolve),
+            )
+            .unwrap();
+    }
+
+    test.handler.event = "push".into();
+    test.handler.data.ref_name = Some("refs/heads/master".into());
+    test.handler.data.before = Some("abcdef0000".into());
+    test.handler.data.after = Some("1111abcdef".into());
+    let commits = some_jira_push_commits();
+    test.handler.data.commits = Some(commits.clone());
+
+    // no script runs, so the single commit is still resolved
+    test.expect_will_run_version_script("master", "1111abcdef", &commits);
 
-    (0..21)
-        .collect::<Vec<u32>>()
-        .into_iter()
-        .map(|_| commit.clone())
-        .collect()
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "push".into()), resp);
 }
 
-fn some_jira_push_commits() -> Vec<PushCommit> {
-    vec![PushCommit {
-        id: "ffeedd00110011".into(),
-        tree_id: "ffeedd00110011".into(),
-        url: "http://commit/ffeedd00110011".into(),
-        message: "Fix [SER-1] Add the feature\n\nThe body ([OTHER-123])".into(),
-    }]
+fn failed_workflow_run() -> WorkflowRun {
+    let mut run = WorkflowRun::new("CI");
+    run.html_url = "http://the-workflow-run".into();
+    run.head_branch = Some("pr-branch".into());
+    run.head_sha = "ffff0000111122".into();
+    run.conclusion = Some("failure".into());
+    run.pull_requests = vec![WorkflowRunPullRequest::new(32, "master")];
+    run
 }
 
 #[tokio::test]
-async fn test_jira_pull_request_opened() {
-    let mut test = new_test_with_jira();
-    test.handler.event = "pull_request".into();
-    test.handler.action = "opened".into();
-    test.handler.data.pull_request = some_pr();
-    test.handler.data.sender = User::new("the-pr-owner");
+async fn test_workflow_run_failed() {
+    let mut test = new_test_with_jira_and_config(|c| {
+        c.github.notify_workflow_failures = Some(true);
+        c.jira.as_mut().unwrap().workflow_failure_comments = Some(true);
+    });
+    test.handler.event = "workflow_run".into();
+    test.handler.action = "completed".into();
+    test.handler.data.workflow_run = Some(failed_workflow_run());
+
+    // JIRA projects are only configured for the PR's base branch, not its head branch
+    {
+        let mut repos = test.config.repos_write();
+        let mut info = repos.get_all().unwrap().remove(0);
+        for config in info.jira_config.iter_mut() {
+            config.release_branch_regex = "^release/".into();
+        }
+        repos.update(&info).unwrap();
+    }
 
     test.github.mock_get_pull_request_commits(
         "some-user",
@@ -2286,222 +7555,299 @@ async fn test_jira_pull_request_opened() {
         Ok(some_jira_commits()),
     );
 
-    let attach = vec![SlackAttachmentBuilder::new("")
-        .title("Pull Request #32: \"The PR\"")
-        .title_link("http://the-pr")
-        .build()];
-    let msg = "Pull Request opened by the.pr.owner";
-
     test.slack.expect(vec![slack::req(
         SlackRecipient::by_name("the-reviews-channel"),
-        &format!("{} {}", msg, REPO_MSG),
-        &attach,
+        &format!("Workflow \"CI\" failed on branch pr-branch {}", REPO_MSG),
+        &[SlackAttachmentBuilder::new("")
+            .title("Workflow run for ffff000")
+            .title_link("http://the-workflow-run")
+            .color("danger")
+            .build()],
         Some("some-user/some-repo/32".to_string()),
-        true,
+        false,
     )]);
 
-    expect_jira_ref_pass_pr(
-        &test.github,
-        some_pr().as_ref().unwrap(),
-        &some_jira_commits(),
-    );
-
     if let Some(ref jira) = test.jira {
         jira.mock_comment_issue(
             "SER-1",
-            "Review submitted for branch master: http://the-pr",
+            "Workflow \"CI\" failed for branch pr-branch: http://the-workflow-run",
             Ok(()),
         );
+    }
 
-        jira.mock_get_issue("SER-1", Ok(new_issue("SER-1")));
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "workflow_run".into()), resp);
+}
 
-        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("001", "the-progress")]));
-        jira.mock_transition_issue("SER-1", &new_transition_req("001"), Ok(()));
+#[tokio::test]
+async fn test_workflow_run_failed_notifications_disabled() {
+    let mut test = new_test();
+    test.handler.event = "workflow_run".into();
+    test.handler.action = "completed".into();
+    test.handler.data.workflow_run = Some(failed_workflow_run());
 
-        jira.mock_get_transitions("SER-1", Ok(vec![new_transition("002", "the-review")]));
-        jira.mock_transition_issue("SER-1", &new_transition_req("002"), Ok(()));
-    }
+    // no commits looked up, and no slack messages or JIRA comments expected
 
     let resp = test.handler.handle_event().await.unwrap();
-    assert_eq!((StatusCode::OK, "pr".into()), resp);
+    assert_eq!((StatusCode::OK, "workflow_run".into()), resp);
 }
 
 #[tokio::test]
-async fn test_jira_pull_request_opened_too_many_commits() {
-    let mut test = new_test_with_jira();
-    test.handler.event = "pull_request".into();
-    test.handler.action = "opened".into();
-    test.handler.data.pull_request = some_pr();
-    test.handler.data.sender = User::new("the-pr-owner");
-
-    test.github.mock_get_pull_request_commits(
-        "some-user",
-        "some-repo",
-        32,
-        Ok(many_jira_commits()),
-    );
+async fn test_workflow_run_succeeded() {
+    let mut test = new_test_with_config(None, |c| c.github.notify_workflow_failures = Some(true));
+    test.handler.event = "workflow_run".into();
+    test.handler.action = "completed".into();
+    let mut run = failed_workflow_run();
+    run.conclusion = Some("success".into());
+    test.handler.data.workflow_run = Some(run);
 
-    expect_jira_ref_pass_pr(
-        &test.github,
-        some_pr().as_ref().unwrap(),
-        &some_jira_commits(),
-    );
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "workflow_run [ignored]".into()), resp);
+}
 
-    let attach = vec![SlackAttachmentBuilder::new("")
-        .title("Pull Request #32: \"The PR\"")
-        .title_link("http://the-pr")
-        .build()];
+fn secret_scanning_alert() -> SecretScanningAlert {
+    // the secret itself is part of the payload, but must not be posted anywhere
+    serde_json::from_str(
+        r#"{
+            "number": 4,
+            "html_url": "http://the-alert",
+            "state": "open",
+            "secret_type": "github_personal_access_token",
+            "secret_type_display_name": "GitHub Personal Access Token",
+            "secret": "ghp_not_a_real_secret"
+        }"#,
+    )
+    .unwrap()
+}
 
-    test.slack.expect(vec![
-        slack::req(
-            SlackRecipient::by_name("the-reviews-channel"),
-            &format!("Pull Request opened by the.pr.owner {}", REPO_MSG),
-            &attach,
-            Some("some-user/some-repo/32".to_string()),
-            true,
-        ),
-        slack::req(
-            SlackRecipient::by_name("the-reviews-channel"),
-            &format!(
-                "Too many commits on Pull Request #32. Ignoring JIRAs. {}",
-                REPO_MSG
-            ),
-            &attach,
-            None,
-            false,
-        ),
-        slack::req(
-            SlackRecipient::user_mention("the.pr.owner"),
-            &"Too many commits on Pull Request #32. Ignoring JIRAs.".to_string(),
-            &attach,
-            None,
-            false,
-        ),
-    ]);
+#[tokio::test]
+async fn test_secret_scanning_alert_created() {
+    let mut test = new_test_with_config(None, |c| {
+        c.slack.security_alerts_channel = Some("security-alerts".into())
+    });
+    test.handler.event = "secret_scanning_alert".into();
+    test.handler.action = "created".into();
+    test.handler.data.alert = Some(secret_scanning_alert());
 
-    // do not set jira expectations
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("security-alerts"),
+        "Secret scanning alert: GitHub Personal Access Token detected in <http://the-github-host/some-user/some-repo|some-user/some-repo>",
+        &[SlackAttachmentBuilder::new("")
+            .title("Alert #4")
+            .title_link("http://the-alert")
+            .color("danger")
+            .build()],
+        None,
+        false,
+    )]);
 
     let resp = test.handler.handle_event().await.unwrap();
-    assert_eq!((StatusCode::OK, "pr".into()), resp);
+    assert_eq!((StatusCode::OK, "secret_scanning_alert".into()), resp);
 }
 
 #[tokio::test]
-async fn test_jira_push_master() {
-    let mut test = new_test_with_jira();
-    test.handler.event = "push".into();
-    test.handler.data.ref_name = Some("refs/heads/master".into());
-    test.handler.data.before = Some("abcdef0000".into());
-    test.handler.data.after = Some("1111abcdef".into());
-    let commits = some_jira_push_commits();
-    test.handler.data.commits = Some(commits.clone());
+async fn test_secret_scanning_alert_no_channel() {
+    let mut test = new_test();
+    test.handler.event = "secret_scanning_alert".into();
+    test.handler.action = "created".into();
+    test.handler.data.alert = Some(secret_scanning_alert());
 
-    test.expect_will_run_version_script("master", "1111abcdef", &commits);
+    // disabled without a security channel: no slack messages expected
 
     let resp = test.handler.handle_event().await.unwrap();
-    assert_eq!((StatusCode::OK, "push".into()), resp);
+    assert_eq!((StatusCode::OK, "secret_scanning_alert".into()), resp);
 }
 
 #[tokio::test]
-async fn test_jira_push_develop() {
-    let mut test = new_test_with_jira();
-    test.handler.event = "push".into();
-    test.handler.data.ref_name = Some("refs/heads/develop".into());
-    test.handler.data.before = Some("abcdef0000".into());
-    test.handler.data.after = Some("1111abcdef".into());
-    let commits = some_jira_push_commits();
-    test.handler.data.commits = Some(commits.clone());
+async fn test_secret_scanning_alert_resolved() {
+    let mut test = new_test_with_config(None, |c| {
+        c.slack.security_alerts_channel = Some("security-alerts".into())
+    });
+    test.handler.event = "secret_scanning_alert".into();
+    test.handler.action = "resolved".into();
+    test.handler.data.alert = Some(secret_scanning_alert());
 
-    test.expect_will_run_version_script("develop", "1111abcdef", &commits);
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!(
+        (StatusCode::OK, "secret_scanning_alert [ignored]".into()),
+        resp
+    );
+}
+
+// shaped like a real check_run webhook, with an id too large for a u32
+fn rerequested_check_run() -> CheckRun {
+    serde_json::from_value(serde_json::json!({
+        "id": 21856376215u64,
+        "name": "jira",
+        "head_sha": "ffff0000",
+        "details_url": "https://octobot.company.com",
+        "status": "completed",
+        "conclusion": "failure",
+        "completed_at": "2024-02-21T17:31:49Z",
+        "check_suite": { "id": 20911593543u64, "status": "requested", "conclusion": null },
+        "pull_requests": [
+            {
+                "id": 1737612345,
+                "number": 32,
+                "head": { "ref": "pr-branch", "sha": "ffff0000" },
+                "base": { "ref": "master", "sha": "1111eeee" }
+            }
+        ]
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_check_run_rerequested() {
+    let mut test = new_test();
+    test.handler.event = "check_run".into();
+    test.handler.action = "rerequested".into();
+    test.handler.data.check_run = Some(rerequested_check_run());
+
+    test.github
+        .get_pull_request("some-user", "some-repo", 32, Ok(some_pr().unwrap()));
+    test.mock_pull_request_commits();
+    expect_jira_ref_fail(&test.github);
 
     let resp = test.handler.handle_event().await.unwrap();
-    assert_eq!((StatusCode::OK, "push".into()), resp);
+    assert_eq!((StatusCode::OK, "check_run".into()), resp);
 }
 
 #[tokio::test]
-async fn test_jira_push_release() {
-    let mut test = new_test_with_jira();
-    test.handler.event = "push".into();
-    test.handler.data.ref_name = Some("refs/heads/release/55".into());
-    test.handler.data.before = Some("abcdef0000".into());
-    test.handler.data.after = Some("1111abcdef".into());
-    let commits = some_jira_push_commits();
-    test.handler.data.commits = Some(commits.clone());
+async fn test_check_run_rerequested_stale_head() {
+    let mut test = new_test();
+    test.handler.event = "check_run".into();
+    test.handler.action = "rerequested".into();
+    let mut run = rerequested_check_run();
+    run.head_sha = "eeee0000".into();
+    test.handler.data.check_run = Some(run);
 
-    test.expect_will_run_version_script("release/55", "1111abcdef", &commits);
+    test.github
+        .get_pull_request("some-user", "some-repo", 32, Ok(some_pr().unwrap()));
+
+    // no check run is submitted for an outdated commit
 
     let resp = test.handler.handle_event().await.unwrap();
-    assert_eq!((StatusCode::OK, "push".into()), resp);
+    assert_eq!((StatusCode::OK, "check_run".into()), resp);
 }
 
 #[tokio::test]
-async fn test_jira_push_other_branch() {
-    let mut test = new_test_with_jira();
+async fn test_check_run_rerequested_other_check() {
+    let mut test = new_test();
+    test.handler.event = "check_run".into();
+    test.handler.action = "rerequested".into();
+    let mut run = rerequested_check_run();
+    run.name = "ci".into();
+    test.handler.data.check_run = Some(run);
+
+    let resp = test.handler.handle_event().await.unwrap();
+    assert_eq!((StatusCode::OK, "check_run [ignored]".into()), resp);
+}
+
+fn release_merge_push_commits() -> Vec<PushCommit> {
+    let mut commits = some_jira_push_commits();
+    commits.push(PushCommit {
+        id: "aabbcc00110011".into(),
+        tree_id: "aabbcc00110011".into(),
+        url: "http://commit/aabbcc00110011".into(),
+        message: "Merge branch 'release/1.0' into release/1.1".into(),
+    });
+    commits
+}
+
+#[tokio::test]
+async fn test_push_release_merge_skipped() {
+    let mut test = new_test_with_config(None, |c| c.github.skip_release_merges = Some(true));
+
     test.handler.event = "push".into();
-    test.handler.data.ref_name = Some("refs/heads/some-branch".into());
+    test.handler.data.ref_name = Some("refs/heads/release/1.1".into());
     test.handler.data.before = Some("abcdef0000".into());
     test.handler.data.after = Some("1111abcdef".into());
+    test.handler.data.commits = Some(release_merge_push_commits());
 
-    test.handler.data.commits = Some(some_jira_push_commits());
-
-    test.github
-        .mock_get_pull_requests("some-user", "some-repo", Some("open"), None, Ok(vec![]));
-
-    // no jira mocks: will fail if called
+    // no version script or notifications expected
 
     let resp = test.handler.handle_event().await.unwrap();
-    assert_eq!((StatusCode::OK, "push".into()), resp);
+    assert_eq!(
+        (StatusCode::OK, "push [release merge, ignored]".into()),
+        resp
+    );
 }
 
 #[tokio::test]
-async fn test_jira_disabled() {
-    let mut test = new_test_with_jira();
+async fn test_push_release_merge_not_skipped_by_default() {
+    let mut test = new_test();
+
     test.handler.event = "push".into();
-    test.handler.data.ref_name = Some("refs/heads/master".into());
+    test.handler.data.ref_name = Some("refs/heads/release/1.1".into());
     test.handler.data.before = Some("abcdef0000".into());
     test.handler.data.after = Some("1111abcdef".into());
-    test.handler.data.commits = Some(some_jira_push_commits());
-
-    // change the repo to an unconfigured one
-    test.handler.repository = Repo::parse(&format!(
-        "http://{}/some-other-user/some-other-repo",
-        test.github.github_host()
-    ))
-    .unwrap();
+    let commits = release_merge_push_commits();
+    test.handler.data.commits = Some(commits.clone());
 
-    // no jira mocks: will fail if called
+    test.expect_will_run_version_script("release/1.1", "1111abcdef", &commits);
 
     let resp = test.handler.handle_event().await.unwrap();
     assert_eq!((StatusCode::OK, "push".into()), resp);
 }
 
 #[tokio::test]
-async fn test_jira_push_triggers_version_script() {
-    let mut test = new_test_with_jira();
-
-    test.config
-        .repos_write()
-        .insert_info(
-            &repos::RepoInfo::new("some-user/versioning-repo", "the-reviews-channel")
-                .with_jira("PRJ"),
-        )
-        .expect("Failed to add repo");
-
-    // change the repo to an unconfigured one
-    test.handler.repository = Repo::parse(&format!(
-        "http://{}/some-user/versioning-repo",
-        test.github.github_host()
-    ))
-    .unwrap();
+async fn test_push_release_non_merge_not_skipped() {
+    let mut test = new_test_with_config(None, |c| c.github.skip_release_merges = Some(true));
 
     test.handler.event = "push".into();
-    test.handler.data.ref_name = Some("refs/heads/master".into());
+    test.handler.data.ref_name = Some("refs/heads/release/1.1".into());
     test.handler.data.before = Some("abcdef0000".into());
     test.handler.data.after = Some("1111abcdef".into());
     let commits = some_jira_push_commits();
     test.handler.data.commits = Some(commits.clone());
 
-    test.expect_will_run_version_script("master", "1111abcdef", &commits);
+    test.expect_will_run_version_script("release/1.1", "1111abcdef", &commits);
 
     let resp = test.handler.handle_event().await.unwrap();
     assert_eq!((StatusCode::OK, "push".into()), resp);
 }
+
+#[tokio::test]
+async fn test_refetch_pull_request() {
+    let test = new_test();
+    let pr = some_pr().unwrap();
+
+    let mut refetched = pr.clone();
+    refetched.reviews = Some(vec![]);
+    test.github
+        .get_pull_request("some-user", "some-repo", 32, Ok(refetched.clone()));
+
+    let result = refetch_pull_request(
+        test.github.as_ref(),
+        &test.config,
+        &test.handler.repository,
+        &pr,
+    )
+    .await;
+    assert_eq!(Some(refetched), result);
+}
+
+#[tokio::test]
+async fn test_refetch_pull_request_disabled() {
+    let test = new_test();
+    let pr = some_pr().unwrap();
+
+    {
+        let mut repos = test.config.repos_write();
+        let info = repos.get_all().unwrap().remove(0);
+        repos
+            .update(&info.with_skip_reviewer_refetch(true))
+            .unwrap();
+    }
+
+    // no get_pull_request call expected: the mock would fail on an unexpected call
+    let result = refetch_pull_request(
+        test.github.as_ref(),
+        &test.config,
+        &test.handler.repository,
+        &pr,
+    )
+    .await;
+    assert_eq!(None, result);
+}
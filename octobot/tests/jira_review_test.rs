@@ -0,0 +1,305 @@
+mod mocks;
+
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use anyhow::anyhow;
+use tempfile::{tempdir, TempDir};
+
+use mocks::mock_jira::MockJira;
+use mocks::mock_slack::MockSlack;
+use mocks::mock_worker::LockedMockWorker;
+use octobot_lib::config::{Config, JiraConfig};
+use octobot_lib::config_db::ConfigDatabase;
+use octobot_lib::github;
+use octobot_lib::jira;
+use octobot_lib::jira::api::CommentVisibility;
+use octobot_lib::jira::{Issue, IssueFields, Status, Transition, TransitionTo};
+use octobot_lib::users::UserInfo;
+use octobot_ops::jira_review::{self, JiraReviewRequest};
+use octobot_ops::webhook_db::WebhookDatabase;
+use octobot_ops::worker::Runner;
+
+struct JiraReviewTest {
+    jira: Arc<MockJira>,
+    runner: Arc<dyn Runner<JiraReviewRequest>>,
+    webhook_db: Arc<WebhookDatabase>,
+    _slack: MockSlack,
+    _temp_dir: TempDir,
+}
+
+fn new_test(review_retries: u32) -> JiraReviewTest {
+    new_test_with_config(review_retries, |_| ())
+}
+
+fn new_test_with_config<F>(review_retries: u32, update: F) -> JiraReviewTest
+where
+    F: FnOnce(&mut JiraConfig),
+{
+    let temp_dir = tempdir().unwrap();
+    let db_file = temp_dir.path().join("db.sqlite3");
+    let db = ConfigDatabase::new(&db_file.to_string_lossy()).expect("create temp database");
+
+    let mut config = Config::new(db);
+    config.jira = Some(JiraConfig {
+        host: "the-host".into(),
+        username: "the-jira-user".into(),
+        password: "the-jira-pass".into(),
+        progress_states: Some(vec!["progress1".into()]),
+        review_states: Some(vec!["reviewing1".into()]),
+        resolved_states: None,
+        fixed_resolutions: None,
+        fix_versions_field: None,
+        pending_versions_field: None,
+        restrict_comment_visibility_to_role: None,
+        login_suffix: None,
+        queued_states: None,
+        skip_review_branches: None,
+        include_commit_count: None,
+        include_deploy_environment: None,
+        warn_unconfigured_projects: None,
+        sync_milestone_versions: None,
+        transition_audit_channel: None,
+        dedupe_comments: None,
+        pr_summary_comment: None,
+        backport_comments: None,
+        workflow_failure_comments: None,
+        release_branch_review_transitions: None,
+        released_states: None,
+        max_quoted_title_length: None,
+        skip_revert_reviews: None,
+        use_remote_links: None,
+        auto_merge_comments: None,
+        review_retries: Some(review_retries),
+        review_retry_delay_secs: Some(0),
+        issue_details_in_notifications: None,
+        sync_assignee: None,
+        warn_shared_keys: None,
+        dry_run: None,
+        extra_headers: None,
+    });
+    update(config.jira.as_mut().unwrap());
+    config
+        .users_write()
+        .insert_info(&UserInfo::new(
+            "the-pr-owner",
+            "the.pr.owner",
+            "",
+            "the-pr-owner@company.com",
+            vec![],
+        ))
+        .unwrap();
+
+    let webhook_db = Arc::new(
+        WebhookDatabase::new(&temp_dir.path().join("webhook.sqlite3").to_string_lossy())
+            .expect("create temp webhook database"),
+    );
+
+    let jira = Arc::new(MockJira::new());
+    let slack = MockSlack::new(vec![]);
+    let runner = jira_review::new_runner(
+        Arc::new(config),
+        Some(jira.clone()),
+        slack.new_sender(),
+        webhook_db.clone(),
+    );
+
+    JiraReviewTest {
+        jira,
+        runner,
+        webhook_db,
+        _slack: slack,
+        _temp_dir: temp_dir,
+    }
+}
+
+fn new_req() -> JiraReviewRequest {
+    let mut pr = github::PullRequest::new();
+    pr.number = 32;
+    pr.base.ref_name = "master".into();
+    pr.html_url = "http://the-pr".into();
+    pr.user = github::User::new("the-pr-owner");
+
+    let mut commit = github::Commit::new();
+    commit.commit.message = "Fix [SER-1] I fixed it.".into();
+    commit.sha = "aabbccddee".into();
+
+    jira_review::req(
+        &pr,
+        &[commit],
+        &["SER".to_string()],
+        "release/",
+        CommentVisibility::Configured,
+    )
+}
+
+const REVIEW_COMMENT: &str = "Review submitted for branch master: http://the-pr";
+
+fn expect_review_comment(jira: &MockJira) {
+    jira.mock_get_issue_property("SER-1", "octobot.comment-hashes", Ok(None));
+    jira.mock_comment_issue("SER-1", REVIEW_COMMENT, Ok(()));
+    jira.mock_set_issue_property(
+        "SER-1",
+        "octobot.comment-hashes",
+        serde_json::json!([jira::workflow::comment_hash(REVIEW_COMMENT)]),
+        Ok(()),
+    );
+}
+
+fn issue_in_state(state: &str) -> Issue {
+    Issue {
+        key: "SER-1".into(),
+        fields: IssueFields {
+            status: Some(Status { name: state.into() }),
+            ..Default::default()
+        },
+    }
+}
+
+fn review_transition() -> Transition {
+    Transition {
+        id: "002".into(),
+        name: "reviewing1".into(),
+        to: TransitionTo {
+            id: String::new(),
+            name: "reviewing1".into(),
+        },
+        fields: None,
+    }
+}
+
+fn expect_submit_for_review(jira: &MockJira) {
+    expect_review_comment(jira);
+    // already in review: no transitions
+    jira.mock_get_issue("SER-1", Ok(issue_in_state("reviewing1")));
+}
+
+// Sends the due retries to a worker expecting `expected`
+fn expect_retry(test: &JiraReviewTest, expected: Vec<JiraReviewRequest>) {
+    let worker = LockedMockWorker::from_reqs("jira_review", expected);
+    jira_review::retry_pending(
+        &test.webhook_db,
+        worker.new_sender().as_ref(),
+        SystemTime::now(),
+    );
+}
+
+fn retry_req(attempts: u32) -> JiraReviewRequest {
+    let mut req = new_req();
+    req.attempts = attempts;
+    req
+}
+
+#[tokio::test]
+async fn test_submit_for_review() {
+    let test = new_test(2);
+
+    test.jira.mock_ping(Ok(()));
+    expect_submit_for_review(&test.jira);
+
+    test.runner.handle(new_req()).await;
+    expect_retry(&test, vec![]);
+}
+
+#[tokio::test]
+async fn test_submit_for_review_syncs_assignee() {
+    let test = new_test_with_config(2, |c| {
+        c.sync_assignee = Some(true);
+        c.login_suffix = Some("@company.com".into());
+    });
+
+    test.jira.mock_ping(Ok(()));
+    expect_submit_for_review(&test.jira);
+    test.jira.mock_get_issue("SER-1", Ok(Issue::new("SER-1")));
+    test.jira.mock_assign_issue("SER-1", "the-pr-owner", Ok(()));
+
+    test.runner.handle(new_req()).await;
+}
+
+#[tokio::test]
+async fn test_submit_for_review_retried() {
+    let test = new_test(2);
+
+    // JIRA is down for the first attempt: the review is queued to retry
+    test.jira.mock_ping(Err(anyhow!("JIRA is down")));
+    test.runner.handle(new_req()).await;
+    expect_retry(&test, vec![retry_req(1)]);
+
+    // then recovers
+    test.jira.mock_ping(Ok(()));
+    expect_submit_for_review(&test.jira);
+    test.runner.handle(retry_req(1)).await;
+    expect_retry(&test, vec![]);
+}
+
+#[tokio::test]
+async fn test_submit_for_review_transition_retried() {
+    let test = new_test(2);
+
+    // commented, but the transition fails
+    test.jira.mock_ping(Ok(()));
+    expect_review_comment(&test.jira);
+    test.jira
+        .mock_get_issue("SER-1", Ok(issue_in_state("progress1")));
+    test.jira
+        .mock_get_transitions("SER-1", Ok(vec![review_transition()]));
+    test.jira.mock_transition_issue(
+        "SER-1",
+        &review_transition().new_request(),
+        Err(anyhow!("JIRA is overloaded")),
+    );
+
+    test.runner.handle(new_req()).await;
+    expect_retry(&test, vec![retry_req(1)]);
+
+    // the retry doesn't repeat the comment, and transitions the issue
+    test.jira.mock_ping(Ok(()));
+    test.jira.mock_get_issue_property(
+        "SER-1",
+        "octobot.comment-hashes",
+        Ok(Some(serde_json::json!([jira::workflow::comment_hash(
+            REVIEW_COMMENT
+        )]))),
+    );
+    test.jira
+        .mock_get_issue("SER-1", Ok(issue_in_state("progress1")));
+    test.jira
+        .mock_get_transitions("SER-1", Ok(vec![review_transition()]));
+    test.jira
+        .mock_transition_issue("SER-1", &review_transition().new_request(), Ok(()));
+
+    test.runner.handle(retry_req(1)).await;
+    expect_retry(&test, vec![]);
+}
+
+#[tokio::test]
+async fn test_submit_for_review_gives_up() {
+    let test = new_test(2);
+
+    // no JIRA comments or transitions, and nothing queued once retries run out
+    test.jira.mock_ping(Err(anyhow!("JIRA is still down")));
+
+    test.runner.handle(retry_req(2)).await;
+    expect_retry(&test, vec![]);
+}
+
+#[tokio::test]
+async fn test_submit_for_review_suppressed() {
+    let test = new_test_with_config(2, |c| {
+        c.transition_audit_channel = Some("jira-audit".into());
+    });
+
+    // the transition audit is dropped like the rest of the webhook's notifications
+    test.jira.mock_ping(Ok(()));
+    expect_review_comment(&test.jira);
+    test.jira
+        .mock_get_issue("SER-1", Ok(issue_in_state("progress1")));
+    test.jira
+        .mock_get_transitions("SER-1", Ok(vec![review_transition()]));
+    test.jira
+        .mock_transition_issue("SER-1", &review_transition().new_request(), Ok(()));
+
+    test.runner
+        .handle(new_req().with_suppressed_notifications(true))
+        .await;
+}
@@ -1,138 +1,1116 @@
 mod mocks;
 
+use std::sync::Arc;
+
 use maplit::hashmap;
 
-use octobot_lib::config::JiraConfig;
-use octobot_lib::github;
-use octobot_lib::jira;
-use octobot_lib::jira::*;
-use octobot_lib::version;
+use octobot_lib::config::JiraConfig;
+use octobot_lib::github;
+use octobot_lib::jira;
+use octobot_lib::jira::api::{CommentVisibility, DryRunSession};
+use octobot_lib::jira::workflow::{AppliedTransition, ResolvedCommits};
+use octobot_lib::jira::*;
+use octobot_lib::version;
+use octobot_ops::webhook_db::WebhookDatabase;
+use serde_json::json;
+
+use mocks::mock_jira::MockJira;
+use mocks::mock_logger;
+
+struct JiraWorkflowTest {
+    jira: MockJira,
+    config: JiraConfig,
+}
+
+fn new_test() -> JiraWorkflowTest {
+    let jira = MockJira::new();
+    let config = JiraConfig {
+        host: "the-host".into(),
+        username: "the-jira-user".into(),
+        password: "the-jira-pass".into(),
+        progress_states: Some(vec!["progress1".into()]),
+        review_states: Some(vec!["reviewing1".into()]),
+        resolved_states: Some(vec!["resolved1".into(), "resolved2".into()]),
+        fixed_resolutions: Some(vec!["it-is-fixed".into()]),
+        fix_versions_field: Some("the-versions".into()),
+        pending_versions_field: Some("the-pending-versions".into()),
+        restrict_comment_visibility_to_role: None,
+        login_suffix: None,
+        queued_states: None,
+        skip_review_branches: None,
+        include_commit_count: None,
+        include_deploy_environment: None,
+        warn_unconfigured_projects: None,
+        sync_milestone_versions: None,
+        transition_audit_channel: None,
+        dedupe_comments: None,
+        pr_summary_comment: None,
+        backport_comments: None,
+        workflow_failure_comments: None,
+        release_branch_review_transitions: None,
+        released_states: None,
+        max_quoted_title_length: None,
+        skip_revert_reviews: None,
+        use_remote_links: None,
+        auto_merge_comments: None,
+        review_retries: None,
+        review_retry_delay_secs: None,
+        issue_details_in_notifications: None,
+        sync_assignee: None,
+        warn_shared_keys: None,
+        dry_run: None,
+        extra_headers: None,
+    };
+
+    JiraWorkflowTest { jira, config }
+}
+
+fn new_pr() -> github::PullRequest {
+    let mut pr = github::PullRequest::new();
+    pr.head.ref_name = "pr-branch".into();
+    pr.base.ref_name = "master".into();
+    pr.html_url = "http://the-pr".into();
+    pr
+}
+
+fn new_commit(msg: &str, hash: &str) -> github::Commit {
+    let mut commit = github::Commit::new();
+    commit.commit.message = msg.into();
+    commit.sha = hash.into();
+    commit.html_url = format!("http://the-commit/{}", hash);
+    commit
+}
+
+fn new_push_commit(msg: &str, hash: &str) -> github::PushCommit {
+    let mut commit = github::PushCommit::new();
+    commit.message = msg.into();
+    commit.id = hash.into();
+    commit.url = format!("http://the-commit/{}", hash);
+    commit
+}
+
+fn new_issue(key: &str, status: Option<&str>) -> Issue {
+    let mut issue = Issue::new(key);
+    issue.fields.status = status.map(|s| Status {
+        name: s.to_string(),
+    });
+    issue
+}
+
+fn new_transition(id: &str, name: &str) -> Transition {
+    Transition {
+        id: id.into(),
+        name: name.into(),
+        to: TransitionTo {
+            id: String::new(),
+            name: format!("{}-inner", name),
+        },
+        fields: None,
+    }
+}
+
+fn new_transition_req(id: &str) -> TransitionRequest {
+    TransitionRequest {
+        transition: IDOrName {
+            id: Some(id.into()),
+            name: None,
+        },
+        fields: None,
+    }
+}
+
+#[tokio::test]
+async fn test_submit_for_review() {
+    let test = new_test();
+    let pr = new_pr();
+    let projects = vec!["SER".to_string(), "CLI".to_string()];
+    let commit = new_commit(
+        "Fix [SER-1] I fixed it. And also relates to [CLI-9999][OTHER-999]",
+        "aabbccddee",
+    );
+
+    test.jira.mock_comment_issue(
+        "SER-1",
+        "Review submitted for branch master: http://the-pr",
+        Ok(()),
+    );
+    test.jira.mock_comment_issue(
+        "CLI-9999",
+        "Referenced by review submitted for branch master: http://the-pr",
+        Ok(()),
+    );
+
+    test.jira
+        .mock_get_issue("SER-1", Ok(new_issue("SER-1", None)));
+    test.jira
+        .mock_get_issue("CLI-9999", Ok(new_issue("CLI-9999", None)));
+
+    test.jira
+        .mock_get_transitions("SER-1", Ok(vec![new_transition("001", "progress1")]));
+    test.jira
+        .mock_transition_issue("SER-1", &new_transition_req("001"), Ok(()));
+
+    test.jira
+        .mock_get_transitions("SER-1", Ok(vec![new_transition("002", "reviewing1")]));
+    test.jira
+        .mock_transition_issue("SER-1", &new_transition_req("002"), Ok(()));
+
+    // mentioned JIRAs should go to in-progress but not "pending review"
+    test.jira
+        .mock_get_transitions("CLI-9999", Ok(vec![new_transition("001", "progress1")]));
+    test.jira
+        .mock_transition_issue("CLI-9999", &new_transition_req("001"), Ok(()));
+
+    let transitions = jira::workflow::submit_for_review(
+        &pr,
+        &vec![commit],
+        &projects,
+        "release/",
+        &test.jira,
+        &test.config,
+        CommentVisibility::Configured,
+    )
+    .await
+    .applied;
+
+    assert_eq!(
+        vec![
+            "SER-1: Unknown → progress1-inner",
+            "SER-1: progress1-inner → reviewing1-inner",
+            "CLI-9999: Unknown → progress1-inner",
+        ],
+        transitions.iter().map(|t| t.describe()).collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn test_submit_for_review_transition_error() {
+    let test = new_test();
+    let pr = new_pr();
+    let projects = vec!["SER".to_string()];
+    let commit = new_commit("Fix [SER-1] I fixed it.", "aabbccddee");
+
+    test.jira.mock_comment_issue(
+        "SER-1",
+        "Review submitted for branch master: http://the-pr",
+        Ok(()),
+    );
+    test.jira
+        .mock_get_issue("SER-1", Ok(new_issue("SER-1", Some("progress1"))));
+    test.jira
+        .mock_get_transitions("SER-1", Ok(vec![new_transition("002", "reviewing1")]));
+    test.jira.mock_transition_issue(
+        "SER-1",
+        &new_transition_req("002"),
+        Err(anyhow::anyhow!("JIRA is down")),
+    );
+
+    let submission = jira::workflow::submit_for_review(
+        &pr,
+        &[commit],
+        &projects,
+        "release/",
+        &test.jira,
+        &test.config,
+        CommentVisibility::Configured,
+    )
+    .await;
+
+    assert!(submission.applied.is_empty());
+    assert_eq!(
+        vec!["Error transitioning [SER-1] to one of [[\"reviewing1\"]]: JIRA is down"],
+        submission.errors
+    );
+}
+
+#[tokio::test]
+async fn test_submit_for_review_dry_run() {
+    mock_logger::init();
+
+    let test = new_test();
+    let pr = new_pr();
+    let projects = vec!["SER".to_string()];
+    let commit = new_commit("Fix [SER-1] I fixed it.", "aabbccddee");
+
+    // only reads reach the wrapped session: no comments or transitions are mocked
+    let mock = Arc::new(MockJira::new());
+    mock.mock_get_issue("SER-1", Ok(new_issue("SER-1", None)));
+    mock.mock_get_transitions("SER-1", Ok(vec![new_transition("001", "progress1")]));
+    mock.mock_get_transitions("SER-1", Ok(vec![new_transition("002", "reviewing1")]));
+
+    let dry_run = DryRunSession::new(mock.clone());
+    jira::workflow::submit_for_review(
+        &pr,
+        &[commit],
+        &projects,
+        "release/",
+        &dry_run,
+        &test.config,
+        CommentVisibility::Configured,
+    )
+    .await;
+
+    assert_eq!(
+        1,
+        mock_logger::messages_containing(
+            "Dry run: would comment on [SER-1] (Configured): Review submitted for branch master: http://the-pr"
+        )
+        .len()
+    );
+    assert_eq!(
+        2,
+        mock_logger::messages_containing("Dry run: would transition [SER-1]").len()
+    );
+}
+
+#[tokio::test]
+async fn test_resolve_issue_dry_run_not_marked_resolved() {
+    mock_logger::init();
+
+    let mut test = new_test();
+    test.config.dry_run = Some(true);
+    let projects = vec!["SER".to_string(), "CLI".to_string()];
+    let commit = new_push_commit("Fix [SER-1] I fixed it. Related to [CLI-45]", "aabbccddee");
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db =
+        WebhookDatabase::new(&temp_dir.path().join("webhook.sqlite3").to_string_lossy()).unwrap();
+
+    // SER-1 needs no transition and CLI-45 is only referenced: both would normally be marked
+    let mock = Arc::new(MockJira::new());
+    mock.mock_get_issue("SER-1", Ok(new_issue("SER-1", Some("resolved1"))));
+
+    let dry_run = DryRunSession::new(mock.clone());
+    jira::workflow::resolve_issue(
+        "master",
+        None,
+        None,
+        &[commit],
+        &projects,
+        &dry_run,
+        &test.config,
+        CommentVisibility::Configured,
+        Some(&db),
+    )
+    .await;
+
+    // so that a real run later still comments on and transitions them
+    assert!(!db.was_resolved("SER-1", "aabbccddee", false).unwrap());
+    assert!(!db.was_resolved("CLI-45", "aabbccddee", false).unwrap());
+}
+
+#[tokio::test]
+async fn test_submit_for_review_remote_links() {
+    let mut test = new_test();
+    test.config.use_remote_links = Some(true);
+    let mut pr = new_pr();
+    pr.number = 32;
+    let projects = vec!["SER".to_string(), "CLI".to_string()];
+    let commit = new_commit(
+        "Fix [SER-1] I fixed it. And also relates to [CLI-9999]",
+        "aabbccddee",
+    );
+
+    // fixed JIRAs are linked instead of commented on; referenced ones still get a comment
+    test.jira.mock_create_remote_link(
+        "SER-1",
+        "http://the-pr",
+        "GitHub PR #32",
+        Some("http://the-pr/favicon.ico"),
+        Ok(()),
+    );
+    test.jira.mock_comment_issue(
+        "CLI-9999",
+        "Referenced by review submitted for branch master: http://the-pr",
+        Ok(()),
+    );
+
+    test.jira
+        .mock_get_issue("SER-1", Ok(new_issue("SER-1", None)));
+    test.jira
+        .mock_get_issue("CLI-9999", Ok(new_issue("CLI-9999", None)));
+
+    test.jira
+        .mock_get_transitions("SER-1", Ok(vec![new_transition("001", "progress1")]));
+    test.jira
+        .mock_transition_issue("SER-1", &new_transition_req("001"), Ok(()));
+    test.jira
+        .mock_get_transitions("SER-1", Ok(vec![new_transition("002", "reviewing1")]));
+    test.jira
+        .mock_transition_issue("SER-1", &new_transition_req("002"), Ok(()));
+
+    test.jira
+        .mock_get_transitions("CLI-9999", Ok(vec![new_transition("001", "progress1")]));
+    test.jira
+        .mock_transition_issue("CLI-9999", &new_transition_req("001"), Ok(()));
+
+    jira::workflow::submit_for_review(
+        &pr,
+        &[commit],
+        &projects,
+        "release/",
+        &test.jira,
+        &test.config,
+        CommentVisibility::Configured,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_submit_for_review_remote_link_failure() {
+    let mut test = new_test();
+    test.config.use_remote_links = Some(true);
+    let mut pr = new_pr();
+    pr.number = 32;
+    let projects = vec!["SER".to_string()];
+    let commit = new_commit("Fix [SER-1] I fixed it.", "aabbccddee");
+
+    // no transitions without the link
+    test.jira.mock_create_remote_link(
+        "SER-1",
+        "http://the-pr",
+        "GitHub PR #32",
+        Some("http://the-pr/favicon.ico"),
+        Err(anyhow::anyhow!("nope")),
+    );
+
+    let transitions = jira::workflow::submit_for_review(
+        &pr,
+        &[commit],
+        &projects,
+        "release/",
+        &test.jira,
+        &test.config,
+        CommentVisibility::Configured,
+    )
+    .await
+    .applied;
+    assert!(transitions.is_empty());
+}
+
+#[tokio::test]
+async fn test_submit_for_review_release_branch_no_transitions() {
+    let mut test = new_test();
+    test.config.release_branch_review_transitions = Some(false);
+    let mut pr = new_pr();
+    pr.base.ref_name = "release/1.0".into();
+    let projects = vec!["SER".to_string(), "CLI".to_string()];
+    let commit = new_commit(
+        "Fix [SER-1] I fixed it. And also relates to [CLI-9999][OTHER-999]",
+        "aabbccddee",
+    );
+
+    // comments only: no issue lookups or transitions
+    test.jira.mock_comment_issue(
+        "SER-1",
+        "Review submitted for branch release/1.0: http://the-pr",
+        Ok(()),
+    );
+    test.jira.mock_comment_issue(
+        "CLI-9999",
+        "Referenced by review submitted for branch release/1.0: http://the-pr",
+        Ok(()),
+    );
+
+    let transitions = jira::workflow::submit_for_review(
+        &pr,
+        &[commit],
+        &projects,
+        "release/",
+        &test.jira,
+        &test.config,
+        CommentVisibility::Configured,
+    )
+    .await
+    .applied;
+
+    assert!(transitions.is_empty());
+}
+
+#[tokio::test]
+async fn test_submit_for_review_release_branch_transitions_by_default() {
+    let test = new_test();
+    let mut pr = new_pr();
+    pr.base.ref_name = "release/1.0".into();
+    let projects = vec!["SER".to_string()];
+    let commit = new_commit("Fix [SER-1] I fixed it.", "aabbccddee");
+
+    test.jira.mock_comment_issue(
+        "SER-1",
+        "Review submitted for branch release/1.0: http://the-pr",
+        Ok(()),
+    );
+    test.jira
+        .mock_get_issue("SER-1", Ok(new_issue("SER-1", Some("progress1"))));
+    test.jira
+        .mock_get_transitions("SER-1", Ok(vec![new_transition("002", "reviewing1")]));
+    test.jira
+        .mock_transition_issue("SER-1", &new_transition_req("002"), Ok(()));
+
+    let transitions = jira::workflow::submit_for_review(
+        &pr,
+        &[commit],
+        &projects,
+        "release/",
+        &test.jira,
+        &test.config,
+        CommentVisibility::Configured,
+    )
+    .await
+    .applied;
+
+    assert_eq!(
+        vec!["SER-1: progress1 → reviewing1-inner"],
+        transitions.iter().map(|t| t.describe()).collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn test_pr_summary_comment() {
+    let test = new_test();
+    let projects = vec!["SER".to_string(), "CLI".to_string()];
+    let commit = new_commit(
+        "Fix [SER-1] I fixed it. And also relates to [CLI-9999][OTHER-999]",
+        "aabbccddee",
+    );
+
+    let mut issue = new_issue("CLI-9999", Some("In Progress"));
+    issue.fields.summary = Some(
+        "The command line crashes whenever it is given an argument that is longer than expected"
+            .into(),
+    );
+    test.jira.mock_get_issue("CLI-9999", Ok(issue));
+    test.jira
+        .mock_get_issue("SER-1", Err(anyhow::anyhow!("Issue does not exist")));
+
+    let comment =
+        jira::workflow::pr_summary_comment(&[commit], &projects, &test.jira, &test.config).await;
+    assert_eq!(
+        Some(
+            "Referenced JIRAs:\n\
+             * [CLI-9999](https://the-host/browse/CLI-9999): The command line crashes whenever it is given an argument that is longer than... (In Progress)\n\
+             * SER-1 (not found)"
+                .to_string()
+        ),
+        comment
+    );
+}
+
+#[tokio::test]
+async fn test_pr_summary_comment_no_jiras() {
+    let test = new_test();
+    let projects = vec!["SER".to_string()];
+    let commit = new_commit("I fixed it. Relates to [OTHER-999]", "aabbccddee");
+
+    let comment =
+        jira::workflow::pr_summary_comment(&[commit], &projects, &test.jira, &test.config).await;
+    assert_eq!(None, comment);
+}
+
+#[tokio::test]
+async fn test_assign_fixed_issues() {
+    let test = new_test();
+    let projects = vec!["SER".to_string(), "CLI".to_string()];
+    let commits = vec![
+        new_commit(
+            "Fix [SER-1][SER-2] I fixed it. And also relates to [CLI-9999]",
+            "aabbccddee",
+        ),
+        new_commit("Fix [SER-1] Fix it some more", "ffeeddccbb"),
+    ];
+
+    test.jira
+        .mock_get_issue("SER-1", Ok(new_issue("SER-1", None)));
+    test.jira.mock_assign_issue("SER-1", "the-author", Ok(()));
+
+    // already assigned manually
+    let mut issue = new_issue("SER-2", None);
+    issue.fields.assignee = Some(Assignee {
+        name: "someone-else".into(),
+    });
+    test.jira.mock_get_issue("SER-2", Ok(issue));
+
+    jira::workflow::assign_fixed_issues(&commits, &projects, "the-author", &test.jira).await;
+}
+
+#[tokio::test]
+async fn test_issue_details() {
+    let test = new_test();
+    let projects = vec!["SER".to_string(), "CLI".to_string()];
+    let commit = new_commit(
+        "Fix [SER-1][SER-2] I fixed it. And also relates to [CLI-9998][CLI-9999][OTHER-999]",
+        "aabbccddee",
+    );
+
+    let mut issue = new_issue("CLI-9998", None);
+    issue.fields.issue_type = Some(IssueType { name: "Bug".into() });
+    issue.fields.priority = Some(Priority {
+        name: "Critical".into(),
+    });
+    test.jira.mock_get_issue("CLI-9998", Ok(issue));
+
+    let mut issue = new_issue("CLI-9999", None);
+    issue.fields.priority = Some(Priority {
+        name: "Minor".into(),
+    });
+    test.jira.mock_get_issue("CLI-9999", Ok(issue));
+
+    test.jira
+        .mock_get_issue("SER-1", Err(anyhow::anyhow!("Issue does not exist")));
+    test.jira
+        .mock_get_issue("SER-2", Ok(new_issue("SER-2", Some("In Progress"))));
+
+    let details = jira::workflow::issue_details(&[commit], &projects, &test.jira).await;
+    assert_eq!(
+        Some("CLI-9998: Bug, priority Critical\nCLI-9999: priority Minor".to_string()),
+        details
+    );
+}
+
+#[tokio::test]
+async fn test_issue_details_none() {
+    let test = new_test();
+    let projects = vec!["SER".to_string()];
+    let commit = new_commit("Fix [SER-1] I fixed it", "aabbccddee");
+
+    test.jira
+        .mock_get_issue("SER-1", Ok(new_issue("SER-1", Some("In Progress"))));
+
+    let details = jira::workflow::issue_details(&[commit], &projects, &test.jira).await;
+    assert_eq!(None, details);
+}
+
+#[tokio::test]
+async fn test_issue_details_from_jira_response() {
+    let test = new_test();
+    let projects = vec!["SER".to_string()];
+    let commit = new_commit("Fix [SER-1] I fixed it", "aabbccddee");
+
+    // as returned by GET /rest/api/2/issue/SER-1
+    let issue: Issue = serde_json::from_value(json!({
+        "id": "10002",
+        "key": "SER-1",
+        "fields": {
+            "summary": "The server crashes",
+            "issuetype": { "id": "1", "name": "Bug", "subtask": false },
+            "priority": { "id": "2", "name": "Critical" },
+            "status": { "id": "3", "name": "In Progress" },
+            "assignee": null
+        }
+    }))
+    .unwrap();
+    test.jira.mock_get_issue("SER-1", Ok(issue));
+
+    let details = jira::workflow::issue_details(&[commit], &projects, &test.jira).await;
+    assert_eq!(Some("SER-1: Bug, priority Critical".to_string()), details);
+}
+
+#[tokio::test]
+async fn test_comment_backport() {
+    let test = new_test();
+    let mut backport_pr = new_pr();
+    backport_pr.html_url = "http://the-backport-pr".into();
+    let projects = vec!["SER".to_string(), "CLI".to_string()];
+    let commit = new_commit(
+        "Fix [SER-1] I fixed it. And also relates to [CLI-9999][OTHER-999]",
+        "aabbccddee",
+    );
+
+    // only fixed JIRAs get a comment
+    test.jira.mock_comment_issue(
+        "SER-1",
+        "Backport to branch release/1.0: http://the-backport-pr",
+        Ok(()),
+    );
+
+    jira::workflow::comment_backport(
+        &backport_pr,
+        "release/1.0",
+        &[commit],
+        &projects,
+        &test.jira,
+        &test.config,
+        CommentVisibility::Configured,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_submit_for_review_public_comments() {
+    let test = new_test();
+    let pr = new_pr();
+    let projects = vec!["SER".to_string()];
+    let commit = new_commit("Fix [SER-1] I fixed it.", "aabbccddee");
+
+    test.jira.mock_comment_issue_with_visibility(
+        "SER-1",
+        "Review submitted for branch master: http://the-pr",
+        CommentVisibility::Public,
+        Ok(()),
+    );
+    test.jira
+        .mock_get_issue("SER-1", Ok(new_issue("SER-1", Some("reviewing1"))));
+
+    jira::workflow::submit_for_review(
+        &pr,
+        &[commit],
+        &projects,
+        "release/",
+        &test.jira,
+        &test.config,
+        CommentVisibility::Public,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_resolve_issue_public_comments() {
+    let test = new_test();
+    let projects = vec!["SER".to_string()];
+    let commit = new_push_commit("Fix [SER-1] I fixed it.", "aabbccddee");
+
+    let comment = "Merged into branch master: [aabbccd|http://the-commit/aabbccddee]\n\
+                   {quote}Fix [SER-1] I fixed it.{quote}";
+    test.jira.mock_comment_issue_with_visibility(
+        "SER-1",
+        comment,
+        CommentVisibility::Public,
+        Ok(()),
+    );
+    test.jira
+        .mock_get_issue("SER-1", Ok(new_issue("SER-1", Some("resolved1"))));
+
+    jira::workflow::resolve_issue(
+        "master",
+        None,
+        None,
+        &[commit],
+        &projects,
+        &test.jira,
+        &test.config,
+        CommentVisibility::Public,
+        None,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_submit_for_review_dedupes_comments() {
+    let mut test = new_test();
+    test.config.dedupe_comments = Some(true);
+    let pr = new_pr();
+    let projects = vec!["SER".to_string()];
+    let commit = new_commit("Fix [SER-1] I fixed it.", "aabbccddee");
+
+    let comment = "Review submitted for branch master: http://the-pr";
+    let hash = jira::workflow::comment_hash(comment);
+
+    // already commented: skip posting it again
+    test.jira.mock_get_issue_property(
+        "SER-1",
+        "octobot.comment-hashes",
+        Ok(Some(json!(["other-hash", hash]))),
+    );
+    test.jira
+        .mock_get_issue("SER-1", Ok(new_issue("SER-1", Some("reviewing1"))));
+
+    jira::workflow::submit_for_review(
+        &pr,
+        &[commit],
+        &projects,
+        "release/",
+        &test.jira,
+        &test.config,
+        CommentVisibility::Configured,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_submit_for_review_records_comment_hash() {
+    let mut test = new_test();
+    test.config.dedupe_comments = Some(true);
+    let pr = new_pr();
+    let projects = vec!["SER".to_string()];
+    let commit = new_commit("Fix [SER-1] I fixed it.", "aabbccddee");
+
+    let comment = "Review submitted for branch master: http://the-pr";
+    let hash = jira::workflow::comment_hash(comment);
+
+    test.jira
+        .mock_get_issue_property("SER-1", "octobot.comment-hashes", Ok(None));
+    test.jira.mock_comment_issue("SER-1", comment, Ok(()));
+    test.jira
+        .mock_set_issue_property("SER-1", "octobot.comment-hashes", json!([hash]), Ok(()));
+    test.jira
+        .mock_get_issue("SER-1", Ok(new_issue("SER-1", Some("reviewing1"))));
+
+    jira::workflow::submit_for_review(
+        &pr,
+        &[commit],
+        &projects,
+        "release/",
+        &test.jira,
+        &test.config,
+        CommentVisibility::Configured,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_mark_auto_merge_enabled() {
+    let test = new_test();
+    let pr = new_pr();
+    let projects = vec!["SER".to_string()];
+    let commit = new_commit("Fix [SER-1] I fixed it. See [SER-2]", "aabbccddee");
 
-use mocks::mock_jira::MockJira;
+    let comment = "Queued for auto-merge into branch master: http://the-pr";
+    let hash = jira::workflow::comment_hash(comment);
 
-struct JiraWorkflowTest {
-    jira: MockJira,
-    config: JiraConfig,
+    // only fixed JIRAs get the note
+    test.jira
+        .mock_get_issue_property("SER-1", "octobot.comment-hashes", Ok(None));
+    test.jira.mock_comment_issue("SER-1", comment, Ok(()));
+    test.jira
+        .mock_set_issue_property("SER-1", "octobot.comment-hashes", json!([hash]), Ok(()));
+
+    jira::workflow::mark_auto_merge_enabled(
+        &pr,
+        &[commit],
+        &projects,
+        &test.jira,
+        CommentVisibility::Configured,
+    )
+    .await;
 }
 
-fn new_test() -> JiraWorkflowTest {
-    let jira = MockJira::new();
-    let config = JiraConfig {
-        host: "the-host".into(),
-        username: "the-jira-user".into(),
-        password: "the-jira-pass".into(),
-        progress_states: Some(vec!["progress1".into()]),
-        review_states: Some(vec!["reviewing1".into()]),
-        resolved_states: Some(vec!["resolved1".into(), "resolved2".into()]),
-        fixed_resolutions: Some(vec!["it-is-fixed".into()]),
-        fix_versions_field: Some("the-versions".into()),
-        pending_versions_field: Some("the-pending-versions".into()),
-        restrict_comment_visibility_to_role: None,
-        login_suffix: None,
-    };
+#[tokio::test]
+async fn test_mark_auto_merge_enabled_again() {
+    let test = new_test();
+    let pr = new_pr();
+    let projects = vec!["SER".to_string()];
+    let commit = new_commit("Fix [SER-1] I fixed it.", "aabbccddee");
 
-    JiraWorkflowTest { jira, config }
-}
+    let comment = "Queued for auto-merge into branch master: http://the-pr";
+    let hash = jira::workflow::comment_hash(comment);
 
-fn new_pr() -> github::PullRequest {
-    let mut pr = github::PullRequest::new();
-    pr.head.ref_name = "pr-branch".into();
-    pr.base.ref_name = "master".into();
-    pr.html_url = "http://the-pr".into();
-    pr
-}
+    // auto-merge was enabled before: no second comment
+    test.jira
+        .mock_get_issue_property("SER-1", "octobot.comment-hashes", Ok(Some(json!([hash]))));
 
-fn new_commit(msg: &str, hash: &str) -> github::Commit {
-    let mut commit = github::Commit::new();
-    commit.commit.message = msg.into();
-    commit.sha = hash.into();
-    commit.html_url = format!("http://the-commit/{}", hash);
-    commit
+    jira::workflow::mark_auto_merge_enabled(
+        &pr,
+        &[commit],
+        &projects,
+        &test.jira,
+        CommentVisibility::Configured,
+    )
+    .await;
 }
 
-fn new_push_commit(msg: &str, hash: &str) -> github::PushCommit {
-    let mut commit = github::PushCommit::new();
-    commit.message = msg.into();
-    commit.id = hash.into();
-    commit.url = format!("http://the-commit/{}", hash);
-    commit
+#[tokio::test]
+async fn test_resolve_issue_with_commit_count() {
+    let mut test = new_test();
+    test.config.include_commit_count = Some(true);
+    let projects = vec!["SER".to_string(), "CLI".to_string()];
+    let commit1 = new_push_commit("Fix [SER-1] I fixed it.", "aabbccddee");
+    let commit2 = new_push_commit("Related to [CLI-45]", "ffbbccddee");
+
+    let comment1 = "Merged into branch master: [aabbccd|http://the-commit/aabbccddee]\n\
+                   {quote}Fix [SER-1] I fixed it.{quote}\n\
+                   (2 commits)";
+    let comment2 = "Referenced by commit merged into branch master: \
+                    [ffbbccd|http://the-commit/ffbbccddee]\n\
+                    {quote}Related to [CLI-45]{quote}\n\
+                    (2 commits)";
+    test.jira.mock_comment_issue("SER-1", comment1, Ok(()));
+    test.jira
+        .mock_get_issue("SER-1", Ok(new_issue("SER-1", Some("resolved1"))));
+    test.jira.mock_comment_issue("CLI-45", comment2, Ok(()));
+
+    jira::workflow::resolve_issue(
+        "master",
+        None,
+        None,
+        &[commit1, commit2],
+        &projects,
+        &test.jira,
+        &test.config,
+        CommentVisibility::Configured,
+        None,
+    )
+    .await;
 }
 
-fn new_issue(key: &str, status: Option<&str>) -> Issue {
-    Issue {
-        key: key.into(),
-        status: status.map(|s| Status {
-            name: s.to_string(),
-        }),
-    }
+#[tokio::test]
+async fn test_resolve_issue_with_commit_count_single() {
+    let mut test = new_test();
+    test.config.include_commit_count = Some(true);
+    let projects = vec!["SER".to_string()];
+    let commit = new_push_commit("Fix [SER-1] I fixed it.", "aabbccddee");
+
+    let comment = "Merged into branch master: [aabbccd|http://the-commit/aabbccddee]\n\
+                   {quote}Fix [SER-1] I fixed it.{quote}\n\
+                   (1 commit)";
+    test.jira.mock_comment_issue("SER-1", comment, Ok(()));
+    test.jira
+        .mock_get_issue("SER-1", Ok(new_issue("SER-1", Some("resolved1"))));
+
+    jira::workflow::resolve_issue(
+        "master",
+        None,
+        None,
+        &[commit],
+        &projects,
+        &test.jira,
+        &test.config,
+        CommentVisibility::Configured,
+        None,
+    )
+    .await;
 }
 
-fn new_transition(id: &str, name: &str) -> Transition {
-    Transition {
-        id: id.into(),
-        name: name.into(),
-        to: TransitionTo {
-            id: String::new(),
-            name: format!("{}-inner", name),
-        },
-        fields: None,
-    }
+#[tokio::test]
+async fn test_resolve_issue_truncates_long_title() {
+    let mut test = new_test();
+    test.config.max_quoted_title_length = Some(30);
+    let projects = vec!["SER".to_string(), "CLI".to_string()];
+    let commit = new_push_commit(
+        "Fix [SER-1] I fixed a whole lot of things, see [CLI-2]",
+        "aabbccddee",
+    );
+
+    test.jira.mock_comment_issue(
+        "SER-1",
+        "Merged into branch master: [aabbccd|http://the-commit/aabbccddee]\n\
+         {quote}Fix [SER-1] I fixed a whole...{quote}",
+        Ok(()),
+    );
+    test.jira
+        .mock_get_issue("SER-1", Ok(new_issue("SER-1", Some("resolved1"))));
+
+    // keys past the cutoff are still found
+    test.jira.mock_comment_issue(
+        "CLI-2",
+        "Referenced by commit merged into branch master: [aabbccd|http://the-commit/aabbccddee]\n\
+         {quote}Fix [SER-1] I fixed a whole...{quote}",
+        Ok(()),
+    );
+
+    jira::workflow::resolve_issue(
+        "master",
+        None,
+        None,
+        &[commit],
+        &projects,
+        &test.jira,
+        &test.config,
+        CommentVisibility::Configured,
+        None,
+    )
+    .await;
 }
 
-fn new_transition_req(id: &str) -> TransitionRequest {
-    TransitionRequest {
-        transition: IDOrName {
-            id: Some(id.into()),
-            name: None,
-        },
-        fields: None,
-    }
+#[tokio::test]
+async fn test_resolve_issue_with_environment() {
+    let mut test = new_test();
+    test.config.include_deploy_environment = Some(true);
+    let projects = vec!["SER".to_string()];
+    let commit = new_push_commit("Fix [SER-1] I fixed it.", "aabbccddee");
+
+    let comment = "Merged into branch master: [aabbccd|http://the-commit/aabbccddee]\n\
+                   {quote}Fix [SER-1] I fixed it.{quote}\n\
+                   Included in version 1.2.3\n\
+                   Deployed to staging";
+    test.jira.mock_comment_issue("SER-1", comment, Ok(()));
+    test.jira
+        .mock_get_issue("SER-1", Ok(new_issue("SER-1", Some("resolved1"))));
+
+    jira::workflow::resolve_issue(
+        "master",
+        Some("1.2.3"),
+        Some("staging"),
+        &[commit],
+        &projects,
+        &test.jira,
+        &test.config,
+        CommentVisibility::Configured,
+        None,
+    )
+    .await;
 }
 
 #[tokio::test]
-async fn test_submit_for_review() {
+async fn test_resolve_issue_with_environment_disabled() {
     let test = new_test();
+    let projects = vec!["SER".to_string()];
+    let commit = new_push_commit("Fix [SER-1] I fixed it.", "aabbccddee");
+
+    let comment = "Merged into branch master: [aabbccd|http://the-commit/aabbccddee]\n\
+                   {quote}Fix [SER-1] I fixed it.{quote}\n\
+                   Included in version 1.2.3";
+    test.jira.mock_comment_issue("SER-1", comment, Ok(()));
+    test.jira
+        .mock_get_issue("SER-1", Ok(new_issue("SER-1", Some("resolved1"))));
+
+    jira::workflow::resolve_issue(
+        "master",
+        Some("1.2.3"),
+        Some("staging"),
+        &[commit],
+        &projects,
+        &test.jira,
+        &test.config,
+        CommentVisibility::Configured,
+        None,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_mark_queued() {
+    let mut test = new_test();
+    test.config.queued_states = Some(vec!["queued1".into()]);
     let pr = new_pr();
     let projects = vec!["SER".to_string(), "CLI".to_string()];
     let commit = new_commit(
-        "Fix [SER-1] I fixed it. And also relates to [CLI-9999][OTHER-999]",
+        "Fix [SER-1][SER-2] I fixed it. And also relates to [CLI-9999]",
         "aabbccddee",
     );
 
     test.jira.mock_comment_issue(
         "SER-1",
-        "Review submitted for branch master: http://the-pr",
+        "Added to merge queue for branch master: http://the-pr",
         Ok(()),
     );
     test.jira.mock_comment_issue(
-        "CLI-9999",
-        "Referenced by review submitted for branch master: http://the-pr",
+        "SER-2",
+        "Added to merge queue for branch master: http://the-pr",
         Ok(()),
     );
 
     test.jira
-        .mock_get_issue("SER-1", Ok(new_issue("SER-1", None)));
+        .mock_get_issue("SER-1", Ok(new_issue("SER-1", Some("reviewing1"))));
     test.jira
-        .mock_get_issue("CLI-9999", Ok(new_issue("CLI-9999", None)));
-
+        .mock_get_transitions("SER-1", Ok(vec![new_transition("005", "queued1")]));
     test.jira
-        .mock_get_transitions("SER-1", Ok(vec![new_transition("001", "progress1")]));
+        .mock_transition_issue("SER-1", &new_transition_req("005"), Ok(()));
+
+    // already queued: no transition
     test.jira
-        .mock_transition_issue("SER-1", &new_transition_req("001"), Ok(()));
+        .mock_get_issue("SER-2", Ok(new_issue("SER-2", Some("queued1"))));
+
+    jira::workflow::mark_queued(
+        &pr,
+        &[commit],
+        &projects,
+        &test.jira,
+        &test.config,
+        CommentVisibility::Configured,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_mark_queued_no_queued_states() {
+    let test = new_test();
+    let pr = new_pr();
+    let projects = vec!["SER".to_string()];
+    let commit = new_commit("Fix [SER-1] I fixed it.", "aabbccddee");
 
+    // no jira expectations
+
+    jira::workflow::mark_queued(
+        &pr,
+        &[commit],
+        &projects,
+        &test.jira,
+        &test.config,
+        CommentVisibility::Configured,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_mark_released() {
+    let mut test = new_test();
+    test.config.released_states = Some(vec!["released1".into()]);
+    let projects = vec!["SER".to_string(), "CLI".to_string()];
+
+    test.jira.mock_find_issues_with_fix_version(
+        "SER",
+        "1.2.3",
+        Ok(vec!["SER-1".into(), "SER-2".into()]),
+    );
     test.jira
-        .mock_get_transitions("SER-1", Ok(vec![new_transition("002", "reviewing1")]));
+        .mock_get_issue("SER-1", Ok(new_issue("SER-1", Some("resolved1"))));
     test.jira
-        .mock_transition_issue("SER-1", &new_transition_req("002"), Ok(()));
-
-    // mentioned JIRAs should go to in-progress but not "pending review"
+        .mock_get_transitions("SER-1", Ok(vec![new_transition("006", "released1")]));
     test.jira
-        .mock_get_transitions("CLI-9999", Ok(vec![new_transition("001", "progress1")]));
+        .mock_transition_issue("SER-1", &new_transition_req("006"), Ok(()));
+
+    // already released: no transition
     test.jira
-        .mock_transition_issue("CLI-9999", &new_transition_req("001"), Ok(()));
+        .mock_get_issue("SER-2", Ok(new_issue("SER-2", Some("released1"))));
+
+    test.jira.mock_find_issues_with_fix_version(
+        "CLI",
+        "1.2.3",
+        Err(anyhow::anyhow!("no such version")),
+    );
+
+    let transitions =
+        jira::workflow::mark_released("1.2.3", &projects, &test.jira, &test.config).await;
+    assert_eq!(
+        vec![AppliedTransition {
+            key: "SER-1".into(),
+            from: Some("resolved1".into()),
+            to: "released1-inner".into(),
+        }],
+        transitions
+    );
+}
+
+#[tokio::test]
+async fn test_mark_released_no_released_states() {
+    let test = new_test();
+    let projects = vec!["SER".to_string()];
+
+    // no jira expectations
+
+    let transitions =
+        jira::workflow::mark_released("1.2.3", &projects, &test.jira, &test.config).await;
+    assert!(transitions.is_empty());
+}
 
-    jira::workflow::submit_for_review(&pr, &vec![commit], &projects, &test.jira, &test.config)
-        .await;
+#[tokio::test]
+async fn test_assign_milestone_version() {
+    let test = new_test();
+    let projects = vec!["SER".to_string(), "CLI".to_string()];
+    let commit = new_commit(
+        "Fix [SER-1][SER-2] I fixed it. And also relates to [CLI-9999]",
+        "aabbccddee",
+    );
+
+    test.jira.mock_assign_fix_version("SER-1", "1.2.3", Ok(()));
+    test.jira.mock_assign_fix_version("SER-2", "1.2.3", Ok(()));
+
+    jira::workflow::assign_milestone_version(&[commit], &projects, &test.jira, "1.2.3").await;
+}
+
+#[tokio::test]
+async fn test_remove_milestone_version() {
+    let test = new_test();
+    let projects = vec!["SER".to_string(), "CLI".to_string()];
+    let commit = new_commit(
+        "Fix [SER-1][SER-2] I fixed it. And also relates to [CLI-9999]",
+        "aabbccddee",
+    );
+
+    test.jira.mock_remove_fix_version("SER-1", "1.2.3", Ok(()));
+    test.jira.mock_remove_fix_version("SER-2", "1.2.3", Ok(()));
+
+    jira::workflow::remove_milestone_version(&[commit], &projects, &test.jira, "1.2.3").await;
 }
 
 #[tokio::test]
@@ -173,15 +1151,34 @@ async fn test_resolve_issue_no_resolution() {
     test.jira
         .mock_get_issue("CLI-9999", Ok(new_issue("CLI-9999", Some("resolved2"))));
 
-    jira::workflow::resolve_issue(
+    let transitions = jira::workflow::resolve_issue(
         "master",
         None,
+        None,
         &vec![commit1, commit2],
         &projects,
         &test.jira,
         &test.config,
+        CommentVisibility::Configured,
+        None,
     )
     .await;
+
+    assert_eq!(
+        vec![
+            AppliedTransition {
+                key: "CLI-9999".into(),
+                from: None,
+                to: "resolved2-inner".into(),
+            },
+            AppliedTransition {
+                key: "SER-1".into(),
+                from: None,
+                to: "resolved1-inner".into(),
+            },
+        ],
+        transitions
+    );
 }
 
 #[tokio::test]
@@ -236,10 +1233,13 @@ async fn test_resolve_issue_with_resolution() {
     jira::workflow::resolve_issue(
         "release/99",
         Some("5.6.7"),
+        None,
         &vec![commit],
         &projects,
         &test.jira,
         &test.config,
+        CommentVisibility::Configured,
+        None,
     )
     .await;
 }
@@ -317,8 +1317,16 @@ async fn test_transition_issues_only_if_necessary() {
     test.jira
         .mock_transition_issue("CLI-9999", &new_transition_req("001"), Ok(()));
 
-    jira::workflow::submit_for_review(&pr, &vec![commit], &projects, &test.jira, &test.config)
-        .await;
+    jira::workflow::submit_for_review(
+        &pr,
+        &vec![commit],
+        &projects,
+        "release/",
+        &test.jira,
+        &test.config,
+        CommentVisibility::Configured,
+    )
+    .await;
 }
 
 #[tokio::test]
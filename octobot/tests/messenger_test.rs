@@ -2,23 +2,31 @@ mod mocks;
 
 use std::sync::Arc;
 
-use octobot_ops::messenger::Participants;
+use octobot_ops::messenger::{Participants, UnmappedUsers};
 use tempfile::{tempdir, TempDir};
 
 use mocks::mock_slack::MockSlack;
 use octobot_lib::config::Config;
 use octobot_lib::config_db::ConfigDatabase;
+use octobot_lib::correlation;
 use octobot_lib::github;
 use octobot_lib::slack::SlackRecipient;
 use octobot_ops::messenger;
-use octobot_ops::slack;
+use octobot_ops::slack::{self, SlackAttachmentBuilder};
 
 fn new_test() -> (Arc<Config>, TempDir) {
+    new_test_with(|_| ())
+}
+
+fn new_test_with<F: FnOnce(&mut Config)>(setup: F) -> (Arc<Config>, TempDir) {
     let temp_dir = tempdir().unwrap();
     let db_file = temp_dir.path().join("db.sqlite3");
     let db = ConfigDatabase::new(&db_file.to_string_lossy()).expect("create temp database");
 
-    let config = Arc::new(Config::new(db));
+    let mut config = Config::new(db);
+    setup(&mut config);
+
+    let config = Arc::new(config);
     config
         .users_write()
         .insert("the-owner", "the.owner")
@@ -56,6 +64,37 @@ fn test_sends_to_owner() {
     );
 }
 
+#[tokio::test]
+async fn test_sends_event_id() {
+    let (config, _temp) = new_test();
+
+    let slack = MockSlack::new(vec![slack::req(
+        SlackRecipient::user_mention("the.owner"),
+        "hello there",
+        &[],
+        None,
+        false,
+    )
+    .with_event_id(Some("delivery-1234".into()))]);
+    let messenger = messenger::new(config, slack.new_sender());
+
+    // messages sent while handling a webhook carry its delivery id
+    correlation::scope(Some("delivery-1234".into()), async {
+        messenger.send_to_all(
+            "hello there",
+            &[],
+            &github::User::new("the-owner"),
+            &github::User::new("the-sender"),
+            &github::Repo::new(),
+            Participants::new(),
+            "",
+            &Vec::<github::Commit>::new(),
+            vec!["some-user/some-repo/1".to_string()],
+        );
+    })
+    .await;
+}
+
 #[test]
 fn test_sends_to_mapped_usernames() {
     let (config, _temp) = new_test();
@@ -314,3 +353,340 @@ fn test_peace_and_quiet_teams() {
         vec!["some-user/some-repo/1".to_string()],
     );
 }
+
+#[test]
+fn test_sends_octobot_link_to_channel() {
+    let (config, _temp) = new_test_with(|c| {
+        c.main.base_url = Some("https://octobot.foo.com".into());
+        c.main.include_octobot_link = Some(true);
+    });
+
+    config
+        .repos_write()
+        .insert("the-owner/the-repo", "the-review-channel")
+        .unwrap();
+
+    // Note: only the channel message gets the link
+    let slack = MockSlack::new(vec![
+        slack::req(
+            SlackRecipient::by_name("the-review-channel"),
+            "hello there (<http://git.foo.com/the-owner/the-repo|the-owner/the-repo>)",
+            &[SlackAttachmentBuilder::new("")
+                .title("View in octobot")
+                .title_link("https://octobot.foo.com/#!/repos?needle=the-owner/the-repo")
+                .build()],
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::user_mention("the.owner"),
+            "hello there",
+            &[],
+            None,
+            false,
+        ),
+    ]);
+    let messenger = messenger::new(config, slack.new_sender());
+
+    messenger.send_to_all(
+        "hello there",
+        &[],
+        &github::User::new("the-owner"),
+        &github::User::new("the-sender"),
+        &github::Repo::parse("http://git.foo.com/the-owner/the-repo").unwrap(),
+        Participants::new(),
+        "",
+        &Vec::<github::Commit>::new(),
+        vec!["some-user/some-repo/1".to_string()],
+    );
+}
+
+#[test]
+fn test_no_octobot_link_without_flag() {
+    let (config, _temp) = new_test_with(|c| {
+        c.main.base_url = Some("https://octobot.foo.com".into());
+    });
+
+    config
+        .repos_write()
+        .insert("the-owner/the-repo", "the-review-channel")
+        .unwrap();
+
+    let slack = MockSlack::new(vec![slack::req(
+        SlackRecipient::by_name("the-review-channel"),
+        "hello there (<http://git.foo.com/the-owner/the-repo|the-owner/the-repo>)",
+        &[],
+        None,
+        false,
+    )]);
+    let messenger = messenger::new(config, slack.new_sender());
+
+    messenger.send_to_channel(
+        "hello there",
+        &[],
+        &github::Repo::parse("http://git.foo.com/the-owner/the-repo").unwrap(),
+        "",
+        &Vec::<github::Commit>::new(),
+        vec![],
+        false,
+    );
+}
+
+#[test]
+fn test_alerts_unmapped_users() {
+    let (config, _temp) = new_test_with(|c| {
+        c.slack.unmapped_users_channel = Some("octobot-ops".into());
+    });
+
+    let slack = MockSlack::new(vec![
+        slack::req(
+            SlackRecipient::user_mention("the.owner"),
+            "hello there",
+            &[],
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::by_name("octobot-ops"),
+            "GitHub users without a Slack mapping: unknown-user",
+            &[],
+            None,
+            false,
+        ),
+    ]);
+    let messenger = messenger::new(config, slack.new_sender())
+        .with_unmapped_users(Arc::new(UnmappedUsers::new()));
+
+    let mut participants = Participants::new();
+    participants.add_user(github::User::new("unknown-user"));
+    participants.add_user(github::User::new("some-app[bot]"));
+
+    messenger.send_to_all(
+        "hello there",
+        &[],
+        &github::User::new("the-owner"),
+        &github::User::new("the-sender"),
+        &github::Repo::new(),
+        participants,
+        "",
+        &Vec::<github::Commit>::new(),
+        vec![],
+    );
+}
+
+#[test]
+fn test_alerts_unmapped_users_rate_limited() {
+    let (config, _temp) = new_test_with(|c| {
+        c.slack.unmapped_users_channel = Some("octobot-ops".into());
+    });
+
+    // only the first unmapped user is alerted within the interval
+    let slack = MockSlack::new(vec![slack::req(
+        SlackRecipient::by_name("octobot-ops"),
+        "GitHub users without a Slack mapping: unknown-user1",
+        &[],
+        None,
+        false,
+    )]);
+    let messenger = messenger::new(config, slack.new_sender())
+        .with_unmapped_users(Arc::new(UnmappedUsers::new()));
+
+    for login in &["unknown-user1", "unknown-user2"] {
+        messenger.send_to_participants(
+            "hello there",
+            &[],
+            &github::User::new(login),
+            &github::User::new("the-sender"),
+            &github::Repo::new(),
+            Participants::new(),
+        );
+    }
+}
+
+#[test]
+fn test_alerts_unmapped_users_after_interval() {
+    let (config, _temp) = new_test_with(|c| {
+        c.slack.unmapped_users_channel = Some("octobot-ops".into());
+        c.slack.unmapped_users_alert_secs = Some(0);
+    });
+
+    let slack = MockSlack::new(vec![
+        slack::req(
+            SlackRecipient::by_name("octobot-ops"),
+            "GitHub users without a Slack mapping: unknown-user1",
+            &[],
+            None,
+            false,
+        ),
+        slack::req(
+            SlackRecipient::by_name("octobot-ops"),
+            "GitHub users without a Slack mapping: unknown-user2",
+            &[],
+            None,
+            false,
+        ),
+    ]);
+    let messenger = messenger::new(config, slack.new_sender())
+        .with_unmapped_users(Arc::new(UnmappedUsers::new()));
+
+    for login in &["unknown-user1", "unknown-user2"] {
+        messenger.send_to_participants(
+            "hello there",
+            &[],
+            &github::User::new(login),
+            &github::User::new("the-sender"),
+            &github::Repo::new(),
+            Participants::new(),
+        );
+    }
+}
+
+#[test]
+fn test_unmapped_users_not_alerted_without_channel() {
+    let (config, _temp) = new_test();
+
+    let slack = MockSlack::new(vec![]);
+    let messenger = messenger::new(config, slack.new_sender())
+        .with_unmapped_users(Arc::new(UnmappedUsers::new()));
+
+    messenger.send_to_participants(
+        "hello there",
+        &[],
+        &github::User::new("unknown-user"),
+        &github::User::new("the-sender"),
+        &github::Repo::new(),
+        Participants::new(),
+    );
+}
+
+#[test]
+fn test_suppressed_sends_nothing() {
+    let (config, _temp) = new_test_with(|c| {
+        c.slack.unmapped_users_channel = Some("octobot-ops".into());
+    });
+
+    let slack = MockSlack::new(vec![]);
+    let messenger = messenger::new(config, slack.new_sender())
+        .with_unmapped_users(Arc::new(UnmappedUsers::new()))
+        .with_suppressed(true);
+
+    let mut participants = Participants::new();
+    participants.add_user(github::User::new("unknown-user"));
+
+    messenger.send_to_all(
+        "hello there",
+        &[],
+        &github::User::new("the-owner"),
+        &github::User::new("the-sender"),
+        &github::Repo::parse("http://git.foo.com/some-org/some-repo").unwrap(),
+        participants,
+        "",
+        &Vec::<github::Commit>::new(),
+        vec!["some-user/some-repo/1".to_string()],
+    );
+}
+
+#[test]
+fn test_sanitizes_channel_mentions() {
+    let (config, _temp) = new_test_with(|c| {
+        c.slack.sanitize_mentions = true;
+    });
+
+    config
+        .repos_write()
+        .insert("the-owner/the-repo", "the-review-channel")
+        .unwrap();
+
+    let slack = MockSlack::new(vec![slack::req(
+        SlackRecipient::by_name("the-review-channel"),
+        "Comment on PR (<http://git.foo.com/the-owner/the-repo|the-owner/the-repo>)",
+        &[
+            SlackAttachmentBuilder::new("@\u{200B}channel please look at this")
+                .title("the.sender said:")
+                .build(),
+        ],
+        None,
+        false,
+    )]);
+    let messenger = messenger::new(config, slack.new_sender());
+
+    messenger.send_to_channel(
+        "Comment on PR",
+        &[SlackAttachmentBuilder::new("@channel please look at this")
+            .title("the.sender said:")
+            .build()],
+        &github::Repo::parse("http://git.foo.com/the-owner/the-repo").unwrap(),
+        "",
+        &Vec::<github::Commit>::new(),
+        vec![],
+        false,
+    );
+}
+
+#[test]
+fn test_mentions_allowed_channel_not_sanitized() {
+    let (config, _temp) = new_test_with(|c| {
+        c.slack.sanitize_mentions = true;
+        c.slack.mentions_allowed_channels = vec!["the-review-channel".into()];
+    });
+
+    config
+        .repos_write()
+        .insert("the-owner/the-repo", "the-review-channel")
+        .unwrap();
+
+    let slack = MockSlack::new(vec![slack::req(
+        SlackRecipient::by_name("the-review-channel"),
+        "Comment on PR (<http://git.foo.com/the-owner/the-repo|the-owner/the-repo>)",
+        &[SlackAttachmentBuilder::new("@channel please look at this").build()],
+        None,
+        false,
+    )]);
+    let messenger = messenger::new(config, slack.new_sender());
+
+    messenger.send_to_channel(
+        "Comment on PR",
+        &[SlackAttachmentBuilder::new("@channel please look at this").build()],
+        &github::Repo::parse("http://git.foo.com/the-owner/the-repo").unwrap(),
+        "",
+        &Vec::<github::Commit>::new(),
+        vec![],
+        false,
+    );
+}
+
+#[test]
+fn test_alerts_installation_change() {
+    let (config, _temp) = new_test_with(|c| {
+        c.slack.installation_alerts_channel = Some("octobot-ops".into());
+    });
+
+    let slack = MockSlack::new(vec![slack::req(
+        SlackRecipient::by_name("octobot-ops"),
+        "Repositories added to the GitHub App installation by the-admin: some-org/new-repo, some-org/other-repo",
+        &[],
+        None,
+        false,
+    )]);
+    let messenger = messenger::new(config, slack.new_sender());
+
+    messenger.send_installation_change(
+        &github::User::new("the-admin"),
+        &["some-org/new-repo".into(), "some-org/other-repo".into()],
+        &[],
+    );
+}
+
+#[test]
+fn test_installation_change_not_alerted_without_channel() {
+    let (config, _temp) = new_test();
+
+    let slack = MockSlack::new(vec![]);
+    let messenger = messenger::new(config, slack.new_sender());
+
+    messenger.send_installation_change(
+        &github::User::new("the-admin"),
+        &["some-org/new-repo".into()],
+        &["some-org/old-repo".into()],
+    );
+}
@@ -15,7 +15,10 @@ pub struct MockGithub {
     create_pr_calls: Mutex<Vec<MockCall<PullRequest>>>,
     get_pr_labels_calls: Mutex<Vec<MockCall<Vec<Label>>>>,
     add_pr_labels_calls: Mutex<Vec<MockCall<()>>>,
+    remove_pr_label_calls: Mutex<Vec<MockCall<()>>>,
     get_pr_commits_calls: Mutex<Vec<MockCall<Vec<Commit>>>>,
+    get_pr_files_calls: Mutex<Vec<MockCall<Vec<PullRequestFile>>>>,
+    get_compare_files_calls: Mutex<Vec<MockCall<Vec<PullRequestFile>>>>,
     get_pr_reviews_calls: Mutex<Vec<MockCall<Vec<Review>>>>,
     assign_pr_calls: Mutex<Vec<MockCall<()>>>,
     request_review_calls: Mutex<Vec<MockCall<()>>>,
@@ -26,7 +29,8 @@ pub struct MockGithub {
     get_timeline_calls: Mutex<Vec<MockCall<Vec<TimelineEvent>>>>,
     get_suites_calls: Mutex<Vec<MockCall<Vec<CheckSuite>>>>,
     get_check_run_calls: Mutex<Vec<MockCall<CheckRun>>>,
-    create_check_run_calls: Mutex<Vec<MockCall<u32>>>,
+    get_check_runs_calls: Mutex<Vec<MockCall<Vec<CheckRun>>>>,
+    create_check_run_calls: Mutex<Vec<MockCall<u64>>>,
     update_check_run_calls: Mutex<Vec<MockCall<()>>>,
     get_team_members_calls: Mutex<Vec<MockCall<Vec<User>>>>,
     get_webhook_deliveries_calls: Mutex<Vec<MockCall<Vec<WebhookDelivery>>>>,
@@ -60,7 +64,10 @@ impl MockGithub {
             create_pr_calls: Mutex::new(vec![]),
             get_pr_labels_calls: Mutex::new(vec![]),
             add_pr_labels_calls: Mutex::new(vec![]),
+            remove_pr_label_calls: Mutex::new(vec![]),
             get_pr_commits_calls: Mutex::new(vec![]),
+            get_pr_files_calls: Mutex::new(vec![]),
+            get_compare_files_calls: Mutex::new(vec![]),
             get_pr_reviews_calls: Mutex::new(vec![]),
             assign_pr_calls: Mutex::new(vec![]),
             request_review_calls: Mutex::new(vec![]),
@@ -71,6 +78,7 @@ impl MockGithub {
             get_timeline_calls: Mutex::new(vec![]),
             get_suites_calls: Mutex::new(vec![]),
             get_check_run_calls: Mutex::new(vec![]),
+            get_check_runs_calls: Mutex::new(vec![]),
             create_check_run_calls: Mutex::new(vec![]),
             update_check_run_calls: Mutex::new(vec![]),
             get_team_members_calls: Mutex::new(vec![]),
@@ -108,6 +116,26 @@ impl Drop for MockGithub {
                 "Unmet add_pull_request_labels calls: {:?}",
                 *self.add_pr_labels_calls.lock().unwrap()
             );
+            assert!(
+                self.remove_pr_label_calls.lock().unwrap().is_empty(),
+                "Unmet remove_pull_request_label calls: {:?}",
+                *self.remove_pr_label_calls.lock().unwrap()
+            );
+            assert!(
+                self.get_pr_commits_calls.lock().unwrap().is_empty(),
+                "Unmet get_pull_request_commits calls: {:?}",
+                *self.get_pr_commits_calls.lock().unwrap()
+            );
+            assert!(
+                self.get_pr_files_calls.lock().unwrap().is_empty(),
+                "Unmet get_pull_request_files calls: {:?}",
+                *self.get_pr_files_calls.lock().unwrap()
+            );
+            assert!(
+                self.get_compare_files_calls.lock().unwrap().is_empty(),
+                "Unmet get_compare_files calls: {:?}",
+                *self.get_compare_files_calls.lock().unwrap()
+            );
             assert!(
                 self.assign_pr_calls.lock().unwrap().len() == 0,
                 "Unmet assign_pull_request calls: {:?}",
@@ -143,6 +171,16 @@ impl Drop for MockGithub {
                 "Unmet get_timeline calls: {:?}",
                 *self.get_timeline_calls.lock().unwrap()
             );
+            assert!(
+                self.get_check_runs_calls.lock().unwrap().len() == 0,
+                "Unmet get_check_runs calls: {:?}",
+                *self.get_check_runs_calls.lock().unwrap()
+            );
+            assert!(
+                self.update_check_run_calls.lock().unwrap().len() == 0,
+                "Unmet update_check_run calls: {:?}",
+                *self.update_check_run_calls.lock().unwrap()
+            );
             assert!(
                 self.get_webhook_deliveries_calls.lock().unwrap().len() == 0,
                 "Unmet get_webhook_deliveries calls: {:?}",
@@ -289,6 +327,27 @@ impl Session for MockGithub {
         call.ret
     }
 
+    async fn remove_pull_request_label(
+        &self,
+        owner: &str,
+        repo: &str,
+        number: u32,
+        label: &str,
+    ) -> Result<()> {
+        let mut calls = self.remove_pr_label_calls.lock().unwrap();
+        assert!(
+            !calls.is_empty(),
+            "Unexpected call to remove_pull_request_label"
+        );
+        let call = calls.remove(0);
+        assert_eq!(call.args[0], owner);
+        assert_eq!(call.args[1], repo);
+        assert_eq!(call.args[2], number.to_string());
+        assert_eq!(call.args[3], label);
+
+        call.ret
+    }
+
     async fn get_pull_request_commits(
         &self,
         owner: &str,
@@ -308,6 +367,43 @@ impl Session for MockGithub {
         call.ret
     }
 
+    async fn get_pull_request_files(
+        &self,
+        owner: &str,
+        repo: &str,
+        number: u32,
+    ) -> Result<Vec<PullRequestFile>> {
+        let mut calls = self.get_pr_files_calls.lock().unwrap();
+        assert!(
+            !calls.is_empty(),
+            "Unexpected call to get_pull_request_files"
+        );
+        let call = calls.remove(0);
+        assert_eq!(call.args[0], owner);
+        assert_eq!(call.args[1], repo);
+        assert_eq!(call.args[2], number.to_string());
+
+        call.ret
+    }
+
+    async fn get_compare_files(
+        &self,
+        owner: &str,
+        repo: &str,
+        base: &str,
+        head: &str,
+    ) -> Result<Vec<PullRequestFile>> {
+        let mut calls = self.get_compare_files_calls.lock().unwrap();
+        assert!(!calls.is_empty(), "Unexpected call to get_compare_files");
+        let call = calls.remove(0);
+        assert_eq!(call.args[0], owner);
+        assert_eq!(call.args[1], repo);
+        assert_eq!(call.args[2], base);
+        assert_eq!(call.args[3], head);
+
+        call.ret
+    }
+
     async fn get_pull_request_reviews(
         &self,
         owner: &str,
@@ -455,7 +551,7 @@ impl Session for MockGithub {
         call.ret
     }
 
-    async fn get_check_run(&self, pr: &PullRequest, id: u32) -> Result<CheckRun> {
+    async fn get_check_run(&self, pr: &PullRequest, id: u64) -> Result<CheckRun> {
         let mut calls = self.get_check_run_calls.lock().unwrap();
         assert!(calls.len() > 0, "Unexpected call to get_check_run");
         let call = calls.remove(0);
@@ -464,7 +560,23 @@ impl Session for MockGithub {
         call.ret
     }
 
-    async fn create_check_run(&self, pr: &PullRequest, run: &CheckRun) -> Result<u32> {
+    async fn get_check_runs(
+        &self,
+        pr: &PullRequest,
+        commit_sha: &str,
+        name: &str,
+    ) -> Result<Vec<CheckRun>> {
+        let mut calls = self.get_check_runs_calls.lock().unwrap();
+        assert!(calls.len() > 0, "Unexpected call to get_check_runs");
+        let call = calls.remove(0);
+        assert_eq!(call.args[0], pr.number().to_string());
+        assert_eq!(call.args[1], commit_sha);
+        assert_eq!(call.args[2], name);
+
+        call.ret
+    }
+
+    async fn create_check_run(&self, pr: &PullRequest, run: &CheckRun) -> Result<u64> {
         let mut calls = self.create_check_run_calls.lock().unwrap();
         assert!(calls.len() > 0, "Unexpected call to create_check_run");
         let call = calls.remove(0);
@@ -477,7 +589,7 @@ impl Session for MockGithub {
     async fn update_check_run(
         &self,
         pr: &PullRequest,
-        check_run_id: u32,
+        check_run_id: u64,
         run: &CheckRun,
     ) -> Result<()> {
         let mut calls = self.update_check_run_calls.lock().unwrap();
@@ -525,6 +637,10 @@ impl Session for MockGithub {
 
         Ok(())
     }
+
+    async fn ping(&self) -> Result<()> {
+        Ok(())
+    }
 }
 
 impl MockGithub {
@@ -609,6 +725,23 @@ impl MockGithub {
         ));
     }
 
+    pub fn mock_remove_pull_request_label(
+        &self,
+        owner: &str,
+        repo: &str,
+        number: u32,
+        label: &str,
+        ret: Result<()>,
+    ) {
+        self.remove_pr_label_calls
+            .lock()
+            .unwrap()
+            .push(MockCall::new(
+                ret,
+                vec![owner, repo, &number.to_string(), label],
+            ));
+    }
+
     pub fn mock_get_pull_request_commits(
         &self,
         owner: &str,
@@ -622,6 +755,33 @@ impl MockGithub {
             .push(MockCall::new(ret, vec![owner, repo, &number.to_string()]));
     }
 
+    pub fn mock_get_pull_request_files(
+        &self,
+        owner: &str,
+        repo: &str,
+        number: u32,
+        ret: Result<Vec<PullRequestFile>>,
+    ) {
+        self.get_pr_files_calls
+            .lock()
+            .unwrap()
+            .push(MockCall::new(ret, vec![owner, repo, &number.to_string()]));
+    }
+
+    pub fn mock_get_compare_files(
+        &self,
+        owner: &str,
+        repo: &str,
+        base: &str,
+        head: &str,
+        ret: Result<Vec<PullRequestFile>>,
+    ) {
+        self.get_compare_files_calls
+            .lock()
+            .unwrap()
+            .push(MockCall::new(ret, vec![owner, repo, base, head]));
+    }
+
     pub fn mock_get_pull_request_reviews(
         &self,
         owner: &str,
@@ -738,7 +898,23 @@ impl MockGithub {
             .push(MockCall::new(ret, vec![owner, repo, &number.to_string()]));
     }
 
-    pub fn mock_create_check_run(&self, pr: &PullRequest, run: &CheckRun, ret: Result<u32>) {
+    pub fn mock_get_check_runs(
+        &self,
+        pr: &PullRequest,
+        commit_sha: &str,
+        name: &str,
+        ret: Result<Vec<CheckRun>>,
+    ) {
+        self.get_check_runs_calls
+            .lock()
+            .unwrap()
+            .push(MockCall::new(
+                ret,
+                vec![&pr.number.to_string(), commit_sha, name],
+            ));
+    }
+
+    pub fn mock_create_check_run(&self, pr: &PullRequest, run: &CheckRun, ret: Result<u64>) {
         self.create_check_run_calls
             .lock()
             .unwrap()
@@ -748,6 +924,26 @@ impl MockGithub {
             ));
     }
 
+    pub fn mock_update_check_run(
+        &self,
+        pr: &PullRequest,
+        check_run_id: u64,
+        run: &CheckRun,
+        ret: Result<()>,
+    ) {
+        self.update_check_run_calls
+            .lock()
+            .unwrap()
+            .push(MockCall::new(
+                ret,
+                vec![
+                    &pr.number.to_string(),
+                    &check_run_id.to_string(),
+                    &format_check_run(run),
+                ],
+            ));
+    }
+
     pub fn mock_get_team_members(&self, repo: &Repo, team_id: u32, ret: Result<Vec<User>>) {
         self.get_team_members_calls
             .lock()
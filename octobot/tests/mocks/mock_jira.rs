@@ -3,22 +3,30 @@ use std::sync::Mutex;
 use std::thread;
 
 use octobot_lib::errors::*;
-use octobot_lib::jira::api::{JiraVersionPosition, Session};
+use octobot_lib::jira::api::{CommentVisibility, JiraVersionPosition, Session};
 use octobot_lib::jira::*;
 use octobot_lib::version;
+use serde_json::Value;
 
 pub struct MockJira {
     get_issue_calls: Mutex<Vec<MockCall<Issue>>>,
     get_transitions_calls: Mutex<Vec<MockCall<Vec<Transition>>>>,
+    assign_issue_calls: Mutex<Vec<MockCall<()>>>,
     transition_issue_calls: Mutex<Vec<MockCall<()>>>,
     comment_issue_calls: Mutex<Vec<MockCall<()>>>,
+    create_remote_link_calls: Mutex<Vec<MockCall<()>>>,
+    get_issue_property_calls: Mutex<Vec<MockCall<Option<Value>>>>,
+    set_issue_property_calls: Mutex<Vec<MockCall<()>>>,
     add_version_calls: Mutex<Vec<MockCall<Version>>>,
     get_versions_calls: Mutex<Vec<MockCall<Vec<Version>>>>,
     assign_fix_version_calls: Mutex<Vec<MockCall<()>>>,
+    remove_fix_version_calls: Mutex<Vec<MockCall<()>>>,
     reorder_version_calls: Mutex<Vec<MockCall<()>>>,
     add_pending_version_calls: Mutex<Vec<MockCall<()>>>,
     remove_pending_versions_calls: Mutex<Vec<MockCall<()>>>,
     find_pending_versions_calls: Mutex<Vec<MockCall<HashMap<String, Vec<version::Version>>>>>,
+    find_issues_with_fix_version_calls: Mutex<Vec<MockCall<Vec<String>>>>,
+    ping_calls: Mutex<Vec<MockCall<()>>>,
 }
 
 #[derive(Debug)]
@@ -41,15 +49,22 @@ impl MockJira {
         MockJira {
             get_issue_calls: Mutex::new(vec![]),
             get_transitions_calls: Mutex::new(vec![]),
+            assign_issue_calls: Mutex::new(vec![]),
             transition_issue_calls: Mutex::new(vec![]),
             comment_issue_calls: Mutex::new(vec![]),
+            create_remote_link_calls: Mutex::new(vec![]),
+            get_issue_property_calls: Mutex::new(vec![]),
+            set_issue_property_calls: Mutex::new(vec![]),
             add_version_calls: Mutex::new(vec![]),
             get_versions_calls: Mutex::new(vec![]),
             assign_fix_version_calls: Mutex::new(vec![]),
+            remove_fix_version_calls: Mutex::new(vec![]),
             reorder_version_calls: Mutex::new(vec![]),
             add_pending_version_calls: Mutex::new(vec![]),
             remove_pending_versions_calls: Mutex::new(vec![]),
             find_pending_versions_calls: Mutex::new(vec![]),
+            find_issues_with_fix_version_calls: Mutex::new(vec![]),
+            ping_calls: Mutex::new(vec![]),
         }
     }
 }
@@ -67,6 +82,11 @@ impl Drop for MockJira {
                 "Unmet get_transitions calls: {:?}",
                 *self.get_transitions_calls.lock().unwrap()
             );
+            assert!(
+                self.assign_issue_calls.lock().unwrap().is_empty(),
+                "Unmet assign_issue calls: {:?}",
+                *self.assign_issue_calls.lock().unwrap()
+            );
             assert!(
                 self.transition_issue_calls.lock().unwrap().len() == 0,
                 "Unmet transition_issue calls: {:?}",
@@ -77,6 +97,26 @@ impl Drop for MockJira {
                 "Unmet comment_issue calls: {:?}",
                 *self.comment_issue_calls.lock().unwrap()
             );
+            assert!(
+                self.create_remote_link_calls.lock().unwrap().is_empty(),
+                "Unmet create_remote_link calls: {:?}",
+                *self.create_remote_link_calls.lock().unwrap()
+            );
+            assert!(
+                self.ping_calls.lock().unwrap().is_empty(),
+                "Unmet ping calls: {:?}",
+                *self.ping_calls.lock().unwrap()
+            );
+            assert!(
+                self.get_issue_property_calls.lock().unwrap().is_empty(),
+                "Unmet get_issue_property calls: {:?}",
+                *self.get_issue_property_calls.lock().unwrap()
+            );
+            assert!(
+                self.set_issue_property_calls.lock().unwrap().is_empty(),
+                "Unmet set_issue_property calls: {:?}",
+                *self.set_issue_property_calls.lock().unwrap()
+            );
             assert!(
                 self.add_version_calls.lock().unwrap().len() == 0,
                 "Unmet add_version calls: {:?}",
@@ -92,6 +132,11 @@ impl Drop for MockJira {
                 "Unmet asign_fix_version calls: {:?}",
                 *self.assign_fix_version_calls.lock().unwrap()
             );
+            assert!(
+                self.remove_fix_version_calls.lock().unwrap().len() == 0,
+                "Unmet remove_fix_version calls: {:?}",
+                *self.remove_fix_version_calls.lock().unwrap()
+            );
             assert!(
                 self.reorder_version_calls.lock().unwrap().len() == 0,
                 "Unmet reorder_version calls: {:?}",
@@ -112,6 +157,14 @@ impl Drop for MockJira {
                 "Unmet find_pending_versions calls: {:?}",
                 *self.find_pending_versions_calls.lock().unwrap()
             );
+            assert!(
+                self.find_issues_with_fix_version_calls
+                    .lock()
+                    .unwrap()
+                    .is_empty(),
+                "Unmet find_issues_with_fix_version calls: {:?}",
+                *self.find_issues_with_fix_version_calls.lock().unwrap()
+            );
         }
     }
 }
@@ -126,6 +179,16 @@ impl Session for MockJira {
 
         call.ret
     }
+    async fn assign_issue(&self, key: &str, jira_username: &str) -> Result<()> {
+        let mut calls = self.assign_issue_calls.lock().unwrap();
+        assert!(!calls.is_empty(), "Unexpected call to assign_issue {}", key);
+        let call = calls.remove(0);
+        assert_eq!(call.args[0], key);
+        assert_eq!(call.args[1], jira_username);
+
+        call.ret
+    }
+
     async fn get_transitions(&self, key: &str) -> Result<Vec<Transition>> {
         let mut calls = self.get_transitions_calls.lock().unwrap();
         assert!(calls.len() > 0, "Unexpected call to get_transitions");
@@ -145,12 +208,57 @@ impl Session for MockJira {
         call.ret
     }
 
-    async fn comment_issue(&self, key: &str, comment: &str) -> Result<()> {
+    async fn comment_issue(
+        &self,
+        key: &str,
+        comment: &str,
+        visibility: CommentVisibility,
+    ) -> Result<()> {
         let mut calls = self.comment_issue_calls.lock().unwrap();
         assert!(calls.len() > 0, "Unexpected call to comment_issue");
         let call = calls.remove(0);
         assert_eq!(call.args[0], key);
         assert_eq!(call.args[1], comment);
+        assert_eq!(call.args[2], format!("{:?}", visibility));
+
+        call.ret
+    }
+
+    async fn create_remote_link(
+        &self,
+        key: &str,
+        url: &str,
+        title: &str,
+        icon_url: Option<&str>,
+    ) -> Result<()> {
+        let mut calls = self.create_remote_link_calls.lock().unwrap();
+        assert!(!calls.is_empty(), "Unexpected call to create_remote_link");
+        let call = calls.remove(0);
+        assert_eq!(call.args[0], key);
+        assert_eq!(call.args[1], url);
+        assert_eq!(call.args[2], title);
+        assert_eq!(call.args[3], format!("{:?}", icon_url));
+
+        call.ret
+    }
+
+    async fn get_issue_property(&self, key: &str, property: &str) -> Result<Option<Value>> {
+        let mut calls = self.get_issue_property_calls.lock().unwrap();
+        assert!(!calls.is_empty(), "Unexpected call to get_issue_property");
+        let call = calls.remove(0);
+        assert_eq!(call.args[0], key);
+        assert_eq!(call.args[1], property);
+
+        call.ret
+    }
+
+    async fn set_issue_property(&self, key: &str, property: &str, value: &Value) -> Result<()> {
+        let mut calls = self.set_issue_property_calls.lock().unwrap();
+        assert!(!calls.is_empty(), "Unexpected call to set_issue_property");
+        let call = calls.remove(0);
+        assert_eq!(call.args[0], key);
+        assert_eq!(call.args[1], property);
+        assert_eq!(call.args[2], value.to_string());
 
         call.ret
     }
@@ -184,6 +292,16 @@ impl Session for MockJira {
         call.ret
     }
 
+    async fn remove_fix_version(&self, key: &str, version: &str) -> Result<()> {
+        let mut calls = self.remove_fix_version_calls.lock().unwrap();
+        assert!(calls.len() > 0, "Unexpected call to remove_fix_version");
+        let call = calls.remove(0);
+        assert_eq!(call.args[0], key);
+        assert_eq!(call.args[1], version);
+
+        call.ret
+    }
+
     async fn reorder_version(
         &self,
         version: &Version,
@@ -236,6 +354,25 @@ impl Session for MockJira {
 
         call.ret
     }
+
+    async fn find_issues_with_fix_version(&self, proj: &str, version: &str) -> Result<Vec<String>> {
+        let mut calls = self.find_issues_with_fix_version_calls.lock().unwrap();
+        assert!(
+            !calls.is_empty(),
+            "Unexpected call to find_issues_with_fix_version"
+        );
+        let call = calls.remove(0);
+        assert_eq!(call.args[0], proj);
+        assert_eq!(call.args[1], version);
+
+        call.ret
+    }
+
+    async fn ping(&self) -> Result<()> {
+        let mut calls = self.ping_calls.lock().unwrap();
+        assert!(!calls.is_empty(), "Unexpected call to ping");
+        calls.remove(0).ret
+    }
 }
 
 impl MockJira {
@@ -246,6 +383,13 @@ impl MockJira {
             .push(MockCall::new(ret, vec![key]));
     }
 
+    pub fn mock_assign_issue(&self, key: &str, jira_username: &str, ret: Result<()>) {
+        self.assign_issue_calls
+            .lock()
+            .unwrap()
+            .push(MockCall::new(ret, vec![key, jira_username]));
+    }
+
     pub fn mock_get_transitions(&self, key: &str, ret: Result<Vec<Transition>>) {
         self.get_transitions_calls
             .lock()
@@ -261,10 +405,57 @@ impl MockJira {
     }
 
     pub fn mock_comment_issue(&self, key: &str, comment: &str, ret: Result<()>) {
-        self.comment_issue_calls
+        self.mock_comment_issue_with_visibility(key, comment, CommentVisibility::Configured, ret);
+    }
+
+    pub fn mock_comment_issue_with_visibility(
+        &self,
+        key: &str,
+        comment: &str,
+        visibility: CommentVisibility,
+        ret: Result<()>,
+    ) {
+        self.comment_issue_calls.lock().unwrap().push(MockCall::new(
+            ret,
+            vec![key, comment, &format!("{:?}", visibility)],
+        ));
+    }
+
+    pub fn mock_create_remote_link(
+        &self,
+        key: &str,
+        url: &str,
+        title: &str,
+        icon_url: Option<&str>,
+        ret: Result<()>,
+    ) {
+        self.create_remote_link_calls
             .lock()
             .unwrap()
-            .push(MockCall::new(ret, vec![key, comment]));
+            .push(MockCall::new(
+                ret,
+                vec![key, url, title, &format!("{:?}", icon_url)],
+            ));
+    }
+
+    pub fn mock_get_issue_property(&self, key: &str, property: &str, ret: Result<Option<Value>>) {
+        self.get_issue_property_calls
+            .lock()
+            .unwrap()
+            .push(MockCall::new(ret, vec![key, property]));
+    }
+
+    pub fn mock_set_issue_property(
+        &self,
+        key: &str,
+        property: &str,
+        value: Value,
+        ret: Result<()>,
+    ) {
+        self.set_issue_property_calls
+            .lock()
+            .unwrap()
+            .push(MockCall::new(ret, vec![key, property, &value.to_string()]));
     }
 
     pub fn mock_add_version(&self, proj: &str, version: &str, ret: Result<Version>) {
@@ -288,6 +479,13 @@ impl MockJira {
             .push(MockCall::new(ret, vec![key, version]));
     }
 
+    pub fn mock_remove_fix_version(&self, key: &str, version: &str, ret: Result<()>) {
+        self.remove_fix_version_calls
+            .lock()
+            .unwrap()
+            .push(MockCall::new(ret, vec![key, version]));
+    }
+
     pub fn mock_reorder_version(
         &self,
         version: &Version,
@@ -332,4 +530,23 @@ impl MockJira {
             .unwrap()
             .push(MockCall::new(ret, vec![proj]));
     }
+
+    pub fn mock_find_issues_with_fix_version(
+        &self,
+        proj: &str,
+        version: &str,
+        ret: Result<Vec<String>>,
+    ) {
+        self.find_issues_with_fix_version_calls
+            .lock()
+            .unwrap()
+            .push(MockCall::new(ret, vec![proj, version]));
+    }
+
+    pub fn mock_ping(&self, ret: Result<()>) {
+        self.ping_calls
+            .lock()
+            .unwrap()
+            .push(MockCall::new(ret, vec![]));
+    }
 }
@@ -0,0 +1,49 @@
+use std::sync::{Mutex, Once};
+
+use log::{Log, Metadata, Record};
+
+// Captures log messages so tests can assert on what would otherwise only be logged.
+struct MockLogger {
+    messages: Mutex<Vec<String>>,
+}
+
+static LOGGER: MockLogger = MockLogger {
+    messages: Mutex::new(Vec::new()),
+};
+static INIT: Once = Once::new();
+
+impl Log for MockLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::Level::Info
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.messages
+                .lock()
+                .unwrap()
+                .push(format!("{}", record.args()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+pub fn init() {
+    INIT.call_once(|| {
+        log::set_logger(&LOGGER).expect("failed to set mock logger");
+        log::set_max_level(log::LevelFilter::Info);
+    });
+}
+
+// Messages logged so far (by any test in this binary) that contain `text`
+pub fn messages_containing(text: &str) -> Vec<String> {
+    LOGGER
+        .messages
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|m| m.contains(text))
+        .cloned()
+        .collect()
+}
@@ -3,6 +3,8 @@ pub mod mock_github;
 #[allow(dead_code, unused_variables)]
 pub mod mock_jira;
 #[allow(dead_code, unused_variables)]
+pub mod mock_logger;
+#[allow(dead_code, unused_variables)]
 pub mod mock_slack;
 #[allow(dead_code, unused_variables)]
 pub mod mock_worker;
@@ -7,7 +7,8 @@ use tempfile::{tempdir, TempDir};
 
 use git_helper::temp_git::TempGit;
 use mocks::mock_github::MockGithub;
-use octobot_lib::config::Config;
+use mocks::mock_jira::MockJira;
+use octobot_lib::config::{Config, JiraConfig};
 use octobot_lib::config_db::ConfigDatabase;
 use octobot_lib::github;
 use octobot_lib::repos;
@@ -27,11 +28,17 @@ struct PRMergeTest {
 }
 
 fn new_test() -> (PRMergeTest, TempDir) {
+    new_test_with(|_| ())
+}
+
+fn new_test_with<F: FnOnce(&mut Config)>(setup: F) -> (PRMergeTest, TempDir) {
     let temp_dir = tempdir().unwrap();
     let db_file = temp_dir.path().join("db.sqlite3");
     let db = ConfigDatabase::new(&db_file.to_string_lossy()).expect("create temp database");
 
-    let config = Arc::new(Config::new(db));
+    let mut config = Config::new(db);
+    setup(&mut config);
+    let config = Arc::new(config);
     config
         .users_write()
         .insert("the-pr-owner", "the.pr.owner")
@@ -124,13 +131,21 @@ async fn test_pr_merge_basic() {
     );
 
     let repo = github::Repo::parse("http://the-github-host/the-owner/the-repo").unwrap();
-    let req = pr_merge::req(&repo, &pr, "release/1.0", "release/", &[]);
+    let req = pr_merge::req(
+        &repo,
+        &pr,
+        "release/1.0",
+        "release/",
+        &[],
+        repos::MergeStrategy::Merge,
+    );
     pr_merge::merge_pull_request(
         &test.git.git,
         &test.github,
         &req,
         test.config,
         test.slack.new_sender(),
+        None,
     )
     .await;
 
@@ -150,6 +165,113 @@ async fn test_pr_merge_basic() {
     );
 }
 
+fn backport_jira_config() -> JiraConfig {
+    JiraConfig {
+        host: "the-jira-host".into(),
+        username: "the-jira-user".into(),
+        password: "the-jira-pass".into(),
+        progress_states: None,
+        review_states: None,
+        resolved_states: None,
+        fixed_resolutions: None,
+        fix_versions_field: None,
+        pending_versions_field: None,
+        restrict_comment_visibility_to_role: None,
+        login_suffix: None,
+        queued_states: None,
+        skip_review_branches: None,
+        include_commit_count: None,
+        include_deploy_environment: None,
+        warn_unconfigured_projects: None,
+        sync_milestone_versions: None,
+        transition_audit_channel: None,
+        dedupe_comments: None,
+        pr_summary_comment: None,
+        backport_comments: Some(true),
+        workflow_failure_comments: None,
+        release_branch_review_transitions: None,
+        released_states: None,
+        max_quoted_title_length: None,
+        skip_revert_reviews: None,
+        use_remote_links: None,
+        auto_merge_comments: None,
+        review_retries: None,
+        review_retry_delay_secs: None,
+        issue_details_in_notifications: None,
+        sync_assignee: None,
+        warn_shared_keys: None,
+        dry_run: None,
+        extra_headers: None,
+    }
+}
+
+#[tokio::test]
+async fn test_pr_merge_comments_on_jiras() {
+    let (test, _temp_dir) = new_test_with(|c| c.jira = Some(backport_jira_config()));
+    let jira = MockJira::new();
+
+    // setup a release branch
+    test.git.run_git(&["push", "origin", "master:release/1.0"]);
+
+    // make a new commit on master
+    test.git.run_git(&["checkout", "master"]);
+    test.git
+        .add_repo_file("file.txt", "contents1", "Fix [SER-1] I made a change");
+    let commit1 = test.git.git.current_commit().unwrap();
+
+    let mut pr = github::PullRequest::new();
+    pr.number = 123;
+    pr.merged = Some(true);
+    pr.merge_commit_sha = Some(commit1.clone());
+    pr.head = github::BranchRef::new("my-feature-branch");
+    pr.base = github::BranchRef::new("master");
+    let pr = pr;
+
+    let mut new_pr = github::PullRequest::new();
+    new_pr.number = 456;
+    new_pr.html_url = "http://the-backport-pr".into();
+    let new_pr = new_pr;
+
+    test.github.mock_create_pull_request(
+        "the-owner",
+        "the-repo",
+        "master->1.0: Fix [SER-1] I made a change",
+        &format!("(cherry-picked from {}, PR #123)", commit1),
+        "my-feature-branch-1.0",
+        "release/1.0",
+        Ok(new_pr),
+    );
+
+    jira.mock_comment_issue(
+        "SER-1",
+        "Backport to branch release/1.0: http://the-backport-pr",
+        Ok(()),
+    );
+
+    let mut commit = github::Commit::new();
+    commit.sha = commit1.clone();
+    commit.commit.message = "Fix [SER-1] I made a change".into();
+
+    let repo = github::Repo::parse("http://the-github-host/the-owner/the-repo").unwrap();
+    let req = pr_merge::req(
+        &repo,
+        &pr,
+        "release/1.0",
+        "release/",
+        &[commit],
+        repos::MergeStrategy::Merge,
+    );
+    pr_merge::merge_pull_request(
+        &test.git.git,
+        &test.github,
+        &req,
+        test.config,
+        test.slack.new_sender(),
+        Some(&jira),
+    )
+    .await;
+}
+
 #[tokio::test]
 async fn test_pr_merge_author_is_assignee() {
     let (test, _temp_dir) = new_test();
@@ -196,13 +318,21 @@ async fn test_pr_merge_author_is_assignee() {
     );
 
     let repo = github::Repo::parse("http://the-github-host/the-owner/the-repo").unwrap();
-    let req = pr_merge::req(&repo, &pr, "release/1.0", "release/", &[]);
+    let req = pr_merge::req(
+        &repo,
+        &pr,
+        "release/1.0",
+        "release/",
+        &[],
+        repos::MergeStrategy::Merge,
+    );
     pr_merge::merge_pull_request(
         &test.git.git,
         &test.github,
         &req,
         test.config,
         test.slack.new_sender(),
+        None,
     )
     .await;
 }
@@ -295,13 +425,21 @@ if (true)    {
     );
 
     let repo = github::Repo::parse("http://the-github-host/the-owner/the-repo").unwrap();
-    let req = pr_merge::req(&repo, &pr, "release/1.0", "release/", &[]);
+    let req = pr_merge::req(
+        &repo,
+        &pr,
+        "release/1.0",
+        "release/",
+        &[],
+        repos::MergeStrategy::Merge,
+    );
     pr_merge::merge_pull_request(
         &test.git.git,
         &test.github,
         &req,
         test.config,
         test.slack.new_sender(),
+        None,
     )
     .await;
 
@@ -399,13 +537,21 @@ if (true) {
     );
 
     let repo = github::Repo::parse("http://the-github-host/the-owner/the-repo").unwrap();
-    let req = pr_merge::req(&repo, &pr, "release/1.0", "release/", &[]);
+    let req = pr_merge::req(
+        &repo,
+        &pr,
+        "release/1.0",
+        "release/",
+        &[],
+        repos::MergeStrategy::Merge,
+    );
     pr_merge::merge_pull_request(
         &test.git.git,
         &test.github,
         &req,
         test.config,
         test.slack.new_sender(),
+        None,
     )
     .await;
 
@@ -483,13 +629,21 @@ async fn test_pr_merge_conventional_commit() {
     );
 
     let repo = github::Repo::parse("http://the-github-host/the-owner/the-repo").unwrap();
-    let req = pr_merge::req(&repo, &pr, "release/1.0", "release/", &[]);
+    let req = pr_merge::req(
+        &repo,
+        &pr,
+        "release/1.0",
+        "release/",
+        &[],
+        repos::MergeStrategy::Merge,
+    );
     pr_merge::merge_pull_request(
         &test.git.git,
         &test.github,
         &req,
         test.config,
         test.slack.new_sender(),
+        None,
     )
     .await;
 
@@ -588,13 +742,379 @@ async fn test_pr_merge_backport_failure() {
     ]);
 
     let repo = github::Repo::parse("http://the-github-host/the-owner/the-repo").unwrap();
-    let req = pr_merge::req(&repo, &pr, "release/1.0", "release/", &[]);
+    let req = pr_merge::req(
+        &repo,
+        &pr,
+        "release/1.0",
+        "release/",
+        &[],
+        repos::MergeStrategy::Merge,
+    );
     pr_merge::merge_pull_request(
         &test.git.git,
         &test.github,
         &req,
         test.config,
         test.slack.new_sender(),
+        None,
     )
     .await;
 }
+
+// Makes a two-commit feature branch squash-merged into master as PR #123
+fn setup_feature_pr(test: &PRMergeTest) -> (github::PullRequest, Vec<github::Commit>) {
+    // setup a release branch
+    test.git.run_git(&["push", "origin", "master:release/1.0"]);
+
+    test.git
+        .run_git(&["checkout", "-b", "my-feature-branch", "master"]);
+    let mut commits = vec![];
+    for (file, msg) in &[("file1.txt", "Add file1"), ("file2.txt", "Add file2")] {
+        test.git.add_repo_file(file, "contents", msg);
+        let mut commit = github::Commit::new();
+        commit.sha = test.git.git.current_commit().unwrap();
+        commit.commit.message = msg.to_string();
+        commits.push(commit);
+    }
+    test.git.run_git(&["push", "origin", "my-feature-branch"]);
+
+    test.git.run_git(&["checkout", "master"]);
+    test.git
+        .run_git(&["merge", "--squash", "my-feature-branch"]);
+    test.git
+        .run_git(&["commit", "-m", "The feature (#123)\n\nSome details"]);
+    test.git.run_git(&["push", "origin", "master"]);
+
+    let mut pr = github::PullRequest::new();
+    pr.number = 123;
+    pr.merged = Some(true);
+    pr.merge_commit_sha = Some(test.git.git.current_commit().unwrap());
+    pr.head = github::BranchRef::new("my-feature-branch");
+    pr.base = github::BranchRef::new("master");
+    pr.user = github::User::new("the-pr-author");
+
+    (pr, commits)
+}
+
+async fn backport_feature_pr(
+    test: PRMergeTest,
+    pr: &github::PullRequest,
+    commits: &[github::Commit],
+    strategy: repos::MergeStrategy,
+    expected_body: &str,
+) -> TempGit {
+    let mut new_pr = github::PullRequest::new();
+    new_pr.number = 456;
+
+    test.github.mock_create_pull_request(
+        "the-owner",
+        "the-repo",
+        "master->1.0: The feature",
+        expected_body,
+        "my-feature-branch-1.0",
+        "release/1.0",
+        Ok(new_pr),
+    );
+    test.github.mock_assign_pull_request(
+        "the-owner",
+        "the-repo",
+        456,
+        vec!["the-pr-author".into()],
+        Ok(()),
+    );
+
+    let repo = github::Repo::parse("http://the-github-host/the-owner/the-repo").unwrap();
+    let req = pr_merge::req(&repo, pr, "release/1.0", "release/", commits, strategy);
+    pr_merge::merge_pull_request(
+        &test.git.git,
+        &test.github,
+        &req,
+        test.config,
+        test.slack.new_sender(),
+        None,
+    )
+    .await;
+
+    test.git
+}
+
+fn backported_subjects(git: &TempGit) -> Vec<String> {
+    git.run_git(&[
+        "log",
+        "--reverse",
+        "--format=%s",
+        "origin/release/1.0..origin/my-feature-branch-1.0",
+    ])
+    .lines()
+    .map(|l| l.to_string())
+    .collect()
+}
+
+#[tokio::test]
+async fn test_pr_merge_merge_strategy() {
+    let (test, _temp_dir) = new_test();
+    let (pr, commits) = setup_feature_pr(&test);
+    let merge_commit = pr.merge_commit_sha.clone().unwrap();
+
+    let git = backport_feature_pr(
+        test,
+        &pr,
+        &commits,
+        repos::MergeStrategy::Merge,
+        &format!(
+            "Some details\n\n(cherry-picked from {}, PR #123)",
+            merge_commit
+        ),
+    )
+    .await;
+
+    assert_eq!(vec!["master->1.0: The feature"], backported_subjects(&git));
+    assert_eq!("contents", git.read_file("file1.txt"));
+    assert_eq!("contents", git.read_file("file2.txt"));
+}
+
+#[tokio::test]
+async fn test_pr_merge_squash_strategy() {
+    let (test, _temp_dir) = new_test();
+    let (pr, commits) = setup_feature_pr(&test);
+    let merge_commit = pr.merge_commit_sha.clone().unwrap();
+
+    let body = format!(
+        "* Add file1\n* Add file2\n\n(cherry-picked from {}, PR #123)",
+        merge_commit
+    );
+    let git = backport_feature_pr(test, &pr, &commits, repos::MergeStrategy::Squash, &body).await;
+
+    assert_eq!(vec!["master->1.0: The feature"], backported_subjects(&git));
+    let (_, message_body) = git
+        .git
+        .get_commit_desc("origin/my-feature-branch-1.0")
+        .unwrap();
+    assert_eq!(body, message_body);
+    assert_eq!("contents", git.read_file("file1.txt"));
+    assert_eq!("contents", git.read_file("file2.txt"));
+}
+
+#[tokio::test]
+async fn test_pr_merge_rebase_strategy() {
+    let (test, _temp_dir) = new_test();
+    let (pr, commits) = setup_feature_pr(&test);
+    let merge_commit = pr.merge_commit_sha.clone().unwrap();
+
+    let git = backport_feature_pr(
+        test,
+        &pr,
+        &commits,
+        repos::MergeStrategy::Rebase,
+        &format!(
+            "Some details\n\n(cherry-picked from {}, PR #123)",
+            merge_commit
+        ),
+    )
+    .await;
+
+    assert_eq!(vec!["Add file1", "Add file2"], backported_subjects(&git));
+    assert_eq!("contents", git.read_file("file1.txt"));
+    assert_eq!("contents", git.read_file("file2.txt"));
+}
+
+#[tokio::test]
+async fn test_pr_merge_rebase_strategy_deleted_branch() {
+    let (test, _temp_dir) = new_test();
+    let (pr, commits) = setup_feature_pr(&test);
+    let merge_commit = pr.merge_commit_sha.clone().unwrap();
+
+    // GitHub keeps the PR's commits under its pull ref once the head branch is deleted
+    test.git
+        .run_git(&["push", "origin", "my-feature-branch:refs/pull/123/head"]);
+    test.git
+        .run_git(&["push", "origin", "--delete", "my-feature-branch"]);
+    test.git.reclone();
+
+    let git = backport_feature_pr(
+        test,
+        &pr,
+        &commits,
+        repos::MergeStrategy::Rebase,
+        &format!(
+            "Some details\n\n(cherry-picked from {}, PR #123)",
+            merge_commit
+        ),
+    )
+    .await;
+
+    assert_eq!(vec!["Add file1", "Add file2"], backported_subjects(&git));
+    assert_eq!("contents", git.read_file("file1.txt"));
+    assert_eq!("contents", git.read_file("file2.txt"));
+}
+
+#[tokio::test]
+async fn test_pr_merge_rebase_strategy_skips_merges() {
+    let (test, _temp_dir) = new_test();
+    test.git.run_git(&["push", "origin", "master:release/1.0"]);
+
+    let mut commits = vec![];
+    let mut add_commit = |git: &TempGit| {
+        let mut commit = github::Commit::new();
+        commit.sha = git.git.current_commit().unwrap();
+        commit.commit.message = git.git.get_commit_desc("HEAD").unwrap().0;
+        commits.push(commit);
+    };
+
+    test.git
+        .run_git(&["checkout", "-b", "my-feature-branch", "master"]);
+    test.git.add_repo_file("file1.txt", "contents", "Add file1");
+    add_commit(&test.git);
+
+    // master moves on and gets merged into the PR branch
+    test.git.run_git(&["checkout", "master"]);
+    test.git
+        .add_repo_file("master.txt", "contents", "Add master file");
+    test.git.run_git(&["checkout", "my-feature-branch"]);
+    test.git
+        .run_git(&["merge", "--no-ff", "-m", "Merge master", "master"]);
+    add_commit(&test.git);
+
+    test.git.add_repo_file("file2.txt", "contents", "Add file2");
+    add_commit(&test.git);
+    test.git.run_git(&["push", "origin", "my-feature-branch"]);
+
+    test.git.run_git(&["checkout", "master"]);
+    test.git
+        .run_git(&["merge", "--squash", "my-feature-branch"]);
+    test.git
+        .run_git(&["commit", "-m", "The feature (#123)\n\nSome details"]);
+    test.git.run_git(&["push", "origin", "master"]);
+
+    let mut pr = github::PullRequest::new();
+    pr.number = 123;
+    pr.merged = Some(true);
+    pr.merge_commit_sha = Some(test.git.git.current_commit().unwrap());
+    pr.head = github::BranchRef::new("my-feature-branch");
+    pr.base = github::BranchRef::new("master");
+    pr.user = github::User::new("the-pr-author");
+
+    let git = backport_feature_pr(
+        test,
+        &pr,
+        &commits,
+        repos::MergeStrategy::Rebase,
+        &format!(
+            "Some details\n\n(cherry-picked from {}, PR #123)",
+            pr.merge_commit_sha.clone().unwrap()
+        ),
+    )
+    .await;
+
+    assert_eq!(vec!["Add file1", "Add file2"], backported_subjects(&git));
+    assert_eq!("contents", git.read_file("file2.txt"));
+    assert!(!git.repo_dir.join("master.txt").exists());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 3)]
+async fn test_pr_merge_concurrent_backports() {
+    let (test, _temp_dir) = new_test();
+    let branches = ["1.0", "2.0", "3.0"];
+
+    // setup release branches
+    for branch in &branches {
+        test.git
+            .run_git(&["push", "origin", &format!("master:release/{}", branch)]);
+    }
+
+    // make a new commit on master
+    test.git.run_git(&["checkout", "master"]);
+    test.git
+        .add_repo_file("file.txt", "contents1", "I made a change");
+    test.git.run_git(&["push", "origin", "master"]);
+    let commit1 = test.git.git.current_commit().unwrap();
+
+    let mut pr = github::PullRequest::new();
+    pr.number = 123;
+    pr.merged = Some(true);
+    pr.merge_commit_sha = Some(commit1.clone());
+    pr.head = github::BranchRef::new("my-feature-branch");
+    pr.base = github::BranchRef::new("master");
+    pr.user = github::User::new("the-pr-author");
+
+    // each backport label is its own request, run in its own clone as the dir pool hands out
+    let clones_dir = tempdir().unwrap();
+    let remote_dir = test.git.repo_dir.parent().unwrap().join("remote");
+    let repo = github::Repo::parse("http://the-github-host/the-owner/the-repo").unwrap();
+    let handles = branches
+        .iter()
+        .enumerate()
+        .map(|(i, branch)| {
+            let clone_dir = clones_dir.path().join((i + 1).to_string());
+            std::fs::create_dir(&clone_dir).unwrap();
+            let git = octobot_ops::git::Git::new("the-host", "the-token", &clone_dir);
+            git.run(&["clone", &remote_dir.to_string_lossy(), "."])
+                .unwrap();
+            git.run(&["config", "commit.gpgsign", "false"]).unwrap();
+            git.run(&["config", "user.name", test.git.user_name()])
+                .unwrap();
+            git.run(&["config", "user.email", test.git.user_email()])
+                .unwrap();
+
+            let github = MockGithub::new();
+            let mut new_pr = github::PullRequest::new();
+            new_pr.number = 456 + i as u32;
+            github.mock_create_pull_request(
+                "the-owner",
+                "the-repo",
+                &format!("master->{}: I made a change", branch),
+                &format!("(cherry-picked from {}, PR #123)", commit1),
+                &format!("my-feature-branch-{}", branch),
+                &format!("release/{}", branch),
+                Ok(new_pr),
+            );
+            github.mock_assign_pull_request(
+                "the-owner",
+                "the-repo",
+                456 + i as u32,
+                vec!["the-pr-author".into()],
+                Ok(()),
+            );
+
+            let req = pr_merge::req(
+                &repo,
+                &pr,
+                &format!("release/{}", branch),
+                "release/",
+                &[],
+                repos::MergeStrategy::Merge,
+            );
+            let config = test.config.clone();
+            let slack = test.slack.new_sender();
+            tokio::spawn(async move {
+                pr_merge::merge_pull_request(&git, &github, &req, config, slack, None).await;
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.await.unwrap();
+    }
+
+    // every backport landed on its own branch without picking up the others' work
+    test.git.run_git(&["fetch"]);
+    for branch in &branches {
+        let backport = format!("origin/my-feature-branch-{}", branch);
+        assert_eq!(
+            "master->".to_string() + branch + ": I made a change",
+            test.git
+                .run_git(&["log", "-1", "--format=%s", &backport])
+                .trim()
+        );
+        assert_eq!("", test.git.run_git(&["diff", "master", &backport]));
+        assert_eq!(
+            "1",
+            test.git
+                .run_git(&[
+                    "rev-list",
+                    "--count",
+                    &format!("origin/release/{}..{}", branch, backport)
+                ])
+                .trim()
+        );
+    }
+}
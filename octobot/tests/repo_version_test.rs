@@ -0,0 +1,355 @@
+mod git_helper;
+mod mocks;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use serde_json::json;
+use tempfile::{tempdir, TempDir};
+
+use git_helper::temp_git::TempGit;
+use mocks::mock_jira::MockJira;
+use mocks::mock_slack::MockSlack;
+use octobot_lib::config::{Config, JiraConfig};
+use octobot_lib::config_db::ConfigDatabase;
+use octobot_lib::errors::*;
+use octobot_lib::github;
+use octobot_lib::github::api::{GithubSession, GithubSessionFactory};
+use octobot_lib::jira::{Issue, IssueFields, Status, Transition, TransitionTo};
+use octobot_lib::metrics::Metrics;
+use octobot_lib::repos::{self, VersionScriptMode};
+use octobot_lib::slack::SlackRecipient;
+use octobot_ops::git_clone_manager::GitCloneManager;
+use octobot_ops::repo_version;
+use octobot_ops::slack::{self, SlackAttachmentBuilder};
+use octobot_ops::webhook_db::WebhookDatabase;
+use octobot_ops::worker::Runner;
+
+// Fails to create sessions, so version scripts always fail before cloning anything
+struct NoGithub;
+
+#[async_trait::async_trait]
+impl GithubSessionFactory for NoGithub {
+    async fn new_service_session(&self) -> Result<GithubSession> {
+        Err(anyhow!("no github"))
+    }
+
+    async fn new_session(&self, _owner: &str, _repo: &str) -> Result<GithubSession> {
+        Err(anyhow!("no github"))
+    }
+
+    async fn get_token_org(&self, _org: &str) -> Result<String> {
+        Err(anyhow!("no github"))
+    }
+
+    async fn get_token_repo(&self, _owner: &str, _repo: &str) -> Result<String> {
+        Err(anyhow!("no github"))
+    }
+
+    fn bot_name(&self) -> String {
+        "octobot".into()
+    }
+}
+
+// Hands out sessions without talking to github, for version scripts run in a prepared clone
+struct LocalGithub;
+
+#[async_trait::async_trait]
+impl GithubSessionFactory for LocalGithub {
+    async fn new_service_session(&self) -> Result<GithubSession> {
+        self.new_session("", "").await
+    }
+
+    async fn new_session(&self, _owner: &str, _repo: &str) -> Result<GithubSession> {
+        GithubSession::new(
+            "the-github-host",
+            "octobot",
+            "the-token",
+            None,
+            None,
+            &HashMap::new(),
+        )
+    }
+
+    async fn get_token_org(&self, _org: &str) -> Result<String> {
+        Ok("the-token".into())
+    }
+
+    async fn get_token_repo(&self, _owner: &str, _repo: &str) -> Result<String> {
+        Ok("the-token".into())
+    }
+
+    fn bot_name(&self) -> String {
+        "octobot".into()
+    }
+}
+
+struct RepoVersionTest {
+    jira: Arc<MockJira>,
+    slack: MockSlack,
+    runner: Arc<dyn Runner<repo_version::RepoVersionRequest>>,
+    temp_dir: TempDir,
+}
+
+fn new_test(version_script: &str, mode: VersionScriptMode) -> RepoVersionTest {
+    new_test_with_github(version_script, mode, Arc::new(NoGithub))
+}
+
+fn new_test_with_github(
+    version_script: &str,
+    mode: VersionScriptMode,
+    github_app: Arc<dyn GithubSessionFactory>,
+) -> RepoVersionTest {
+    let temp_dir = tempdir().unwrap();
+    let db_file = temp_dir.path().join("db.sqlite3");
+    let db = ConfigDatabase::new(&db_file.to_string_lossy()).expect("create temp database");
+
+    let mut config = Config::new(db);
+    config.main.clone_root_dir = temp_dir.path().join("repos").to_string_lossy().into();
+    let jira_config: JiraConfig = serde_json::from_value(json!({
+        "host": "the-host",
+        "username": "the-jira-user",
+        "password": "the-jira-pass",
+        "resolved_states": ["resolved1"],
+    }))
+    .unwrap();
+    config.jira = Some(jira_config);
+    let config = Arc::new(config);
+
+    config
+        .repos_write()
+        .insert_info(
+            &repos::RepoInfo::new("the-owner/the-repo", "the-reviews-channel")
+                .with_jira_config(
+                    repos::RepoJiraConfig::new("SER").with_version_script(version_script),
+                )
+                .with_version_script_mode(mode),
+        )
+        .expect("Failed to add the-owner/the-repo");
+
+    let jira = Arc::new(MockJira::new());
+    let slack = MockSlack::new(vec![]);
+    let clone_mgr = Arc::new(GitCloneManager::new(github_app.clone(), config.clone()));
+    let webhook_db =
+        WebhookDatabase::new(&temp_dir.path().join("webhook.sqlite3").to_string_lossy())
+            .expect("create temp webhook database");
+
+    let runner = repo_version::new_runner(
+        config,
+        github_app,
+        Some(jira.clone()),
+        Some(Arc::new(webhook_db)),
+        clone_mgr,
+        slack.new_sender(),
+        Metrics::new(),
+    );
+
+    RepoVersionTest {
+        jira,
+        slack,
+        runner,
+        temp_dir,
+    }
+}
+
+fn the_repo() -> github::Repo {
+    github::Repo::parse("http://the-github-host/the-owner/the-repo").unwrap()
+}
+
+fn new_req() -> repo_version::RepoVersionRequest {
+    let mut commit = github::PushCommit::new();
+    commit.message = "Fix [SER-1] I fixed it.".into();
+    commit.id = "aabbccddee".into();
+    commit.url = "http://the-commit/aabbccddee".into();
+
+    repo_version::req(&the_repo(), "master", "aabbccddee", &[commit])
+}
+
+fn expect_plain_resolve(test: &RepoVersionTest) {
+    test.jira.mock_comment_issue(
+        "SER-1",
+        "Merged into branch master: [aabbccd|http://the-commit/aabbccddee]\n\
+         {quote}Fix [SER-1] I fixed it.{quote}",
+        Ok(()),
+    );
+    test.jira.mock_get_issue(
+        "SER-1",
+        Ok(Issue {
+            key: "SER-1".into(),
+            fields: IssueFields {
+                status: Some(Status {
+                    name: "resolved1".into(),
+                }),
+                ..Default::default()
+            },
+        }),
+    );
+}
+
+// Commented, but the issue still needs resolving
+fn expect_resolve_transition(test: &RepoVersionTest, ret: Result<()>) {
+    test.jira.mock_comment_issue(
+        "SER-1",
+        "Merged into branch master: [aabbccd|http://the-commit/aabbccddee]\n\
+         {quote}Fix [SER-1] I fixed it.{quote}",
+        Ok(()),
+    );
+    test.jira.mock_get_issue(
+        "SER-1",
+        Ok(Issue {
+            key: "SER-1".into(),
+            fields: IssueFields {
+                status: Some(Status {
+                    name: "progress1".into(),
+                }),
+                ..Default::default()
+            },
+        }),
+    );
+
+    let transition = Transition {
+        id: "003".into(),
+        name: "resolved1".into(),
+        to: TransitionTo {
+            id: String::new(),
+            name: "resolved1".into(),
+        },
+        fields: None,
+    };
+    test.jira
+        .mock_get_transitions("SER-1", Ok(vec![transition.clone()]));
+    test.jira
+        .mock_transition_issue("SER-1", &transition.new_request(), ret);
+}
+
+fn expect_script_failure(test: &mut RepoVersionTest) {
+    let attach = SlackAttachmentBuilder::new("no github")
+        .title("./version.sh")
+        .color("danger")
+        .build();
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        "Error running version script for [SER] \
+         (<http://the-github-host/the-owner/the-repo|the-owner/the-repo>)",
+        &[attach],
+        None,
+        false,
+    )]);
+}
+
+#[tokio::test]
+async fn test_script_mode_without_script_resolves() {
+    let test = new_test("", VersionScriptMode::Script);
+
+    expect_plain_resolve(&test);
+
+    test.runner.handle(new_req()).await;
+}
+
+#[tokio::test]
+async fn test_script_mode_falls_back_to_resolve() {
+    let mut test = new_test("./version.sh", VersionScriptMode::Script);
+
+    expect_script_failure(&mut test);
+    expect_plain_resolve(&test);
+
+    test.runner.handle(new_req()).await;
+}
+
+#[tokio::test]
+async fn test_resolve_mode_skips_script() {
+    let test = new_test("./version.sh", VersionScriptMode::Resolve);
+
+    // no script failure: it never runs
+    expect_plain_resolve(&test);
+
+    test.runner.handle(new_req()).await;
+}
+
+#[tokio::test]
+async fn test_both_mode_resolves_then_runs_script() {
+    let mut test = new_test("./version.sh", VersionScriptMode::Both);
+
+    // resolved once up front, not again after the script fails
+    expect_plain_resolve(&test);
+    expect_script_failure(&mut test);
+
+    test.runner.handle(new_req()).await;
+}
+
+#[tokio::test]
+async fn test_same_commit_pushed_twice_resolves_once() {
+    let test = new_test("", VersionScriptMode::Resolve);
+
+    // only one comment and issue lookup: the second push of the commit is skipped
+    expect_plain_resolve(&test);
+
+    test.runner.handle(new_req()).await;
+    test.runner.handle(new_req()).await;
+}
+
+#[tokio::test]
+async fn test_failed_transition_resolved_again() {
+    let test = new_test("", VersionScriptMode::Resolve);
+
+    // the first transition fails, so the next push of the commit tries again
+    expect_resolve_transition(&test, Err(anyhow!("JIRA is down")));
+    expect_resolve_transition(&test, Ok(()));
+
+    test.runner.handle(new_req()).await;
+    test.runner.handle(new_req()).await;
+}
+
+#[tokio::test]
+async fn test_resolve_only_skips_script() {
+    let test = new_test("./version.sh", VersionScriptMode::Script);
+
+    // no script failure: it never runs
+    expect_plain_resolve(&test);
+
+    test.runner.handle(new_req().with_resolve_only(true)).await;
+}
+
+// Clones a repo with one commit to where the clone manager will look for the-owner/the-repo.
+// Returns the commit's hash.
+fn setup_clone(test: &RepoVersionTest, git: &TempGit) -> String {
+    let clone_dir = test
+        .temp_dir
+        .path()
+        .join("repos/the-github-host/the-owner/the-repo/1");
+    std::fs::create_dir_all(&clone_dir).unwrap();
+    git.run_git(&["clone", "../remote", &clone_dir.to_string_lossy()]);
+
+    git.git.current_commit().unwrap()
+}
+
+#[tokio::test]
+async fn test_empty_version_output_warns_without_resolving() {
+    let mut test = new_test_with_github(
+        "echo VERSION=; echo ENV=prod",
+        VersionScriptMode::Script,
+        Arc::new(LocalGithub),
+    );
+    let git = TempGit::new();
+    let commit_hash = setup_clone(&test, &git);
+
+    let attach = SlackAttachmentBuilder::new("Version script produced no version")
+        .title("echo VERSION=; echo ENV=prod")
+        .color("danger")
+        .build();
+    test.slack.expect(vec![slack::req(
+        SlackRecipient::by_name("the-reviews-channel"),
+        "Error running version script for [SER] \
+         (<http://the-github-host/the-owner/the-repo|the-owner/the-repo>)",
+        &[attach],
+        None,
+        false,
+    )]);
+
+    // no JIRA mocks: nothing is commented on without a version
+
+    let mut req = new_req();
+    req.commit_hash = commit_hash;
+    test.runner.handle(req).await;
+}
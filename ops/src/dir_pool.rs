@@ -3,6 +3,8 @@ use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
 pub struct DirPool {
     root_dir: PathBuf,
     available_dirs: Mutex<HashMap<String, AvailableDirs>>,
@@ -25,21 +27,45 @@ pub struct HeldDir {
     repo_root: PathBuf,
     dir: PathBuf,
     pool: Arc<DirPool>,
+    _permit: Option<OwnedSemaphorePermit>,
 }
 
 pub struct ArcDirPool {
     pool: Arc<DirPool>,
+    // bounds the number of directories held at once
+    slots: Option<Arc<Semaphore>>,
 }
 
 impl ArcDirPool {
     pub fn new(root_dir: &str) -> ArcDirPool {
+        ArcDirPool::new_with_limit(root_dir, None)
+    }
+
+    pub fn new_with_limit(root_dir: &str, max_held: Option<usize>) -> ArcDirPool {
         ArcDirPool {
             pool: Arc::new(DirPool::new(root_dir)),
+            slots: max_held
+                .filter(|max| *max > 0)
+                .map(|max| Arc::new(Semaphore::new(max))),
         }
     }
 
-    pub fn take_directory(&self, host: &str, owner: &str, repo: &str) -> HeldDir {
-        DirPool::take_directory(self.pool.clone(), host, owner, repo)
+    // Waits for a free slot if the pool is at its limit
+    pub async fn take_directory(&self, host: &str, owner: &str, repo: &str) -> HeldDir {
+        let permit = match self.slots {
+            Some(ref slots) => Some(
+                slots
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("dir pool semaphore closed"),
+            ),
+            None => None,
+        };
+
+        let mut held = DirPool::take_directory(self.pool.clone(), host, owner, repo);
+        held._permit = permit;
+        held
     }
 
     pub fn clean(&self, expiration: Duration) {
@@ -142,6 +168,7 @@ impl HeldDir {
             dir,
             repo_root,
             pool,
+            _permit: None,
         }
     }
 
@@ -179,27 +206,63 @@ mod tests {
         assert_eq!(3, dirs.get_entry().id);
     }
 
-    #[test]
-    fn test_dir_pool() {
+    #[tokio::test]
+    async fn test_dir_pool() {
         let dir_pool = ArcDirPool::new("<root>");
 
         {
-            let dir_a1 = dir_pool.take_directory("h1", "o1", "repo-A");
+            let dir_a1 = dir_pool.take_directory("h1", "o1", "repo-A").await;
             assert_eq!("<root>/h1/o1/repo-A/1", dir_a1.dir().to_string_lossy());
 
-            let dir_a2 = dir_pool.take_directory("h1", "o1", "repo-A");
+            let dir_a2 = dir_pool.take_directory("h1", "o1", "repo-A").await;
             assert_eq!("<root>/h1/o1/repo-A/2", dir_a2.dir().to_string_lossy());
 
             // test that different repos should have different counts
-            let dir_b1 = dir_pool.take_directory("h1", "o1", "repo-B");
+            let dir_b1 = dir_pool.take_directory("h1", "o1", "repo-B").await;
             assert_eq!("<root>/h1/o1/repo-B/1", dir_b1.dir().to_string_lossy());
         }
 
         // going out of scope should return it to the pool
-        let dir_a1_again = dir_pool.take_directory("h1", "o1", "repo-A");
+        let dir_a1_again = dir_pool.take_directory("h1", "o1", "repo-A").await;
         assert_eq!(
             "<root>/h1/o1/repo-A/1",
             dir_a1_again.dir().to_string_lossy()
         );
     }
+
+    #[tokio::test]
+    async fn test_dir_pool_limit() {
+        let dir_pool = Arc::new(ArcDirPool::new_with_limit("<root>", Some(2)));
+
+        let dir_a1 = dir_pool.take_directory("h1", "o1", "repo-A").await;
+        let dir_b1 = dir_pool.take_directory("h1", "o1", "repo-B").await;
+
+        // a third directory has to wait for one of the others to be returned
+        let pool = dir_pool.clone();
+        let waiting = tokio::spawn(async move { pool.take_directory("h1", "o1", "repo-A").await });
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+
+        drop(dir_b1);
+
+        let dir_a2 = tokio::time::timeout(Duration::from_secs(5), waiting)
+            .await
+            .expect("timed out waiting for directory")
+            .unwrap();
+        assert_eq!("<root>/h1/o1/repo-A/2", dir_a2.dir().to_string_lossy());
+
+        drop(dir_a1);
+    }
+
+    #[tokio::test]
+    async fn test_dir_pool_no_limit() {
+        let dir_pool = ArcDirPool::new_with_limit("<root>", Some(0));
+
+        let mut dirs = vec![];
+        for _ in 0..10 {
+            dirs.push(dir_pool.take_directory("h1", "o1", "repo-A").await);
+        }
+        assert_eq!("<root>/h1/o1/repo-A/10", dirs[9].dir().to_string_lossy());
+    }
 }
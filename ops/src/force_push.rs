@@ -6,6 +6,7 @@ use crate::diffs::DiffOfDiffs;
 use crate::git::Git;
 use crate::git_clone_manager::GitCloneManager;
 use crate::worker;
+use octobot_lib::correlation;
 use octobot_lib::errors::*;
 use octobot_lib::github;
 use octobot_lib::github::api::GithubSessionFactory;
@@ -168,6 +169,8 @@ pub struct ForcePushRequest {
     pub pull_request: github::PullRequest,
     pub before_hash: String,
     pub after_hash: String,
+    // id of the webhook delivery that queued this request, for log correlation
+    pub event_id: Option<String>,
 }
 
 struct Runner {
@@ -187,6 +190,13 @@ pub fn req(
         pull_request: pull_request.clone(),
         before_hash: before_hash.to_string(),
         after_hash: after_hash.to_string(),
+        event_id: None,
+    }
+}
+
+impl ForcePushRequest {
+    pub fn with_event_id(self, event_id: Option<String>) -> ForcePushRequest {
+        ForcePushRequest { event_id, ..self }
     }
 }
 
@@ -202,9 +212,8 @@ pub fn new_runner(
     })
 }
 
-#[async_trait::async_trait]
-impl worker::Runner<ForcePushRequest> for Runner {
-    async fn handle(&self, req: ForcePushRequest) {
+impl Runner {
+    async fn run(&self, req: ForcePushRequest) {
         let _scoped_count = metrics::scoped_inc(&self.metrics.current_force_push_count);
         let _scoped_timer = self.metrics.force_push_duration.start_timer();
 
@@ -246,3 +255,10 @@ impl worker::Runner<ForcePushRequest> for Runner {
         }
     }
 }
+
+#[async_trait::async_trait]
+impl worker::Runner<ForcePushRequest> for Runner {
+    async fn handle(&self, req: ForcePushRequest) {
+        correlation::scope(req.event_id.clone(), self.run(req)).await;
+    }
+}
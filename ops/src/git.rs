@@ -89,6 +89,15 @@ impl Git {
         }
     }
 
+    // fetch from origin, pruning branches and tags that no longer exist there so that renamed or
+    // deleted branches don't leave stale refs behind.
+    pub fn fetch_prune(&self) -> Result<()> {
+        self.run(&["fetch", "--prune", "origin"])?;
+        // prune local tags deleted from remotes: important to avoid stale/bad version tags
+        self.run(&["fetch", "--prune", "origin", "+refs/tags/*:refs/tags/*"])?;
+        Ok(())
+    }
+
     pub fn clean(&self) -> Result<()> {
         self.run(&["reset", "--hard"])?;
         self.run(&["clean", "-fdx"])?;
@@ -117,6 +126,12 @@ impl Git {
         Ok((title, body.join("\n")))
     }
 
+    pub fn is_merge_commit(&self, commit_hash: &str) -> Result<bool> {
+        // first entry is the commit itself, followed by its parents
+        let output = self.run(&["rev-list", "--parents", "-n", "1", commit_hash])?;
+        Ok(output.split_whitespace().count() > 2)
+    }
+
     pub fn get_commit_author(&self, commit_hash: &str) -> Result<(String, String)> {
         let message = self.run(&["log", "-1", "--pretty=%an\n%ae", commit_hash])?;
 
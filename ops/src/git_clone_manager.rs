@@ -27,18 +27,38 @@ impl GitCloneManager {
         let clone_root_dir = config.main.clone_root_dir.to_string();
 
         GitCloneManager {
-            dir_pool: ArcDirPool::new(&clone_root_dir),
+            dir_pool: ArcDirPool::new_with_limit(
+                &clone_root_dir,
+                config.main.max_concurrent_clones,
+            ),
             github_app: github_app.clone(),
         }
     }
 
     pub async fn clone(&self, owner: &str, repo: &str) -> Result<HeldDir> {
+        self.clone_with(owner, repo, false).await
+    }
+
+    // Like `clone`, but discards any previously cloned copy first
+    pub async fn fresh_clone(&self, owner: &str, repo: &str) -> Result<HeldDir> {
+        self.clone_with(owner, repo, true).await
+    }
+
+    async fn clone_with(&self, owner: &str, repo: &str, fresh: bool) -> Result<HeldDir> {
         let session = self.github_app.new_session(owner, repo).await?;
 
         let held_clone_dir = self
             .dir_pool
-            .take_directory(session.github_host(), owner, repo);
-        self.clone_repo(&session, owner, repo, held_clone_dir.dir())?;
+            .take_directory(session.github_host(), owner, repo)
+            .await;
+
+        let clone_dir = held_clone_dir.dir();
+        if fresh && clone_dir.exists() {
+            info!("Removing existing clone in {:?}", clone_dir);
+            fs::remove_dir_all(clone_dir)
+                .map_err(|e| anyhow!("Error removing clone directory '{:?}': {}", clone_dir, e))?;
+        }
+        self.clone_repo(&session, owner, repo, clone_dir)?;
 
         Ok(held_clone_dir)
     }
@@ -71,8 +91,7 @@ impl GitCloneManager {
                 repo,
                 clone_dir
             );
-            // prune local tags deleted from remotes: important to avoid stale/bad version tags
-            git.run(&["fetch", "--prune", "origin", "+refs/tags/*:refs/tags/*"])?;
+            git.fetch_prune()?;
         } else {
             info!(
                 "Cloning https://{}/{}/{} into {:?}",
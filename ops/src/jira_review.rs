@@ -0,0 +1,214 @@
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use log::{error, warn};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::messenger;
+use crate::slack::SlackRequest;
+use crate::webhook_db::WebhookDatabase;
+use crate::worker;
+use octobot_lib::config::Config;
+use octobot_lib::correlation;
+use octobot_lib::github;
+use octobot_lib::jira;
+use octobot_lib::jira::api::CommentVisibility;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct JiraReviewRequest {
+    pub pull_request: github::PullRequest,
+    pub commits: Vec<github::Commit>,
+    pub projects: Vec<String>,
+    pub release_branch_prefix: String,
+    pub visibility: CommentVisibility,
+    // id of the webhook delivery that queued this request, for log correlation
+    pub event_id: Option<String>,
+    // drop slack notifications, as for the webhook that queued this request
+    #[serde(default)]
+    pub suppress_notifications: bool,
+    // submissions already attempted
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+struct Runner {
+    config: Arc<Config>,
+    jira_session: Option<Arc<dyn jira::api::Session>>,
+    slack: Arc<dyn worker::Worker<SlackRequest>>,
+    webhook_db: Arc<WebhookDatabase>,
+}
+
+pub fn req(
+    pull_request: &github::PullRequest,
+    commits: &[github::Commit],
+    projects: &[String],
+    release_branch_prefix: &str,
+    visibility: CommentVisibility,
+) -> JiraReviewRequest {
+    JiraReviewRequest {
+        pull_request: pull_request.clone(),
+        commits: commits.into(),
+        projects: projects.into(),
+        release_branch_prefix: release_branch_prefix.to_string(),
+        visibility,
+        event_id: None,
+        suppress_notifications: false,
+        attempts: 0,
+    }
+}
+
+impl JiraReviewRequest {
+    pub fn with_event_id(self, event_id: Option<String>) -> JiraReviewRequest {
+        JiraReviewRequest { event_id, ..self }
+    }
+
+    pub fn with_suppressed_notifications(self, suppress_notifications: bool) -> JiraReviewRequest {
+        JiraReviewRequest {
+            suppress_notifications,
+            ..self
+        }
+    }
+}
+
+pub fn new_runner(
+    config: Arc<Config>,
+    jira_session: Option<Arc<dyn jira::api::Session>>,
+    slack: Arc<dyn worker::Worker<SlackRequest>>,
+    webhook_db: Arc<WebhookDatabase>,
+) -> Arc<dyn worker::Runner<JiraReviewRequest>> {
+    Arc::new(Runner {
+        config,
+        jira_session,
+        slack,
+        webhook_db,
+    })
+}
+
+// Sends queued review retries that are due back to the worker
+pub fn retry_pending(
+    webhook_db: &WebhookDatabase,
+    worker: &dyn worker::Worker<JiraReviewRequest>,
+    now: SystemTime,
+) {
+    let due = match webhook_db.take_due_jira_reviews(now) {
+        Ok(d) => d,
+        Err(e) => {
+            error!("Error looking up JIRA reviews to retry: {}", e);
+            return;
+        }
+    };
+
+    for request in due {
+        match serde_json::from_str::<JiraReviewRequest>(&request) {
+            Ok(req) => worker.send(req),
+            Err(e) => error!("Dropping invalid queued JIRA review: {}", e),
+        }
+    }
+}
+
+impl Runner {
+    async fn run(&self, mut req: JiraReviewRequest) {
+        let (jira_session, jira_config) = match (&self.jira_session, &self.config.jira) {
+            (Some(s), Some(c)) => (s, c),
+            _ => return,
+        };
+
+        // a retry repeats the whole submission, so make sure JIRAs commented on by an earlier
+        // attempt don't get the same comment again
+        let mut jira_config = jira_config.clone();
+        jira_config.dedupe_comments = Some(true);
+
+        let errors = match jira_session.ping().await {
+            Err(e) => vec![format!("JIRA is unavailable: {}", e)],
+            Ok(()) => {
+                let submission = jira::workflow::submit_for_review(
+                    &req.pull_request,
+                    &req.commits,
+                    &req.projects,
+                    &req.release_branch_prefix,
+                    jira_session.as_ref(),
+                    &jira_config,
+                    req.visibility,
+                )
+                .await;
+
+                messenger::new(self.config.clone(), self.slack.clone())
+                    .with_suppressed(req.suppress_notifications)
+                    .send_transition_audit(
+                        &submission.applied,
+                        &format!("review of PR#{}", req.pull_request.number),
+                    );
+
+                submission.errors
+            }
+        };
+
+        if errors.is_empty() {
+            self.sync_assignee(&req, jira_session.as_ref()).await;
+            return;
+        }
+
+        req.attempts += 1;
+        let retries = jira_config.review_retries();
+        if req.attempts > retries {
+            error!(
+                "Giving up submitting PR #{} for review after {} retries: {}",
+                req.pull_request.number,
+                retries,
+                errors.join("; ")
+            );
+            return;
+        }
+
+        let delay = jira_config.review_retry_delay() * 2u32.saturating_pow(req.attempts - 1);
+        warn!(
+            "Error submitting PR #{} for review, retry {} of {} in {:?}: {}",
+            req.pull_request.number,
+            req.attempts,
+            retries,
+            delay,
+            errors.join("; ")
+        );
+
+        let queued = serde_json::to_string(&req)
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .and_then(|r| {
+                self.webhook_db
+                    .queue_jira_review(&r, SystemTime::now() + delay)
+            });
+        if let Err(e) = queued {
+            error!(
+                "Error queueing review retry for PR #{}: {}",
+                req.pull_request.number, e
+            );
+        }
+    }
+
+    async fn sync_assignee(&self, req: &JiraReviewRequest, jira_session: &dyn jira::api::Session) {
+        let jira_config = match self.config.jira {
+            Some(ref c) if c.sync_assignee() => c,
+            _ => return,
+        };
+
+        let assignee = self.config.users().jira_username(
+            req.pull_request.user.login(),
+            jira_config.login_suffix.as_deref(),
+        );
+        if let Some(assignee) = assignee {
+            jira::workflow::assign_fixed_issues(
+                &req.commits,
+                &req.projects,
+                &assignee,
+                jira_session,
+            )
+            .await;
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl worker::Runner<JiraReviewRequest> for Runner {
+    async fn handle(&self, req: JiraReviewRequest) {
+        correlation::scope(req.event_id.clone(), self.run(req)).await;
+    }
+}
@@ -7,6 +7,7 @@ mod docker;
 pub mod force_push;
 pub mod git;
 pub mod git_clone_manager;
+pub mod jira_review;
 pub mod messenger;
 pub mod migrate_slack;
 pub mod pr_merge;
@@ -14,6 +15,10 @@ pub mod repo_version;
 pub mod slack;
 mod slack_db;
 mod slack_db_migrations;
+mod slack_retry_db;
+mod slack_retry_db_migrations;
+pub mod stale_prs;
+mod token_bucket;
 pub mod util;
 pub mod webhook_db;
 mod webhook_db_migrations;
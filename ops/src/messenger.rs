@@ -1,17 +1,74 @@
 use log::info;
-use std::collections::BTreeMap;
-use std::sync::Arc;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use crate::slack::{self, SlackAttachment, SlackRequest};
+use crate::slack::{
+    self, SlackAttachment, SlackAttachmentBuilder, SlackBlock, SlackBlockBuilder, SlackRequest,
+};
 use crate::util;
 use crate::worker::Worker;
 use octobot_lib::config::Config;
+use octobot_lib::correlation;
 use octobot_lib::github;
+use octobot_lib::jira::workflow::AppliedTransition;
 use octobot_lib::slack::SlackRecipient;
 
 pub struct Messenger {
     config: Arc<Config>,
     slack: Arc<dyn Worker<SlackRequest>>,
+    channel_override: Option<String>,
+    unmapped_users: Option<Arc<UnmappedUsers>>,
+    suppressed: bool,
+    use_blocks: bool,
+}
+
+// Collects github logins which have no slack mapping so that they can be reported,
+// at most once per alert interval.
+pub struct UnmappedUsers {
+    state: Mutex<UnmappedUsersState>,
+}
+
+struct UnmappedUsersState {
+    pending: BTreeSet<String>,
+    last_alert: Option<Instant>,
+}
+
+impl UnmappedUsers {
+    pub fn new() -> UnmappedUsers {
+        UnmappedUsers {
+            state: Mutex::new(UnmappedUsersState {
+                pending: BTreeSet::new(),
+                last_alert: None,
+            }),
+        }
+    }
+
+    pub fn record(&self, login: &str) {
+        self.state.lock().unwrap().pending.insert(login.to_string());
+    }
+
+    // Returns (and clears) the pending logins if there are any and an alert is due
+    pub fn take_due(&self, interval: Duration) -> Option<Vec<String>> {
+        let mut state = self.state.lock().unwrap();
+        if state.pending.is_empty() {
+            return None;
+        }
+        let now = Instant::now();
+        if let Some(last) = state.last_alert {
+            if now.duration_since(last) < interval {
+                return None;
+            }
+        }
+        state.last_alert = Some(now);
+        Some(std::mem::take(&mut state.pending).into_iter().collect())
+    }
+}
+
+impl Default for UnmappedUsers {
+    fn default() -> UnmappedUsers {
+        UnmappedUsers::new()
+    }
 }
 
 #[derive(PartialEq, Clone, Debug)]
@@ -54,6 +111,10 @@ impl Participants {
         self.users.remove(login);
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+
     pub fn add_user(&mut self, user: github::User) {
         self.add(Participant {
             user,
@@ -91,10 +152,59 @@ pub fn new(config: Arc<Config>, slack: Arc<dyn Worker<SlackRequest>>) -> Messeng
     Messenger {
         slack: slack.clone(),
         config,
+        channel_override: None,
+        unmapped_users: None,
+        suppressed: false,
+        use_blocks: false,
     }
 }
 
 impl Messenger {
+    // Returns a messenger that sends channel messages to the given channel instead of the
+    // repo's configured channels.
+    pub fn with_channel_override(&self, channel: &str) -> Messenger {
+        Messenger {
+            config: self.config.clone(),
+            slack: self.slack.clone(),
+            channel_override: Some(channel.to_string()),
+            unmapped_users: self.unmapped_users.clone(),
+            suppressed: self.suppressed,
+            use_blocks: self.use_blocks,
+        }
+    }
+
+    // Returns a messenger that renders its channel messages and DMs as Block Kit blocks.
+    pub fn with_blocks(&self) -> Messenger {
+        Messenger {
+            config: self.config.clone(),
+            slack: self.slack.clone(),
+            channel_override: self.channel_override.clone(),
+            unmapped_users: self.unmapped_users.clone(),
+            suppressed: self.suppressed,
+            use_blocks: true,
+        }
+    }
+
+    // Returns a messenger that tracks DM recipients without a slack mapping, alerting the
+    // configured unmapped users channel.
+    pub fn with_unmapped_users(self, unmapped_users: Arc<UnmappedUsers>) -> Messenger {
+        let mut messenger = self;
+        messenger.unmapped_users = Some(unmapped_users);
+        messenger
+    }
+
+    // Returns a messenger that drops all slack messages, e.g. while processing redelivered
+    // webhooks whose notifications would be stale.
+    pub fn with_suppressed(self, suppressed: bool) -> Messenger {
+        let mut messenger = self;
+        messenger.suppressed = suppressed;
+        messenger
+    }
+
+    pub fn is_suppressed(&self) -> bool {
+        self.suppressed
+    }
+
     // TODO
     #[allow(clippy::too_many_arguments)]
     pub fn send_to_all<T: github::CommitLike>(
@@ -104,17 +214,34 @@ impl Messenger {
         item_owner: &github::User,
         sender: &github::User,
         repo: &github::Repo,
-        mut participants: Participants,
+        participants: Participants,
         branch: &str,
         commits: &[T],
         thread_guids: Vec<String>,
     ) {
-        if self.is_ignored_user(sender) {
+        if self.is_ignored_user(sender, repo) {
             info!("Ignoring message from ignored user: {}", sender.login(),);
             return;
         }
 
         self.send_to_channel(msg, attachments, repo, branch, commits, thread_guids, false);
+        self.send_to_participants(msg, attachments, item_owner, sender, repo, participants);
+    }
+
+    // Sends only direct messages: to the item owner and participants, excluding the sender
+    pub fn send_to_participants(
+        &self,
+        msg: &str,
+        attachments: &[SlackAttachment],
+        item_owner: &github::User,
+        sender: &github::User,
+        repo: &github::Repo,
+        mut participants: Participants,
+    ) {
+        if self.is_ignored_user(sender, repo) {
+            info!("Ignoring message from ignored user: {}", sender.login(),);
+            return;
+        }
 
         participants.add_user(item_owner.clone());
 
@@ -125,11 +252,23 @@ impl Messenger {
         self.send_to_slackbots(participants, repo, msg, attachments);
     }
 
-    fn is_ignored_user(&self, user: &github::User) -> bool {
+    // Sends a direct message to just the given participants
+    pub fn send_to_users(
+        &self,
+        msg: &str,
+        attachments: &[SlackAttachment],
+        repo: &github::Repo,
+        mut participants: Participants,
+    ) {
+        participants.remove("octobot");
+
+        self.send_to_slackbots(participants, repo, msg, attachments);
+    }
+
+    pub fn is_ignored_user(&self, user: &github::User, repo: &github::Repo) -> bool {
         self.config
-            .slack
-            .ignored_users
-            .contains(&user.login().to_string())
+            .repos()
+            .is_ignored_user(repo, user.login(), &self.config.slack.ignored_users)
     }
 
     pub fn send_to_owner<T: github::CommitLike>(
@@ -173,34 +312,171 @@ impl Messenger {
         //  slack db, but that wouldn't respect the users' choice if they change the setting later.
         let use_threads = self.config.repos().notify_use_threads(repo) && !thread_guids.is_empty();
 
-        for channel in self.config.repos().lookup_channels(repo, branch, commits) {
-            let channel_msg = format!(
+        let mut attachments = attachments.to_vec();
+        if let Some(link) = self.config.main.octobot_link(&repo.full_name) {
+            attachments.push(
+                SlackAttachmentBuilder::new("")
+                    .title("View in octobot")
+                    .title_link(link)
+                    .build(),
+            );
+        }
+
+        let channels = match self.channel_override {
+            Some(ref channel) => vec![channel.clone()],
+            None => self.config.repos().lookup_channels(repo, branch, commits),
+        };
+
+        for channel in channels {
+            let mut channel_msg = format!(
                 "{} ({})",
                 msg,
                 util::make_link(&repo.html_url, &repo.full_name)
             );
+            let mut attachments = attachments.clone();
+            if self.config.slack.sanitizes_mentions(&channel) {
+                channel_msg = util::sanitize_slack_mentions(&channel_msg);
+                attachments = attachments.iter().map(|a| a.sanitize_mentions()).collect();
+            }
+            let blocks = self.blocks(&channel_msg, &attachments);
             if !use_threads {
-                self.slack.send(slack::req(
-                    SlackRecipient::new(&channel, &channel),
-                    &channel_msg,
-                    attachments,
-                    None,
-                    initial_thread,
-                ));
-            } else {
-                for thread_guid in &thread_guids {
-                    self.slack.send(slack::req(
+                self.send(
+                    slack::req(
                         SlackRecipient::new(&channel, &channel),
                         &channel_msg,
-                        attachments,
-                        Some(thread_guid.to_owned()),
+                        &attachments,
+                        None,
                         initial_thread,
-                    ));
+                    )
+                    .with_blocks(blocks),
+                );
+            } else {
+                for thread_guid in &thread_guids {
+                    self.send(
+                        slack::req(
+                            SlackRecipient::new(&channel, &channel),
+                            &channel_msg,
+                            &attachments,
+                            Some(thread_guid.to_owned()),
+                            initial_thread,
+                        )
+                        .with_blocks(blocks.clone()),
+                    );
                 }
             }
         }
     }
 
+    // Records JIRA transitions to the configured audit channel, if any.
+    // `cause` describes what triggered the transitions, e.g. "merge of PR#32".
+    pub fn send_transition_audit(&self, transitions: &[AppliedTransition], cause: &str) {
+        let channel = match self
+            .config
+            .jira
+            .as_ref()
+            .and_then(|j| j.transition_audit_channel.as_ref())
+        {
+            Some(c) if !c.is_empty() => c,
+            _ => return,
+        };
+
+        for transition in transitions {
+            let msg = format!("{} (by octobot on {})", transition.describe(), cause);
+            self.send(slack::req(
+                SlackRecipient::by_name(channel),
+                &msg,
+                &[],
+                None,
+                false,
+            ));
+        }
+    }
+
+    // Alerts the configured installation channel, if any, about repos added to or removed from
+    // the GitHub App installation.
+    pub fn send_installation_change(
+        &self,
+        sender: &github::User,
+        added: &[String],
+        removed: &[String],
+    ) {
+        let channel = match self.config.slack.installation_alerts_channel {
+            Some(ref c) if !c.is_empty() => c,
+            _ => return,
+        };
+
+        for (verb, repos) in [("added to", added), ("removed from", removed)] {
+            if repos.is_empty() {
+                continue;
+            }
+            let msg = format!(
+                "Repositories {} the GitHub App installation by {}: {}",
+                verb,
+                sender.login(),
+                repos.join(", ")
+            );
+            self.send(slack::req(
+                SlackRecipient::by_name(channel),
+                &msg,
+                &[],
+                None,
+                false,
+            ));
+        }
+    }
+
+    // Alerts the configured security channel, if any, about a detected secret.
+    pub fn send_secret_scanning_alert(
+        &self,
+        repo: &github::Repo,
+        alert: &github::SecretScanningAlert,
+    ) {
+        let channel = match self.config.slack.security_alerts_channel {
+            Some(ref c) if !c.is_empty() => c,
+            _ => return,
+        };
+
+        let msg = format!(
+            "Secret scanning alert: {} detected in {}",
+            alert.secret_type_name(),
+            util::make_link(&repo.html_url, &repo.full_name),
+        );
+        let attachments = vec![SlackAttachmentBuilder::new("")
+            .title(format!("Alert #{}", alert.number))
+            .title_link(alert.html_url.as_str())
+            .color("danger")
+            .build()];
+        self.send(slack::req(
+            SlackRecipient::by_name(channel),
+            &msg,
+            &attachments,
+            None,
+            false,
+        ));
+    }
+
+    pub fn send_changes_requested_escalation(
+        &self,
+        channel: &str,
+        pull_request: &github::PullRequest,
+        repo: &github::Repo,
+        count: usize,
+    ) {
+        let msg = format!(
+            "Changes have been requested {} times on PR \"{}\" in {}",
+            count,
+            util::make_link(&pull_request.html_url, &pull_request.title),
+            util::make_link(&repo.html_url, &repo.full_name),
+        );
+        self.send(slack::req(
+            SlackRecipient::by_name(channel),
+            &msg,
+            &[],
+            None,
+            false,
+        ));
+    }
+
     fn send_to_slackbots(
         &self,
         users: Participants,
@@ -221,11 +497,66 @@ impl Messenger {
             );
 
             if let Some(user_dm) = user_dm {
-                self.slack
-                    .send(slack::req(user_dm, msg, attachments, None, false));
+                self.send(
+                    slack::req(user_dm, msg, attachments, None, false)
+                        .with_blocks(self.blocks(msg, attachments)),
+                );
+            } else if !user.login().ends_with("[bot]")
+                && self.config.users().lookup_info(user.login()).is_none()
+            {
+                self.record_unmapped_user(user.login());
             }
         }
     }
+
+    // The Block Kit rendering of a message, if this messenger uses blocks
+    fn blocks(&self, msg: &str, attachments: &[SlackAttachment]) -> Vec<SlackBlock> {
+        if !self.use_blocks {
+            return vec![];
+        }
+        let mut builder = SlackBlockBuilder::new();
+        builder.section(msg);
+        for attachment in attachments {
+            builder.attachment(attachment);
+        }
+        builder.build()
+    }
+
+    fn send(&self, req: SlackRequest) {
+        if self.suppressed {
+            info!("Suppressing slack message to {}", req.channel.name);
+            return;
+        }
+        // tie the message back to the webhook being handled, if any
+        self.slack.send(req.with_event_id(correlation::current()));
+    }
+
+    fn record_unmapped_user(&self, login: &str) {
+        let unmapped_users = match self.unmapped_users {
+            Some(ref u) if !self.suppressed => u,
+            _ => return,
+        };
+        let channel = match self.config.slack.unmapped_users_channel {
+            Some(ref c) if !c.is_empty() => c,
+            _ => return,
+        };
+
+        unmapped_users.record(login);
+        if let Some(logins) =
+            unmapped_users.take_due(self.config.slack.unmapped_users_alert_interval())
+        {
+            self.send(slack::req(
+                SlackRecipient::by_name(channel),
+                &format!(
+                    "GitHub users without a Slack mapping: {}",
+                    logins.join(", ")
+                ),
+                &[],
+                None,
+                false,
+            ));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -5,6 +5,7 @@ use anyhow::anyhow;
 use conventional::{Commit, Simple as _};
 use log::{error, info};
 use regex::Regex;
+use tokio::sync::Semaphore;
 
 use crate::git::Git;
 use crate::git_clone_manager::GitCloneManager;
@@ -12,10 +13,13 @@ use crate::messenger;
 use crate::slack::{SlackAttachmentBuilder, SlackRequest};
 use crate::worker;
 use octobot_lib::config::Config;
+use octobot_lib::correlation;
 use octobot_lib::errors::*;
 use octobot_lib::github;
 use octobot_lib::github::api::{GithubSessionFactory, Session};
+use octobot_lib::jira;
 use octobot_lib::metrics::{self, Metrics};
+use octobot_lib::repos::MergeStrategy;
 
 async fn clone_and_merge_pull_request<'a>(
     github_app: &'a dyn GithubSessionFactory,
@@ -23,6 +27,7 @@ async fn clone_and_merge_pull_request<'a>(
     req: &'a PRMergeRequest,
     config: Arc<Config>,
     slack: Arc<dyn worker::Worker<SlackRequest>>,
+    jira: Option<&'a dyn jira::api::Session>,
 ) {
     let owner = &req.repo.owner.login();
     let repo = &req.repo.name;
@@ -44,7 +49,7 @@ async fn clone_and_merge_pull_request<'a>(
     let clone_dir = held_clone_dir.dir();
     let git = Git::new(session.github_host(), session.github_token(), clone_dir);
 
-    merge_pull_request(&git, &session, req, config, slack).await
+    merge_pull_request(&git, &session, req, config, slack, jira).await
 }
 
 pub async fn merge_pull_request<'a>(
@@ -53,8 +58,16 @@ pub async fn merge_pull_request<'a>(
     req: &'a PRMergeRequest,
     config: Arc<Config>,
     slack: Arc<dyn worker::Worker<SlackRequest>>,
+    jira: Option<&'a dyn jira::api::Session>,
 ) {
-    if let Err(e) = try_merge_pull_request(git, session, req).await {
+    let result = try_merge_pull_request(git, session, req).await;
+    if let Ok(ref new_pr) = result {
+        if let Some(jira) = jira {
+            comment_backport_jiras(new_pr, req, &config, jira).await;
+        }
+    }
+
+    if let Err(e) = result {
         let msg = format!(
             "Error backporting PR from {} to {}",
             req.pull_request.head.ref_name, req.target_branch
@@ -119,6 +132,37 @@ pub async fn merge_pull_request<'a>(
     }
 }
 
+async fn comment_backport_jiras(
+    new_pr: &github::PullRequest,
+    req: &PRMergeRequest,
+    config: &Config,
+    jira: &dyn jira::api::Session,
+) {
+    let jira_config = match config.jira {
+        Some(ref c) if c.backport_comments() => c,
+        _ => return,
+    };
+
+    let (projects, visibility) = {
+        let repos = config.repos();
+        (
+            repos.jira_projects(&req.repo, &req.target_branch),
+            repos.jira_comment_visibility(&req.repo),
+        )
+    };
+
+    jira::workflow::comment_backport(
+        new_pr,
+        &req.target_branch,
+        &req.commits,
+        &projects,
+        jira,
+        jira_config,
+        visibility,
+    )
+    .await;
+}
+
 pub async fn try_merge_pull_request(
     git: &Git,
     session: &dyn Session,
@@ -160,6 +204,8 @@ pub async fn try_merge_pull_request(
     let (title, body, whitespace_mode) = cherry_pick(
         git,
         merge_commit_sha,
+        &req.commits,
+        req.merge_strategy,
         &pr_branch_name,
         pull_request.number,
         &req.target_branch,
@@ -232,9 +278,15 @@ pub async fn try_merge_pull_request(
     Ok(new_pr)
 }
 
+// Applies the merged PR onto a new branch off the target branch according to the merge strategy.
+// Squash and rebase pick the PR's own commits, skipping merges, and fall back to the merge commit
+// without them.
+#[allow(clippy::too_many_arguments)]
 pub fn cherry_pick(
     git: &Git,
     commit_hash: &str,
+    pr_commits: &[github::Commit],
+    strategy: MergeStrategy,
     pr_branch_name: &str,
     pr_number: u32,
     target_branch: &str,
@@ -242,30 +294,58 @@ pub fn cherry_pick(
     release_branch_prefix: &str,
 ) -> Result<(String, String, String)> {
     git.checkout_branch(pr_branch_name, &format!("origin/{}", target_branch))?;
+    let start = git.current_commit()?;
 
     let (user, email) = git.get_commit_author(commit_hash)?;
     let email = format!("user.email={}", email);
     let user = format!("user.name={}", user);
     let user_opts = ["-c", &email, "-c", &user];
 
+    let mut picks: Vec<&str> = vec![];
+    if strategy != MergeStrategy::Merge && !pr_commits.is_empty() {
+        // the PR's commits may be gone from origin along with its deleted head branch
+        let pr_ref = format!("refs/pull/{}/head", pr_number);
+        if let Err(e) = git.run(&["fetch", "origin", &pr_ref]) {
+            info!("Could not fetch {}: {}", pr_ref, e);
+        }
+
+        // merges into the PR branch only bring in changes from elsewhere
+        for commit in pr_commits {
+            if !git.is_merge_commit(&commit.sha)? {
+                picks.push(&commit.sha);
+            }
+        }
+    }
+    if picks.is_empty() {
+        picks.push(commit_hash);
+    }
+    let mut pick_opts = vec![];
+    if strategy == MergeStrategy::Squash {
+        pick_opts.push("--no-commit");
+    }
+
     // cherry-pick!
 
     let mut whitespace_mode = "";
-    if let Err(e) = do_cherry_pick(git, commit_hash, &[], &user_opts) {
+    if let Err(e) = do_cherry_pick(git, &start, &picks, &pick_opts, &user_opts) {
         info!(
             "Could not cherry-pick normally. Ignoring changed whitespace. {}",
             e
         );
 
         whitespace_mode = "ignore-space-change";
-        if let Err(e) = do_cherry_pick(git, commit_hash, &["-X", whitespace_mode], &user_opts) {
+        let mut opts = pick_opts.clone();
+        opts.extend(["-X", whitespace_mode]);
+        if let Err(e) = do_cherry_pick(git, &start, &picks, &opts, &user_opts) {
             info!(
                 "Could not cherry-pick with `-X {}`. Ignoring all whitespace. {}",
                 whitespace_mode, e
             );
 
             whitespace_mode = "ignore-all-space";
-            if let Err(e) = do_cherry_pick(git, commit_hash, &["-X", whitespace_mode], &user_opts) {
+            let mut opts = pick_opts.clone();
+            opts.extend(["-X", whitespace_mode]);
+            if let Err(e) = do_cherry_pick(git, &start, &picks, &opts, &user_opts) {
                 info!("Could not cherry-pick with `-X {}`: {}", whitespace_mode, e);
                 return Err(e);
             }
@@ -282,32 +362,66 @@ pub fn cherry_pick(
         release_branch_prefix,
     );
 
-    // change commit message
-    let mut amend_args = vec![];
-    amend_args.extend(user_opts.iter());
-    amend_args.extend(["commit", "--amend", "-F", "-"].iter());
-    git.run_with_stdin(&amend_args, &format!("{}\n\n{}", &title, &body))?;
+    let body = match strategy {
+        MergeStrategy::Merge => {
+            // change commit message
+            let mut amend_args = vec![];
+            amend_args.extend(user_opts.iter());
+            amend_args.extend(["commit", "--amend", "-F", "-"].iter());
+            git.run_with_stdin(&amend_args, &format!("{}\n\n{}", &title, &body))?;
+            body
+        }
+        MergeStrategy::Squash => {
+            let body = make_squash_body(pr_commits, commit_hash, pr_number);
+            let mut commit_args = vec![];
+            commit_args.extend(user_opts.iter());
+            commit_args.extend(["commit", "--allow-empty", "-F", "-"].iter());
+            git.run_with_stdin(&commit_args, &format!("{}\n\n{}", &title, &body))?;
+            body
+        }
+        // the picked commits keep their own messages
+        MergeStrategy::Rebase => body,
+    };
 
     Ok((title, body, whitespace_mode.into()))
 }
 
 fn do_cherry_pick(
     git: &Git,
-    commit_hash: &str,
+    start: &str,
+    commit_hashes: &[&str],
     opts: &[&str],
     user_opts: &[&str],
 ) -> Result<String> {
-    git.run(&["reset", "--hard"])?;
+    // clear out any partially applied series from a previous attempt
+    git.run(&["cherry-pick", "--quit"])?;
+    git.run(&["reset", "--hard", start])?;
 
     let mut args = vec!["-c", "merge.renameLimit=999999"];
     args.extend(user_opts.iter());
     args.extend(["cherry-pick", "--allow-empty"].iter());
     args.extend(opts);
-    args.push(commit_hash);
+    args.extend(commit_hashes);
 
     git.run(&args)
 }
 
+// Lists the titles of the squashed commits in place of the merge commit's description
+fn make_squash_body(pr_commits: &[github::Commit], commit_hash: &str, pr_number: u32) -> String {
+    let mut body = pr_commits
+        .iter()
+        .filter_map(|c| c.commit.message.lines().next())
+        .map(|title| format!("* {}\n", title))
+        .collect::<String>();
+
+    if !body.is_empty() {
+        body += "\n";
+    }
+    body += format!("(cherry-picked from {}, PR #{})", commit_hash, pr_number).as_str();
+
+    body
+}
+
 fn make_merge_desc(
     orig_desc: (String, String),
     commit_hash: &str,
@@ -370,6 +484,9 @@ pub struct PRMergeRequest {
     pub target_branch: String,
     pub release_branch_prefix: String,
     pub commits: Vec<github::Commit>,
+    pub merge_strategy: MergeStrategy,
+    // id of the webhook delivery that queued this request, for log correlation
+    pub event_id: Option<String>,
 }
 
 struct Runner {
@@ -377,7 +494,10 @@ struct Runner {
     github_app: Arc<dyn GithubSessionFactory>,
     clone_mgr: Arc<GitCloneManager>,
     slack: Arc<dyn worker::Worker<SlackRequest>>,
+    jira_session: Option<Arc<dyn jira::api::Session>>,
     metrics: Arc<Metrics>,
+    // bounds the number of backports running at once
+    slots: Option<Arc<Semaphore>>,
 }
 
 pub fn req(
@@ -386,6 +506,7 @@ pub fn req(
     target_branch: &str,
     release_branch_prefix: &str,
     commits: &[github::Commit],
+    merge_strategy: MergeStrategy,
 ) -> PRMergeRequest {
     PRMergeRequest {
         repo: repo.clone(),
@@ -393,6 +514,14 @@ pub fn req(
         target_branch: target_branch.to_string(),
         release_branch_prefix: release_branch_prefix.to_string(),
         commits: commits.into(),
+        merge_strategy,
+        event_id: None,
+    }
+}
+
+impl PRMergeRequest {
+    pub fn with_event_id(self, event_id: Option<String>) -> PRMergeRequest {
+        PRMergeRequest { event_id, ..self }
     }
 }
 
@@ -401,20 +530,39 @@ pub fn new_runner(
     github_app: Arc<dyn GithubSessionFactory>,
     clone_mgr: Arc<GitCloneManager>,
     slack: Arc<dyn worker::Worker<SlackRequest>>,
+    jira_session: Option<Arc<dyn jira::api::Session>>,
     metrics: Arc<Metrics>,
 ) -> Arc<dyn worker::Runner<PRMergeRequest>> {
+    let slots = config
+        .main
+        .max_concurrent_backports
+        .filter(|max| *max > 0)
+        .map(|max| Arc::new(Semaphore::new(max)));
     Arc::new(Runner {
         config,
         github_app,
         clone_mgr,
         slack,
+        jira_session,
         metrics,
+        slots,
     })
 }
 
-#[async_trait::async_trait]
-impl worker::Runner<PRMergeRequest> for Runner {
-    async fn handle(&self, req: PRMergeRequest) {
+impl Runner {
+    async fn run(&self, req: PRMergeRequest) {
+        // each backport takes its own clone from the pool, so they only wait on the limit
+        let _permit = match self.slots {
+            Some(ref slots) => Some(
+                slots
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("backport semaphore closed"),
+            ),
+            None => None,
+        };
+
         let _scoped_count = metrics::scoped_inc(&self.metrics.current_backport_count);
         let _scoped_timer = self.metrics.backport_duration.start_timer();
 
@@ -424,14 +572,117 @@ impl worker::Runner<PRMergeRequest> for Runner {
             &req,
             self.config.clone(),
             self.slack.clone(),
+            self.jira_session.as_deref(),
         )
         .await;
     }
 }
 
+#[async_trait::async_trait]
+impl worker::Runner<PRMergeRequest> for Runner {
+    async fn handle(&self, req: PRMergeRequest) {
+        correlation::scope(req.event_id.clone(), self.run(req)).await;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use octobot_lib::config_db::ConfigDatabase;
+    use octobot_lib::github::api::GithubSession;
+
+    // Fails every session after a delay, tracking how many were being created at once
+    #[derive(Default)]
+    struct SlowGithub {
+        active: AtomicUsize,
+        max_active: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl GithubSessionFactory for SlowGithub {
+        async fn new_service_session(&self) -> Result<GithubSession> {
+            Err(anyhow!("no github"))
+        }
+
+        async fn new_session(&self, _owner: &str, _repo: &str) -> Result<GithubSession> {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_active.fetch_max(active, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            Err(anyhow!("no github"))
+        }
+
+        async fn get_token_org(&self, _org: &str) -> Result<String> {
+            Err(anyhow!("no github"))
+        }
+
+        async fn get_token_repo(&self, _owner: &str, _repo: &str) -> Result<String> {
+            Err(anyhow!("no github"))
+        }
+
+        fn bot_name(&self) -> String {
+            "octobot".into()
+        }
+    }
+
+    struct NoSlack;
+
+    impl worker::Worker<SlackRequest> for NoSlack {
+        fn send(&self, _: SlackRequest) {}
+    }
+
+    // Runs backports to each branch at once, returning the most that were running together
+    async fn run_backports(max_concurrent_backports: Option<usize>, branches: &[&str]) -> usize {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_file = temp_dir.path().join("db.sqlite3");
+        let db = ConfigDatabase::new(&db_file.to_string_lossy()).unwrap();
+        let mut config = Config::new(db);
+        config.main.clone_root_dir = temp_dir.path().to_string_lossy().to_string();
+        config.main.max_concurrent_backports = max_concurrent_backports;
+        let config = Arc::new(config);
+
+        let github = Arc::new(SlowGithub::default());
+        let runner = new_runner(
+            config.clone(),
+            github.clone(),
+            Arc::new(GitCloneManager::new(github.clone(), config)),
+            Arc::new(NoSlack),
+            None,
+            Metrics::new(),
+        );
+
+        let repo = github::Repo::parse("http://git.company.com/some-user/some-repo").unwrap();
+        let pr = github::PullRequest::new();
+        let handles = branches
+            .iter()
+            .map(|branch| {
+                let runner = runner.clone();
+                let req = req(&repo, &pr, branch, "release/", &[], MergeStrategy::Merge);
+                tokio::spawn(async move { runner.handle(req).await })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        github.max_active.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test]
+    async fn test_backports_run_in_parallel() {
+        let branches = ["release/1.0", "release/2.0", "release/3.0"];
+        assert_eq!(3, run_backports(None, &branches).await);
+    }
+
+    #[tokio::test]
+    async fn test_max_concurrent_backports() {
+        let branches = ["release/1.0", "release/2.0", "release/3.0"];
+        assert_eq!(1, run_backports(Some(1), &branches).await);
+        assert_eq!(2, run_backports(Some(2), &branches).await);
+    }
 
     #[test]
     fn test_make_merge_desc() {
@@ -1,4 +1,5 @@
 use std::borrow::Borrow;
+use std::fmt;
 use std::path::Path;
 use std::sync::Arc;
 
@@ -17,17 +18,21 @@ use crate::messenger;
 use crate::slack::{SlackAttachmentBuilder, SlackRequest};
 use crate::worker;
 use octobot_lib::config::{Config, JiraConfig};
+use octobot_lib::correlation;
 use octobot_lib::errors::*;
 use octobot_lib::github;
 use octobot_lib::github::api::{GithubSessionFactory, Session};
 use octobot_lib::jira;
+use octobot_lib::jira::workflow::ResolvedCommits;
 use octobot_lib::metrics::{self, Metrics};
+use octobot_lib::repos::VersionScriptMode;
 
 #[cfg(target_os = "linux")]
 use crate::docker;
 
 #[allow(clippy::too_many_arguments)]
 pub async fn comment_repo_version(
+    config: &Config,
     version_script: &str,
     jira_config: &JiraConfig,
     jira: &dyn jira::api::Session,
@@ -39,9 +44,15 @@ pub async fn comment_repo_version(
     commit_hash: &str,
     commits: &[github::PushCommit],
     jira_projects: &[String],
-) -> Result<()> {
+    visibility: jira::api::CommentVisibility,
+    resolved: Option<&dyn ResolvedCommits>,
+) -> Result<Vec<jira::workflow::AppliedTransition>> {
     let github = github_app.new_session(owner, repo).await?;
-    let held_clone_dir = clone_mgr.clone(owner, repo).await?;
+    let held_clone_dir = if config.main.fresh_version_clones() {
+        clone_mgr.fresh_clone(owner, repo).await?
+    } else {
+        clone_mgr.clone(owner, repo).await?
+    };
     let clone_dir = held_clone_dir.dir();
 
     let git = Git::new(github.github_host(), github.github_token(), clone_dir);
@@ -49,28 +60,96 @@ pub async fn comment_repo_version(
     // setup branch
     git.checkout_branch(branch_name, commit_hash)?;
 
-    let version = run_script(version_script, clone_dir)?;
+    let output = parse_version_output(&run_script(version_script, clone_dir)?);
 
-    let maybe_version = if !version.is_empty() {
-        Some(version.as_str())
-    } else {
-        None
-    };
+    // Fail rather than commenting without a version: the caller warns the repo channel
+    output.validate()?;
+    let maybe_version = Some(output.version.as_str());
 
     // resolve with version
-    jira::workflow::resolve_issue(
+    let transitions = jira::workflow::resolve_issue(
         branch_name,
         maybe_version,
+        output.environment.as_deref(),
         commits,
         jira_projects,
         jira,
         jira_config,
+        visibility,
+        resolved,
     )
     .await;
 
     jira::workflow::add_pending_version(maybe_version, commits, jira_projects, jira).await;
 
-    Ok(())
+    Ok(transitions)
+}
+
+// Output of a version script: either just the version, or `KEY=value` lines with
+// a VERSION and optionally the deploy ENV.
+#[derive(Debug, PartialEq)]
+pub struct VersionOutput {
+    pub version: String,
+    pub environment: Option<String>,
+}
+
+impl VersionOutput {
+    // A version is a single token, e.g. "1.2.3" -- not blank, and not leftover script output
+    pub fn validate(&self) -> std::result::Result<(), InvalidVersionOutput> {
+        if self.version.is_empty() {
+            return Err(InvalidVersionOutput(
+                "Version script produced no version".into(),
+            ));
+        }
+        if self
+            .version
+            .contains(|c: char| c.is_whitespace() || c == '=')
+        {
+            return Err(InvalidVersionOutput(format!(
+                "Version script produced an invalid version: {:?}",
+                self.version
+            )));
+        }
+        Ok(())
+    }
+}
+
+// The version script ran but didn't print a usable version
+#[derive(Debug)]
+pub struct InvalidVersionOutput(String);
+
+impl fmt::Display for InvalidVersionOutput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for InvalidVersionOutput {}
+
+pub fn parse_version_output(output: &str) -> VersionOutput {
+    let plain = VersionOutput {
+        version: output.trim().to_string(),
+        environment: None,
+    };
+
+    let mut version = None;
+    let mut environment = None;
+    for line in output.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+        match line.split_once('=') {
+            Some(("VERSION", v)) => version = Some(v.trim().to_string()),
+            Some(("ENV", e)) if !e.trim().is_empty() => environment = Some(e.trim().to_string()),
+            Some((key, _)) if key.chars().all(|c| c.is_ascii_uppercase() || c == '_') => (),
+            _ => return plain,
+        }
+    }
+
+    match version {
+        Some(version) => VersionOutput {
+            version,
+            environment,
+        },
+        None => plain,
+    }
 }
 
 // Only run version scripts on Linux since firejail is only for Linux and it doesn't
@@ -182,12 +261,17 @@ pub struct RepoVersionRequest {
     pub branch: String,
     pub commit_hash: String,
     pub commits: Vec<github::PushCommit>,
+    // id of the webhook delivery that queued this request, for log correlation
+    pub event_id: Option<String>,
+    // skip the version script and only resolve the JIRAs, e.g. for pushes too small to version
+    pub resolve_only: bool,
 }
 
 struct Runner {
     config: Arc<Config>,
     github_app: Arc<dyn GithubSessionFactory>,
     jira_session: Option<Arc<dyn jira::api::Session>>,
+    resolved_commits: Option<Arc<dyn ResolvedCommits>>,
     clone_mgr: Arc<GitCloneManager>,
     slack: Arc<dyn worker::Worker<SlackRequest>>,
     metrics: Arc<Metrics>,
@@ -204,6 +288,21 @@ pub fn req(
         branch: branch.to_string(),
         commit_hash: commit_hash.to_string(),
         commits: commits.into(),
+        event_id: None,
+        resolve_only: false,
+    }
+}
+
+impl RepoVersionRequest {
+    pub fn with_event_id(self, event_id: Option<String>) -> RepoVersionRequest {
+        RepoVersionRequest { event_id, ..self }
+    }
+
+    pub fn with_resolve_only(self, resolve_only: bool) -> RepoVersionRequest {
+        RepoVersionRequest {
+            resolve_only,
+            ..self
+        }
     }
 }
 
@@ -211,6 +310,7 @@ pub fn new_runner(
     config: Arc<Config>,
     github_app: Arc<dyn GithubSessionFactory>,
     jira_session: Option<Arc<dyn jira::api::Session>>,
+    resolved_commits: Option<Arc<dyn ResolvedCommits>>,
     clone_mgr: Arc<GitCloneManager>,
     slack: Arc<dyn worker::Worker<SlackRequest>>,
     metrics: Arc<Metrics>,
@@ -219,22 +319,30 @@ pub fn new_runner(
         config,
         github_app,
         jira_session,
+        resolved_commits,
         clone_mgr,
         slack,
         metrics,
     })
 }
 
-#[async_trait::async_trait]
-impl worker::Runner<RepoVersionRequest> for Runner {
-    async fn handle(&self, req: RepoVersionRequest) {
+impl Runner {
+    async fn run(&self, req: RepoVersionRequest) {
         let _scoped_count = metrics::scoped_inc(&self.metrics.current_repo_version_count);
         let _scoped_timer = self.metrics.repo_version_duration.start_timer();
 
         let configs;
+        let visibility;
+        let mode;
         {
             let repos_lock = self.config.repos();
             configs = repos_lock.jira_configs(&req.repo, &req.branch);
+            visibility = repos_lock.jira_comment_visibility(&req.repo);
+            mode = if req.resolve_only {
+                VersionScriptMode::Resolve
+            } else {
+                repos_lock.version_script_mode(&req.repo)
+            };
         }
 
         if let Some(ref jira_session) = self.jira_session {
@@ -246,11 +354,31 @@ impl worker::Runner<RepoVersionRequest> for Runner {
                     }
 
                     let mut resolved = false;
+                    let messenger = messenger::new(self.config.clone(), self.slack.clone());
+                    let audit_cause = format!("merge into {}", req.branch);
                     let jira = jira_session.borrow();
                     let jira_projects = vec![config.jira_project.clone()];
 
-                    if !config.version_script.is_empty() {
-                        if let Err(e) = comment_repo_version(
+                    if mode == VersionScriptMode::Both {
+                        let transitions = jira::workflow::resolve_issue(
+                            &req.branch,
+                            None,
+                            None,
+                            &req.commits,
+                            &jira_projects,
+                            jira,
+                            jira_config,
+                            visibility,
+                            self.resolved_commits.as_deref(),
+                        )
+                        .await;
+                        messenger.send_transition_audit(&transitions, &audit_cause);
+                        resolved = true;
+                    }
+
+                    if !config.version_script.is_empty() && mode != VersionScriptMode::Resolve {
+                        match comment_repo_version(
+                            &self.config,
                             &config.version_script,
                             jira_config,
                             jira,
@@ -262,48 +390,63 @@ impl worker::Runner<RepoVersionRequest> for Runner {
                             &req.commit_hash,
                             &req.commits,
                             &jira_projects,
+                            visibility,
+                            self.resolved_commits.as_deref(),
                         )
                         .await
                         {
-                            error!(
-                                "Error running version script {}: {}",
-                                config.version_script, e
-                            );
-                            let messenger = messenger::new(self.config.clone(), self.slack.clone());
-
-                            let attach = SlackAttachmentBuilder::new(&format!("{}", e))
-                                .title(config.version_script.clone())
-                                .color("danger")
-                                .build();
-
-                            messenger.send_to_channel(
-                                &format!(
-                                    "Error running version script for [{}]",
-                                    config.jira_project
-                                ),
-                                &[attach],
-                                &req.repo,
-                                &req.branch,
-                                &req.commits,
-                                vec![req.repo.html_url.to_string()],
-                                false,
-                            );
-                        } else {
-                            resolved = true
+                            Ok(transitions) => {
+                                messenger.send_transition_audit(&transitions, &audit_cause);
+                                resolved = true;
+                            }
+                            Err(e) => {
+                                error!(
+                                    "Error running version script {}: {}",
+                                    config.version_script, e
+                                );
+
+                                // don't comment on the JIRAs without the version they're missing
+                                if e.is::<InvalidVersionOutput>() {
+                                    resolved = true;
+                                }
+
+                                let attach = SlackAttachmentBuilder::new(&format!("{}", e))
+                                    .title(config.version_script.clone())
+                                    .color("danger")
+                                    .build();
+
+                                messenger.send_to_channel(
+                                    &format!(
+                                        "Error running version script for [{}]",
+                                        config.jira_project
+                                    ),
+                                    &[attach],
+                                    &req.repo,
+                                    &req.branch,
+                                    &req.commits,
+                                    vec![req.repo.html_url.to_string()],
+                                    false,
+                                );
+                            }
                         }
                     }
 
-                    // resolve the issue with no version if version script is missing or failed
+                    // resolve the issue with no version if version script is missing, skipped,
+                    // or failed to run
                     if !resolved {
-                        jira::workflow::resolve_issue(
+                        let transitions = jira::workflow::resolve_issue(
                             &req.branch,
                             None,
+                            None,
                             &req.commits,
                             &jira_projects,
                             jira,
                             jira_config,
+                            visibility,
+                            self.resolved_commits.as_deref(),
                         )
                         .await;
+                        messenger.send_transition_audit(&transitions, &audit_cause);
                     }
                 }
             }
@@ -311,6 +454,13 @@ impl worker::Runner<RepoVersionRequest> for Runner {
     }
 }
 
+#[async_trait::async_trait]
+impl worker::Runner<RepoVersionRequest> for Runner {
+    async fn handle(&self, req: RepoVersionRequest) {
+        correlation::scope(req.event_id.clone(), self.run(req)).await;
+    }
+}
+
 #[cfg(target_os = "linux")]
 #[cfg(test)]
 mod tests {
@@ -320,6 +470,63 @@ mod tests {
 
     use tempfile::tempdir;
 
+    #[test]
+    fn test_parse_version_output_plain() {
+        assert_eq!(
+            VersionOutput {
+                version: "1.2.3.4".into(),
+                environment: None,
+            },
+            parse_version_output("1.2.3.4\n")
+        );
+    }
+
+    #[test]
+    fn test_parse_version_output_structured() {
+        assert_eq!(
+            VersionOutput {
+                version: "1.2.3.4".into(),
+                environment: Some("staging".into()),
+            },
+            parse_version_output("VERSION=1.2.3.4\nENV=staging\nOTHER_KEY=ignored\n")
+        );
+        assert_eq!(
+            VersionOutput {
+                version: "1.2.3.4".into(),
+                environment: None,
+            },
+            parse_version_output("VERSION=1.2.3.4\n")
+        );
+    }
+
+    #[test]
+    fn test_parse_version_output_not_structured() {
+        // no VERSION key, or other lines: treat it all as the version
+        assert_eq!("ENV=staging", parse_version_output("ENV=staging").version);
+        assert_eq!(
+            "VERSION=1.2\nsome text",
+            parse_version_output("VERSION=1.2\nsome text").version
+        );
+    }
+
+    #[test]
+    fn test_validate_version_output() {
+        assert!(parse_version_output("1.2.3.4\n").validate().is_ok());
+        assert!(parse_version_output("VERSION=1.2.3.4\nENV=staging")
+            .validate()
+            .is_ok());
+
+        let err =
+            |output: &str| format!("{}", parse_version_output(output).validate().unwrap_err());
+        assert_eq!("Version script produced no version", err(""));
+        assert_eq!(
+            "Version script produced no version",
+            err("VERSION=\nENV=prod")
+        );
+        assert!(err("ENV=staging").contains("invalid version"));
+        assert!(err("VERSION=1.2\nsome text").contains("invalid version"));
+    }
+
     #[test]
     fn test_run_script() {
         let dir = tempdir().unwrap();
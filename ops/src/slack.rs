@@ -1,18 +1,22 @@
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 
 use anyhow::bail;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use serde_derive::{Deserialize, Serialize};
 
 use crate::slack_db::SlackDatabase;
+use crate::slack_retry_db::SlackRetryDatabase;
+use crate::token_bucket::TokenBucket;
 use crate::util;
 use crate::worker;
+use octobot_lib::correlation;
 use octobot_lib::errors::*;
 use octobot_lib::http_client::HTTPClient;
 use octobot_lib::metrics::Metrics;
 use octobot_lib::slack::SlackRecipient;
 
-#[derive(Serialize, Clone, PartialEq, Eq, Debug)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
 pub struct SlackAttachment {
     pub text: String,
     pub title: Option<String>,
@@ -40,6 +44,17 @@ impl SlackAttachment {
             mrkdwn_in: None,
         }
     }
+
+    // A copy of this attachment with broad mentions neutralized
+    pub fn sanitize_mentions(&self) -> SlackAttachment {
+        let mut attachment = self.clone();
+        attachment.text = util::sanitize_slack_mentions(&self.text);
+        attachment.title = self
+            .title
+            .as_ref()
+            .map(|t| util::sanitize_slack_mentions(t));
+        attachment
+    }
 }
 
 pub struct SlackAttachmentBuilder {
@@ -84,29 +99,160 @@ impl SlackAttachmentBuilder {
     }
 }
 
-#[derive(Serialize, Clone, PartialEq)]
+// A Block Kit text object
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct SlackText {
+    #[serde(rename = "type")]
+    pub text_type: String,
+    pub text: String,
+}
+
+impl SlackText {
+    pub fn markdown(text: &str) -> SlackText {
+        SlackText {
+            text_type: "mrkdwn".into(),
+            text: text.to_string(),
+        }
+    }
+}
+
+// A Block Kit layout block: https://api.slack.com/reference/block-kit/blocks
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SlackBlock {
+    Section { text: SlackText },
+    Context { elements: Vec<SlackText> },
+}
+
+// Slack rejects messages with more blocks than this
+const MAX_BLOCKS: usize = 50;
+
+pub struct SlackBlockBuilder {
+    blocks: Vec<SlackBlock>,
+}
+
+impl SlackBlockBuilder {
+    pub fn new() -> SlackBlockBuilder {
+        SlackBlockBuilder { blocks: vec![] }
+    }
+
+    pub fn section(&mut self, markdown: &str) -> &mut SlackBlockBuilder {
+        self.blocks.push(SlackBlock::Section {
+            text: SlackText::markdown(markdown),
+        });
+        self
+    }
+
+    pub fn context(&mut self, markdown: &str) -> &mut SlackBlockBuilder {
+        self.blocks.push(SlackBlock::Context {
+            elements: vec![SlackText::markdown(markdown)],
+        });
+        self
+    }
+
+    // Renders a legacy attachment: titled attachments become a section headed by the
+    // (linked) title, untitled ones a context line.
+    pub fn attachment(&mut self, attachment: &SlackAttachment) -> &mut SlackBlockBuilder {
+        let title = match (&attachment.title, &attachment.title_link) {
+            (Some(title), Some(link)) => Some(format!("*{}*", util::make_link(link, title))),
+            (Some(title), None) => Some(format!("*{}*", title)),
+            _ => None,
+        };
+        match title {
+            Some(title) if attachment.text.is_empty() => self.section(&title),
+            Some(title) => self.section(&format!("{}\n{}", title, attachment.text)),
+            None if attachment.text.is_empty() => self,
+            None => self.context(&attachment.text),
+        }
+    }
+
+    // Caps the blocks at Slack's limit, summarizing the overflow (e.g. the commits of a
+    // large push) in the last block.
+    pub fn build(&self) -> Vec<SlackBlock> {
+        let mut blocks = self.blocks.clone();
+        if blocks.len() > MAX_BLOCKS {
+            let more = blocks.len() - (MAX_BLOCKS - 1);
+            blocks.truncate(MAX_BLOCKS - 1);
+            blocks.push(SlackBlock::Context {
+                elements: vec![SlackText::markdown(&format!("...and {} more", more))],
+            });
+        }
+        blocks
+    }
+}
+
+impl Default for SlackBlockBuilder {
+    fn default() -> SlackBlockBuilder {
+        SlackBlockBuilder::new()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 struct SlackMessage {
     text: String,
     attachments: Vec<SlackAttachment>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    blocks: Vec<SlackBlock>,
     channel: String,
     // Provide a thread_ts value for the posted message to act as a reply to a parent message.
     // https://api.slack.com/methods/chat.postMessage#arg_thread_ts
     thread_ts: Option<String>,
 }
 
+// The outcome of posting a message
+enum Delivery {
+    // sent, with the message's ts
+    Sent(String),
+    // failed, but may succeed if tried again later
+    Retry(String),
+    // failed for good
+    Failed(String),
+}
+
 // the main object for sending messages to slack
 pub struct Slack {
     client: Arc<HTTPClient>,
     recent_messages: Mutex<Vec<SlackMessage>>,
     slack_db: Mutex<SlackDatabase>,
+    retry_db: Mutex<SlackRetryDatabase>,
+    rate_limiter: TokenBucket,
 }
 
 const TRIM_MESSAGES_AT: usize = 200;
 const TRIM_MESSAGES_TO: usize = 20;
 
+// Retries back off exponentially from the base delay, up to the max delay
+const MAX_SEND_ATTEMPTS: u32 = 8;
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(30);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(3600);
+
+const DEFAULT_MAX_MESSAGES_PER_MINUTE: u32 = 60;
+
 impl Slack {
-    pub fn new(bot_token: String, slack_db_path: String, metrics: Arc<Metrics>) -> Slack {
+    pub fn new(
+        bot_token: String,
+        slack_db_path: String,
+        slack_retry_db_path: String,
+        metrics: Arc<Metrics>,
+    ) -> Slack {
+        Slack::new_with_api_base(
+            "https://slack.com/api",
+            bot_token,
+            slack_db_path,
+            slack_retry_db_path,
+            metrics,
+        )
+    }
+
+    fn new_with_api_base(
+        api_base: &str,
+        bot_token: String,
+        slack_db_path: String,
+        slack_retry_db_path: String,
+        metrics: Arc<Metrics>,
+    ) -> Slack {
         let slack_db = SlackDatabase::new(&slack_db_path).expect("init slack db");
+        let retry_db = SlackRetryDatabase::new(&slack_retry_db_path).expect("init slack retry db");
 
         let mut headers = reqwest::header::HeaderMap::new();
         headers.append(
@@ -115,7 +261,7 @@ impl Slack {
         );
 
         let client = Arc::new(
-            HTTPClient::new_with_headers("https://slack.com/api", headers)
+            HTTPClient::new_with_headers(api_base, headers)
                 .unwrap()
                 .with_metrics(
                     metrics.slack_api_responses.clone(),
@@ -125,16 +271,27 @@ impl Slack {
         Slack {
             client,
             slack_db: Mutex::new(slack_db),
+            retry_db: Mutex::new(retry_db),
             recent_messages: Mutex::new(Vec::new()),
+            rate_limiter: TokenBucket::new(DEFAULT_MAX_MESSAGES_PER_MINUTE, Instant::now()),
+        }
+    }
+
+    pub fn with_max_messages_per_minute(self, per_minute: u32) -> Slack {
+        Slack {
+            rate_limiter: TokenBucket::new(per_minute, Instant::now()),
+            ..self
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn send(
         &self,
         channel_id: &str,
         channel_name: &str,
         msg: &str,
         attachments: Vec<SlackAttachment>,
+        blocks: Vec<SlackBlock>,
         initial_thread: bool,
         thread_guid: &str,
     ) {
@@ -150,11 +307,18 @@ impl Slack {
             };
         }
 
+        // blocks supersede attachments; the text is kept as the notification fallback
+        let attachments = if blocks.is_empty() {
+            attachments
+        } else {
+            vec![]
+        };
         let slack_msg = SlackMessage {
             text: msg.to_string(),
             attachments,
+            blocks,
             channel: channel_id.to_string(),
-            thread_ts: parent_thread.clone(),
+            thread_ts: parent_thread,
         };
 
         if !self.is_unique(&slack_msg) {
@@ -164,34 +328,185 @@ impl Slack {
 
         debug!("Sending message to #{}", channel_name);
 
-        let res: Result<SlackResponse> = self.client.post("/chat.postMessage", &slack_msg).await;
-        match res {
-            Ok(r) => {
-                if r.ok {
-                    let thread = r.ts.unwrap_or_default();
-                    info!(
-                        "Successfully sent slack message to {}, ts: \"{}\"",
-                        channel_name, thread
+        match self.post_message(&slack_msg).await {
+            Delivery::Sent(ts) => {
+                self.record_sent(&slack_msg, channel_name, &ts, initial_thread, thread_guid)
+            }
+            Delivery::Retry(e) => {
+                warn!(
+                    "Error sending slack message to {} ({}), will retry: {}",
+                    channel_name, channel_id, e
+                );
+                self.queue_retry(&slack_msg, channel_name, initial_thread, thread_guid);
+            }
+            Delivery::Failed(e) => error!(
+                "Error sending slack message to {} ({}): {}",
+                channel_name, channel_id, e
+            ),
+        }
+    }
+
+    // Resends queued messages whose retry is due
+    pub async fn retry_pending(&self, now: SystemTime) {
+        let pending = {
+            let retry_db = self.retry_db.lock().unwrap();
+            match retry_db.due(now) {
+                Ok(p) => p,
+                Err(e) => {
+                    error!("Error looking up slack messages to retry: {}", e);
+                    return;
+                }
+            }
+        };
+
+        for p in pending {
+            let slack_msg: SlackMessage = match serde_json::from_str(&p.message) {
+                Ok(m) => m,
+                Err(e) => {
+                    error!("Dropping invalid queued slack message {}: {}", p.id, e);
+                    self.remove_retry(p.id);
+                    continue;
+                }
+            };
+
+            match self.post_message(&slack_msg).await {
+                Delivery::Sent(ts) => {
+                    self.remove_retry(p.id);
+                    self.record_sent(
+                        &slack_msg,
+                        &p.channel_name,
+                        &ts,
+                        p.initial_thread,
+                        &p.thread_guid,
+                    );
+                }
+                Delivery::Retry(e) if p.attempts + 1 < MAX_SEND_ATTEMPTS => {
+                    warn!(
+                        "Error resending slack message to {} (attempt {}), will retry: {}",
+                        p.channel_name,
+                        p.attempts + 1,
+                        e
                     );
-                    if initial_thread && !thread_guid.is_empty() && parent_thread.is_none() {
-                        let slack_db = self.slack_db.lock().unwrap();
-                        slack_db
-                            .insert_thread(thread_guid, channel_id, thread.as_str())
-                            .ok();
+                    let retry_db = self.retry_db.lock().unwrap();
+                    if let Err(e) =
+                        retry_db.reschedule(p.id, p.attempts + 1, now + retry_delay(p.attempts + 1))
+                    {
+                        error!("Error rescheduling slack message: {}", e);
                     }
-                } else {
+                }
+                Delivery::Retry(e) | Delivery::Failed(e) => {
                     error!(
-                        "Error sending slack message to {}: {} ({})",
-                        channel_name,
-                        channel_id,
-                        r.error.unwrap_or_default(),
-                    )
+                        "Giving up sending slack message to {} after {} attempt(s): {}",
+                        p.channel_name,
+                        p.attempts + 1,
+                        e
+                    );
+                    self.remove_retry(p.id);
                 }
             }
-            Err(e) => error!(
-                "Error sending slack message to {} ({}): {}",
-                channel_name, channel_id, e
-            ),
+        }
+    }
+
+    // Waits for a turn to send under the rate limit, including any pause requested by slack
+    async fn throttle(&self) {
+        let wait = self.rate_limiter.reserve(Instant::now());
+        if !wait.is_zero() {
+            debug!("Waiting {:?} to send slack message", wait);
+            tokio::time::sleep(wait).await;
+        }
+
+        let pause = self.rate_limiter.pause_remaining(Instant::now());
+        if !pause.is_zero() {
+            tokio::time::sleep(pause).await;
+        }
+    }
+
+    async fn post_message(&self, slack_msg: &SlackMessage) -> Delivery {
+        self.throttle().await;
+
+        let res = match self.client.post_raw("/chat.postMessage", slack_msg).await {
+            Ok(r) => r,
+            Err(e) => return Delivery::Retry(e.to_string()),
+        };
+
+        let status = res.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            if let Some(retry_after) = retry_after(res.headers()) {
+                warn!("Rate limited by slack, pausing for {:?}", retry_after);
+                self.rate_limiter.pause_until(Instant::now() + retry_after);
+            }
+        }
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+            return Delivery::Retry(format!("HTTP {}", status));
+        }
+        if !status.is_success() {
+            return Delivery::Failed(format!("HTTP {}", status));
+        }
+
+        match self.client.parse_json::<SlackResponse>(res).await {
+            Ok(r) if r.ok => Delivery::Sent(r.ts.unwrap_or_default()),
+            Ok(r) => {
+                let e = r.error.unwrap_or_default();
+                if is_retryable_error(&e) {
+                    Delivery::Retry(e)
+                } else {
+                    Delivery::Failed(e)
+                }
+            }
+            Err(e) => Delivery::Failed(e.to_string()),
+        }
+    }
+
+    fn record_sent(
+        &self,
+        slack_msg: &SlackMessage,
+        channel_name: &str,
+        thread: &str,
+        initial_thread: bool,
+        thread_guid: &str,
+    ) {
+        info!(
+            "Successfully sent slack message to {}, ts: \"{}\"",
+            channel_name, thread
+        );
+        if initial_thread && !thread_guid.is_empty() && slack_msg.thread_ts.is_none() {
+            let slack_db = self.slack_db.lock().unwrap();
+            slack_db
+                .insert_thread(thread_guid, &slack_msg.channel, thread)
+                .ok();
+        }
+    }
+
+    fn queue_retry(
+        &self,
+        slack_msg: &SlackMessage,
+        channel_name: &str,
+        initial_thread: bool,
+        thread_guid: &str,
+    ) {
+        let message = match serde_json::to_string(slack_msg) {
+            Ok(m) => m,
+            Err(e) => {
+                error!("Error serializing slack message to retry: {}", e);
+                return;
+            }
+        };
+        let retry_db = self.retry_db.lock().unwrap();
+        if let Err(e) = retry_db.enqueue(
+            channel_name,
+            &message,
+            initial_thread,
+            thread_guid,
+            SystemTime::now() + retry_delay(1),
+        ) {
+            error!("Error queueing slack message to retry: {}", e);
+        }
+    }
+
+    fn remove_retry(&self, id: i64) {
+        let retry_db = self.retry_db.lock().unwrap();
+        if let Err(e) = retry_db.remove(id) {
+            error!("Error removing queued slack message: {}", e);
         }
     }
 
@@ -280,13 +595,46 @@ impl Slack {
     }
 }
 
+// Slack API errors which are worth retrying: https://api.slack.com/methods/chat.postMessage#errors
+fn is_retryable_error(error: &str) -> bool {
+    matches!(
+        error,
+        "ratelimited"
+            | "internal_error"
+            | "fatal_error"
+            | "service_unavailable"
+            | "request_timeout"
+    )
+}
+
+// The delay requested by a rate-limited response's Retry-After header, in seconds
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+// How long to wait before the next send after the given number of attempts
+fn retry_delay(attempts: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+    RETRY_BASE_DELAY
+        .checked_mul(factor)
+        .map_or(RETRY_MAX_DELAY, |d| d.min(RETRY_MAX_DELAY))
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct SlackRequest {
     pub channel: SlackRecipient,
     pub thread_guid: Option<String>,
     pub msg: String,
     pub attachments: Vec<SlackAttachment>,
+    // Block Kit rendering of the message, posted instead of the attachments if present
+    pub blocks: Vec<SlackBlock>,
     pub initial_thread: bool,
+    // id of the webhook delivery that queued this message, for log correlation
+    pub event_id: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Clone, Deserialize)]
@@ -322,10 +670,22 @@ pub fn req(
 ) -> SlackRequest {
     SlackRequest {
         channel,
-        thread_guid,
         msg: msg.into(),
         attachments: attachments.into(),
+        blocks: vec![],
+        thread_guid,
         initial_thread,
+        event_id: None,
+    }
+}
+
+impl SlackRequest {
+    pub fn with_blocks(self, blocks: Vec<SlackBlock>) -> SlackRequest {
+        SlackRequest { blocks, ..self }
+    }
+
+    pub fn with_event_id(self, event_id: Option<String>) -> SlackRequest {
+        SlackRequest { event_id, ..self }
     }
 }
 
@@ -336,15 +696,352 @@ pub fn new_runner(slack: Arc<Slack>) -> Arc<dyn worker::Runner<SlackRequest>> {
 #[async_trait::async_trait]
 impl worker::Runner<SlackRequest> for Runner {
     async fn handle(&self, req: SlackRequest) {
+        correlation::scope(req.event_id.clone(), self.run(req)).await;
+    }
+}
+
+impl Runner {
+    async fn run(&self, req: SlackRequest) {
         self.slack
             .send(
                 &req.channel.id,
                 &req.channel.name,
                 &req.msg,
                 req.attachments,
+                req.blocks,
                 req.initial_thread,
                 req.thread_guid.unwrap_or_default().as_str(),
             )
             .await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_push_blocks_json() {
+        let mut builder = SlackBlockBuilder::new();
+        builder
+            .section("joe.sender pushed 2 commit(s) to branch some-branch")
+            .attachment(
+                &SlackAttachmentBuilder::new("")
+                    .title("Pull Request #32: \"The PR\"")
+                    .title_link("http://the-pr")
+                    .build(),
+            )
+            .attachment(&SlackAttachment::new("<http://commit1|aaaaaa0>: add stuff"))
+            .attachment(&SlackAttachment::new("<http://commit2|1111abc>: fix stuff"));
+
+        assert_eq!(
+            json!([
+                {
+                    "type": "section",
+                    "text": {
+                        "type": "mrkdwn",
+                        "text": "joe.sender pushed 2 commit(s) to branch some-branch"
+                    }
+                },
+                {
+                    "type": "section",
+                    "text": {
+                        "type": "mrkdwn",
+                        "text": "*<http://the-pr|Pull Request #32: \"The PR\">*"
+                    }
+                },
+                {
+                    "type": "context",
+                    "elements": [
+                        {"type": "mrkdwn", "text": "<http://commit1|aaaaaa0>: add stuff"}
+                    ]
+                },
+                {
+                    "type": "context",
+                    "elements": [
+                        {"type": "mrkdwn", "text": "<http://commit2|1111abc>: fix stuff"}
+                    ]
+                }
+            ]),
+            serde_json::to_value(builder.build()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_titled_attachment_block() {
+        let blocks = SlackBlockBuilder::new()
+            .attachment(
+                &SlackAttachmentBuilder::new("Size: small")
+                    .title("Pull Request #32")
+                    .build(),
+            )
+            .attachment(&SlackAttachment::new(""))
+            .build();
+
+        assert_eq!(
+            vec![SlackBlock::Section {
+                text: SlackText::markdown("*Pull Request #32*\nSize: small")
+            }],
+            blocks
+        );
+    }
+
+    #[test]
+    fn test_large_push_blocks_capped() {
+        let mut builder = SlackBlockBuilder::new();
+        builder.section("joe.sender pushed 60 commit(s) to branch some-branch");
+        for i in 0..60 {
+            builder.attachment(&SlackAttachment::new(&format!("commit {}", i)));
+        }
+        let blocks = builder.build();
+
+        assert_eq!(MAX_BLOCKS, blocks.len());
+        assert_eq!(
+            SlackBlock::Context {
+                elements: vec![SlackText::markdown("commit 47")]
+            },
+            blocks[MAX_BLOCKS - 2]
+        );
+        assert_eq!(
+            SlackBlock::Context {
+                elements: vec![SlackText::markdown("...and 12 more")]
+            },
+            blocks[MAX_BLOCKS - 1]
+        );
+    }
+
+    type RequestBodies = Arc<Mutex<Vec<String>>>;
+
+    // Serves the canned (status, body) responses in order, counting and recording requests received
+    async fn serve_responses(
+        responses: Vec<(u16, &'static str)>,
+    ) -> (String, Arc<std::sync::atomic::AtomicUsize>, RequestBodies) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let count = Arc::new(AtomicUsize::new(0));
+        let responses = Arc::new(Mutex::new(responses));
+        let bodies: RequestBodies = Arc::new(Mutex::new(vec![]));
+
+        let served = count.clone();
+        let received = bodies.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let served = served.clone();
+                let received = received.clone();
+                let responses = responses.clone();
+                tokio::spawn(async move {
+                    let mut buf = vec![];
+                    let mut chunk = [0u8; 4096];
+                    loop {
+                        // wait for a complete request: headers plus content-length body
+                        let text = String::from_utf8_lossy(&buf).to_string();
+                        if let Some(end) = text.find("\r\n\r\n") {
+                            let len = text[..end]
+                                .lines()
+                                .find_map(|l| {
+                                    l.to_lowercase()
+                                        .strip_prefix("content-length:")
+                                        .map(|v| v.trim().parse::<usize>().unwrap())
+                                })
+                                .unwrap_or(0);
+                            if buf.len() >= end + 4 + len {
+                                let body = String::from_utf8_lossy(&buf[end + 4..end + 4 + len]);
+                                received.lock().unwrap().push(body.to_string());
+                                buf.drain(..end + 4 + len);
+                                served.fetch_add(1, Ordering::SeqCst);
+                                let (status, body) = responses.lock().unwrap().remove(0);
+                                let resp = format!(
+                                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\n\
+                                     Content-Length: {}\r\n\r\n{}",
+                                    status,
+                                    body.len(),
+                                    body
+                                );
+                                stream.write_all(resp.as_bytes()).await.unwrap();
+                                continue;
+                            }
+                        }
+                        match stream.read(&mut chunk).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+                        }
+                    }
+                });
+            }
+        });
+
+        (format!("http://{}", addr), count, bodies)
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(None, retry_after(&headers));
+
+        headers.insert(reqwest::header::RETRY_AFTER, "30".parse().unwrap());
+        assert_eq!(Some(Duration::from_secs(30)), retry_after(&headers));
+
+        // HTTP dates aren't used by slack
+        headers.insert(
+            reqwest::header::RETRY_AFTER,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(None, retry_after(&headers));
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(Duration::from_secs(30), retry_delay(1));
+        assert_eq!(Duration::from_secs(60), retry_delay(2));
+        assert_eq!(Duration::from_secs(240), retry_delay(4));
+        assert_eq!(Duration::from_secs(3600), retry_delay(8));
+        assert_eq!(Duration::from_secs(3600), retry_delay(100));
+    }
+
+    #[tokio::test]
+    async fn test_send_retries_rate_limited_message() {
+        use std::sync::atomic::Ordering;
+
+        let (api_base, requests, _) = serve_responses(vec![
+            (429, r#"{"ok": false, "error": "ratelimited"}"#),
+            (200, r#"{"ok": true, "ts": "1234.5678"}"#),
+        ])
+        .await;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = |name: &str| temp_dir.path().join(name).to_string_lossy().to_string();
+        let slack = Slack::new_with_api_base(
+            &api_base,
+            "the-token".into(),
+            db_path("slack.sqlite3"),
+            db_path("slack_retry.sqlite3"),
+            Metrics::new(),
+        );
+
+        slack
+            .send(
+                "C123",
+                "the-channel",
+                "hello",
+                vec![],
+                vec![],
+                true,
+                "the-guid",
+            )
+            .await;
+        assert_eq!(1, requests.load(Ordering::SeqCst));
+
+        // not due yet
+        let now = SystemTime::now();
+        slack.retry_pending(now).await;
+        assert_eq!(1, requests.load(Ordering::SeqCst));
+
+        slack.retry_pending(now + retry_delay(1)).await;
+        assert_eq!(2, requests.load(Ordering::SeqCst));
+
+        // delivered: nothing left to retry, and the thread is remembered
+        slack.retry_pending(now + 10 * RETRY_MAX_DELAY).await;
+        assert_eq!(2, requests.load(Ordering::SeqCst));
+        assert_eq!(
+            Some("1234.5678".to_string()),
+            slack
+                .slack_db
+                .lock()
+                .unwrap()
+                .lookup_previous_thread("the-guid", "C123")
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_drops_permanent_failure() {
+        use std::sync::atomic::Ordering;
+
+        let (api_base, requests, _) = serve_responses(vec![(
+            200,
+            r#"{"ok": false, "error": "channel_not_found"}"#,
+        )])
+        .await;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = |name: &str| temp_dir.path().join(name).to_string_lossy().to_string();
+        let slack = Slack::new_with_api_base(
+            &api_base,
+            "the-token".into(),
+            db_path("slack.sqlite3"),
+            db_path("slack_retry.sqlite3"),
+            Metrics::new(),
+        );
+
+        slack
+            .send("C123", "the-channel", "hello", vec![], vec![], false, "")
+            .await;
+        assert_eq!(1, requests.load(Ordering::SeqCst));
+
+        // not queued for retry
+        slack
+            .retry_pending(SystemTime::now() + 10 * RETRY_MAX_DELAY)
+            .await;
+        assert_eq!(1, requests.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_send_replies_in_thread() {
+        let (api_base, _, bodies) = serve_responses(vec![
+            (200, r#"{"ok": true, "ts": "1234.5678"}"#),
+            (200, r#"{"ok": true, "ts": "1234.9999"}"#),
+            (200, r#"{"ok": true, "ts": "1235.0000"}"#),
+        ])
+        .await;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = |name: &str| temp_dir.path().join(name).to_string_lossy().to_string();
+        let slack = Arc::new(Slack::new_with_api_base(
+            &api_base,
+            "the-token".into(),
+            db_path("slack.sqlite3"),
+            db_path("slack_retry.sqlite3"),
+            Metrics::new(),
+        ));
+        let runner = new_runner(slack);
+
+        let channel = SlackRecipient::new("C123", "the-channel");
+        runner
+            .handle(req(
+                channel.clone(),
+                "opened",
+                &[],
+                Some("the-guid".into()),
+                true,
+            ))
+            .await;
+        runner
+            .handle(req(
+                channel.clone(),
+                "merged",
+                &[],
+                Some("the-guid".into()),
+                false,
+            ))
+            .await;
+        // a reopened PR's announcement goes to its existing thread too
+        runner
+            .handle(req(channel, "reopened", &[], Some("the-guid".into()), true))
+            .await;
+
+        let thread_ts = |body: &str| {
+            let value: serde_json::Value = serde_json::from_str(body).unwrap();
+            value["thread_ts"].as_str().map(|ts| ts.to_string())
+        };
+        let bodies = bodies.lock().unwrap();
+        assert_eq!(3, bodies.len());
+        assert_eq!(None, thread_ts(&bodies[0]));
+        assert_eq!(Some("1234.5678".to_string()), thread_ts(&bodies[1]));
+        assert_eq!(Some("1234.5678".to_string()), thread_ts(&bodies[2]));
+    }
+}
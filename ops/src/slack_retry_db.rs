@@ -0,0 +1,169 @@
+use std::time::{Duration, SystemTime};
+
+use anyhow::anyhow;
+use rusqlite::types::ToSql;
+
+use octobot_lib::db::{self, migrations, Connection, Database};
+use octobot_lib::errors::*;
+
+use crate::slack_retry_db_migrations;
+
+// A slack message which failed to send with a retryable error
+#[derive(Debug, PartialEq, Clone)]
+pub struct PendingMessage {
+    pub id: i64,
+    pub channel_name: String,
+    // the serialized chat.postMessage body
+    pub message: String,
+    pub initial_thread: bool,
+    pub thread_guid: String,
+    // sends attempted so far
+    pub attempts: u32,
+}
+
+// Persists slack messages waiting to be retried so they survive restarts
+#[derive(Clone)]
+pub struct SlackRetryDatabase {
+    db: Database,
+}
+
+impl SlackRetryDatabase {
+    pub fn new(db_file: &str) -> Result<SlackRetryDatabase> {
+        let db = Database::new(db_file)?;
+
+        let mut connection = db.connect()?;
+
+        let migrations = slack_retry_db_migrations::all_migrations();
+        migrations::migrate(&mut connection, &migrations)?;
+
+        Ok(SlackRetryDatabase { db })
+    }
+
+    fn connect(&self) -> Result<Connection> {
+        self.db.connect()
+    }
+
+    pub fn enqueue(
+        &self,
+        channel_name: &str,
+        message: &str,
+        initial_thread: bool,
+        thread_guid: &str,
+        next_attempt: SystemTime,
+    ) -> Result<()> {
+        let conn = self.connect()?;
+        conn.execute(
+            r#"INSERT INTO pending_messages (channel_name, message, initial_thread, thread_guid,
+                                             attempts, next_attempt)
+               VALUES (?1, ?2, ?3, ?4, 1, ?5)"#,
+            [
+                &channel_name as &dyn ToSql,
+                &message,
+                &db::to_tinyint(initial_thread),
+                &thread_guid,
+                &to_timestamp(next_attempt)?,
+            ],
+        )
+        .map_err(|e| anyhow!("Error queueing slack message to {}: {}", channel_name, e))?;
+
+        Ok(())
+    }
+
+    // Messages whose next attempt is due, oldest first
+    pub fn due(&self, now: SystemTime) -> Result<Vec<PendingMessage>> {
+        let conn = self.connect()?;
+        let mut stmt = conn.prepare(
+            r#"SELECT id, channel_name, message, initial_thread, thread_guid, attempts
+               FROM pending_messages WHERE next_attempt <= ?1 ORDER BY id"#,
+        )?;
+        let found = stmt
+            .query_map([&to_timestamp(now)?], |row| {
+                Ok(PendingMessage {
+                    id: row.get(0)?,
+                    channel_name: row.get(1)?,
+                    message: row.get(2)?,
+                    initial_thread: db::to_bool(row.get(3)?),
+                    thread_guid: row.get(4)?,
+                    attempts: row.get(5)?,
+                })
+            })
+            .map_err(|e| anyhow!("Error fetching pending slack messages: {}", e))?;
+
+        let mut result = vec![];
+        for pending in found {
+            result.push(pending?);
+        }
+
+        Ok(result)
+    }
+
+    pub fn reschedule(&self, id: i64, attempts: u32, next_attempt: SystemTime) -> Result<()> {
+        let conn = self.connect()?;
+        conn.execute(
+            "UPDATE pending_messages SET attempts = ?1, next_attempt = ?2 WHERE id = ?3",
+            [&attempts as &dyn ToSql, &to_timestamp(next_attempt)?, &id],
+        )
+        .map_err(|e| anyhow!("Error rescheduling slack message {}: {}", id, e))?;
+
+        Ok(())
+    }
+
+    pub fn remove(&self, id: i64) -> Result<()> {
+        let conn = self.connect()?;
+        conn.execute("DELETE FROM pending_messages WHERE id = ?1", [&id])
+            .map_err(|e| anyhow!("Error removing slack message {}: {}", id, e))?;
+
+        Ok(())
+    }
+}
+
+fn to_timestamp(time: SystemTime) -> Result<u64> {
+    Ok(time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::{tempdir, TempDir};
+
+    fn new_test() -> (SlackRetryDatabase, TempDir) {
+        let temp_dir = tempdir().unwrap();
+        let db_file = temp_dir.path().join("slack_retry.sqlite3");
+        let db = SlackRetryDatabase::new(&db_file.to_string_lossy()).expect("create temp database");
+
+        (db, temp_dir)
+    }
+
+    #[test]
+    fn test_pending_messages() {
+        let (db, _temp) = new_test();
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let minute = Duration::from_secs(60);
+
+        db.enqueue("the-channel", "{}", true, "the-guid", now)
+            .unwrap();
+        db.enqueue("other-channel", "{}", false, "", now + minute)
+            .unwrap();
+
+        let due = db.due(now).unwrap();
+        assert_eq!(1, due.len());
+        assert_eq!("the-channel", due[0].channel_name);
+        assert!(due[0].initial_thread);
+        assert_eq!("the-guid", due[0].thread_guid);
+        assert_eq!(1, due[0].attempts);
+
+        db.reschedule(due[0].id, 2, now + 2 * minute).unwrap();
+        let due = db.due(now + minute).unwrap();
+        assert_eq!(1, due.len());
+        assert_eq!("other-channel", due[0].channel_name);
+
+        db.remove(due[0].id).unwrap();
+        let due = db.due(now + 2 * minute).unwrap();
+        assert_eq!(1, due.len());
+        assert_eq!("the-channel", due[0].channel_name);
+        assert_eq!(2, due[0].attempts);
+    }
+}
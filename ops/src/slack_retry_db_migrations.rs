@@ -0,0 +1,15 @@
+use octobot_lib::db::migrations::{sql, Migration};
+
+pub fn all_migrations() -> Vec<Box<dyn Migration>> {
+    vec![sql(r#"
+    create table pending_messages (
+      id integer PRIMARY KEY AUTOINCREMENT,
+      channel_name varchar not null,
+      message varchar not null,
+      initial_thread tinyint not null,
+      thread_guid varchar not null,
+      attempts integer not null,
+      next_attempt integer not null
+    );
+    "#)]
+}
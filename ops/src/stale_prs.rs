@@ -0,0 +1,436 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use log::{error, info};
+
+use crate::messenger::{Messenger, Participants};
+use crate::util;
+use crate::webhook_db::WebhookDatabase;
+use octobot_lib::config::Config;
+use octobot_lib::github;
+use octobot_lib::github::api::{GithubSessionFactory, Session};
+
+// Remembers which stale PRs have been nagged about (and how many times) so that each one is only
+// nagged again once the nag interval has passed.
+pub struct StalePRNags {
+    nagged: Mutex<HashMap<(String, u32), (SystemTime, u32)>>,
+}
+
+impl StalePRNags {
+    pub fn new() -> StalePRNags {
+        StalePRNags {
+            nagged: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Returns the escalation level of the nag due for the PR, if any: 1 for the first nag,
+    // increasing with each interval the PR stays stale.
+    pub fn next_nag(
+        &self,
+        repo: &str,
+        number: u32,
+        now: SystemTime,
+        interval: Duration,
+    ) -> Option<u32> {
+        let mut nagged = self.nagged.lock().unwrap();
+        match nagged.entry((repo.to_string(), number)) {
+            Entry::Vacant(e) => {
+                e.insert((now, 1));
+                Some(1)
+            }
+            Entry::Occupied(mut e) => {
+                let (last, level) = *e.get();
+                let elapsed = now.duration_since(last).unwrap_or_default();
+                if elapsed < interval {
+                    return None;
+                }
+                e.insert((now, level + 1));
+                Some(level + 1)
+            }
+        }
+    }
+
+    // Forgets the repo's PRs which are no longer stale, so they start over if they go stale again
+    pub fn retain(&self, repo: &str, stale: &[u32]) {
+        self.nagged
+            .lock()
+            .unwrap()
+            .retain(|(r, number), _| r != repo || stale.contains(number));
+    }
+}
+
+impl Default for StalePRNags {
+    fn default() -> StalePRNags {
+        StalePRNags::new()
+    }
+}
+
+// Whether the PR is an open, non-draft PR created at least `threshold` ago.
+// Reviews are not considered here since they are not included in PR listings.
+pub fn is_stale(pr: &github::PullRequest, now: SystemTime, threshold: Duration) -> bool {
+    if pr.state != "open" || pr.is_draft() {
+        return false;
+    }
+    match pr.created_time() {
+        Some(created) => now.duration_since(created).unwrap_or_default() >= threshold,
+        None => false,
+    }
+}
+
+pub fn nag_message(pr: &github::PullRequest, age: Duration, level: u32) -> String {
+    let hours = age.as_secs() / 3600;
+    let age_desc = if hours >= 48 {
+        format!("{} days", hours / 24)
+    } else {
+        format!("{} hours", hours)
+    };
+    let msg = format!(
+        "Pull Request \"{}\" has been open for {} without a review",
+        util::make_link(&pr.html_url, &pr.title),
+        age_desc
+    );
+    if level > 1 {
+        format!(":warning: Still waiting: {} (reminder #{})", msg, level)
+    } else {
+        msg
+    }
+}
+
+// Whether the PR is an open, non-draft PR without any activity for at least `threshold`
+pub fn is_inactive(pr: &github::PullRequest, now: SystemTime, threshold: Duration) -> bool {
+    if pr.state != "open" || pr.is_draft() {
+        return false;
+    }
+    match pr.updated_time() {
+        Some(updated) => now.duration_since(updated).unwrap_or_default() >= threshold,
+        None => false,
+    }
+}
+
+pub fn reminder_message(pr: &github::PullRequest, days: u64) -> String {
+    format!(
+        "Reminder: Pull Request \"{}\" has had no activity for {} day{}",
+        util::make_link(&pr.html_url, &pr.title),
+        days,
+        if days == 1 { "" } else { "s" }
+    )
+}
+
+// Nags each configured repo's channel about PRs open longer than the configured threshold
+// without any reviews, and reminds assignees and reviewers of PRs without recent activity in
+// repos with `stale_pr_days` set.
+pub async fn nag_stale_prs(
+    config: &Config,
+    github_app: &dyn GithubSessionFactory,
+    messenger: &Messenger,
+    nags: &StalePRNags,
+    webhook_db: &WebhookDatabase,
+    now: SystemTime,
+) {
+    let threshold = config.slack.stale_pr_threshold();
+
+    let repos = match config.repos().get_all() {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Error looking up repos to check for stale PRs: {}", e);
+            return;
+        }
+    };
+
+    for info in repos {
+        let stale_pr_days = info.stale_pr_days.filter(|d| *d > 0);
+        if threshold.is_none() && stale_pr_days.is_none() {
+            continue;
+        }
+
+        // skip org-wide entries
+        let (owner, name) = match info.repo.split_once('/') {
+            Some(r) => r,
+            None => continue,
+        };
+
+        let session = match github_app.new_session(owner, name).await {
+            Ok(s) => s,
+            Err(e) => {
+                error!("Error creating github session for {}: {}", info.repo, e);
+                continue;
+            }
+        };
+
+        let prs = match session
+            .get_pull_requests(owner, name, Some("open"), None)
+            .await
+        {
+            Ok(p) => p,
+            Err(e) => {
+                error!("Error looking up open PRs for {}: {}", info.repo, e);
+                continue;
+            }
+        };
+
+        if let Some(threshold) = threshold {
+            nag_unreviewed_prs(
+                config, &session, messenger, nags, &info.repo, &prs, now, threshold,
+            )
+            .await;
+        }
+        if let Some(days) = stale_pr_days {
+            remind_inactive_prs(messenger, webhook_db, &info.repo, &prs, now, days);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn nag_unreviewed_prs(
+    config: &Config,
+    session: &dyn Session,
+    messenger: &Messenger,
+    nags: &StalePRNags,
+    repo: &str,
+    prs: &[github::PullRequest],
+    now: SystemTime,
+    threshold: Duration,
+) {
+    let interval = config.slack.stale_pr_nag_interval();
+    let (owner, name) = repo.split_once('/').unwrap_or_default();
+
+    let mut stale = vec![];
+    for pr in prs.iter().filter(|pr| is_stale(pr, now, threshold)) {
+        match session
+            .get_pull_request_reviews(owner, name, pr.number)
+            .await
+        {
+            Ok(reviews) if reviews.is_empty() => (),
+            Ok(_) => continue,
+            Err(e) => {
+                error!("Error looking up reviews for {}#{}: {}", repo, pr.number, e);
+                continue;
+            }
+        };
+        stale.push(pr.number);
+
+        if let Some(level) = nags.next_nag(repo, pr.number, now, interval) {
+            info!("Nagging about stale PR {}#{}", repo, pr.number);
+            let age = pr
+                .created_time()
+                .and_then(|c| now.duration_since(c).ok())
+                .unwrap_or_default();
+            messenger.send_to_channel(
+                &nag_message(pr, age, level),
+                &[],
+                &pr.base.repo,
+                &pr.base.ref_name,
+                &Vec::<github::Commit>::new(),
+                vec![],
+                false,
+            );
+        }
+    }
+    nags.retain(repo, &stale);
+}
+
+// Reminds assignees and reviewers of inactive PRs, at most once a day per PR
+fn remind_inactive_prs(
+    messenger: &Messenger,
+    webhook_db: &WebhookDatabase,
+    repo: &str,
+    prs: &[github::PullRequest],
+    now: SystemTime,
+    days: u32,
+) {
+    let day = Duration::from_secs(24 * 3600);
+    for pr in prs.iter().filter(|pr| is_inactive(pr, now, days * day)) {
+        match webhook_db.last_stale_pr_reminder(repo, pr.number) {
+            Ok(Some(last)) if now.duration_since(last).unwrap_or_default() < day => continue,
+            Ok(_) => (),
+            Err(e) => {
+                error!(
+                    "Error looking up last reminder for {}#{}: {}",
+                    repo, pr.number, e
+                );
+                continue;
+            }
+        };
+
+        let mut participants = Participants::new();
+        for user in &pr.assignees {
+            participants.add_user(user.clone());
+        }
+        for user in pr.requested_reviewers.as_deref().unwrap_or(&[]) {
+            participants.add_user(user.clone());
+        }
+        if participants.is_empty() {
+            continue;
+        }
+
+        info!("Reminding about inactive PR {}#{}", repo, pr.number);
+        let inactive_days = pr
+            .updated_time()
+            .and_then(|u| now.duration_since(u).ok())
+            .unwrap_or_default()
+            .as_secs()
+            / day.as_secs();
+        messenger.send_to_users(
+            &reminder_message(pr, inactive_days),
+            &[],
+            &pr.base.repo,
+            participants,
+        );
+
+        if let Err(e) = webhook_db.record_stale_pr_reminder(repo, pr.number, now) {
+            error!("Error recording reminder for {}#{}: {}", repo, pr.number, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_pr(created_at: &str) -> github::PullRequest {
+        let mut pr = github::PullRequest::new();
+        pr.number = 5;
+        pr.title = "The PR".into();
+        pr.html_url = "http://the-pr".into();
+        pr.created_at = Some(created_at.into());
+        pr
+    }
+
+    fn at(rfc3339: &str) -> SystemTime {
+        new_pr(rfc3339).created_time().unwrap()
+    }
+
+    #[test]
+    fn test_is_stale() {
+        let now = at("2023-05-10T12:00:00Z");
+        let day = Duration::from_secs(24 * 3600);
+
+        assert!(is_stale(&new_pr("2023-05-09T12:00:00Z"), now, day));
+        assert!(is_stale(&new_pr("2023-05-01T00:00:00Z"), now, day));
+        assert!(!is_stale(&new_pr("2023-05-09T12:00:01Z"), now, day));
+
+        let mut draft = new_pr("2023-05-01T00:00:00Z");
+        draft.draft = Some(true);
+        assert!(!is_stale(&draft, now, day));
+
+        let mut closed = new_pr("2023-05-01T00:00:00Z");
+        closed.state = "closed".into();
+        assert!(!is_stale(&closed, now, day));
+
+        let mut unknown = new_pr("2023-05-01T00:00:00Z");
+        unknown.created_at = None;
+        assert!(!is_stale(&unknown, now, day));
+    }
+
+    #[test]
+    fn test_is_inactive() {
+        let now = at("2023-05-10T12:00:00Z");
+        let day = Duration::from_secs(24 * 3600);
+
+        let updated = |updated_at: &str| {
+            let mut pr = new_pr("2023-05-01T00:00:00Z");
+            pr.updated_at = Some(updated_at.into());
+            pr
+        };
+
+        assert!(is_inactive(&updated("2023-05-07T12:00:00Z"), now, 3 * day));
+        assert!(!is_inactive(&updated("2023-05-07T12:00:01Z"), now, 3 * day));
+
+        let mut draft = updated("2023-05-01T00:00:00Z");
+        draft.draft = Some(true);
+        assert!(!is_inactive(&draft, now, day));
+
+        let mut closed = updated("2023-05-01T00:00:00Z");
+        closed.state = "closed".into();
+        assert!(!is_inactive(&closed, now, day));
+
+        // only the creation time is known
+        assert!(!is_inactive(&new_pr("2023-05-01T00:00:00Z"), now, day));
+    }
+
+    #[test]
+    fn test_reminder_message() {
+        let pr = new_pr("2023-05-01T00:00:00Z");
+
+        assert_eq!(
+            "Reminder: Pull Request \"<http://the-pr|The PR>\" has had no activity for 1 day",
+            reminder_message(&pr, 1)
+        );
+        assert_eq!(
+            "Reminder: Pull Request \"<http://the-pr|The PR>\" has had no activity for 4 days",
+            reminder_message(&pr, 4)
+        );
+    }
+
+    #[test]
+    fn test_next_nag_dedupes_and_escalates() {
+        let nags = StalePRNags::new();
+        let hour = Duration::from_secs(3600);
+        let start = at("2023-05-10T12:00:00Z");
+
+        assert_eq!(Some(1), nags.next_nag("some/repo", 5, start, 24 * hour));
+        assert_eq!(None, nags.next_nag("some/repo", 5, start + hour, 24 * hour));
+        assert_eq!(
+            Some(1),
+            nags.next_nag("some/repo", 6, start + hour, 24 * hour)
+        );
+        assert_eq!(
+            Some(1),
+            nags.next_nag("other/repo", 5, start + hour, 24 * hour)
+        );
+        assert_eq!(
+            Some(2),
+            nags.next_nag("some/repo", 5, start + 24 * hour, 24 * hour)
+        );
+        assert_eq!(
+            None,
+            nags.next_nag("some/repo", 5, start + 30 * hour, 24 * hour)
+        );
+        assert_eq!(
+            Some(3),
+            nags.next_nag("some/repo", 5, start + 48 * hour, 24 * hour)
+        );
+    }
+
+    #[test]
+    fn test_retain_forgets_no_longer_stale() {
+        let nags = StalePRNags::new();
+        let hour = Duration::from_secs(3600);
+        let start = at("2023-05-10T12:00:00Z");
+
+        nags.next_nag("some/repo", 5, start, 24 * hour);
+        nags.next_nag("some/repo", 6, start, 24 * hour);
+        nags.next_nag("other/repo", 5, start, 24 * hour);
+
+        // 5 was reviewed
+        nags.retain("some/repo", &[6]);
+
+        assert_eq!(
+            Some(1),
+            nags.next_nag("some/repo", 5, start + hour, 24 * hour)
+        );
+        assert_eq!(None, nags.next_nag("some/repo", 6, start + hour, 24 * hour));
+        assert_eq!(
+            None,
+            nags.next_nag("other/repo", 5, start + hour, 24 * hour)
+        );
+    }
+
+    #[test]
+    fn test_nag_message() {
+        let pr = new_pr("2023-05-01T00:00:00Z");
+        let hour = Duration::from_secs(3600);
+
+        assert_eq!(
+            "Pull Request \"<http://the-pr|The PR>\" has been open for 30 hours without a review",
+            nag_message(&pr, 30 * hour, 1)
+        );
+        assert_eq!(
+            ":warning: Still waiting: Pull Request \"<http://the-pr|The PR>\" has been open for \
+             3 days without a review (reminder #2)",
+            nag_message(&pr, 72 * hour, 2)
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// A token bucket spacing out requests to an average rate while allowing short bursts. Callers
+// pass in the current time so the schedule can be tested without waiting.
+pub struct TokenBucket {
+    capacity: f64,
+    // tokens added per second
+    rate: f64,
+    state: Mutex<State>,
+}
+
+struct State {
+    // tokens available as of `updated`. Negative once requests are waiting for tokens.
+    tokens: f64,
+    updated: Instant,
+    paused_until: Option<Instant>,
+}
+
+impl TokenBucket {
+    // A full bucket refilling at `per_minute` tokens per minute, holding up to ten seconds' worth
+    pub fn new(per_minute: u32, now: Instant) -> TokenBucket {
+        let rate = f64::from(per_minute.max(1)) / 60.0;
+        let capacity = (rate * 10.0).floor().max(1.0);
+        TokenBucket {
+            capacity,
+            rate,
+            state: Mutex::new(State {
+                tokens: capacity,
+                updated: now,
+                paused_until: None,
+            }),
+        }
+    }
+
+    // Takes a token, returning how long to wait before using it
+    pub fn reserve(&self, now: Instant) -> Duration {
+        let mut state = self.state.lock().unwrap();
+        if now > state.updated {
+            let elapsed = now.duration_since(state.updated).as_secs_f64();
+            state.tokens = (state.tokens + elapsed * self.rate).min(self.capacity);
+            state.updated = now;
+        }
+
+        state.tokens -= 1.0;
+        let available = if state.tokens < 0.0 {
+            state.updated + Duration::from_secs_f64(-state.tokens / self.rate)
+        } else {
+            state.updated
+        };
+        available.saturating_duration_since(now)
+    }
+
+    // Stops handing out tokens until the given time, e.g. when told to back off
+    pub fn pause_until(&self, until: Instant) {
+        let mut state = self.state.lock().unwrap();
+        if state.paused_until.is_some_and(|p| p >= until) {
+            return;
+        }
+        state.paused_until = Some(until);
+
+        // start refilling from empty once the pause is over, rather than bursting
+        if until > state.updated {
+            state.updated = until;
+        }
+        state.tokens = state.tokens.min(0.0);
+    }
+
+    // How much longer a pause lasts, if any
+    pub fn pause_remaining(&self, now: Instant) -> Duration {
+        let state = self.state.lock().unwrap();
+        state
+            .paused_until
+            .map_or(Duration::ZERO, |p| p.saturating_duration_since(now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spaces_out_bursts() {
+        let start = Instant::now();
+        let bucket = TokenBucket::new(60, start);
+
+        // 100 messages queued at once: ten go right away, the rest one per second
+        let waits = (0..100).map(|_| bucket.reserve(start)).collect::<Vec<_>>();
+        for (i, wait) in waits.iter().enumerate() {
+            let expected = Duration::from_secs(i.saturating_sub(9) as u64);
+            assert!(
+                (wait.as_secs_f64() - expected.as_secs_f64()).abs() < 0.001,
+                "message {}: expected {:?}, got {:?}",
+                i,
+                expected,
+                wait
+            );
+        }
+
+        // the queue drains at the configured rate
+        let later = start + Duration::from_secs(60);
+        let wait = bucket.reserve(later);
+        assert!((wait.as_secs_f64() - 31.0).abs() < 0.001, "{:?}", wait);
+    }
+
+    #[test]
+    fn test_refills_up_to_capacity() {
+        let start = Instant::now();
+        let bucket = TokenBucket::new(120, start);
+        for _ in 0..20 {
+            assert_eq!(Duration::ZERO, bucket.reserve(start));
+        }
+        assert!(bucket.reserve(start) > Duration::ZERO);
+
+        // idle for an hour: still only a burst of 20
+        let later = start + Duration::from_secs(3600);
+        for _ in 0..20 {
+            assert_eq!(Duration::ZERO, bucket.reserve(later));
+        }
+        let wait = bucket.reserve(later);
+        assert!((wait.as_secs_f64() - 0.5).abs() < 0.001, "{:?}", wait);
+    }
+
+    #[test]
+    fn test_pause() {
+        let start = Instant::now();
+        let bucket = TokenBucket::new(60, start);
+        assert_eq!(Duration::ZERO, bucket.pause_remaining(start));
+
+        bucket.pause_until(start + Duration::from_secs(30));
+        assert_eq!(Duration::from_secs(30), bucket.pause_remaining(start));
+        assert_eq!(
+            Duration::from_secs(10),
+            bucket.pause_remaining(start + Duration::from_secs(20))
+        );
+
+        // a shorter pause doesn't cut the current one short
+        bucket.pause_until(start + Duration::from_secs(5));
+        assert_eq!(Duration::from_secs(30), bucket.pause_remaining(start));
+
+        // after the pause, tokens trickle back in rather than all at once
+        let wait = bucket.reserve(start);
+        assert!((wait.as_secs_f64() - 31.0).abs() < 0.001, "{:?}", wait);
+        let wait = bucket.reserve(start + Duration::from_secs(30));
+        assert!((wait.as_secs_f64() - 2.0).abs() < 0.001, "{:?}", wait);
+    }
+}
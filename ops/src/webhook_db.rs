@@ -1,12 +1,13 @@
 use std::sync::Mutex;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 use anyhow::anyhow;
 use rusqlite::types::ToSql;
 use rusqlite::Connection;
 
-use octobot_lib::db::{migrations, Database};
+use octobot_lib::db::{self, migrations, Database};
 use octobot_lib::errors::*;
+use octobot_lib::jira::workflow::ResolvedCommits;
 
 use crate::util;
 use crate::webhook_db_migrations;
@@ -37,6 +38,13 @@ impl WebhookDatabase {
         })
     }
 
+    // Runs a trivial query to verify the database is usable
+    pub fn check(&self) -> Result<()> {
+        let data = self.data.lock().unwrap();
+        data.db.connect()?.query_row("SELECT 1", [], |_| Ok(()))?;
+        Ok(())
+    }
+
     pub fn get_latest_guid(&self) -> Result<Option<String>> {
         let data = self.data.lock().unwrap();
         let connection = data.db.connect()?;
@@ -119,6 +127,39 @@ impl WebhookDatabase {
         stmt.exists([&guid]).map_err(|e| anyhow!("{}", e))
     }
 
+    // When assignees and reviewers of the PR were last reminded that it is inactive, if ever
+    pub fn last_stale_pr_reminder(&self, repo: &str, number: u32) -> Result<Option<SystemTime>> {
+        let data = self.data.lock().unwrap();
+        let conn = data.db.connect()?;
+        let mut stmt = conn
+            .prepare("SELECT timestamp FROM stale_pr_reminders where repo = ?1 and number = ?2")?;
+        let mut rows = stmt.query([&repo as &dyn ToSql, &number])?;
+
+        match rows.next()? {
+            Some(row) => {
+                let timestamp: u64 = row.get(0)?;
+                Ok(Some(
+                    SystemTime::UNIX_EPOCH + Duration::from_secs(timestamp),
+                ))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn record_stale_pr_reminder(&self, repo: &str, number: u32, now: SystemTime) -> Result<()> {
+        let timestamp = now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+
+        let data = self.data.lock().unwrap();
+        let conn = data.db.connect()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO stale_pr_reminders (repo, number, timestamp) VALUES (?1, ?2, ?3)",
+            [&repo as &dyn ToSql, &number, &timestamp],
+        )
+        .map_err(|e| anyhow!("Error recording stale PR reminder {}#{}: {}", repo, number, e))?;
+
+        Ok(())
+    }
+
     pub fn clean(&self, expiration: SystemTime) -> Result<()> {
         let deadline = expiration.duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
 
@@ -132,6 +173,173 @@ impl WebhookDatabase {
             [&deadline as &dyn ToSql],
         )
         .map_err(|e| anyhow!("Error cleaning webhook db: {}", e))?;
+        conn.execute(
+            "DELETE FROM stale_pr_reminders where timestamp < ?1",
+            [&deadline as &dyn ToSql],
+        )
+        .map_err(|e| anyhow!("Error cleaning stale PR reminders: {}", e))?;
+        conn.execute(
+            "DELETE FROM resolved_jira_commits where timestamp < ?1",
+            [&deadline as &dyn ToSql],
+        )
+        .map_err(|e| anyhow!("Error cleaning resolved JIRA commits: {}", e))?;
+
+        Ok(())
+    }
+
+    // Persists a serialized JIRA review request to retry at `next_attempt`, so that it survives
+    // restarts
+    pub fn queue_jira_review(&self, request: &str, next_attempt: SystemTime) -> Result<()> {
+        let next_attempt = next_attempt
+            .duration_since(SystemTime::UNIX_EPOCH)?
+            .as_secs();
+
+        let data = self.data.lock().unwrap();
+        let conn = data.db.connect()?;
+        conn.execute(
+            "INSERT INTO pending_jira_reviews (request, next_attempt) VALUES (?1, ?2)",
+            [&request as &dyn ToSql, &next_attempt],
+        )
+        .map_err(|e| anyhow!("Error queueing JIRA review: {}", e))?;
+
+        Ok(())
+    }
+
+    // Removes and returns the queued JIRA review requests that are due, oldest first
+    pub fn take_due_jira_reviews(&self, now: SystemTime) -> Result<Vec<String>> {
+        let now = now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+
+        let data = self.data.lock().unwrap();
+        let mut conn = data.db.connect()?;
+        let tx = conn.transaction()?;
+        let due = {
+            let mut stmt = tx.prepare(
+                "SELECT request FROM pending_jira_reviews WHERE next_attempt <= ?1 ORDER BY id",
+            )?;
+            let found = stmt.query_map([&now], |row| row.get(0))?;
+            let mut due = vec![];
+            for request in found {
+                due.push(request?);
+            }
+            due
+        };
+        tx.execute(
+            "DELETE FROM pending_jira_reviews WHERE next_attempt <= ?1",
+            [&now],
+        )
+        .map_err(|e| anyhow!("Error removing due JIRA reviews: {}", e))?;
+        tx.commit()?;
+
+        Ok(due)
+    }
+
+    // The number of unresolved review conversations on the PR, if any are known
+    pub fn unresolved_review_threads(&self, repo: &str, number: u32) -> Result<Option<usize>> {
+        let data = self.data.lock().unwrap();
+        let conn = data.db.connect()?;
+        Self::get_unresolved_review_threads(&conn, repo, number)
+    }
+
+    fn get_unresolved_review_threads(
+        conn: &Connection,
+        repo: &str,
+        number: u32,
+    ) -> Result<Option<usize>> {
+        let mut stmt =
+            conn.prepare("SELECT unresolved FROM review_threads where repo = ?1 and number = ?2")?;
+        let mut rows = stmt.query([&repo as &dyn ToSql, &number])?;
+
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    // Records a review conversation started or unresolved on the PR
+    pub fn add_review_thread(&self, repo: &str, number: u32) -> Result<()> {
+        let data = self.data.lock().unwrap();
+        let conn = data.db.connect()?;
+        conn.execute(
+            "INSERT INTO review_threads (repo, number, unresolved) VALUES (?1, ?2, 1) \
+             ON CONFLICT (repo, number) DO UPDATE SET unresolved = unresolved + 1",
+            [&repo as &dyn ToSql, &number],
+        )
+        .map_err(|e| anyhow!("Error adding review thread {}#{}: {}", repo, number, e))?;
+
+        Ok(())
+    }
+
+    // Records a review conversation resolved on the PR. Returns the number still unresolved, if
+    // any were known.
+    pub fn resolve_review_thread(&self, repo: &str, number: u32) -> Result<Option<usize>> {
+        let data = self.data.lock().unwrap();
+        let mut conn = data.db.connect()?;
+        let tx = conn.transaction()?;
+        let remaining = match Self::get_unresolved_review_threads(&tx, repo, number)? {
+            Some(count) => count.saturating_sub(1),
+            None => return Ok(None),
+        };
+        if remaining == 0 {
+            tx.execute(
+                "DELETE FROM review_threads where repo = ?1 and number = ?2",
+                [&repo as &dyn ToSql, &number],
+            )
+        } else {
+            tx.execute(
+                "UPDATE review_threads SET unresolved = ?3 where repo = ?1 and number = ?2",
+                [&repo as &dyn ToSql, &number, &remaining],
+            )
+        }
+        .map_err(|e| anyhow!("Error resolving review thread {}#{}: {}", repo, number, e))?;
+        tx.commit()?;
+
+        Ok(Some(remaining))
+    }
+
+    pub fn clear_review_threads(&self, repo: &str, number: u32) -> Result<()> {
+        let data = self.data.lock().unwrap();
+        let conn = data.db.connect()?;
+        conn.execute(
+            "DELETE FROM review_threads where repo = ?1 and number = ?2",
+            [&repo as &dyn ToSql, &number],
+        )
+        .map_err(|e| anyhow!("Error clearing review threads {}#{}: {}", repo, number, e))?;
+
+        Ok(())
+    }
+}
+
+impl ResolvedCommits for WebhookDatabase {
+    fn was_resolved(&self, key: &str, commit_sha: &str, versioned: bool) -> Result<bool> {
+        let data = self.data.lock().unwrap();
+        let conn = data.db.connect()?;
+        let mut stmt = conn.prepare(
+            "SELECT 1 FROM resolved_jira_commits \
+             where jira_key = ?1 and commit_sha = ?2 and versioned = ?3",
+        )?;
+
+        stmt.exists([&key as &dyn ToSql, &commit_sha, &db::to_tinyint(versioned)])
+            .map_err(|e| anyhow!("{}", e))
+    }
+
+    fn mark_resolved(&self, key: &str, commit_sha: &str, versioned: bool) -> Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)?
+            .as_secs();
+
+        let data = self.data.lock().unwrap();
+        let conn = data.db.connect()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO resolved_jira_commits (jira_key, commit_sha, versioned, timestamp) \
+             VALUES (?1, ?2, ?3, ?4)",
+            [
+                &key as &dyn ToSql,
+                &commit_sha,
+                &db::to_tinyint(versioned),
+                &timestamp,
+            ],
+        )
+        .map_err(|e| anyhow!("Error marking [{}] resolved for {}: {}", key, commit_sha, e))?;
 
         Ok(())
     }
@@ -139,7 +347,7 @@ impl WebhookDatabase {
 
 #[cfg(test)]
 mod tests {
-    use std::{path::PathBuf, time::Duration};
+    use std::path::PathBuf;
 
     use super::*;
     use tempfile::{tempdir, TempDir};
@@ -186,4 +394,111 @@ mod tests {
         assert!(db.maybe_record(event1).unwrap());
         assert!(db.maybe_record(event2).unwrap());
     }
+
+    #[test]
+    fn test_stale_pr_reminders() {
+        let (db, db_file, _temp) = new_test();
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        assert_eq!(None, db.last_stale_pr_reminder("some/repo", 5).unwrap());
+
+        db.record_stale_pr_reminder("some/repo", 5, now).unwrap();
+        assert_eq!(
+            Some(now),
+            db.last_stale_pr_reminder("some/repo", 5).unwrap()
+        );
+        assert_eq!(None, db.last_stale_pr_reminder("some/repo", 6).unwrap());
+        assert_eq!(None, db.last_stale_pr_reminder("other/repo", 5).unwrap());
+
+        let later = now + Duration::from_secs(3600);
+        db.record_stale_pr_reminder("some/repo", 5, later).unwrap();
+
+        let reload_db =
+            WebhookDatabase::new(&db_file.to_string_lossy()).expect("create temp database");
+        assert_eq!(
+            Some(later),
+            reload_db.last_stale_pr_reminder("some/repo", 5).unwrap()
+        );
+
+        db.clean(later + Duration::from_secs(1)).unwrap();
+        assert_eq!(None, db.last_stale_pr_reminder("some/repo", 5).unwrap());
+    }
+
+    #[test]
+    fn test_resolved_commits() {
+        let (db, db_file, _temp) = new_test();
+
+        assert!(!db.was_resolved("SER-1", "aabbcc", false).unwrap());
+
+        db.mark_resolved("SER-1", "aabbcc", false).unwrap();
+        assert!(db.was_resolved("SER-1", "aabbcc", false).unwrap());
+        assert!(!db.was_resolved("SER-1", "aabbcc", true).unwrap());
+        assert!(!db.was_resolved("SER-1", "ddeeff", false).unwrap());
+        assert!(!db.was_resolved("SER-2", "aabbcc", false).unwrap());
+
+        let reload_db =
+            WebhookDatabase::new(&db_file.to_string_lossy()).expect("create temp database");
+        assert!(reload_db.was_resolved("SER-1", "aabbcc", false).unwrap());
+
+        db.clean(SystemTime::now() + Duration::from_secs(1))
+            .unwrap();
+        assert!(!db.was_resolved("SER-1", "aabbcc", false).unwrap());
+    }
+
+    #[test]
+    fn test_pending_jira_reviews() {
+        let (db, db_file, _temp) = new_test();
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let minute = Duration::from_secs(60);
+
+        db.queue_jira_review("first", now).unwrap();
+        db.queue_jira_review("later", now + minute).unwrap();
+        db.queue_jira_review("second", now - minute).unwrap();
+
+        // queued reviews survive a restart
+        let reload_db =
+            WebhookDatabase::new(&db_file.to_string_lossy()).expect("create temp database");
+        assert_eq!(
+            vec!["first", "second"],
+            reload_db.take_due_jira_reviews(now).unwrap()
+        );
+        assert!(db.take_due_jira_reviews(now).unwrap().is_empty());
+        assert_eq!(
+            vec!["later"],
+            db.take_due_jira_reviews(now + minute).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_review_threads() {
+        let (db, db_file, _temp) = new_test();
+
+        assert_eq!(None, db.unresolved_review_threads("some/repo", 5).unwrap());
+        assert_eq!(None, db.resolve_review_thread("some/repo", 5).unwrap());
+
+        db.add_review_thread("some/repo", 5).unwrap();
+        db.add_review_thread("some/repo", 5).unwrap();
+        db.add_review_thread("other/repo", 5).unwrap();
+        assert_eq!(
+            Some(2),
+            db.unresolved_review_threads("some/repo", 5).unwrap()
+        );
+        assert_eq!(None, db.unresolved_review_threads("some/repo", 6).unwrap());
+
+        // counts survive a restart
+        let reload_db =
+            WebhookDatabase::new(&db_file.to_string_lossy()).expect("create temp database");
+        assert_eq!(
+            Some(1),
+            reload_db.resolve_review_thread("some/repo", 5).unwrap()
+        );
+        assert_eq!(
+            Some(0),
+            reload_db.resolve_review_thread("some/repo", 5).unwrap()
+        );
+        assert_eq!(None, db.unresolved_review_threads("some/repo", 5).unwrap());
+
+        db.clear_review_threads("other/repo", 5).unwrap();
+        assert_eq!(None, db.unresolved_review_threads("other/repo", 5).unwrap());
+    }
 }
@@ -1,7 +1,8 @@
 use octobot_lib::db::migrations::{sql, Migration};
 
 pub fn all_migrations() -> Vec<Box<dyn Migration>> {
-    vec![sql(r#"
+    vec![
+        sql(r#"
     create table processed_webhooks (
       guid varchar not null,
       timestamp integer not null,
@@ -13,5 +14,38 @@ pub fn all_migrations() -> Vec<Box<dyn Migration>> {
       guid varchar not null,
       delivered_at integer not null
     );
-    "#)]
+    "#),
+        sql(r#"
+    create table stale_pr_reminders (
+      repo varchar not null,
+      number integer not null,
+      timestamp integer not null,
+      PRIMARY KEY( repo, number )
+    );
+    "#),
+        sql(r#"
+    create table resolved_jira_commits (
+      jira_key varchar not null,
+      commit_sha varchar not null,
+      versioned tinyint not null,
+      timestamp integer not null,
+      PRIMARY KEY( jira_key, commit_sha, versioned )
+    );
+    "#),
+        sql(r#"
+    create table pending_jira_reviews (
+      id integer PRIMARY KEY AUTOINCREMENT,
+      request varchar not null,
+      next_attempt integer not null
+    );
+    "#),
+        sql(r#"
+    create table review_threads (
+      repo varchar not null,
+      number integer not null,
+      unresolved integer not null,
+      PRIMARY KEY( repo, number )
+    );
+    "#),
+    ]
 }
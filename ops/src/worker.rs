@@ -1,9 +1,16 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use tokio;
 
 pub trait Worker<T: Send + 'static>: Send + Sync {
     fn send(&self, req: T);
+
+    // Number of requests sent but not yet handled
+    fn pending(&self) -> usize {
+        0
+    }
 }
 
 #[async_trait::async_trait]
@@ -14,6 +21,7 @@ pub trait Runner<T: Send + 'static>: Send + Sync {
 pub struct TokioWorker<T: Send + Sync + 'static> {
     runner: Arc<dyn Runner<T>>,
     runtime: Arc<Mutex<tokio::runtime::Runtime>>,
+    pending: Arc<AtomicUsize>,
 }
 
 impl<T: Send + Sync + 'static> TokioWorker<T> {
@@ -21,16 +29,105 @@ impl<T: Send + Sync + 'static> TokioWorker<T> {
         runtime: Arc<Mutex<tokio::runtime::Runtime>>,
         runner: Arc<dyn Runner<T>>,
     ) -> Arc<dyn Worker<T>> {
-        Arc::new(TokioWorker { runner, runtime })
+        Arc::new(TokioWorker {
+            runner,
+            runtime,
+            pending: Arc::new(AtomicUsize::new(0)),
+        })
     }
 }
 
 impl<T: Send + Sync + 'static> Worker<T> for TokioWorker<T> {
     fn send(&self, req: T) {
         let runner = self.runner.clone();
-        self.runtime
+        let pending = PendingGuard::new(self.pending.clone());
+        self.runtime.lock().unwrap().spawn(async move {
+            let _pending = pending;
+            runner.handle(req).await
+        });
+    }
+
+    fn pending(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
+    }
+}
+
+// Counts a request as pending until it has been handled (or its task is dropped)
+struct PendingGuard(Arc<AtomicUsize>);
+
+impl PendingGuard {
+    fn new(count: Arc<AtomicUsize>) -> PendingGuard {
+        count.fetch_add(1, Ordering::SeqCst);
+        PendingGuard(count)
+    }
+}
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// Waits for `pending` to reach zero, giving up after `timeout`. Returns how many requests remain.
+pub async fn wait_for_drain<F: Fn() -> usize>(pending: F, timeout: Duration) -> usize {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = pending();
+        if remaining == 0 || Instant::now() >= deadline {
+            return remaining;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct SleepRunner;
+
+    #[async_trait::async_trait]
+    impl Runner<u64> for SleepRunner {
+        async fn handle(&self, millis: u64) {
+            tokio::time::sleep(Duration::from_millis(millis)).await;
+        }
+    }
+
+    fn new_test() -> (Arc<Mutex<tokio::runtime::Runtime>>, Arc<dyn Worker<u64>>) {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let runtime = Arc::new(Mutex::new(runtime));
+        let worker = TokioWorker::new_worker(runtime.clone(), Arc::new(SleepRunner));
+        (runtime, worker)
+    }
+
+    #[test]
+    fn test_drain() {
+        let (runtime, worker) = new_test();
+        worker.send(10);
+        worker.send(20);
+        assert_eq!(2, worker.pending());
+
+        let remaining = runtime
             .lock()
             .unwrap()
-            .spawn(async move { runner.handle(req).await });
+            .block_on(wait_for_drain(|| worker.pending(), Duration::from_secs(5)));
+        assert_eq!(0, remaining);
+        assert_eq!(0, worker.pending());
+    }
+
+    #[test]
+    fn test_drain_timeout() {
+        let (runtime, worker) = new_test();
+        worker.send(10);
+        worker.send(60_000);
+
+        let remaining = runtime.lock().unwrap().block_on(wait_for_drain(
+            || worker.pending(),
+            Duration::from_millis(500),
+        ));
+        assert_eq!(1, remaining);
     }
 }
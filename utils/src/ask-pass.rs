@@ -12,7 +12,9 @@ fn main() {
         None => String::new(),
     };
 
+    // git only prompts with the host, so ignore any path prefix the github is served under.
     // only care about a single host for now, but keep this logic just incase...
+    let octobot_host = octobot_host.split('/').next().unwrap_or_default();
     if host != octobot_host {
         println!("this is the wrong password");
     } else {